@@ -0,0 +1,41 @@
+//! Embedded use through the `Reflex` facade, caching a structured result.
+
+use anyhow::Result;
+
+#[cfg(feature = "mock")]
+#[derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize, Debug)]
+struct Answer {
+    text: String,
+    sources: Vec<String>,
+}
+
+#[cfg(feature = "mock")]
+#[tokio::main]
+async fn main() -> Result<()> {
+    use reflex::{CrossEncoderScorer, MockTieredCache, Reflex};
+
+    let reflex = Reflex::new(
+        MockTieredCache::new_mock().await?,
+        CrossEncoderScorer::stub()?,
+    );
+    let tenant_id = 1;
+
+    for question in ["What is Rust?", "What is Rust?"] {
+        let answer = reflex
+            .get_or_compute(question, question, tenant_id, || async {
+                Ok::<_, std::io::Error>(Answer {
+                    text: "A systems programming language.".to_string(),
+                    sources: vec!["https://www.rust-lang.org".to_string()],
+                })
+            })
+            .await?;
+        println!("{:?}: {:?}", answer.status, answer.value);
+    }
+
+    Ok(())
+}
+
+#[cfg(not(feature = "mock"))]
+fn main() {
+    eprintln!("Run with: cargo run --example get_or_compute --features mock");
+}
@@ -266,6 +266,11 @@ impl L1CacheHandle {
         self.inner.run_pending_tasks();
     }
 
+    /// Returns an iterator of currently stored hashes.
+    pub fn hashes(&self) -> impl Iterator<Item = [u8; 32]> + '_ {
+        self.inner.hashes()
+    }
+
     /// Returns the number of strong references to the underlying cache.
     #[inline]
     pub fn strong_count(&self) -> usize {
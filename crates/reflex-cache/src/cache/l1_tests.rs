@@ -27,6 +27,8 @@ fn test_reflex_status_header_values() {
         ReflexStatus::HitL3Verified.as_header_value(),
         "HIT_L3_VERIFIED"
     );
+    assert_eq!(ReflexStatus::HitInflight.as_header_value(), "HIT_INFLIGHT");
+    assert_eq!(ReflexStatus::HitDegraded.as_header_value(), "HIT_DEGRADED");
     assert_eq!(ReflexStatus::Miss.as_header_value(), "MISS");
 }
 
@@ -35,6 +37,8 @@ fn test_reflex_status_is_hit() {
     assert!(ReflexStatus::HitL1Exact.is_hit());
     assert!(ReflexStatus::HitL2Semantic.is_hit());
     assert!(ReflexStatus::HitL3Verified.is_hit());
+    assert!(ReflexStatus::HitInflight.is_hit());
+    assert!(ReflexStatus::HitDegraded.is_hit());
     assert!(!ReflexStatus::Miss.is_hit());
 }
 
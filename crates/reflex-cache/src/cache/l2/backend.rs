@@ -24,6 +24,31 @@ pub trait BqSearchBackend: Send + Sync {
         tenant_filter: Option<u64>,
     ) -> impl std::future::Future<Output = Result<Vec<SearchResult>, VectorDbError>> + Send;
 
+    /// Like [`search_bq`](Self::search_bq), returning only points indexed with
+    /// `scope_filter` when it is set.
+    ///
+    /// The default searches without the scope and drops the other points
+    /// afterwards, so they still count against `limit`; backends that can
+    /// filter in the index should override it.
+    fn search_bq_scoped(
+        &self,
+        collection: &str,
+        query: Vec<f32>,
+        limit: u64,
+        tenant_filter: Option<u64>,
+        scope_filter: Option<u64>,
+    ) -> impl std::future::Future<Output = Result<Vec<SearchResult>, VectorDbError>> + Send {
+        async move {
+            let mut results = self
+                .search_bq(collection, query, limit, tenant_filter)
+                .await?;
+            if scope_filter.is_some() {
+                results.retain(|result| result.scope_hash == scope_filter);
+            }
+            Ok(results)
+        }
+    }
+
     /// Upserts points into the collection.
     fn upsert_points(
         &self,
@@ -31,6 +56,24 @@ pub trait BqSearchBackend: Send + Sync {
         points: Vec<VectorPoint>,
         consistency: WriteConsistency,
     ) -> impl std::future::Future<Output = Result<(), VectorDbError>> + Send;
+
+    /// Deletes points by id (ids that do not exist are ignored).
+    ///
+    /// The default refuses with [`VectorDbError::DeleteFailed`], so search-only
+    /// backends need not implement it.
+    fn delete_points(
+        &self,
+        collection: &str,
+        ids: Vec<u64>,
+    ) -> impl std::future::Future<Output = Result<(), VectorDbError>> + Send {
+        let _ = ids;
+        async move {
+            Err(VectorDbError::DeleteFailed {
+                collection: collection.to_string(),
+                message: "delete_points is not supported by this backend".to_string(),
+            })
+        }
+    }
 }
 
 impl BqSearchBackend for BqClient {
@@ -53,6 +96,18 @@ impl BqSearchBackend for BqClient {
             .await
     }
 
+    async fn search_bq_scoped(
+        &self,
+        collection: &str,
+        query: Vec<f32>,
+        limit: u64,
+        tenant_filter: Option<u64>,
+        scope_filter: Option<u64>,
+    ) -> Result<Vec<SearchResult>, VectorDbError> {
+        self.search_bq_scoped(collection, query, limit, tenant_filter, scope_filter)
+            .await
+    }
+
     async fn upsert_points(
         &self,
         collection: &str,
@@ -61,6 +116,10 @@ impl BqSearchBackend for BqClient {
     ) -> Result<(), VectorDbError> {
         self.upsert_points(collection, points, consistency).await
     }
+
+    async fn delete_points(&self, collection: &str, ids: Vec<u64>) -> Result<(), VectorDbError> {
+        self.delete_points(collection, ids).await
+    }
 }
 
 #[cfg(any(test, feature = "mock"))]
@@ -84,6 +143,18 @@ impl BqSearchBackend for MockBqClient {
             .await
     }
 
+    async fn search_bq_scoped(
+        &self,
+        collection: &str,
+        query: Vec<f32>,
+        limit: u64,
+        tenant_filter: Option<u64>,
+        scope_filter: Option<u64>,
+    ) -> Result<Vec<SearchResult>, VectorDbError> {
+        self.search_bq_scoped(collection, query, limit, tenant_filter, scope_filter)
+            .await
+    }
+
     async fn upsert_points(
         &self,
         collection: &str,
@@ -92,4 +163,8 @@ impl BqSearchBackend for MockBqClient {
     ) -> Result<(), VectorDbError> {
         self.upsert_points(collection, points, consistency).await
     }
+
+    async fn delete_points(&self, collection: &str, ids: Vec<u64>) -> Result<(), VectorDbError> {
+        self.delete_points(collection, ids).await
+    }
 }
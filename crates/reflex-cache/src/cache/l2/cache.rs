@@ -1,10 +1,12 @@
 use std::sync::Arc;
+use std::time::Instant;
 
 use futures_util::future::join_all;
 use tokio::sync::RwLock;
 use tracing::{debug, info, instrument, warn};
 
 use crate::embedding::sinter::SinterEmbedder;
+use crate::telemetry;
 use crate::vectordb::VectorPoint;
 use crate::vectordb::rescoring::{CandidateEntry, RescorerConfig, VectorRescorer};
 
@@ -83,15 +85,28 @@ impl<B: BqSearchBackend, S: StorageLoader> L2SemanticCache<B, S> {
     }
 
     /// Searches for semantic matches for `prompt` within `tenant_id`.
-    #[instrument(skip(self, prompt), fields(tenant_id = tenant_id, prompt_len = prompt.len()))]
     pub async fn search(&self, prompt: &str, tenant_id: u64) -> L2CacheResult<L2LookupResult> {
+        self.search_scoped(prompt, tenant_id, None).await
+    }
+
+    /// Like [`search`](Self::search), over only the points indexed with
+    /// `scope_hash` when it is set.
+    #[instrument(skip(self, prompt), fields(tenant_id = tenant_id, prompt_len = prompt.len()))]
+    pub async fn search_scoped(
+        &self,
+        prompt: &str,
+        tenant_id: u64,
+        scope_hash: Option<u64>,
+    ) -> L2CacheResult<L2LookupResult> {
         debug!("Generating embedding for prompt");
+        let started = Instant::now();
         let embedding_f16 =
             self.embedder
                 .embed(prompt)
                 .map_err(|e| L2CacheError::EmbeddingFailed {
                     reason: e.to_string(),
                 })?;
+        telemetry::record_stage(telemetry::STAGE_EMBEDDING, started);
 
         let embedding_f32: Vec<f32> = embedding_f16.iter().map(|v| v.to_f32()).collect();
 
@@ -100,15 +115,18 @@ impl<B: BqSearchBackend, S: StorageLoader> L2SemanticCache<B, S> {
             "Embedding generated, starting BQ search"
         );
 
+        let started = Instant::now();
         let bq_results = self
             .bq_backend
-            .search_bq(
+            .search_bq_scoped(
                 &self.config.collection_name,
                 embedding_f32,
                 self.config.top_k_bq,
                 Some(tenant_id),
+                scope_hash,
             )
             .await?;
+        telemetry::record_stage(telemetry::STAGE_BQ_SEARCH, started);
 
         let bq_candidates_count = bq_results.len();
         debug!(
@@ -130,7 +148,9 @@ impl<B: BqSearchBackend, S: StorageLoader> L2SemanticCache<B, S> {
                     let id = result.id;
                     let score = result.score;
                     async move {
+                        let started = Instant::now();
                         let entry = self.storage.load(&key, tenant_id).await;
+                        telemetry::record_stage(telemetry::STAGE_STORAGE_LOAD, started);
                         (id, score, key, entry)
                     }
                 })
@@ -160,12 +180,14 @@ impl<B: BqSearchBackend, S: StorageLoader> L2SemanticCache<B, S> {
             "Storage entries loaded, starting rescore"
         );
 
+        let started = Instant::now();
         let scored_candidates = self
             .rescorer
             .rescore(&embedding_f16, candidate_entries)
             .map_err(|e| L2CacheError::RescoringFailed {
                 reason: e.to_string(),
             })?;
+        telemetry::record_stage(telemetry::STAGE_RESCORING, started);
 
         info!(
             tenant_id = tenant_id,
@@ -211,6 +233,7 @@ impl<B: BqSearchBackend, S: StorageLoader> L2SemanticCache<B, S> {
             context_hash,
             timestamp,
             storage_key: Some(storage_key.to_string()),
+            scope_hash: None,
         };
 
         self.bq_backend
@@ -259,6 +282,20 @@ impl<B: BqSearchBackend, S: StorageLoader> L2SemanticCacheHandle<B, S> {
         self.inner.read().await.search(prompt, tenant_id).await
     }
 
+    /// Scoped search (see [`L2SemanticCache::search_scoped`]).
+    pub async fn search_scoped(
+        &self,
+        prompt: &str,
+        tenant_id: u64,
+        scope_hash: Option<u64>,
+    ) -> L2CacheResult<L2LookupResult> {
+        self.inner
+            .read()
+            .await
+            .search_scoped(prompt, tenant_id, scope_hash)
+            .await
+    }
+
     /// Delegates to [`L2SemanticCache::index`].
     pub async fn index(
         &self,
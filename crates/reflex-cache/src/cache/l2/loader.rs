@@ -1,5 +1,5 @@
 use crate::storage::mmap::{AlignedMmapBuilder, MmapFileHandle};
-use crate::storage::{CacheEntry, StorageError, StorageWriter};
+use crate::storage::{ArchivedCacheEntry, CacheEntry, StorageError, StorageWriter};
 
 /// Loads cached entries (typically from disk) given a storage key.
 pub trait StorageLoader: Send + Sync {
@@ -83,6 +83,26 @@ impl StorageWriter for MockStorageLoader {
         MmapFileHandle::open(temp_file.path())
             .map_err(|e| crate::storage::StorageError::WriteFailed(e.to_string()))
     }
+
+    fn delete(&self, key: &str) -> Result<bool, StorageError> {
+        Ok(self
+            .entries
+            .write()
+            .expect("lock poisoned")
+            .remove(key)
+            .is_some())
+    }
+
+    fn list_keys(&self, tenant_id: Option<u64>) -> Result<Vec<String>, StorageError> {
+        Ok(self
+            .entries
+            .read()
+            .expect("lock poisoned")
+            .iter()
+            .filter(|(_, entry)| tenant_id.is_none_or(|t| entry.tenant_id == t))
+            .map(|(key, _)| key.clone())
+            .collect())
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -145,6 +165,81 @@ impl StorageWriter for NvmeStorageLoader {
             .write_readonly(data)
             .map_err(|e| StorageError::WriteFailed(format!("Failed to write file: {}", e)))
     }
+
+    fn delete(&self, key: &str) -> Result<bool, StorageError> {
+        let rel = sanitize_storage_key(key).ok_or_else(|| {
+            StorageError::Io(format!("Invalid storage key (path traversal?): {}", key))
+        })?;
+
+        match std::fs::remove_file(self.storage_path.join(rel)) {
+            Ok(()) => Ok(true),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(false),
+            Err(e) => Err(StorageError::Io(format!("Failed to delete {}: {}", key, e))),
+        }
+    }
+
+    /// Keys are `{tenant_id}/{file}.rkyv`; only numeric tenant directories are scanned.
+    fn list_keys(&self, tenant_id: Option<u64>) -> Result<Vec<String>, StorageError> {
+        let io_err = |e: std::io::Error| StorageError::Io(format!("Failed to list keys: {}", e));
+
+        let tenants: Vec<u64> = match tenant_id {
+            Some(t) => vec![t],
+            None => match std::fs::read_dir(&self.storage_path) {
+                Ok(dir) => dir
+                    .filter_map(Result::ok)
+                    .filter(|e| e.path().is_dir())
+                    .filter_map(|e| e.file_name().to_str()?.parse().ok())
+                    .collect(),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+                Err(e) => return Err(io_err(e)),
+            },
+        };
+
+        let mut keys = Vec::new();
+        for tenant in tenants {
+            let dir = match std::fs::read_dir(self.storage_path.join(tenant.to_string())) {
+                Ok(dir) => dir,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(io_err(e)),
+            };
+            for entry in dir {
+                let path = entry.map_err(io_err)?.path();
+                if path.extension().is_some_and(|ext| ext == "rkyv")
+                    && let Some(name) = path.file_name().and_then(|n| n.to_str())
+                {
+                    keys.push(format!("{}/{}", tenant, name));
+                }
+            }
+        }
+        Ok(keys)
+    }
+}
+
+/// Reads the `format` marker of an archived entry without validating the rest,
+/// or `None` when `bytes` is too short to hold an entry.
+fn archived_format(bytes: &[u8]) -> Option<u64> {
+    if bytes.len() < std::mem::size_of::<ArchivedCacheEntry>() {
+        return None;
+    }
+    let at = rkyv::api::root_position::<ArchivedCacheEntry>(bytes.len())
+        + std::mem::offset_of!(ArchivedCacheEntry, format);
+    let marker = bytes.get(at..at + 8)?;
+    Some(u64::from_le_bytes(marker.try_into().ok()?))
+}
+
+/// Deletes an entry file written in another [`CacheEntry::FORMAT`], which no
+/// loader of this version can read. Its vector point is left behind; searches
+/// skip it until the same context is stored again.
+fn discard_unreadable(file_path: &std::path::Path, handle: MmapFileHandle) {
+    drop(handle);
+    match std::fs::remove_file(file_path) {
+        Ok(()) => tracing::info!("Deleted unreadable cache entry at {:?}", file_path),
+        Err(e) => tracing::warn!(
+            "Failed to delete unreadable cache entry at {:?}: {}",
+            file_path,
+            e
+        ),
+    }
 }
 
 impl StorageLoader for NvmeStorageLoader {
@@ -176,13 +271,28 @@ impl StorageLoader for NvmeStorageLoader {
             let bytes = handle.as_slice();
 
             let entry: CacheEntry = match from_bytes::<CacheEntry, Error>(bytes) {
-                Ok(e) => e,
+                Ok(e) if e.format == CacheEntry::FORMAT => e,
+                Ok(e) => {
+                    tracing::warn!(
+                        "Cache entry at {:?} has format {:#018x}, expected {:#018x}",
+                        file_path,
+                        e.format,
+                        CacheEntry::FORMAT
+                    );
+                    discard_unreadable(&file_path, handle);
+                    return None;
+                }
                 Err(e) => {
                     tracing::warn!(
                         "Failed to deserialize cache entry at {:?}: {}",
                         file_path,
                         e
                     );
+                    // Only a marker that is present and wrong proves the file
+                    // is from another version; anything else is left alone.
+                    if archived_format(bytes).is_some_and(|format| format != CacheEntry::FORMAT) {
+                        discard_unreadable(&file_path, handle);
+                    }
                     return None;
                 }
             };
@@ -89,10 +89,12 @@ async fn test_mock_storage_loader_insert_and_load() {
     let loader = MockStorageLoader::new();
 
     let entry = CacheEntry {
+        format: CacheEntry::FORMAT,
         tenant_id: 1000,
         context_hash: 2000,
         timestamp: 1702500000,
         embedding: vec![0u8; crate::constants::EMBEDDING_F16_BYTES],
+        verification_text: String::new(),
         payload_blob: vec![0xDE, 0xAD, 0xBE, 0xEF],
     };
 
@@ -113,13 +115,168 @@ async fn test_mock_storage_loader_load_missing() {
     assert!(result.is_none());
 }
 
+#[test]
+fn test_mock_storage_loader_list_and_delete() {
+    use crate::storage::StorageWriter;
+
+    let loader = MockStorageLoader::new();
+    for (key, tenant_id) in [("a", 1), ("b", 1), ("c", 2)] {
+        loader.insert(
+            key,
+            CacheEntry {
+                format: CacheEntry::FORMAT,
+                tenant_id,
+                context_hash: 0,
+                timestamp: 0,
+                embedding: vec![],
+                verification_text: String::new(),
+                payload_blob: vec![],
+            },
+        );
+    }
+
+    let mut tenant_keys = loader.list_keys(Some(1)).unwrap();
+    tenant_keys.sort();
+    assert_eq!(tenant_keys, vec!["a", "b"]);
+    assert_eq!(loader.list_keys(None).unwrap().len(), 3);
+
+    assert!(loader.delete("a").unwrap());
+    assert!(!loader.delete("a").unwrap());
+    assert_eq!(loader.len(), 2);
+}
+
+#[test]
+fn test_nvme_storage_loader_list_and_delete() {
+    use crate::storage::StorageWriter;
+
+    let dir = tempfile::TempDir::new().unwrap();
+    let loader = NvmeStorageLoader::new(dir.path().to_path_buf());
+    loader.write("7/00000000000000aa.rkyv", b"one").unwrap();
+    loader.write("7/00000000000000bb.rkyv", b"two").unwrap();
+    loader.write("8/00000000000000cc.rkyv", b"three").unwrap();
+    std::fs::create_dir_all(dir.path().join("not-a-tenant")).unwrap();
+
+    let mut keys = loader.list_keys(Some(7)).unwrap();
+    keys.sort();
+    assert_eq!(
+        keys,
+        vec!["7/00000000000000aa.rkyv", "7/00000000000000bb.rkyv"]
+    );
+    assert_eq!(loader.list_keys(None).unwrap().len(), 3);
+    assert!(loader.list_keys(Some(9)).unwrap().is_empty());
+
+    assert!(loader.delete("7/00000000000000aa.rkyv").unwrap());
+    assert!(!loader.delete("7/00000000000000aa.rkyv").unwrap());
+    assert!(loader.delete("../escape.rkyv").is_err());
+    assert_eq!(loader.list_keys(Some(7)).unwrap().len(), 1);
+}
+
+/// The layout of entries written before [`CacheEntry::FORMAT`].
+#[derive(rkyv::Archive, rkyv::Serialize)]
+struct FormatOneEntry {
+    tenant_id: u64,
+    context_hash: u64,
+    timestamp: i64,
+    embedding: Vec<u8>,
+    payload_blob: Vec<u8>,
+}
+
+#[tokio::test]
+async fn test_nvme_storage_loader_discards_entries_of_other_formats() {
+    use crate::storage::StorageWriter;
+
+    let dir = tempfile::TempDir::new().unwrap();
+    let loader = NvmeStorageLoader::new(dir.path().to_path_buf());
+    let current = CacheEntry {
+        format: CacheEntry::FORMAT,
+        tenant_id: 7,
+        context_hash: 0xaa,
+        timestamp: 1,
+        embedding: vec![1, 2],
+        verification_text: "What is Rust?".to_string(),
+        payload_blob: b"{}".to_vec(),
+    };
+    let older = FormatOneEntry {
+        tenant_id: 7,
+        context_hash: 0xbb,
+        timestamp: 1,
+        // Real entries carry a full embedding, so they are never shorter
+        // than a current entry.
+        embedding: vec![1; 64],
+        payload_blob: b"{}".to_vec(),
+    };
+    let bumped = CacheEntry {
+        format: CacheEntry::FORMAT + 1,
+        context_hash: 0xcc,
+        ..current.clone()
+    };
+    let encode = |entry: &CacheEntry| rkyv::to_bytes::<rkyv::rancor::Error>(entry).unwrap();
+    loader
+        .write("7/00000000000000aa.rkyv", &encode(&current))
+        .unwrap();
+    loader
+        .write(
+            "7/00000000000000bb.rkyv",
+            &rkyv::to_bytes::<rkyv::rancor::Error>(&older).unwrap(),
+        )
+        .unwrap();
+    loader
+        .write("7/00000000000000cc.rkyv", &encode(&bumped))
+        .unwrap();
+
+    assert_eq!(
+        loader.load("7/00000000000000aa.rkyv", 7).await,
+        Some(current)
+    );
+    assert_eq!(loader.load("7/00000000000000bb.rkyv", 7).await, None);
+    assert_eq!(loader.load("7/00000000000000cc.rkyv", 7).await, None);
+    assert_eq!(
+        loader.list_keys(Some(7)).unwrap(),
+        vec!["7/00000000000000aa.rkyv"]
+    );
+}
+
+#[tokio::test]
+async fn test_nvme_storage_loader_keeps_damaged_entries_of_this_format() {
+    use crate::storage::StorageWriter;
+
+    let dir = tempfile::TempDir::new().unwrap();
+    let loader = NvmeStorageLoader::new(dir.path().to_path_buf());
+    let entry = CacheEntry {
+        format: CacheEntry::FORMAT,
+        tenant_id: 7,
+        context_hash: 0xaa,
+        timestamp: 1,
+        embedding: vec![1, 2],
+        verification_text: "What is Rust?".to_string(),
+        payload_blob: b"{}".to_vec(),
+    };
+    let mut bytes = rkyv::to_bytes::<rkyv::rancor::Error>(&entry)
+        .unwrap()
+        .to_vec();
+    let text = bytes
+        .windows(13)
+        .position(|w| w == b"What is Rust?")
+        .unwrap();
+    bytes[text] = 0xff;
+    loader.write("7/00000000000000aa.rkyv", &bytes).unwrap();
+
+    assert_eq!(loader.load("7/00000000000000aa.rkyv", 7).await, None);
+    assert_eq!(
+        loader.list_keys(Some(7)).unwrap(),
+        vec!["7/00000000000000aa.rkyv"]
+    );
+}
+
 #[test]
 fn test_l2_lookup_result_methods() {
     let entry = CacheEntry {
+        format: CacheEntry::FORMAT,
         tenant_id: 1,
         context_hash: 2,
         timestamp: 3,
         embedding: vec![],
+        verification_text: String::new(),
         payload_blob: vec![],
     };
 
@@ -162,10 +319,12 @@ fn test_l2_lookup_result_empty() {
 #[test]
 fn test_l2_lookup_result_into_candidates() {
     let entry = CacheEntry {
+        format: CacheEntry::FORMAT,
         tenant_id: 1,
         context_hash: 2,
         timestamp: 3,
         embedding: vec![],
+        verification_text: String::new(),
         payload_blob: vec![],
     };
 
@@ -206,10 +365,12 @@ async fn test_mock_l2_cache_index_and_search() {
         .expect("should create cache");
 
     let entry = CacheEntry {
+        format: CacheEntry::FORMAT,
         tenant_id: 1000,
         context_hash: 2000,
         timestamp: 1702500000,
         embedding: vec![0u8; crate::constants::EMBEDDING_F16_BYTES],
+        verification_text: String::new(),
         payload_blob: vec![0xDE, 0xAD, 0xBE, 0xEF],
     };
 
@@ -257,18 +418,22 @@ async fn test_mock_l2_cache_tenant_isolation() {
         .expect("should create cache");
 
     let entry_tenant_a = CacheEntry {
+        format: CacheEntry::FORMAT,
         tenant_id: 1000,
         context_hash: 2000,
         timestamp: 1702500000,
         embedding: vec![0u8; crate::constants::EMBEDDING_F16_BYTES],
+        verification_text: String::new(),
         payload_blob: vec![0x01],
     };
 
     let entry_tenant_b = CacheEntry {
+        format: CacheEntry::FORMAT,
         tenant_id: 2000,
         context_hash: 3000,
         timestamp: 1702500000,
         embedding: vec![0u8; crate::constants::EMBEDDING_F16_BYTES],
+        verification_text: String::new(),
         payload_blob: vec![0x02],
     };
 
@@ -313,10 +478,12 @@ async fn test_mock_l2_cache_multiple_entries() {
 
     for i in 0..10 {
         let entry = CacheEntry {
+            format: CacheEntry::FORMAT,
             tenant_id: 1000,
             context_hash: i as u64,
             timestamp: 1702500000 + i as i64,
             embedding: vec![((i * 7) % 256) as u8; crate::constants::EMBEDDING_F16_BYTES],
+            verification_text: String::new(),
             payload_blob: vec![i as u8],
         };
 
@@ -375,10 +542,12 @@ async fn test_l2_cache_handle_concurrent_search() {
         .expect("should create cache");
 
     let entry = CacheEntry {
+        format: CacheEntry::FORMAT,
         tenant_id: 1000,
         context_hash: 2000,
         timestamp: 1702500000,
         embedding: vec![0u8; crate::constants::EMBEDDING_F16_BYTES],
+        verification_text: String::new(),
         payload_blob: vec![],
     };
     cache.storage().insert("key_1", entry);
@@ -458,3 +627,43 @@ async fn test_handle_debug_impl() {
     assert!(debug_str.contains("L2SemanticCacheHandle"));
     assert!(debug_str.contains("strong_count"));
 }
+
+/// A backend that searches but cannot delete.
+struct SearchOnly;
+
+impl BqSearchBackend for SearchOnly {
+    async fn is_ready(&self) -> bool {
+        true
+    }
+
+    async fn ensure_collection(&self, _name: &str, _vector_size: u64) -> Result<(), VectorDbError> {
+        Ok(())
+    }
+
+    async fn search_bq(
+        &self,
+        _collection: &str,
+        _query: Vec<f32>,
+        _limit: u64,
+        _tenant_filter: Option<u64>,
+    ) -> Result<Vec<crate::vectordb::SearchResult>, VectorDbError> {
+        Ok(Vec::new())
+    }
+
+    async fn upsert_points(
+        &self,
+        _collection: &str,
+        _points: Vec<crate::vectordb::VectorPoint>,
+        _consistency: crate::vectordb::WriteConsistency,
+    ) -> Result<(), VectorDbError> {
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn test_delete_points_defaults_to_unsupported() {
+    assert!(matches!(
+        SearchOnly.delete_points("cache", vec![1]).await,
+        Err(VectorDbError::DeleteFailed { collection, .. }) if collection == "cache"
+    ));
+}
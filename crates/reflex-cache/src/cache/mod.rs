@@ -34,6 +34,6 @@ pub use tiered::MockTieredCache;
 pub use tiered::{TieredCache, TieredCacheHandle, TieredLookupResult};
 
 pub use types::{
-    REFLEX_STATUS_ERROR, REFLEX_STATUS_HEADER, REFLEX_STATUS_HEALTHY, REFLEX_STATUS_NOT_READY,
-    REFLEX_STATUS_READY, REFLEX_STATUS_STORED, ReflexStatus,
+    LookupOptions, REFLEX_STATUS_ERROR, REFLEX_STATUS_HEADER, REFLEX_STATUS_HEALTHY,
+    REFLEX_STATUS_NOT_READY, REFLEX_STATUS_READY, REFLEX_STATUS_STORED, ReflexStatus,
 };
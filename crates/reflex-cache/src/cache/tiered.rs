@@ -1,7 +1,9 @@
 //! Tiered cache: L1 exact + L2 semantic.
 //!
-//! Use [`TieredCache::lookup`] for the default flow, or
-//! [`TieredCache::lookup_with_semantic_query`] when the exact key and semantic query differ.
+//! Use [`TieredCache::lookup`] for the default flow,
+//! [`TieredCache::lookup_with_semantic_query`] when the exact key and semantic query differ,
+//! or [`TieredCache::lookup_with_options`] when the caller also needs per-request
+//! [`LookupOptions`].
 
 use std::sync::Arc;
 
@@ -16,7 +18,8 @@ use super::l2::{
     BqSearchBackend, L2CacheError, L2CacheResult, L2LookupResult, L2SemanticCache, StorageLoader,
 };
 
-use super::{L1CacheHandle, L1LookupResult, ReflexStatus};
+use super::{L1CacheHandle, L1LookupResult, LookupOptions, ReflexStatus};
+use crate::storage::ArchivedCacheEntry;
 use crate::storage::mmap::MmapFileHandle;
 #[cfg(any(test, feature = "mock"))]
 use crate::vectordb::bq::MockBqClient;
@@ -97,24 +100,63 @@ impl<B: BqSearchBackend, S: StorageLoader> TieredCache<B, S> {
     }
 
     /// Looks up with separate exact key (L1) and semantic query (L2).
-    #[instrument(skip(self, exact_key, semantic_query), fields(key_len = exact_key.len(), query_len = semantic_query.len(), tenant_id = tenant_id))]
     pub async fn lookup_with_semantic_query(
         &self,
         exact_key: &str,
         semantic_query: &str,
         tenant_id: u64,
     ) -> L2CacheResult<TieredLookupResult> {
+        self.lookup_with_options(
+            exact_key,
+            semantic_query,
+            tenant_id,
+            LookupOptions::default(),
+        )
+        .await
+    }
+
+    /// Like [`lookup_with_semantic_query`](Self::lookup_with_semantic_query),
+    /// constrained by `options`.
+    ///
+    /// Entries that fail `options` (e.g. older than `max_age_secs`) are skipped
+    /// in both tiers; an L1 entry that is too old falls through to L2. At most
+    /// `top_k` L2 candidates are returned when it is set. With `exact_only`,
+    /// an L1 miss is a miss. A `scope_hash` limits L2 to points indexed with
+    /// it; L1 keys are exact and need no scope.
+    #[instrument(skip(self, exact_key, semantic_query), fields(key_len = exact_key.len(), query_len = semantic_query.len(), tenant_id = tenant_id))]
+    pub async fn lookup_with_options(
+        &self,
+        exact_key: &str,
+        semantic_query: &str,
+        tenant_id: u64,
+        options: LookupOptions,
+    ) -> L2CacheResult<TieredLookupResult> {
+        let now = unix_now();
+
         debug!("Checking L1 cache");
         let l1_key = format!("{}:{}", tenant_id, exact_key);
         if let Some(result) = self.l1.lookup(&l1_key) {
-            info!("L1 cache hit");
-            return Ok(TieredLookupResult::HitL1(result));
+            if l1_is_fresh(&result, &options, now) {
+                info!("L1 cache hit");
+                return Ok(TieredLookupResult::HitL1(result));
+            }
+            debug!(max_age = options.max_age_secs, "L1 entry too old, skipping");
+        }
+
+        if options.exact_only {
+            debug!("L1 miss, exact-only lookup");
+            return Ok(TieredLookupResult::Miss);
         }
 
         debug!("L1 miss, checking L2 cache");
 
-        match self.l2.search(semantic_query, tenant_id).await {
+        match self
+            .l2
+            .search_scoped(semantic_query, tenant_id, options.scope_hash)
+            .await
+        {
             Ok(result) => {
+                let result = retain_fresh(result, &options, now);
                 if result.has_candidates() {
                     info!(
                         candidates = result.candidates().len(),
@@ -180,6 +222,50 @@ impl<B: BqSearchBackend, S: StorageLoader> TieredCache<B, S> {
         self.l1.remove_prompt(&l1_key)
     }
 
+    /// Removes every L1 entry whose stored [`CacheEntry`](crate::storage::CacheEntry)
+    /// matches `predicate`, returning the number removed.
+    ///
+    /// L1 keys are one-way hashes, so this scans the resident entries; use it
+    /// when only the entry's tenant, context hash or timestamp is known.
+    /// Entries that are not valid archived cache entries are left alone.
+    pub fn remove_l1_where<F>(&self, predicate: F) -> usize
+    where
+        F: Fn(&ArchivedCacheEntry) -> bool,
+    {
+        let matching: Vec<[u8; 32]> = self
+            .l1
+            .hashes()
+            .filter(|hash| {
+                self.l1.lookup_by_hash(hash).is_some_and(|result| {
+                    result
+                        .handle()
+                        .access_archived::<ArchivedCacheEntry>()
+                        .is_ok_and(&predicate)
+                })
+            })
+            .collect();
+
+        matching
+            .iter()
+            .filter(|hash| self.l1.remove(hash).is_some())
+            .count()
+    }
+
+    /// Calls `visit` with every resident L1 entry that is a valid archived
+    /// [`CacheEntry`](crate::storage::CacheEntry).
+    pub fn for_each_l1_entry<F>(&self, mut visit: F)
+    where
+        F: FnMut(&ArchivedCacheEntry),
+    {
+        for hash in self.l1.hashes() {
+            if let Some(result) = self.l1.lookup_by_hash(&hash)
+                && let Ok(entry) = result.handle().access_archived::<ArchivedCacheEntry>()
+            {
+                visit(entry);
+            }
+        }
+    }
+
     /// Returns `true` if L1 contains a key for this tenant+prompt.
     pub fn contains_l1(&self, prompt: &str, tenant_id: u64) -> bool {
         let l1_key = format!("{}:{}", tenant_id, prompt);
@@ -212,6 +298,42 @@ impl<B: BqSearchBackend, S: StorageLoader> TieredCache<B, S> {
     }
 }
 
+fn unix_now() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or_default()
+}
+
+/// Unconstrained lookups never touch the payload; otherwise an unreadable entry counts as stale.
+fn l1_is_fresh(result: &L1LookupResult, options: &LookupOptions, now: i64) -> bool {
+    if options.max_age_secs.is_none() {
+        return true;
+    }
+    result
+        .handle()
+        .access_archived::<ArchivedCacheEntry>()
+        .map(|entry| options.is_fresh(entry.timestamp.to_native(), now))
+        .unwrap_or(false)
+}
+
+/// Drops L2 candidates that are too old, then keeps the best `top_k`.
+fn retain_fresh(result: L2LookupResult, options: &LookupOptions, now: i64) -> L2LookupResult {
+    if options.max_age_secs.is_none() && options.top_k.is_none() {
+        return result;
+    }
+    let tenant_id = result.tenant_id();
+    let bq_candidates_count = result.bq_candidates_count();
+    let query_embedding = result.query_embedding().to_vec();
+    let candidates = result
+        .into_candidates()
+        .into_iter()
+        .filter(|c| options.is_fresh(c.entry.timestamp, now))
+        .take(options.top_k.unwrap_or(usize::MAX))
+        .collect();
+    L2LookupResult::new(query_embedding, candidates, tenant_id, bq_candidates_count)
+}
+
 #[cfg(any(test, feature = "mock"))]
 /// Type alias for a tiered cache backed by mocks.
 pub type MockTieredCache = TieredCache<MockBqClient, MockStorageLoader>;
@@ -276,6 +398,21 @@ impl<B: BqSearchBackend, S: StorageLoader> TieredCacheHandle<B, S> {
             .await
     }
 
+    /// Delegates to [`TieredCache::lookup_with_options`].
+    pub async fn lookup_with_options(
+        &self,
+        exact_key: &str,
+        semantic_query: &str,
+        tenant_id: u64,
+        options: LookupOptions,
+    ) -> L2CacheResult<TieredLookupResult> {
+        self.inner
+            .read()
+            .await
+            .lookup_with_options(exact_key, semantic_query, tenant_id, options)
+            .await
+    }
+
     /// Returns the number of strong references to the underlying handle.
     pub fn strong_count(&self) -> usize {
         Arc::strong_count(&self.inner)
@@ -1,5 +1,5 @@
 use super::tiered::{TieredCache, TieredCacheHandle, TieredLookupResult};
-use super::types::ReflexStatus;
+use super::types::{LookupOptions, ReflexStatus};
 use crate::storage::CacheEntry;
 use crate::storage::mmap::MmapFileHandle;
 
@@ -78,10 +78,12 @@ async fn test_mock_tiered_cache_l2_hit() {
     let cache = TieredCache::new_mock().await.expect("should create cache");
 
     let entry = CacheEntry {
+        format: CacheEntry::FORMAT,
         tenant_id: 1000,
         context_hash: 2000,
         timestamp: 1702500000,
         embedding: vec![0u8; crate::constants::EMBEDDING_F16_BYTES],
+        verification_text: String::new(),
         payload_blob: vec![0xDE, 0xAD],
     };
 
@@ -130,10 +132,12 @@ async fn test_mock_tiered_cache_l1_takes_priority() {
     let cache = TieredCache::new_mock().await.expect("should create cache");
 
     let entry = CacheEntry {
+        format: CacheEntry::FORMAT,
         tenant_id: 1000,
         context_hash: 2000,
         timestamp: 1702500000,
         embedding: vec![0u8; crate::constants::EMBEDDING_F16_BYTES],
+        verification_text: String::new(),
         payload_blob: vec![],
     };
     cache.mock_storage().insert("storage_key", entry);
@@ -206,3 +210,278 @@ async fn test_tiered_cache_handle_debug() {
     assert!(debug_str.contains("TieredCacheHandle"));
     assert!(debug_str.contains("strong_count"));
 }
+
+#[test]
+fn test_lookup_options_freshness() {
+    let options = LookupOptions::default();
+    assert!(options.is_fresh(0, 1_000_000));
+
+    let options = LookupOptions::default().max_age(60);
+    assert!(options.is_fresh(1000, 1060));
+    assert!(!options.is_fresh(1000, 1061));
+    assert!(options.is_fresh(2000, 1000));
+}
+
+#[tokio::test]
+async fn test_mock_tiered_cache_max_age_filters_l2_candidates() {
+    let cache = TieredCache::new_mock().await.expect("should create cache");
+
+    let entry = CacheEntry {
+        format: CacheEntry::FORMAT,
+        tenant_id: 1000,
+        context_hash: 2000,
+        timestamp: 1702500000,
+        embedding: vec![0u8; crate::constants::EMBEDDING_F16_BYTES],
+        verification_text: String::new(),
+        payload_blob: vec![0xDE, 0xAD],
+    };
+    cache.mock_storage().insert("storage_key_1", entry);
+    cache
+        .index_l2("Old question", 1000, 2000, "storage_key_1", 1702500000)
+        .await
+        .expect("should index");
+
+    let stale = cache
+        .lookup_with_options(
+            "Old question",
+            "Old question",
+            1000,
+            LookupOptions::default().max_age(3600),
+        )
+        .await
+        .expect("lookup should succeed");
+    assert!(!stale.is_hit());
+
+    // Without options the semantic lookup is unconstrained.
+    let unbounded = cache
+        .lookup_with_semantic_query("Old question", "Old question", 1000)
+        .await
+        .expect("lookup should succeed");
+    assert!(unbounded.is_l2_hit());
+}
+
+#[tokio::test]
+async fn test_mock_tiered_cache_top_k_caps_l2_candidates() {
+    let cache = TieredCache::new_mock().await.expect("should create cache");
+
+    for (i, key) in ["storage_key_1", "storage_key_2", "storage_key_3"]
+        .iter()
+        .enumerate()
+    {
+        let entry = CacheEntry {
+            format: CacheEntry::FORMAT,
+            tenant_id: 1000,
+            context_hash: 2000 + i as u64,
+            timestamp: 1702500000,
+            embedding: vec![0u8; crate::constants::EMBEDDING_F16_BYTES],
+            verification_text: String::new(),
+            payload_blob: vec![0xDE, 0xAD],
+        };
+        cache.mock_storage().insert(key, entry);
+        cache
+            .index_l2("Shared question", 1000, 2000 + i as u64, key, 1702500000)
+            .await
+            .expect("should index");
+    }
+
+    let lookup =
+        |options| cache.lookup_with_options("Shared question", "Shared question", 1000, options);
+    let TieredLookupResult::HitL2(all) = lookup(LookupOptions::default()).await.unwrap() else {
+        panic!("expected an L2 hit");
+    };
+    let TieredLookupResult::HitL2(capped) =
+        lookup(LookupOptions::default().top_k(1)).await.unwrap()
+    else {
+        panic!("expected an L2 hit");
+    };
+
+    assert!(all.candidates().len() > 1);
+    assert_eq!(capped.candidates().len(), 1);
+}
+
+#[tokio::test]
+async fn test_mock_tiered_cache_scope_limits_l2_candidates() {
+    use crate::vectordb::{VectorPoint, WriteConsistency, generate_point_id};
+
+    let cache = TieredCache::new_mock().await.expect("should create cache");
+    let l2 = cache.l2();
+    let vector: Vec<f32> = l2
+        .embedder()
+        .embed("Shared question")
+        .expect("should embed")
+        .iter()
+        .map(|v| v.to_f32())
+        .collect();
+
+    for (i, scope) in [Some(7), Some(8), None].into_iter().enumerate() {
+        let context_hash = 2000 + i as u64;
+        let key = format!("storage_key_{}", i);
+        let entry = CacheEntry {
+            format: CacheEntry::FORMAT,
+            tenant_id: 1000,
+            context_hash,
+            timestamp: 1702500000,
+            embedding: vec![0u8; crate::constants::EMBEDDING_F16_BYTES],
+            verification_text: String::new(),
+            payload_blob: vec![0xDE, 0xAD],
+        };
+        cache.mock_storage().insert(&key, entry);
+        let mut point = VectorPoint::new(
+            generate_point_id(1000, context_hash),
+            vector.clone(),
+            1000,
+            context_hash,
+        )
+        .with_storage_key(key);
+        point.scope_hash = scope;
+        l2.bq_backend()
+            .upsert_points(
+                &l2.config().collection_name,
+                vec![point],
+                WriteConsistency::Strong,
+            )
+            .await
+            .expect("should index");
+    }
+
+    let lookup = |options| cache.lookup_with_options("other key", "Shared question", 1000, options);
+    let TieredLookupResult::HitL2(all) = lookup(LookupOptions::default()).await.unwrap() else {
+        panic!("expected an L2 hit");
+    };
+    assert_eq!(all.candidates().len(), 3);
+    let TieredLookupResult::HitL2(scoped) =
+        lookup(LookupOptions::default().scope(8)).await.unwrap()
+    else {
+        panic!("expected an L2 hit");
+    };
+    let hashes: Vec<_> = scoped
+        .candidates()
+        .iter()
+        .map(|c| c.entry.context_hash)
+        .collect();
+    assert_eq!(hashes, vec![2001]);
+    assert!(matches!(
+        lookup(LookupOptions::default().scope(9)).await.unwrap(),
+        TieredLookupResult::Miss
+    ));
+}
+
+#[tokio::test]
+async fn test_mock_tiered_cache_exact_only_skips_l2() {
+    let cache = TieredCache::new_mock().await.expect("should create cache");
+
+    let entry = CacheEntry {
+        format: CacheEntry::FORMAT,
+        tenant_id: 1000,
+        context_hash: 2000,
+        timestamp: 1702500000,
+        embedding: vec![0u8; crate::constants::EMBEDDING_F16_BYTES],
+        verification_text: String::new(),
+        payload_blob: vec![0xDE, 0xAD],
+    };
+    cache.mock_storage().insert("storage_key", entry);
+    cache
+        .index_l2("Shared question", 1000, 2000, "storage_key", 1702500000)
+        .await
+        .expect("should index");
+
+    let lookup = |options| cache.lookup_with_options("other key", "Shared question", 1000, options);
+    assert!(lookup(LookupOptions::default()).await.unwrap().is_l2_hit());
+    assert!(matches!(
+        lookup(LookupOptions::default().exact_only()).await.unwrap(),
+        TieredLookupResult::Miss
+    ));
+}
+
+#[tokio::test]
+async fn test_mock_tiered_cache_max_age_skips_stale_l1_entry() {
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    let cache = TieredCache::new_mock().await.expect("should create cache");
+
+    let entry = CacheEntry {
+        format: CacheEntry::FORMAT,
+        tenant_id: 1000,
+        context_hash: 2000,
+        timestamp: 1702500000,
+        embedding: vec![],
+        verification_text: String::new(),
+        payload_blob: vec![],
+    };
+    let bytes = rkyv::to_bytes::<rkyv::rancor::Error>(&entry).expect("serialize");
+    let mut file = NamedTempFile::new().expect("create temp file");
+    file.write_all(&bytes).expect("write");
+    file.flush().expect("flush");
+    cache.insert_l1(
+        "exact key",
+        1000,
+        MmapFileHandle::open(file.path()).expect("open mmap"),
+    );
+
+    let fresh_enough = cache
+        .lookup_with_options(
+            "exact key",
+            "exact key",
+            1000,
+            LookupOptions::default().max_age(u64::MAX),
+        )
+        .await
+        .expect("lookup should succeed");
+    assert!(fresh_enough.is_l1_hit());
+
+    let stale = cache
+        .lookup_with_options(
+            "exact key",
+            "exact key",
+            1000,
+            LookupOptions::default().max_age(60),
+        )
+        .await
+        .expect("lookup should succeed");
+    assert!(!stale.is_l1_hit());
+}
+
+#[tokio::test]
+async fn test_remove_l1_where_matches_archived_entries() {
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    let cache = TieredCache::new_mock().await.expect("should create cache");
+    let mut files = Vec::new();
+
+    for (prompt, tenant_id) in [("a", 1u64), ("b", 1), ("c", 2)] {
+        let entry = CacheEntry {
+            format: CacheEntry::FORMAT,
+            tenant_id,
+            context_hash: 0,
+            timestamp: 0,
+            embedding: vec![],
+            verification_text: String::new(),
+            payload_blob: vec![],
+        };
+        let bytes = rkyv::to_bytes::<rkyv::rancor::Error>(&entry).expect("serialize");
+        let mut file = NamedTempFile::new().expect("create temp file");
+        file.write_all(&bytes).expect("write");
+        file.flush().expect("flush");
+        cache.insert_l1(
+            prompt,
+            tenant_id,
+            MmapFileHandle::open(file.path()).expect("open mmap"),
+        );
+        files.push(file);
+    }
+    cache.run_pending_tasks_l1();
+
+    let mut tenants = Vec::new();
+    cache.for_each_l1_entry(|entry| tenants.push(entry.tenant_id.to_native()));
+    tenants.sort_unstable();
+    assert_eq!(tenants, vec![1, 1, 2]);
+
+    let removed = cache.remove_l1_where(|entry| entry.tenant_id == 1);
+    assert_eq!(removed, 2);
+
+    cache.run_pending_tasks_l1();
+    assert_eq!(cache.l1_len(), 1);
+    assert!(cache.contains_l1("c", 2));
+}
@@ -13,6 +13,9 @@ pub const REFLEX_STATUS_ERROR: &str = "error";
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 /// High-level cache status used for metrics and response headers.
+///
+/// New kinds of hit may be added, so matches need a wildcard arm.
+#[non_exhaustive]
 pub enum ReflexStatus {
     /// L1 exact-match hit.
     HitL1Exact,
@@ -20,6 +23,10 @@ pub enum ReflexStatus {
     HitL2Semantic,
     /// L3 verified hit.
     HitL3Verified,
+    /// Served by a concurrent identical request's provider call.
+    HitInflight,
+    /// Looser-threshold semantic hit served while the provider is unavailable.
+    HitDegraded,
     /// Cache miss.
     Miss,
 }
@@ -32,6 +39,8 @@ impl ReflexStatus {
             ReflexStatus::HitL1Exact => "HIT_L1_EXACT",
             ReflexStatus::HitL2Semantic => "HIT_L2_SEMANTIC",
             ReflexStatus::HitL3Verified => "HIT_L3_VERIFIED",
+            ReflexStatus::HitInflight => "HIT_INFLIGHT",
+            ReflexStatus::HitDegraded => "HIT_DEGRADED",
             ReflexStatus::Miss => "MISS",
         }
     }
@@ -48,3 +57,58 @@ impl std::fmt::Display for ReflexStatus {
         write!(f, "{}", self.as_header_value())
     }
 }
+
+/// Per-request constraints for a tiered lookup.
+///
+/// The default imposes no constraints, matching [`TieredCache::lookup`](super::TieredCache::lookup).
+/// Build from the default with the setters, e.g.
+/// `LookupOptions::default().max_age(60).top_k(4)`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct LookupOptions {
+    /// Reject entries whose `timestamp` is more than this many seconds old.
+    pub max_age_secs: Option<u64>,
+    /// Keep at most this many L2 candidates (best first) for verification.
+    pub top_k: Option<usize>,
+    /// Serve only exact (L1) hits; the semantic tier is not searched.
+    pub exact_only: bool,
+    /// Search only L2 points indexed with this scope
+    /// ([`VectorPoint::scope_hash`](crate::vectordb::VectorPoint::scope_hash)).
+    pub scope_hash: Option<u64>,
+}
+
+impl LookupOptions {
+    /// Sets the maximum acceptable entry age in seconds.
+    pub fn max_age(mut self, secs: u64) -> Self {
+        self.max_age_secs = Some(secs);
+        self
+    }
+
+    /// Caps the number of L2 candidates handed to verification.
+    pub fn top_k(mut self, top_k: usize) -> Self {
+        self.top_k = Some(top_k);
+        self
+    }
+
+    /// Skips the semantic tier, so only exact repeats hit.
+    pub fn exact_only(mut self) -> Self {
+        self.exact_only = true;
+        self
+    }
+
+    /// Limits the semantic tier to points indexed with `scope_hash`.
+    pub fn scope(mut self, scope_hash: u64) -> Self {
+        self.scope_hash = Some(scope_hash);
+        self
+    }
+
+    /// Returns `true` if an entry written at `timestamp` satisfies these options at `now`.
+    ///
+    /// Both values are Unix seconds. Entries from the future count as age zero.
+    pub fn is_fresh(&self, timestamp: i64, now: i64) -> bool {
+        match self.max_age_secs {
+            Some(max_age) => (now.saturating_sub(timestamp)).max(0) as u64 <= max_age,
+            None => true,
+        }
+    }
+}
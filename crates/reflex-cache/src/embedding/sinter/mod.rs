@@ -176,6 +176,22 @@ impl SinterEmbedder {
         }
     }
 
+    /// Counts the tokens the embedder would see for `text`.
+    ///
+    /// Uses the loaded tokenizer (special tokens included, before truncation to
+    /// `max_seq_len`). Stub mode has no tokenizer and counts whitespace-separated words.
+    pub fn count_tokens(&self, text: &str) -> Result<usize, EmbeddingError> {
+        match &self.backend {
+            EmbedderBackend::Model { tokenizer, .. } => tokenizer
+                .encode(text, true)
+                .map(|encoding| encoding.len())
+                .map_err(|e| EmbeddingError::TokenizationFailed {
+                    reason: e.to_string(),
+                }),
+            EmbedderBackend::Stub { .. } => Ok(text.split_whitespace().count()),
+        }
+    }
+
     fn embed_with_model(
         &self,
         text: &str,
@@ -761,6 +761,14 @@ mod embedder_tests {
         assert_eq!(emb.len(), SINTER_EMBEDDING_DIM);
     }
 
+    #[test]
+    fn test_sinter_count_tokens_stub() {
+        let embedder = SinterEmbedder::load(SinterConfig::stub()).expect("Should load");
+
+        assert_eq!(embedder.count_tokens("").expect("count"), 0);
+        assert_eq!(embedder.count_tokens("one two  three").expect("count"), 3);
+    }
+
     #[test]
     fn test_sinter_embed_stub_normalized() {
         let config = SinterConfig::stub();
@@ -0,0 +1,40 @@
+use thiserror::Error;
+
+use crate::cache::L2CacheError;
+use crate::embedding::EmbeddingError;
+use crate::scoring::ScoringError;
+use crate::storage::StorageError;
+
+#[derive(Debug, Error)]
+/// Errors returned by [`Reflex`](super::Reflex).
+pub enum ReflexError {
+    /// Tiered lookup or vector index error.
+    #[error("cache error: {0}")]
+    Cache(#[from] L2CacheError),
+
+    /// L3 verification failed.
+    #[error("scoring error: {0}")]
+    Scoring(#[from] ScoringError),
+
+    /// Embedding the semantic text failed.
+    #[error("embedding error: {0}")]
+    Embedding(#[from] EmbeddingError),
+
+    /// Writing the entry to storage, or encoding its payload, failed.
+    #[error("storage error: {0}")]
+    Storage(#[from] StorageError),
+
+    /// The entry could not be serialized.
+    #[error("serialization failed: {reason}")]
+    Serialization {
+        /// Error message.
+        reason: String,
+    },
+
+    /// The `get_or_compute` closure failed; nothing was stored.
+    #[error("compute failed: {0}")]
+    Compute(Box<dyn std::error::Error + Send + Sync>),
+}
+
+/// Convenience result type for facade operations.
+pub type ReflexResult<T> = Result<T, ReflexError>;
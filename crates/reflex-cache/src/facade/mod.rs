@@ -0,0 +1,25 @@
+//! High-level cache facade for embedded use.
+//!
+//! [`Reflex`] runs the full L1 → L2 → L3 pipeline behind three calls:
+//! [`lookup`](Reflex::lookup), [`store`](Reflex::store) and
+//! [`get_or_compute`](Reflex::get_or_compute). It wraps a [`TieredCache`](crate::TieredCache)
+//! whose storage is also a [`StorageWriter`](crate::storage::StorageWriter), and a
+//! [`CrossEncoderScorer`](crate::CrossEncoderScorer) for verification.
+//!
+//! Each entry is stored under an exact `key` (L1) and embedded from a
+//! `semantic_text` (L2). The value can be any
+//! [`Payload`](crate::storage::Payload); L3 compares the query's semantic text
+//! with the one stored as each candidate's
+//! [`verification_text`](crate::CacheEntry::verification_text), never with the
+//! cached value itself.
+
+/// Facade errors.
+pub mod error;
+/// The [`Reflex`] facade.
+pub mod reflex;
+
+#[cfg(test)]
+mod tests;
+
+pub use error::{ReflexError, ReflexResult};
+pub use reflex::{Cached, Reflex};
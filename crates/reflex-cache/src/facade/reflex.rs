@@ -0,0 +1,364 @@
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Instant;
+
+use tracing::{debug, info, instrument, warn};
+
+use super::error::{ReflexError, ReflexResult};
+use crate::cache::{
+    BqSearchBackend, LookupOptions, ReflexStatus, StorageLoader, TieredCache, TieredLookupResult,
+};
+use crate::scoring::{CrossEncoderScorer, VerificationResult};
+use crate::storage::{
+    ArchivedCacheEntry, ArchivedPayload, CacheEntry, Payload, StorageError, StorageWriter,
+    encode_payload, storage_key,
+};
+use crate::vectordb::{VectorPoint, WriteConsistency, generate_point_id};
+
+/// A cached value and where it came from.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cached<T = Vec<u8>> {
+    /// The value as it was stored.
+    pub value: T,
+    /// `HitL1Exact` or `HitL3Verified` for hits; `Miss` for a value
+    /// [`get_or_compute`](Reflex::get_or_compute) just computed and stored.
+    pub status: ReflexStatus,
+    /// Storage key of the entry; see [`storage_key`].
+    pub storage_key: String,
+    /// Unix seconds when the entry was stored.
+    pub stored_at: i64,
+}
+
+/// Embeddable L1/L2/L3 cache: lookup, store and get-or-compute.
+///
+/// Values are any `rkyv` archivable type (see [`Payload`]); each entry also
+/// keeps its semantic text, which L3 verifies against.
+///
+/// # Example
+/// ```rust,no_run
+/// # #[cfg(feature = "mock")]
+/// # async fn run() -> reflex::ReflexResult<()> {
+/// use reflex::{CrossEncoderScorer, MockTieredCache, Reflex};
+///
+/// let cache = MockTieredCache::new_mock().await?;
+/// let reflex = Reflex::new(cache, CrossEncoderScorer::stub()?);
+///
+/// let answer = reflex
+///     .get_or_compute("What is Rust?", "What is Rust?", 1, || async {
+///         Ok::<_, std::io::Error>("A systems programming language.".to_string())
+///     })
+///     .await?;
+/// println!("{:?}", answer.status);
+/// # Ok(())
+/// # }
+/// ```
+pub struct Reflex<B, S>
+where
+    B: BqSearchBackend,
+    S: StorageLoader + StorageWriter,
+{
+    cache: Arc<TieredCache<B, S>>,
+    scorer: Arc<CrossEncoderScorer>,
+    threshold: Option<f32>,
+}
+
+impl<B, S> Clone for Reflex<B, S>
+where
+    B: BqSearchBackend,
+    S: StorageLoader + StorageWriter,
+{
+    fn clone(&self) -> Self {
+        Self {
+            cache: Arc::clone(&self.cache),
+            scorer: Arc::clone(&self.scorer),
+            threshold: self.threshold,
+        }
+    }
+}
+
+impl<B, S> std::fmt::Debug for Reflex<B, S>
+where
+    B: BqSearchBackend,
+    S: StorageLoader + StorageWriter,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Reflex")
+            .field("cache", &self.cache)
+            .field("scorer", &self.scorer)
+            .field("threshold", &self.threshold)
+            .finish()
+    }
+}
+
+impl<B, S> Reflex<B, S>
+where
+    B: BqSearchBackend,
+    S: StorageLoader + StorageWriter + Clone + 'static,
+{
+    /// Creates a facade over `cache`, verifying semantic hits with `scorer`.
+    pub fn new(cache: TieredCache<B, S>, scorer: CrossEncoderScorer) -> Self {
+        Self::from_shared(Arc::new(cache), Arc::new(scorer))
+    }
+
+    /// Creates a facade over a cache and scorer that are shared elsewhere.
+    pub fn from_shared(cache: Arc<TieredCache<B, S>>, scorer: Arc<CrossEncoderScorer>) -> Self {
+        Self {
+            cache,
+            scorer,
+            threshold: None,
+        }
+    }
+
+    /// Verifies semantic hits against `threshold` instead of the scorer's.
+    pub fn with_threshold(mut self, threshold: f32) -> Self {
+        self.threshold = Some(threshold);
+        self
+    }
+
+    /// Returns the tiered cache.
+    pub fn cache(&self) -> &TieredCache<B, S> {
+        &self.cache
+    }
+
+    /// Returns the L3 scorer.
+    pub fn scorer(&self) -> &CrossEncoderScorer {
+        &self.scorer
+    }
+
+    /// Returns the L3 threshold in effect.
+    pub fn threshold(&self) -> f32 {
+        self.threshold.unwrap_or_else(|| self.scorer.threshold())
+    }
+
+    /// Looks up `key` in L1, then `semantic_text` in L2, verifying L2
+    /// candidates with L3.
+    pub async fn lookup<T>(
+        &self,
+        key: &str,
+        semantic_text: &str,
+        tenant_id: u64,
+    ) -> ReflexResult<Option<Cached<T>>>
+    where
+        T: Payload,
+        T::Archived: ArchivedPayload<T>,
+    {
+        self.lookup_with_options(key, semantic_text, tenant_id, LookupOptions::default())
+            .await
+    }
+
+    /// Like [`lookup`](Self::lookup), constrained by `options`.
+    ///
+    /// Entries whose payload is not a `T` are skipped.
+    #[instrument(skip(self, key, semantic_text), fields(tenant_id = tenant_id))]
+    pub async fn lookup_with_options<T>(
+        &self,
+        key: &str,
+        semantic_text: &str,
+        tenant_id: u64,
+        options: LookupOptions,
+    ) -> ReflexResult<Option<Cached<T>>>
+    where
+        T: Payload,
+        T::Archived: ArchivedPayload<T>,
+    {
+        let result = self
+            .cache
+            .lookup_with_options(key, semantic_text, tenant_id, options)
+            .await?;
+
+        match result {
+            TieredLookupResult::HitL1(l1_result) => {
+                let Ok(entry) = l1_result.handle().access_archived::<ArchivedCacheEntry>() else {
+                    warn!("L1 entry is not a valid cache entry, treating as miss");
+                    return Ok(None);
+                };
+                let value = match entry.payload::<T>() {
+                    Ok(value) => value,
+                    Err(e) => {
+                        warn!(error = %e, "L1 payload is not the requested type, treating as miss");
+                        return Ok(None);
+                    }
+                };
+                Ok(Some(Cached {
+                    value,
+                    status: ReflexStatus::HitL1Exact,
+                    storage_key: storage_key(
+                        entry.tenant_id.to_native(),
+                        entry.context_hash.to_native(),
+                    ),
+                    stored_at: entry.timestamp.to_native(),
+                }))
+            }
+            TieredLookupResult::HitL2(l2_result) => {
+                let mut values = Vec::new();
+                let mut candidates = Vec::new();
+                for c in l2_result.candidates() {
+                    match c.entry.payload::<T>() {
+                        Ok(value) => {
+                            values.push((c.entry.context_hash, value));
+                            candidates.push((c.entry.clone(), c.score));
+                        }
+                        Err(e) => debug!(error = %e, "Skipping L2 candidate of another type"),
+                    }
+                }
+
+                let (scored, verification) =
+                    self.scorer.verify_candidates_with_details_and_threshold(
+                        semantic_text,
+                        candidates,
+                        self.threshold(),
+                    )?;
+                let VerificationResult::Verified { score } = verification else {
+                    debug!(%verification, "L3 verification did not pass");
+                    return Ok(None);
+                };
+                let Some(best) = scored.into_iter().next() else {
+                    return Ok(None);
+                };
+                let Some((_, value)) = values
+                    .into_iter()
+                    .find(|(context_hash, _)| *context_hash == best.entry.context_hash)
+                else {
+                    return Ok(None);
+                };
+                info!(score, "L3 verification passed");
+                Ok(Some(Cached {
+                    value,
+                    status: ReflexStatus::HitL3Verified,
+                    storage_key: storage_key(best.entry.tenant_id, best.entry.context_hash),
+                    stored_at: best.entry.timestamp,
+                }))
+            }
+            TieredLookupResult::Miss => Ok(None),
+        }
+    }
+
+    /// Stores `value` under `key` and `semantic_text`, replacing any entry
+    /// with the same key. Returns the entry's storage key.
+    ///
+    /// The entry is written to storage, inserted into L1 and indexed for L2
+    /// before this returns: the upsert waits until the point is searchable.
+    /// `semantic_text` is what L3 verifies later queries against.
+    pub async fn store<T: Payload>(
+        &self,
+        key: &str,
+        semantic_text: &str,
+        tenant_id: u64,
+        value: &T,
+    ) -> ReflexResult<String> {
+        self.store_at(key, semantic_text, tenant_id, value, unix_now())
+            .await
+    }
+
+    /// Looks up `key` and `semantic_text`; on a miss, stores and returns the
+    /// value `compute` produces.
+    ///
+    /// A failed `compute` stores nothing and returns [`ReflexError::Compute`].
+    pub async fn get_or_compute<T, F, Fut, E>(
+        &self,
+        key: &str,
+        semantic_text: &str,
+        tenant_id: u64,
+        compute: F,
+    ) -> ReflexResult<Cached<T>>
+    where
+        T: Payload,
+        T::Archived: ArchivedPayload<T>,
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<T, E>>,
+        E: Into<Box<dyn std::error::Error + Send + Sync>>,
+    {
+        if let Some(hit) = self.lookup(key, semantic_text, tenant_id).await? {
+            return Ok(hit);
+        }
+
+        let value = compute()
+            .await
+            .map_err(|e| ReflexError::Compute(e.into()))?;
+        let stored_at = unix_now();
+        let storage_key = self
+            .store_at(key, semantic_text, tenant_id, &value, stored_at)
+            .await?;
+        Ok(Cached {
+            value,
+            status: ReflexStatus::Miss,
+            storage_key,
+            stored_at,
+        })
+    }
+
+    async fn store_at<T: Payload>(
+        &self,
+        key: &str,
+        semantic_text: &str,
+        tenant_id: u64,
+        value: &T,
+        timestamp: i64,
+    ) -> ReflexResult<String> {
+        let l2 = self.cache.l2();
+
+        let started = Instant::now();
+        let embedding_f16 = l2.embedder().embed(semantic_text)?;
+        crate::telemetry::record_stage(crate::telemetry::STAGE_EMBEDDING, started);
+
+        let context_hash = crate::hashing::hash_to_u64(key.as_bytes());
+        let entry = CacheEntry {
+            format: CacheEntry::FORMAT,
+            tenant_id,
+            context_hash,
+            timestamp,
+            embedding: embedding_f16.iter().flat_map(|v| v.to_le_bytes()).collect(),
+            verification_text: semantic_text.to_string(),
+            payload_blob: encode_payload(value)?,
+        };
+        let bytes = rkyv::to_bytes::<rkyv::rancor::Error>(&entry).map_err(|e| {
+            ReflexError::Serialization {
+                reason: e.to_string(),
+            }
+        })?;
+
+        let storage_key = storage_key(tenant_id, context_hash);
+        let storage = l2.storage().clone();
+        let write_key = storage_key.clone();
+        let handle = tokio::task::spawn_blocking(move || storage.write(&write_key, bytes.as_ref()))
+            .await
+            .map_err(|e| {
+                StorageError::WriteFailed(format!("storage write task failed: {}", e))
+            })??;
+        self.cache.insert_l1(key, tenant_id, handle);
+
+        let config = l2.config();
+        let point = VectorPoint {
+            id: generate_point_id(tenant_id, context_hash),
+            vector: embedding_f16.iter().map(|v| v.to_f32()).collect(),
+            tenant_id,
+            context_hash,
+            timestamp,
+            storage_key: Some(storage_key.clone()),
+            scope_hash: None,
+        };
+        let backend = l2.bq_backend();
+        backend
+            .ensure_collection(&config.collection_name, config.vector_size)
+            .await
+            .map_err(crate::cache::L2CacheError::from)?;
+        backend
+            .upsert_points(
+                &config.collection_name,
+                vec![point],
+                WriteConsistency::Strong,
+            )
+            .await
+            .map_err(crate::cache::L2CacheError::from)?;
+
+        debug!(storage_key = %storage_key, "Stored entry");
+        Ok(storage_key)
+    }
+}
+
+fn unix_now() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or_default()
+}
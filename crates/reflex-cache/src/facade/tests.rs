@@ -0,0 +1,175 @@
+use super::*;
+use crate::cache::{MockTieredCache, ReflexStatus};
+use crate::scoring::CrossEncoderScorer;
+use crate::storage::CacheEntry;
+use rkyv::{Archive, Deserialize, Serialize};
+
+const PROMPT: &str = "How to implement a binary tree in Rust. This includes struct definitions, insert methods, and traversal logic for a complete implementation.";
+const NEIGHBOUR: &str = "How do I implement a binary tree in Rust? I need struct definitions, insert methods, and traversal logic.";
+const TENANT: u64 = 1000;
+
+#[derive(Archive, Serialize, Deserialize, Debug, Clone, PartialEq)]
+struct Answer {
+    text: String,
+    tokens: u32,
+}
+
+fn answer() -> Answer {
+    Answer {
+        text: "Use a Box<Node> for each child.".to_string(),
+        tokens: 8,
+    }
+}
+
+async fn reflex() -> Reflex<crate::vectordb::bq::MockBqClient, crate::cache::MockStorageLoader> {
+    let cache = MockTieredCache::new_mock().await.expect("mock cache");
+    Reflex::new(cache, CrossEncoderScorer::stub().expect("stub scorer"))
+}
+
+#[tokio::test]
+async fn test_lookup_misses_on_empty_cache() {
+    let reflex = reflex().await;
+    let hit = reflex
+        .lookup::<Answer>(PROMPT, PROMPT, TENANT)
+        .await
+        .unwrap();
+    assert!(hit.is_none());
+}
+
+#[tokio::test]
+async fn test_store_then_exact_lookup_hits_l1() {
+    let reflex = reflex().await;
+    let storage_key = reflex
+        .store("key-1", PROMPT, TENANT, &answer())
+        .await
+        .unwrap();
+    assert!(storage_key.starts_with("1000/"));
+    assert_eq!(reflex.cache().mock_storage().len(), 1);
+
+    let hit = reflex
+        .lookup::<Answer>("key-1", PROMPT, TENANT)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(hit.value, answer());
+    assert_eq!(hit.status, ReflexStatus::HitL1Exact);
+    assert_eq!(hit.storage_key, storage_key);
+}
+
+#[tokio::test]
+async fn test_semantic_neighbour_is_verified_by_l3() {
+    let reflex = reflex().await;
+    reflex
+        .store("key-1", PROMPT, TENANT, &answer())
+        .await
+        .unwrap();
+
+    let hit = reflex
+        .lookup::<Answer>("key-2", NEIGHBOUR, TENANT)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(hit.value, answer());
+    assert_eq!(hit.status, ReflexStatus::HitL3Verified);
+}
+
+#[tokio::test]
+async fn test_threshold_above_any_score_rejects_neighbours() {
+    let reflex = reflex().await.with_threshold(1.0);
+    reflex
+        .store("key-1", PROMPT, TENANT, &answer())
+        .await
+        .unwrap();
+
+    assert!(
+        reflex
+            .lookup::<Answer>("key-2", NEIGHBOUR, TENANT)
+            .await
+            .unwrap()
+            .is_none()
+    );
+}
+
+#[tokio::test]
+async fn test_tenants_do_not_share_entries() {
+    let reflex = reflex().await;
+    reflex
+        .store("key-1", PROMPT, TENANT, &answer())
+        .await
+        .unwrap();
+
+    assert!(
+        reflex
+            .lookup::<Answer>("key-1", PROMPT, TENANT + 1)
+            .await
+            .unwrap()
+            .is_none()
+    );
+}
+
+#[tokio::test]
+async fn test_get_or_compute_computes_once() {
+    let reflex = reflex().await;
+    let calls = std::sync::atomic::AtomicUsize::new(0);
+    let compute = || async {
+        calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        Ok::<_, std::io::Error>(b"computed".to_vec())
+    };
+
+    let first = reflex
+        .get_or_compute("key-1", PROMPT, TENANT, compute)
+        .await
+        .unwrap();
+    assert_eq!(first.status, ReflexStatus::Miss);
+    assert_eq!(first.value, b"computed");
+
+    let second = reflex
+        .get_or_compute("key-1", PROMPT, TENANT, compute)
+        .await
+        .unwrap();
+    assert_eq!(second.status, ReflexStatus::HitL1Exact);
+    assert_eq!(second.value, b"computed");
+    assert_eq!(second.storage_key, first.storage_key);
+    assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn test_failed_compute_stores_nothing() {
+    let reflex = reflex().await;
+    let result = reflex
+        .get_or_compute("key-1", PROMPT, TENANT, || async {
+            Err::<Answer, _>(std::io::Error::other("provider down"))
+        })
+        .await;
+
+    assert!(matches!(result, Err(ReflexError::Compute(_))));
+    assert!(reflex.cache().mock_storage().is_empty());
+}
+
+#[tokio::test]
+async fn test_entries_of_another_type_are_skipped() {
+    let reflex = reflex().await;
+    let entry = CacheEntry {
+        format: CacheEntry::FORMAT,
+        tenant_id: TENANT,
+        context_hash: 2000,
+        timestamp: 1702500000,
+        embedding: vec![0u8; crate::constants::EMBEDDING_F16_BYTES],
+        verification_text: PROMPT.to_string(),
+        payload_blob: vec![0xDE, 0xAD],
+    };
+    reflex.cache().mock_storage().insert("storage_key_1", entry);
+    reflex
+        .cache()
+        .index_l2(PROMPT, TENANT, 2000, "storage_key_1", 1702500000)
+        .await
+        .unwrap();
+
+    assert!(
+        reflex
+            .lookup::<Answer>("key-2", NEIGHBOUR, TENANT)
+            .await
+            .unwrap()
+            .is_none()
+    );
+}
@@ -33,9 +33,11 @@
 //!
 //! - [`cache`] - Tiered cache (L1 exact + L2 semantic)
 //! - [`config`] - Environment-backed configuration
+//! - [`facade`] - [`Reflex`]: lookup, store and get-or-compute over all tiers
 //! - [`embedding`] - Embedding + reranker models
 //! - [`scoring`] - L3 verification (cross-encoder)
 //! - [`storage`] - Persistent cache entry storage
+//! - [`telemetry`] - Metric names recorded through the `metrics` facade
 //! - [`vectordb`] - Qdrant + binary quantization utilities
 //!
 //! Links: repo/issues at the crate `repository` URL.
@@ -48,11 +50,13 @@ pub mod cache;
 pub mod config;
 pub mod constants;
 pub mod embedding;
+pub mod facade;
 pub mod hashing;
 pub mod lifecycle;
 pub mod payload;
 pub mod scoring;
 pub mod storage;
+pub mod telemetry;
 pub mod vectordb;
 
 pub use cache::{
@@ -70,7 +74,7 @@ pub use cache::{
     REFLEX_STATUS_HEALTHY, REFLEX_STATUS_NOT_READY, REFLEX_STATUS_READY, REFLEX_STATUS_STORED,
     ReflexStatus,
 };
-pub use cache::{TieredCache, TieredCacheHandle, TieredLookupResult};
+pub use cache::{LookupOptions, TieredCache, TieredCacheHandle, TieredLookupResult};
 
 pub use config::{Config, ConfigError};
 pub use constants::{DimConfig, DimValidationError, validate_embedding_dim};
@@ -78,6 +82,7 @@ pub use embedding::{
     DEFAULT_THRESHOLD, EmbeddingError, Reranker, RerankerConfig, RerankerError,
     SINTER_EMBEDDING_DIM, SINTER_MAX_SEQ_LEN, SinterConfig, SinterEmbedder,
 };
+pub use facade::{Cached, Reflex, ReflexError, ReflexResult};
 pub use hashing::{hash_context, hash_prompt, hash_tenant_id, hash_to_u64};
 pub use lifecycle::{
     ActivityRecorder, DEFAULT_IDLE_TIMEOUT_SECS, DEFAULT_SNAPSHOT_FILENAME, DehydrationResult,
@@ -3,7 +3,7 @@
 //! Given a query and candidate `CacheEntry`s, score them with [`Reranker`](crate::embedding::Reranker)
 //! and decide whether the best candidate is above the configured threshold.
 //!
-//! Note: [`CrossEncoderScorer`] treats `CacheEntry::payload_blob` as UTF-8 candidate text.
+//! Candidates are scored on `CacheEntry::verification_text`; the payload is never read.
 
 /// Scoring/verification errors.
 pub mod error;
@@ -59,6 +59,17 @@ impl CrossEncoderScorer {
         &self,
         query: &str,
         candidates: Vec<(CacheEntry, f32)>,
+    ) -> Result<(Option<CacheEntry>, VerificationResult), ScoringError> {
+        self.verify_candidates_with_threshold(query, candidates, self.threshold())
+    }
+
+    /// Like [`verify_candidates`](Self::verify_candidates), but against `threshold`
+    /// instead of the configured one (e.g. a per-tenant override).
+    pub fn verify_candidates_with_threshold(
+        &self,
+        query: &str,
+        candidates: Vec<(CacheEntry, f32)>,
+        threshold: f32,
     ) -> Result<(Option<CacheEntry>, VerificationResult), ScoringError> {
         if candidates.is_empty() {
             debug!("No candidates provided for verification");
@@ -86,18 +97,18 @@ impl CrossEncoderScorer {
         debug!(
             top_score = top.cross_encoder_score,
             original_score = top.original_score,
-            threshold = self.threshold(),
+            threshold = threshold,
             "Top candidate after reranking"
         );
 
         let score = top.cross_encoder_score;
 
-        if score > self.threshold() {
+        if score > threshold {
             let entry = top.entry.clone();
 
             info!(
                 score = score,
-                threshold = self.threshold(),
+                threshold = threshold,
                 "L3 verification passed - cache hit"
             );
 
@@ -105,7 +116,7 @@ impl CrossEncoderScorer {
         } else {
             debug!(
                 score = score,
-                threshold = self.threshold(),
+                threshold = threshold,
                 "Top candidate below threshold - cache miss"
             );
 
@@ -122,8 +133,7 @@ impl CrossEncoderScorer {
         candidates
             .into_iter()
             .map(|(entry, original_score)| {
-                let candidate_text = String::from_utf8_lossy(&entry.payload_blob);
-                let cross_encoder_score = self.reranker.score(query, &candidate_text)?;
+                let cross_encoder_score = self.reranker.score(query, &entry.verification_text)?;
 
                 Ok(VerifiedCandidate::new(
                     entry,
@@ -139,6 +149,20 @@ impl CrossEncoderScorer {
         &self,
         query: &str,
         candidates: Vec<(CacheEntry, f32)>,
+    ) -> Result<(Vec<VerifiedCandidate>, VerificationResult), ScoringError> {
+        self.verify_candidates_with_details_and_threshold(query, candidates, self.threshold())
+    }
+
+    /// Like [`verify_candidates_with_details`](Self::verify_candidates_with_details),
+    /// but against `threshold` instead of the configured one.
+    ///
+    /// The scored candidates are sorted best-first; when the result is
+    /// verified, the first one is the winning entry.
+    pub fn verify_candidates_with_details_and_threshold(
+        &self,
+        query: &str,
+        candidates: Vec<(CacheEntry, f32)>,
+        threshold: f32,
     ) -> Result<(Vec<VerifiedCandidate>, VerificationResult), ScoringError> {
         if candidates.is_empty() {
             return Ok((vec![], VerificationResult::NoCandidates));
@@ -155,7 +179,7 @@ impl CrossEncoderScorer {
         // SAFETY: candidates is non-empty (checked above), and score_candidates
         // maps 1:1, so scored is guaranteed non-empty
         let score = scored[0].cross_encoder_score;
-        let result = if score > self.threshold() {
+        let result = if score > threshold {
             VerificationResult::Verified { score }
         } else {
             VerificationResult::Rejected { top_score: score }
@@ -5,23 +5,27 @@ use crate::cache::ReflexStatus;
 use crate::embedding::{RerankerConfig, RerankerError};
 use crate::storage::CacheEntry;
 
-fn create_test_entry(payload: &str) -> CacheEntry {
+fn create_test_entry(text: &str) -> CacheEntry {
     CacheEntry {
+        format: CacheEntry::FORMAT,
         tenant_id: 1,
         context_hash: 2,
         timestamp: 1702500000,
         embedding: vec![],
-        payload_blob: payload.as_bytes().to_vec(),
+        verification_text: text.to_string(),
+        payload_blob: vec![],
     }
 }
 
-fn create_test_entry_with_context(payload: &str, tenant_id: u64, context_hash: u64) -> CacheEntry {
+fn create_test_entry_with_context(text: &str, tenant_id: u64, context_hash: u64) -> CacheEntry {
     CacheEntry {
+        format: CacheEntry::FORMAT,
         tenant_id,
         context_hash,
         timestamp: 1702500000,
         embedding: vec![],
-        payload_blob: payload.as_bytes().to_vec(),
+        verification_text: text.to_string(),
+        payload_blob: vec![],
     }
 }
 
@@ -110,7 +114,7 @@ fn test_verified_candidate_new() {
 
     assert_eq!(candidate.cross_encoder_score, 0.95);
     assert_eq!(candidate.original_score, 0.85);
-    assert_eq!(candidate.entry.payload_blob, entry.payload_blob);
+    assert_eq!(candidate.entry.verification_text, entry.verification_text);
 }
 
 #[test]
@@ -244,6 +248,42 @@ fn test_verify_candidates_miss_when_below_threshold() {
     assert!(matches!(verification, VerificationResult::Rejected { .. }));
 }
 
+#[test]
+fn test_verify_candidates_with_threshold_overrides_config() {
+    let config = RerankerConfig::stub().with_threshold(0.99);
+    let scorer = CrossEncoderScorer::new(config).unwrap();
+    let candidates = vec![(create_test_entry("anything at all"), 0.50)];
+
+    let (strict, _) = scorer
+        .verify_candidates("What is Rust?", candidates.clone())
+        .unwrap();
+    let (lenient, verification) = scorer
+        .verify_candidates_with_threshold("What is Rust?", candidates, -1.0)
+        .unwrap();
+
+    assert!(strict.is_none());
+    assert!(lenient.is_some());
+    assert!(verification.is_verified());
+}
+
+#[test]
+fn test_verify_candidates_with_details_and_threshold_overrides_config() {
+    let config = RerankerConfig::stub().with_threshold(0.99);
+    let scorer = CrossEncoderScorer::new(config).unwrap();
+    let candidates = vec![(create_test_entry("anything at all"), 0.50)];
+
+    let (_, strict) = scorer
+        .verify_candidates_with_details("What is Rust?", candidates.clone())
+        .unwrap();
+    let (scored, lenient) = scorer
+        .verify_candidates_with_details_and_threshold("What is Rust?", candidates, -1.0)
+        .unwrap();
+
+    assert!(!strict.is_verified());
+    assert!(lenient.is_verified());
+    assert_eq!(lenient.score(), Some(scored[0].cross_encoder_score));
+}
+
 #[test]
 fn test_verify_candidates_sorting() {
     let config = RerankerConfig::stub().with_threshold(0.0);
@@ -418,10 +458,12 @@ fn test_empty_payload_blob() {
     let scorer = CrossEncoderScorer::stub().unwrap();
 
     let entry = CacheEntry {
+        format: CacheEntry::FORMAT,
         tenant_id: 1,
         context_hash: 2,
         timestamp: 1000,
         embedding: vec![],
+        verification_text: String::new(),
         payload_blob: vec![],
     };
 
@@ -501,7 +543,7 @@ fn test_full_l3_verification_flow() {
     if verification.is_verified() {
         assert!(result.is_some());
         let entry = result.unwrap();
-        let text = String::from_utf8_lossy(&entry.payload_blob);
+        let text = &entry.verification_text;
         assert!(text.to_lowercase().contains("rust"));
 
         assert_eq!(verification.to_cache_status(), ReflexStatus::HitL3Verified);
@@ -670,8 +712,8 @@ fn test_verify_candidates_verified_path_explicitly() {
     }
 
     let entry = result.unwrap();
-    let text = String::from_utf8_lossy(&entry.payload_blob);
-    assert!(text.contains("Rust"), "Entry payload should contain 'Rust'");
+    let text = &entry.verification_text;
+    assert!(text.contains("Rust"), "Entry text should contain 'Rust'");
 }
 
 #[test]
@@ -726,15 +768,17 @@ fn test_verify_candidates_with_details_rejected_path() {
 }
 
 #[test]
-fn test_score_candidates_processes_payload_blob() {
+fn test_score_candidates_processes_verification_text() {
     let scorer = CrossEncoderScorer::stub().unwrap();
 
     let entry = CacheEntry {
+        format: CacheEntry::FORMAT,
         tenant_id: 1,
         context_hash: 2,
         timestamp: 1000,
         embedding: vec![],
-        payload_blob: "test payload content".as_bytes().to_vec(),
+        verification_text: "test payload content".to_string(),
+        payload_blob: vec![],
     };
 
     let candidates = vec![(entry, 0.75)];
@@ -822,15 +866,37 @@ fn test_verify_candidates_original_scores_preserved() {
     assert!(original_scores.contains(&0.789));
 }
 
+#[test]
+fn test_score_candidates_ignores_payload_blob() {
+    let scorer = CrossEncoderScorer::stub().unwrap();
+
+    let plain = create_test_entry("How do I reverse a list in Rust?");
+    let with_json = CacheEntry {
+        payload_blob: br#"{"semantic_request":"What is the capital of France?"}"#.to_vec(),
+        ..plain.clone()
+    };
+
+    let scored = scorer
+        .score_candidates(
+            "How do I reverse a list in Rust?",
+            vec![(plain, 0.5), (with_json, 0.5)],
+        )
+        .unwrap();
+
+    assert_eq!(scored[0].cross_encoder_score, scored[1].cross_encoder_score);
+}
+
 #[test]
 fn test_binary_payload_blob() {
     let scorer = CrossEncoderScorer::stub().unwrap();
 
     let entry = CacheEntry {
+        format: CacheEntry::FORMAT,
         tenant_id: 1,
         context_hash: 2,
         timestamp: 1000,
         embedding: vec![],
+        verification_text: String::new(),
         payload_blob: vec![0xFF, 0xFE, 0x00, 0x01],
     };
 
@@ -901,8 +967,8 @@ fn test_verify_candidates_entry_clone() {
     let config = RerankerConfig::stub().with_threshold(0.0);
     let scorer = CrossEncoderScorer::new(config).unwrap();
 
-    let original_payload = "unique test payload 12345";
-    let entry = create_test_entry(original_payload);
+    let original_text = "unique test text 12345";
+    let entry = create_test_entry(original_text);
     let original_tenant_id = entry.tenant_id;
     let original_context_hash = entry.context_hash;
 
@@ -913,10 +979,7 @@ fn test_verify_candidates_entry_clone() {
     let returned_entry = result.expect("Should have entry");
     assert_eq!(returned_entry.tenant_id, original_tenant_id);
     assert_eq!(returned_entry.context_hash, original_context_hash);
-    assert_eq!(
-        String::from_utf8_lossy(&returned_entry.payload_blob),
-        original_payload
-    );
+    assert_eq!(returned_entry.verification_text, original_text);
 }
 
 #[test]
@@ -979,11 +1042,13 @@ fn test_verify_candidates_preserves_entry_fields() {
     let scorer = CrossEncoderScorer::new(config).unwrap();
 
     let entry = CacheEntry {
+        format: CacheEntry::FORMAT,
         tenant_id: 42,
         context_hash: 12345,
         timestamp: 1702500000,
         embedding: vec![1, 2, 3],
-        payload_blob: "test content".as_bytes().to_vec(),
+        verification_text: "test content".to_string(),
+        payload_blob: vec![],
     };
 
     let candidates = vec![(entry, 0.9)];
@@ -1003,7 +1068,7 @@ fn test_verified_candidate_fields() {
 
     assert_eq!(candidate.cross_encoder_score, 0.88);
     assert_eq!(candidate.original_score, 0.77);
-    assert_eq!(candidate.entry.payload_blob, entry.payload_blob);
+    assert_eq!(candidate.entry.verification_text, entry.verification_text);
 }
 
 #[test]
@@ -10,4 +10,12 @@ pub enum StorageError {
     /// Write failed.
     #[error("write failed: {0}")]
     WriteFailed(String),
+
+    /// The store does not implement this operation.
+    #[error("unsupported storage operation: {0}")]
+    Unsupported(String),
+
+    /// A payload could not be encoded, or the bytes are not the expected type.
+    #[error("payload error: {0}")]
+    Payload(String),
 }
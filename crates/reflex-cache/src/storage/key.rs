@@ -0,0 +1,28 @@
+//! Storage keys: where an entry lives relative to the storage root.
+
+/// Storage key of the entry with `tenant_id` and `context_hash`:
+/// `{tenant_id}/{context_hash:016x}.rkyv`.
+pub fn storage_key(tenant_id: u64, context_hash: u64) -> String {
+    format!("{}/{:016x}.rkyv", tenant_id, context_hash)
+}
+
+/// Inverse of [`storage_key`]: `(tenant_id, context_hash)`, or `None` for a
+/// key of any other shape.
+pub fn parse_storage_key(key: &str) -> Option<(u64, u64)> {
+    let (tenant, file) = key.split_once('/')?;
+    let hash = file.strip_suffix(".rkyv")?;
+    Some((tenant.parse().ok()?, u64::from_str_radix(hash, 16).ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_storage_key_round_trips() {
+        let key = storage_key(7, 0xff);
+        assert_eq!(key, "7/00000000000000ff.rkyv");
+        assert_eq!(parse_storage_key(&key), Some((7, 0xff)));
+        assert_eq!(parse_storage_key("7/ff.bin"), None);
+    }
+}
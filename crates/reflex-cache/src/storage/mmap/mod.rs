@@ -19,6 +19,7 @@ use std::io::{self, Write};
 use std::ops::Deref;
 use std::path::Path;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
 
 use memmap2::{Mmap, MmapMut, MmapOptions as Memmap2Options};
 use rkyv::Portable;
@@ -483,17 +484,27 @@ impl AlignedMmapBuilder {
     }
 
     /// Writes bytes and opens a read-only handle.
+    ///
+    /// The bytes go to a temporary file that is then renamed over the
+    /// target, so readers see either the old file or the complete new one.
     pub fn write_readonly(self, data: &[u8]) -> MmapResult<MmapFileHandle> {
-        let mut file = OpenOptions::new()
-            .read(true)
-            .write(true)
-            .create(true)
-            .truncate(true)
-            .open(&self.path)?;
-
-        file.write_all(data)?;
-        file.flush()?;
-        drop(file);
+        static NEXT_TEMP: AtomicU64 = AtomicU64::new(0);
+
+        let mut temp_name = self.path.file_name().unwrap_or_default().to_os_string();
+        temp_name.push(format!(
+            ".{}.{}.tmp",
+            std::process::id(),
+            NEXT_TEMP.fetch_add(1, Ordering::Relaxed)
+        ));
+        let temp_path = self.path.with_file_name(temp_name);
+
+        let written = File::create(&temp_path)
+            .and_then(|mut file| file.write_all(data))
+            .and_then(|()| std::fs::rename(&temp_path, &self.path));
+        if let Err(e) = written {
+            let _ = std::fs::remove_file(&temp_path);
+            return Err(e.into());
+        }
 
         MmapFileHandle::open(&self.path)
     }
@@ -18,10 +18,12 @@ fn create_test_file(content: &[u8]) -> NamedTempFile {
 
 fn create_serialized_entry() -> Vec<u8> {
     let entry = CacheEntry {
+        format: CacheEntry::FORMAT,
         tenant_id: 12345,
         context_hash: 67890,
         timestamp: 1702500000,
         embedding: vec![0x01, 0x02, 0x03, 0x04],
+        verification_text: String::new(),
         payload_blob: b"test payload".to_vec(),
     };
 
@@ -1,19 +1,25 @@
 //! Storage primitives.
 //!
-//! - [`CacheEntry`] is the on-disk record.
+//! - [`CacheEntry`] is the on-disk record, filed under its [`storage_key`].
+//! - [`typed`] reads and writes typed entry payloads.
 //! - [`mmap`] provides aligned memory-mapped IO helpers used by caches.
 
 /// Storage error types.
 pub mod error;
+mod key;
 /// Memory-mapped IO helpers.
 pub mod mmap;
 mod model;
 /// NVMe-backed storage implementation.
 pub mod nvme;
+/// Typed entry payloads.
+pub mod typed;
 /// Storage writer trait.
 pub mod writer;
 
 pub use error::StorageError;
+pub use key::{parse_storage_key, storage_key};
 pub use model::ArchivedCacheEntry;
 pub use model::CacheEntry;
+pub use typed::{ArchivedPayload, Payload, decode_payload, encode_payload};
 pub use writer::StorageWriter;
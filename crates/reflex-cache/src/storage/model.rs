@@ -2,25 +2,34 @@
 
 use rkyv::{Archive, Deserialize, Serialize};
 
+use super::error::StorageError;
+use super::typed::{ArchivedPayload, Payload, decode_payload};
+
 /// Cached entry persisted to disk.
 ///
-/// Stored as `rkyv` bytes (often memory-mapped).
+/// Stored as `rkyv` bytes (often memory-mapped). `rkyv` archives carry no
+/// schema, so [`format`](Self::format) marks the layout an entry was written
+/// with; loaders reject entries of any other format.
 ///
 /// # Example
 /// ```rust
 /// use reflex::CacheEntry;
 ///
 /// let entry = CacheEntry {
+///     format: CacheEntry::FORMAT,
 ///     tenant_id: 1,
 ///     context_hash: 42,
 ///     timestamp: 0,
 ///     embedding: vec![],
+///     verification_text: "What is Rust?".to_string(),
 ///     payload_blob: vec![],
 /// };
 /// assert_eq!(entry.tenant_id, 1);
 /// ```
 #[derive(Archive, Deserialize, Serialize, Debug, PartialEq, Clone)]
 pub struct CacheEntry {
+    /// Layout marker; always [`CacheEntry::FORMAT`] when written.
+    pub format: u64,
     /// Tenant identifier (used for isolation).
     pub tenant_id: u64,
     /// Hash of the conversation context.
@@ -29,10 +38,43 @@ pub struct CacheEntry {
     pub timestamp: i64,
     /// Embedding vector bytes (little-endian f16).
     pub embedding: Vec<u8>,
-    /// Encoded response payload bytes.
+    /// Text the L3 reranker compares queries against (usually the semantic
+    /// text the entry was stored under).
+    pub verification_text: String,
+    /// Encoded payload bytes; see [`typed`](super::typed) for typed
+    /// access.
     pub payload_blob: Vec<u8>,
 }
 
+impl CacheEntry {
+    /// Magic and layout version of entries written by this crate: `RFLX`
+    /// followed by the version, currently 2.
+    ///
+    /// Version 1 entries (before `verification_text`) have no marker. Bump
+    /// the version whenever the layout changes.
+    pub const FORMAT: u64 = u64::from_be_bytes(*b"RFLX\0\0\0\x02");
+
+    /// Deserializes the payload as a `T` written by
+    /// [`encode_payload`](super::typed::encode_payload).
+    pub fn payload<T: Payload>(&self) -> Result<T, StorageError>
+    where
+        T::Archived: ArchivedPayload<T>,
+    {
+        decode_payload(&self.payload_blob)
+    }
+}
+
+impl ArchivedCacheEntry {
+    /// Deserializes the payload as a `T` written by
+    /// [`encode_payload`](super::typed::encode_payload).
+    pub fn payload<T: Payload>(&self) -> Result<T, StorageError>
+    where
+        T::Archived: ArchivedPayload<T>,
+    {
+        decode_payload(self.payload_blob.as_slice())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -44,22 +86,26 @@ mod tests {
 
     fn create_test_entry() -> CacheEntry {
         CacheEntry {
+            format: CacheEntry::FORMAT,
             tenant_id: 12345678901234567890_u64,
             context_hash: 9876543210987654321_u64,
             timestamp: 1702500000_i64,
             embedding: vec![0x01, 0x02, 0x03, 0x04],
+            verification_text: String::new(),
             payload_blob: vec![0xDE, 0xAD, 0xBE, 0xEF],
         }
     }
 
     fn create_full_embedding_entry() -> CacheEntry {
         CacheEntry {
+            format: CacheEntry::FORMAT,
             tenant_id: 1,
             context_hash: 2,
             timestamp: 1702500000,
             embedding: (0..crate::constants::EMBEDDING_F16_BYTES)
                 .map(|i| (i % 256) as u8)
                 .collect(),
+            verification_text: String::new(),
             payload_blob: vec![0x00; TEST_PAYLOAD_BYTES],
         }
     }
@@ -172,10 +218,12 @@ mod tests {
     #[test]
     fn test_empty_vectors() {
         let entry = CacheEntry {
+            format: CacheEntry::FORMAT,
             tenant_id: 1,
             context_hash: 2,
             timestamp: 3,
             embedding: vec![],
+            verification_text: String::new(),
             payload_blob: vec![],
         };
 
@@ -190,10 +238,12 @@ mod tests {
     #[test]
     fn test_boundary_values_max() {
         let entry = CacheEntry {
+            format: CacheEntry::FORMAT,
             tenant_id: u64::MAX,
             context_hash: u64::MAX,
             timestamp: i64::MAX,
             embedding: vec![0xFF],
+            verification_text: String::new(),
             payload_blob: vec![0xFF],
         };
 
@@ -209,10 +259,12 @@ mod tests {
     #[test]
     fn test_boundary_values_min() {
         let entry = CacheEntry {
+            format: CacheEntry::FORMAT,
             tenant_id: u64::MIN,
             context_hash: u64::MIN,
             timestamp: i64::MIN,
             embedding: vec![0x00],
+            verification_text: String::new(),
             payload_blob: vec![0x00],
         };
 
@@ -228,10 +280,12 @@ mod tests {
     #[test]
     fn test_negative_timestamp() {
         let entry = CacheEntry {
+            format: CacheEntry::FORMAT,
             tenant_id: 1,
             context_hash: 2,
             timestamp: -1000000000_i64,
             embedding: vec![],
+            verification_text: String::new(),
             payload_blob: vec![],
         };
 
@@ -247,10 +301,12 @@ mod tests {
         let large_payload: Vec<u8> = (0..1_000_000).map(|i| (i % 256) as u8).collect();
 
         let entry = CacheEntry {
+            format: CacheEntry::FORMAT,
             tenant_id: 1,
             context_hash: 2,
             timestamp: 3,
             embedding: vec![],
+            verification_text: String::new(),
             payload_blob: large_payload.clone(),
         };
 
@@ -280,4 +336,41 @@ mod tests {
 
         assert!(bytes.len() >= crate::constants::EMBEDDING_F16_BYTES + TEST_PAYLOAD_BYTES);
     }
+
+    #[derive(Archive, Serialize, Deserialize, Debug, PartialEq)]
+    struct Answer {
+        text: String,
+        tokens: u32,
+    }
+
+    #[test]
+    fn test_typed_payload_roundtrip() {
+        let answer = Answer {
+            text: "A systems programming language.".to_string(),
+            tokens: 6,
+        };
+        let entry = CacheEntry {
+            verification_text: "What is Rust?".to_string(),
+            payload_blob: crate::storage::encode_payload(&answer).unwrap(),
+            ..create_test_entry()
+        };
+
+        assert_eq!(entry.payload::<Answer>().unwrap(), answer);
+
+        let bytes = to_bytes::<Error>(&entry).expect("serialization should succeed");
+        let archived =
+            access::<ArchivedCacheEntry, Error>(&bytes).expect("archive access should succeed");
+        assert_eq!(archived.verification_text.as_str(), "What is Rust?");
+        assert_eq!(archived.payload::<Answer>().unwrap(), answer);
+    }
+
+    #[test]
+    fn test_payload_of_another_type_is_an_error() {
+        let entry = create_test_entry();
+
+        assert!(matches!(
+            entry.payload::<Answer>(),
+            Err(StorageError::Payload(_))
+        ));
+    }
 }
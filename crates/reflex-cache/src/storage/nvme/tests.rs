@@ -10,12 +10,14 @@ fn create_test_storage() -> (NvmeStorage, TempDir) {
 
 fn create_test_entry(tenant_id: u64) -> CacheEntry {
     CacheEntry {
+        format: CacheEntry::FORMAT,
         tenant_id,
         context_hash: 67890,
         timestamp: 1702500000,
         embedding: (0..crate::constants::EMBEDDING_F16_BYTES)
             .map(|i| (i % 256) as u8)
             .collect(),
+        verification_text: String::new(),
         payload_blob: b"test payload data".to_vec(),
     }
 }
@@ -244,18 +246,22 @@ fn test_overwrite_existing_entry() {
     let entry_id = 1;
 
     let entry1 = CacheEntry {
+        format: CacheEntry::FORMAT,
         tenant_id,
         context_hash: 111,
         timestamp: 1000,
         embedding: vec![0x01; 100],
+        verification_text: String::new(),
         payload_blob: b"first".to_vec(),
     };
 
     let entry2 = CacheEntry {
+        format: CacheEntry::FORMAT,
         tenant_id,
         context_hash: 222,
         timestamp: 2000,
         embedding: vec![0x02; 100],
+        verification_text: String::new(),
         payload_blob: b"second".to_vec(),
     };
 
@@ -277,10 +283,12 @@ fn test_entry_with_empty_vectors() {
     let (storage, _dir) = create_test_storage();
 
     let entry = CacheEntry {
+        format: CacheEntry::FORMAT,
         tenant_id: 12345,
         context_hash: 67890,
         timestamp: 1702500000,
         embedding: vec![],
+        verification_text: String::new(),
         payload_blob: vec![],
     };
 
@@ -301,10 +309,12 @@ fn test_entry_with_large_payload() {
     let large_payload: Vec<u8> = (0..1_000_000).map(|i| (i % 256) as u8).collect();
 
     let entry = CacheEntry {
+        format: CacheEntry::FORMAT,
         tenant_id: 12345,
         context_hash: 67890,
         timestamp: 1702500000,
         embedding: vec![0u8; crate::constants::EMBEDDING_F16_BYTES],
+        verification_text: String::new(),
         payload_blob: large_payload.clone(),
     };
 
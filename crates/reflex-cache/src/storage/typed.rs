@@ -0,0 +1,81 @@
+//! Typed payloads for [`CacheEntry::payload_blob`](super::CacheEntry::payload_blob).
+//!
+//! Any `rkyv` archivable type can be cached: [`encode_payload`] writes it,
+//! [`decode_payload`] (or [`CacheEntry::payload`](super::CacheEntry::payload))
+//! reads it back. The text L3 verifies against lives in
+//! [`CacheEntry::verification_text`](super::CacheEntry::verification_text),
+//! so the payload never has to be text.
+//!
+//! # Example
+//! ```rust
+//! use reflex::storage::typed::{decode_payload, encode_payload};
+//!
+//! #[derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize, Debug, PartialEq)]
+//! struct Answer {
+//!     text: String,
+//!     tokens: u32,
+//! }
+//!
+//! let answer = Answer { text: "A systems language.".into(), tokens: 4 };
+//! let blob = encode_payload(&answer).unwrap();
+//! assert_eq!(decode_payload::<Answer>(&blob).unwrap(), answer);
+//! ```
+
+use rkyv::api::high::{HighDeserializer, HighSerializer, HighValidator};
+use rkyv::bytecheck::CheckBytes;
+use rkyv::rancor::Error as RkyvError;
+use rkyv::ser::allocator::ArenaHandle;
+use rkyv::util::AlignedVec;
+use rkyv::{Archive, Deserialize, Serialize};
+
+use super::error::StorageError;
+
+/// A type that can be written as a payload.
+///
+/// Implemented for every type deriving `rkyv::Archive` and `rkyv::Serialize`.
+pub trait Payload:
+    Archive + for<'a> Serialize<HighSerializer<AlignedVec, ArenaHandle<'a>, RkyvError>>
+{
+}
+
+impl<T> Payload for T where
+    T: Archive + for<'a> Serialize<HighSerializer<AlignedVec, ArenaHandle<'a>, RkyvError>>
+{
+}
+
+/// The archived form of a payload `T`, which can be validated and read back.
+///
+/// Implemented for the archived form of every type deriving
+/// `rkyv::Deserialize`.
+pub trait ArchivedPayload<T>:
+    for<'a> CheckBytes<HighValidator<'a, RkyvError>> + Deserialize<T, HighDeserializer<RkyvError>>
+{
+}
+
+impl<A, T> ArchivedPayload<T> for A where
+    A: for<'a> CheckBytes<HighValidator<'a, RkyvError>>
+        + Deserialize<T, HighDeserializer<RkyvError>>
+{
+}
+
+/// Serializes `value` into payload bytes.
+pub fn encode_payload<T: Payload>(value: &T) -> Result<Vec<u8>, StorageError> {
+    rkyv::to_bytes::<RkyvError>(value)
+        .map(|bytes| bytes.to_vec())
+        .map_err(|e| StorageError::Payload(e.to_string()))
+}
+
+/// Validates and deserializes payload bytes written by [`encode_payload`].
+///
+/// Fails for bytes that do not hold a `T`, such as payloads written by other
+/// means.
+pub fn decode_payload<T: Payload>(bytes: &[u8]) -> Result<T, StorageError>
+where
+    T::Archived: ArchivedPayload<T>,
+{
+    // Payloads are nested inside an archived entry with no alignment
+    // guarantee, so they are copied before validation.
+    let mut aligned = AlignedVec::<16>::with_capacity(bytes.len());
+    aligned.extend_from_slice(bytes);
+    rkyv::from_bytes::<T, RkyvError>(&aligned).map_err(|e| StorageError::Payload(e.to_string()))
+}
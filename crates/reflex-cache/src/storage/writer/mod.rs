@@ -2,7 +2,50 @@ use crate::storage::error::StorageError;
 use crate::storage::mmap::MmapFileHandle;
 
 /// Writes opaque bytes to storage and returns a readable mmap handle.
+///
+/// Also covers the maintenance side of the store: deleting and enumerating keys.
+/// Those methods have defaults that return [`StorageError::Unsupported`], so
+/// write-only stores need only implement [`write`](Self::write).
 pub trait StorageWriter: Send + Sync {
     /// Writes `data` under `key`.
     fn write(&self, key: &str, data: &[u8]) -> Result<MmapFileHandle, StorageError>;
+
+    /// Deletes `key`, returning `false` if it did not exist.
+    fn delete(&self, key: &str) -> Result<bool, StorageError> {
+        let _ = key;
+        Err(StorageError::Unsupported("delete".to_string()))
+    }
+
+    /// Lists stored keys, optionally restricted to one tenant (unordered).
+    fn list_keys(&self, tenant_id: Option<u64>) -> Result<Vec<String>, StorageError> {
+        let _ = tenant_id;
+        Err(StorageError::Unsupported("list_keys".to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct WriteOnly;
+
+    impl StorageWriter for WriteOnly {
+        fn write(&self, _key: &str, _data: &[u8]) -> Result<MmapFileHandle, StorageError> {
+            Err(StorageError::WriteFailed(
+                "read-only test store".to_string(),
+            ))
+        }
+    }
+
+    #[test]
+    fn test_maintenance_defaults_to_unsupported() {
+        assert!(matches!(
+            WriteOnly.delete("1/0000000000000001.rkyv"),
+            Err(StorageError::Unsupported(_))
+        ));
+        assert!(matches!(
+            WriteOnly.list_keys(None),
+            Err(StorageError::Unsupported(_))
+        ));
+    }
 }
@@ -0,0 +1,25 @@
+//! Metrics recorded through the [`metrics`](https://docs.rs/metrics) facade.
+//!
+//! The library only records. Nothing is collected unless the host application
+//! installs a recorder; `reflex-server` installs a Prometheus recorder and
+//! serves it at `/metrics`.
+
+use std::time::Instant;
+
+/// Histogram of per-stage latency in seconds, labelled by `stage`.
+pub const STAGE_DURATION_SECONDS: &str = "reflex_stage_duration_seconds";
+
+/// `stage` label: computing a query or entry embedding.
+pub const STAGE_EMBEDDING: &str = "embedding";
+/// `stage` label: the binary-quantized vector search.
+pub const STAGE_BQ_SEARCH: &str = "bq_search";
+/// `stage` label: loading one candidate entry from storage.
+pub const STAGE_STORAGE_LOAD: &str = "storage_load";
+/// `stage` label: full-precision rescoring of loaded candidates.
+pub const STAGE_RESCORING: &str = "rescoring";
+
+/// Records the time elapsed since `started` under `stage`.
+pub fn record_stage(stage: &'static str, started: Instant) {
+    metrics::histogram!(STAGE_DURATION_SECONDS, "stage" => stage)
+        .record(started.elapsed().as_secs_f64());
+}
@@ -82,4 +82,12 @@ impl BqSearchBackend for BqBackend {
             BqBackend::Mock(c) => c.upsert_points(collection, points, consistency).await,
         }
     }
+
+    async fn delete_points(&self, collection: &str, ids: Vec<u64>) -> Result<(), VectorDbError> {
+        match self {
+            BqBackend::Real(c) => c.delete_points(collection, ids).await,
+            #[cfg(any(test, feature = "mock"))]
+            BqBackend::Mock(c) => c.delete_points(collection, ids).await,
+        }
+    }
 }
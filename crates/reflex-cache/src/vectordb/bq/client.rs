@@ -93,6 +93,19 @@ impl BqClient {
         query: Vec<f32>,
         limit: u64,
         tenant_filter: Option<u64>,
+    ) -> Result<Vec<SearchResult>, VectorDbError> {
+        self.search_bq_scoped(collection, query, limit, tenant_filter, None)
+            .await
+    }
+
+    /// Searches the binary-quantized index for points indexed with `scope_filter`.
+    pub async fn search_bq_scoped(
+        &self,
+        collection: &str,
+        query: Vec<f32>,
+        limit: u64,
+        tenant_filter: Option<u64>,
+        scope_filter: Option<u64>,
     ) -> Result<Vec<SearchResult>, VectorDbError> {
         if limit == 0 {
             return Ok(Vec::new());
@@ -108,9 +121,13 @@ impl BqClient {
             .with_payload(true)
             .params(search_params);
 
-        if let Some(tenant_id) = tenant_filter {
-            let filter = Filter::must([Condition::matches("tenant_id", tenant_id as i64)]);
-            search_builder = search_builder.filter(filter);
+        let conditions: Vec<Condition> = tenant_filter
+            .map(|tenant_id| Condition::matches("tenant_id", tenant_id as i64))
+            .into_iter()
+            .chain(scope_filter.map(|scope| Condition::matches("scope_hash", scope as i64)))
+            .collect();
+        if !conditions.is_empty() {
+            search_builder = search_builder.filter(Filter::must(conditions));
         }
 
         let search_result = self
@@ -27,6 +27,7 @@ struct MockBqPoint {
     context_hash: u64,
     timestamp: i64,
     storage_key: Option<String>,
+    scope_hash: Option<u64>,
 }
 
 impl MockBqClient {
@@ -131,6 +132,7 @@ impl MockBqClient {
                     context_hash: point.context_hash,
                     timestamp: point.timestamp,
                     storage_key: point.storage_key,
+                    scope_hash: point.scope_hash,
                 },
             );
         }
@@ -145,6 +147,19 @@ impl MockBqClient {
         query: Vec<f32>,
         limit: u64,
         tenant_filter: Option<u64>,
+    ) -> Result<Vec<SearchResult>, VectorDbError> {
+        self.search_bq_scoped(collection, query, limit, tenant_filter, None)
+            .await
+    }
+
+    /// Searches the mock BQ collection for points indexed with `scope_filter`.
+    pub async fn search_bq_scoped(
+        &self,
+        collection: &str,
+        query: Vec<f32>,
+        limit: u64,
+        tenant_filter: Option<u64>,
+        scope_filter: Option<u64>,
     ) -> Result<Vec<SearchResult>, VectorDbError> {
         let collections = self
             .collections
@@ -167,6 +182,7 @@ impl MockBqClient {
             .points
             .iter()
             .filter(|(_, p)| tenant_filter.is_none() || tenant_filter == Some(p.tenant_id))
+            .filter(|(_, p)| scope_filter.is_none() || scope_filter == p.scope_hash)
             .map(|(&id, p)| {
                 let hamming = hamming_distance(&query_binary, &p.binary);
                 (id, p, hamming)
@@ -194,6 +210,7 @@ impl MockBqClient {
                         context_hash: p.context_hash,
                         timestamp: p.timestamp,
                         storage_key: p.storage_key.clone(),
+                        scope_hash: p.scope_hash,
                     }
                 })
                 .collect()
@@ -213,6 +230,7 @@ impl MockBqClient {
                         context_hash: p.context_hash,
                         timestamp: p.timestamp,
                         storage_key: p.storage_key.clone(),
+                        scope_hash: p.scope_hash,
                     }
                 })
                 .collect()
@@ -21,6 +21,7 @@ fn create_test_point(id: u64, tenant_id: u64) -> VectorPoint {
         context_hash: id * 100,
         timestamp: 1702512000 + id as i64,
         storage_key: Some(format!("key_{}", id)),
+        scope_hash: None,
     }
 }
 
@@ -232,6 +233,43 @@ async fn test_mock_search_with_tenant_filter() {
     }
 }
 
+#[tokio::test]
+async fn test_mock_search_with_scope_filter() {
+    let client = MockBqClient::new();
+    client
+        .ensure_bq_collection(TEST_COLLECTION, TEST_VECTOR_SIZE)
+        .await
+        .unwrap();
+
+    let points: Vec<_> = (0..6)
+        .map(|i| create_test_point(i, 1000).with_scope_hash(i % 2))
+        .chain([create_test_point(6, 1000)])
+        .collect();
+    client
+        .upsert_points(TEST_COLLECTION, points, WriteConsistency::Strong)
+        .await
+        .unwrap();
+
+    let results = client
+        .search_bq_scoped(
+            TEST_COLLECTION,
+            create_test_vector(0),
+            10,
+            Some(1000),
+            Some(1),
+        )
+        .await
+        .unwrap();
+    let ids: std::collections::BTreeSet<_> = results.iter().map(|r| r.id).collect();
+    assert_eq!(ids, [1, 3, 5].into());
+
+    let unscoped = client
+        .search_bq(TEST_COLLECTION, create_test_vector(0), 10, Some(1000))
+        .await
+        .unwrap();
+    assert_eq!(unscoped.len(), 7);
+}
+
 #[tokio::test]
 async fn test_mock_search_without_rescore() {
     let config = BqConfig::new().rescore(false);
@@ -328,6 +366,7 @@ async fn test_mock_upsert_wrong_dimension() {
         context_hash: 2000,
         timestamp: 0,
         storage_key: None,
+        scope_hash: None,
     };
 
     let result = client
@@ -129,6 +129,9 @@ impl QdrantClient {
                 if let Some(key) = p.storage_key {
                     payload.insert("storage_key".to_string(), key.into());
                 }
+                if let Some(scope_hash) = p.scope_hash {
+                    payload.insert("scope_hash".to_string(), (scope_hash as i64).into());
+                }
 
                 PointStruct::new(p.id, p.vector, payload)
             })
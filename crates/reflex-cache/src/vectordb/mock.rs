@@ -20,6 +20,7 @@ struct MockStoredPoint {
     context_hash: u64,
     timestamp: i64,
     storage_key: Option<String>,
+    scope_hash: Option<u64>,
 }
 
 impl MockVectorDbClient {
@@ -114,6 +115,7 @@ impl VectorDbClient for MockVectorDbClient {
                     context_hash: point.context_hash,
                     timestamp: point.timestamp,
                     storage_key: point.storage_key,
+                    scope_hash: point.scope_hash,
                 },
             );
         }
@@ -156,6 +158,7 @@ impl VectorDbClient for MockVectorDbClient {
                     context_hash: p.context_hash,
                     timestamp: p.timestamp,
                     storage_key: p.storage_key.clone(),
+                    scope_hash: p.scope_hash,
                 }
             })
             .collect();
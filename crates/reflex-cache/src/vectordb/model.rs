@@ -19,6 +19,8 @@ pub struct VectorPoint {
     pub timestamp: i64,
     /// Optional storage key for loading the full entry.
     pub storage_key: Option<String>,
+    /// Optional scope; scoped searches only return points with the same one.
+    pub scope_hash: Option<u64>,
 }
 
 impl VectorPoint {
@@ -31,6 +33,7 @@ impl VectorPoint {
             context_hash,
             timestamp: 0,
             storage_key: None,
+            scope_hash: None,
         }
     }
 
@@ -56,6 +59,12 @@ impl VectorPoint {
         self.storage_key = Some(key);
         self
     }
+
+    /// Sets the scope.
+    pub fn with_scope_hash(mut self, scope_hash: u64) -> Self {
+        self.scope_hash = Some(scope_hash);
+        self
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -73,6 +82,8 @@ pub struct SearchResult {
     pub timestamp: i64,
     /// Optional storage key for loading the full entry.
     pub storage_key: Option<String>,
+    /// Scope the point was indexed with, if any.
+    pub scope_hash: Option<u64>,
 }
 
 impl SearchResult {
@@ -107,6 +118,11 @@ impl SearchResult {
             .and_then(|v| v.as_str())
             .map(|s| s.to_string());
 
+        let scope_hash = payload
+            .get("scope_hash")
+            .and_then(|v| v.as_integer())
+            .map(|i| i as u64);
+
         Some(SearchResult {
             id,
             score: point.score,
@@ -114,6 +130,7 @@ impl SearchResult {
             context_hash,
             timestamp,
             storage_key,
+            scope_hash,
         })
     }
 }
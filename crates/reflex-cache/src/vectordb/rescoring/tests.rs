@@ -9,10 +9,12 @@ fn create_test_entry(embedding_f32: &[f32]) -> CacheEntry {
     let embedding_bytes: Vec<u8> = embedding_f16.iter().flat_map(|v| v.to_le_bytes()).collect();
 
     CacheEntry {
+        format: CacheEntry::FORMAT,
         tenant_id: 1,
         context_hash: 2,
         timestamp: 1702500000,
         embedding: embedding_bytes,
+        verification_text: String::new(),
         payload_blob: vec![],
     }
 }
@@ -555,10 +557,12 @@ fn test_rescorer_drops_invalid_f16_parse() {
 
     // Create an entry with odd-length bytes that cannot be parsed as f16
     let invalid_entry = CacheEntry {
+        format: CacheEntry::FORMAT,
         tenant_id: 1,
         context_hash: 2,
         timestamp: 1702500000,
         embedding: vec![0u8; 5], // Odd length - cannot be cast to f16 slice
+        verification_text: String::new(),
         payload_blob: vec![],
     };
 
@@ -627,10 +631,12 @@ fn test_rescorer_drops_wrong_dimension_embedding() {
         .flat_map(|v| v.to_le_bytes())
         .collect();
     let wrong_dim_entry = CacheEntry {
+        format: CacheEntry::FORMAT,
         tenant_id: 2,
         context_hash: 3,
         timestamp: 1702500000,
         embedding: wrong_dim_bytes,
+        verification_text: String::new(),
         payload_blob: vec![],
     };
 
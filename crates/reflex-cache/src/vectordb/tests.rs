@@ -25,6 +25,7 @@ fn create_test_point(id: u64, tenant_id: u64) -> VectorPoint {
         context_hash: id * 100,
         timestamp: 1702512000 + id as i64,
         storage_key: Some(format!("key_{}", id)),
+        scope_hash: None,
     }
 }
 
@@ -123,6 +124,7 @@ async fn test_upsert_replaces_existing() {
         context_hash: 999,
         timestamp: 9999,
         storage_key: Some("updated".to_string()),
+        scope_hash: None,
     };
     client
         .upsert_points(
@@ -196,6 +198,7 @@ async fn test_upsert_wrong_dimension() {
         context_hash: 2000,
         timestamp: 0,
         storage_key: None,
+        scope_hash: None,
     };
 
     let result = client
@@ -71,10 +71,12 @@ impl CacheEntryBuilder {
 
     pub fn build(self) -> CacheEntry {
         CacheEntry {
+            format: CacheEntry::FORMAT,
             tenant_id: self.tenant_id.unwrap_or(DEFAULT_TENANT_ID),
             context_hash: self.context_hash.unwrap_or(DEFAULT_CONTEXT_HASH),
             timestamp: self.timestamp.unwrap_or(FIXED_TIMESTAMP),
             embedding: self.embedding.unwrap_or_default(),
+            verification_text: String::new(),
             payload_blob: self.payload_blob.unwrap_or_default(),
         }
     }
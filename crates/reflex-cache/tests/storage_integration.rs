@@ -293,10 +293,12 @@ fn test_serialized_size_scaling() {
 #[test]
 fn test_extreme_field_values() {
     let entry = CacheEntry {
+        format: CacheEntry::FORMAT,
         tenant_id: u64::MAX,
         context_hash: u64::MAX,
         timestamp: i64::MAX,
         embedding: vec![0xFF; 100],
+        verification_text: String::new(),
         payload_blob: vec![0xFF; 100],
     };
 
@@ -312,10 +314,12 @@ fn test_extreme_field_values() {
 #[test]
 fn test_minimum_field_values() {
     let entry = CacheEntry {
+        format: CacheEntry::FORMAT,
         tenant_id: 0,
         context_hash: 0,
         timestamp: i64::MIN,
         embedding: vec![],
+        verification_text: String::new(),
         payload_blob: vec![],
     };
 
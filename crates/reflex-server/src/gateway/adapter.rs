@@ -1,15 +1,19 @@
+use std::collections::HashSet;
+
 use async_openai::types::chat::{
-    ChatChoice, ChatCompletionMessageToolCall, ChatCompletionMessageToolCalls,
-    ChatCompletionRequestAssistantMessageContent, ChatCompletionRequestDeveloperMessageContent,
-    ChatCompletionRequestMessage, ChatCompletionRequestSystemMessageContent,
-    ChatCompletionRequestToolMessageContent, ChatCompletionRequestUserMessageContent,
-    ChatCompletionRequestUserMessageContentPart, ChatCompletionResponseMessage,
-    ChatCompletionToolChoiceOption, ChatCompletionTools, CompletionUsage,
-    CreateChatCompletionRequest, CreateChatCompletionResponse, FinishReason, FunctionCall,
-    ToolChoiceOptions,
+    ChatChoice, ChatCompletionMessageCustomToolCall, ChatCompletionMessageToolCall,
+    ChatCompletionMessageToolCalls, ChatCompletionRequestAssistantMessageContent,
+    ChatCompletionRequestDeveloperMessageContent, ChatCompletionRequestMessage,
+    ChatCompletionRequestSystemMessageContent, ChatCompletionRequestToolMessageContent,
+    ChatCompletionRequestUserMessageContent, ChatCompletionRequestUserMessageContentPart,
+    ChatCompletionResponseMessage, ChatCompletionToolChoiceOption, ChatCompletionTools,
+    CompletionUsage, CreateChatCompletionRequest, CreateChatCompletionResponse, CustomTool,
+    CustomToolPropertiesFormat, FinishReason, FunctionCall, InputAudioFormat,
+    ToolChoiceAllowedMode, ToolChoiceOptions,
 };
 use genai::chat::{
-    ChatMessage, ChatRequest, ChatResponse, MessageContent, Tool, ToolCall, ToolResponse,
+    ChatMessage, ChatRequest, ChatResponse, ContentPart, MessageContent, Tool, ToolCall,
+    ToolResponse, Usage,
 };
 use serde_json::Value;
 
@@ -23,31 +27,109 @@ pub fn adapt_openai_to_genai(req: CreateChatCompletionRequest) -> ChatRequest {
     let mut chat_req = ChatRequest::new(messages);
 
     if let Some(tools) = &req.tools {
-        let genai_tools: Vec<Tool> = tools.iter().filter_map(openai_tool_to_genai_tool).collect();
+        let genai_tools = offered_tools(tools, req.tool_choice.as_ref());
         if !genai_tools.is_empty() {
             chat_req = chat_req.with_tools(genai_tools);
         }
     }
 
-    if let Some(tool_choice) = &req.tool_choice {
-        match tool_choice {
-            ChatCompletionToolChoiceOption::Mode(ToolChoiceOptions::None) => {}
-            ChatCompletionToolChoiceOption::Mode(ToolChoiceOptions::Auto) => {}
-            ChatCompletionToolChoiceOption::Mode(ToolChoiceOptions::Required) => {}
-            ChatCompletionToolChoiceOption::Function(named) => {
-                tracing::debug!(
-                    "tool_choice specifies function '{}', but genai does not support forced tool selection",
-                    named.function.name
-                );
-            }
-            _ => {}
+    chat_req
+}
+
+/// Why the request's `tool_choice` cannot be sent upstream, if it cannot.
+///
+/// genai has no `tool_choice` parameter, so a choice that forces a tool call
+/// (`required`, a named function or custom tool, or `allowed_tools` in
+/// `required` mode) cannot be honoured and is refused rather than
+/// approximated. `auto`, `none` and `allowed_tools` in `auto` mode only limit
+/// the tools offered, which [`offered_tools`] does.
+pub fn unsupported_tool_choice(req: &CreateChatCompletionRequest) -> Option<String> {
+    let forced = match req.tool_choice.as_ref()? {
+        ChatCompletionToolChoiceOption::Mode(ToolChoiceOptions::Required) => {
+            "\"required\"".to_string()
+        }
+        ChatCompletionToolChoiceOption::Function(named) => {
+            format!("function `{}`", named.function.name)
+        }
+        ChatCompletionToolChoiceOption::Custom(named) => {
+            format!("custom tool `{}`", named.custom.name)
+        }
+        ChatCompletionToolChoiceOption::AllowedTools(choice)
+            if choice
+                .allowed_tools
+                .iter()
+                .any(|allowed| allowed.mode == ToolChoiceAllowedMode::Required) =>
+        {
+            "allowed_tools in \"required\" mode".to_string()
+        }
+        _ => return None,
+    };
+    Some(format!(
+        "tool_choice {} forces a tool call, which is not supported; use \"auto\" or name the tools in allowed_tools",
+        forced
+    ))
+}
+
+/// Converts the request's tools, keeping only those `tool_choice` allows.
+///
+/// genai has no `tool_choice` parameter, so the choice is honoured through
+/// what the model is offered: `none` offers no tools, a named function or
+/// custom tool offers only that tool, and `allowed_tools` offers its subset.
+/// `auto` and `required` offer every tool. Requests whose choice forces a
+/// call are refused first (see [`unsupported_tool_choice`]).
+fn offered_tools(
+    tools: &[ChatCompletionTools],
+    tool_choice: Option<&ChatCompletionToolChoiceOption>,
+) -> Vec<Tool> {
+    let allowed: Option<HashSet<&str>> = match tool_choice {
+        None
+        | Some(ChatCompletionToolChoiceOption::Mode(
+            ToolChoiceOptions::Auto | ToolChoiceOptions::Required,
+        )) => None,
+        Some(ChatCompletionToolChoiceOption::Mode(ToolChoiceOptions::None)) => return Vec::new(),
+        Some(ChatCompletionToolChoiceOption::Function(named)) => {
+            Some(HashSet::from([named.function.name.as_str()]))
+        }
+        Some(ChatCompletionToolChoiceOption::Custom(named)) => {
+            Some(HashSet::from([named.custom.name.as_str()]))
         }
+        Some(ChatCompletionToolChoiceOption::AllowedTools(choice)) => Some(
+            choice
+                .allowed_tools
+                .iter()
+                .flat_map(|allowed| &allowed.tools)
+                .filter_map(allowed_tool_name)
+                .collect(),
+        ),
+    };
+
+    tools
+        .iter()
+        .filter(|tool| {
+            allowed
+                .as_ref()
+                .is_none_or(|names| names.contains(tool_name(tool)))
+        })
+        .map(openai_tool_to_genai_tool)
+        .collect()
+}
+
+fn tool_name(tool: &ChatCompletionTools) -> &str {
+    match tool {
+        ChatCompletionTools::Function(func_tool) => &func_tool.function.name,
+        ChatCompletionTools::Custom(custom_tool) => &custom_tool.custom.name,
     }
+}
 
-    chat_req
+/// Name of an `allowed_tools` entry (`{"type": "function", "function": {"name": ..}}`).
+fn allowed_tool_name(tool: &Value) -> Option<&str> {
+    tool.pointer("/function/name")
+        .or_else(|| tool.pointer("/custom/name"))
+        .or_else(|| tool.get("name"))?
+        .as_str()
 }
 
-fn openai_tool_to_genai_tool(tool: &ChatCompletionTools) -> Option<Tool> {
+fn openai_tool_to_genai_tool(tool: &ChatCompletionTools) -> Tool {
     match tool {
         ChatCompletionTools::Function(func_tool) => {
             let func = &func_tool.function;
@@ -61,34 +143,223 @@ fn openai_tool_to_genai_tool(tool: &ChatCompletionTools) -> Option<Tool> {
                 genai_tool = genai_tool.with_schema(params.clone());
             }
 
-            Some(genai_tool)
+            genai_tool
+        }
+        // A custom tool takes free-form text; it is offered as a function
+        // with a single `input` string, the shape assistant history uses.
+        ChatCompletionTools::Custom(custom_tool) => {
+            let custom = &custom_tool.custom;
+            let mut description = custom.description.clone().unwrap_or_default();
+            if let CustomToolPropertiesFormat::Grammar { grammar } = &custom.format {
+                let syntax = serde_json::to_value(&grammar.syntax)
+                    .ok()
+                    .and_then(|v| v.as_str().map(str::to_string))
+                    .unwrap_or_default();
+                description = format!(
+                    "{}\n\nThe input must match this {} grammar:\n{}",
+                    description, syntax, grammar.definition
+                )
+                .trim_start()
+                .to_string();
+            }
+
+            let mut genai_tool = Tool::new(&custom.name).with_schema(serde_json::json!({
+                "type": "object",
+                "properties": { "input": { "type": "string" } },
+                "required": ["input"],
+            }));
+            if !description.is_empty() {
+                genai_tool = genai_tool.with_description(description);
+            }
+            genai_tool
         }
-        ChatCompletionTools::Custom(_) => None,
     }
 }
 
-pub fn adapt_genai_to_openai(resp: ChatResponse, model: String) -> CreateChatCompletionResponse {
-    let tool_calls = resp.tool_calls();
-    let content = resp.first_text().unwrap_or_default().to_string();
-
-    let openai_tool_calls: Vec<ChatCompletionMessageToolCalls> = tool_calls
-        .into_iter()
-        .map(|tc| {
-            ChatCompletionMessageToolCalls::Function(ChatCompletionMessageToolCall {
-                id: tc.call_id.clone(),
-                function: FunctionCall {
-                    name: tc.fn_name.clone(),
-                    arguments: serde_json::to_string(&tc.fn_arguments)
-                        .unwrap_or_else(|_| "{}".to_string()),
-                },
-            })
+/// Names of the request's custom tools, whose calls are returned as
+/// `custom` tool calls rather than function calls.
+pub fn custom_tool_names(req: &CreateChatCompletionRequest) -> HashSet<String> {
+    req.tools
+        .iter()
+        .flatten()
+        .filter_map(|tool| match tool {
+            ChatCompletionTools::Custom(custom_tool) => Some(custom_tool.custom.name.clone()),
+            ChatCompletionTools::Function(_) => None,
         })
+        .collect()
+}
+
+/// Converts a buffered genai response to a single-choice OpenAI response.
+///
+/// When the raw provider body is captured, its finish reason, refusal and
+/// (for OpenAI-compatible providers) response id and fingerprint are kept.
+pub fn adapt_genai_to_openai(
+    resp: ChatResponse,
+    model: String,
+    request: &CreateChatCompletionRequest,
+) -> CreateChatCompletionResponse {
+    let raw = resp.captured_raw_body.as_ref();
+    let tool_calls = resp.tool_calls();
+    let finish_reason = raw.and_then(finish_reason_from_raw).unwrap_or_else(|| {
+        inferred_finish_reason(&tool_calls, &resp.usage, max_completion_tokens(request))
+    });
+    let choice = ChoiceParts {
+        content: resp.first_text().unwrap_or_default().to_string(),
+        refusal: raw.and_then(refusal_from_raw),
+        tool_calls: tool_calls.into_iter().cloned().collect(),
+        finish_reason,
+    };
+
+    let mut response = openai_response_from_parts(
+        model,
+        choice,
+        openai_usage(&resp.usage),
+        &custom_tool_names(request),
+    );
+    if let Some(raw) = raw {
+        keep_provider_identity(&mut response, raw);
+    }
+    response
+}
+
+/// Keeps the id, creation time and fingerprint of an OpenAI-compatible body.
+#[allow(deprecated)]
+fn keep_provider_identity(response: &mut CreateChatCompletionResponse, raw: &Value) {
+    if raw.get("object").and_then(Value::as_str) != Some("chat.completion") {
+        return;
+    }
+    if let Some(id) = raw.get("id").and_then(Value::as_str) {
+        response.id = id.to_string();
+    }
+    if let Some(created) = raw.get("created").and_then(Value::as_u64) {
+        response.created = created as u32;
+    }
+    response.system_fingerprint = raw
+        .get("system_fingerprint")
+        .and_then(Value::as_str)
+        .map(str::to_string);
+}
+
+/// The completion token cap of `req`, under either of its field names.
+#[allow(deprecated)]
+pub fn max_completion_tokens(req: &CreateChatCompletionRequest) -> Option<u32> {
+    req.max_completion_tokens.or(req.max_tokens)
+}
+
+/// Choices the request asks for (`n`, at least one).
+///
+/// genai asks for one choice per call, so this is also the number of
+/// provider calls a miss makes, and the miss tokens it is charged.
+pub fn requested_choices(req: &CreateChatCompletionRequest) -> u8 {
+    req.n.unwrap_or(1).max(1)
+}
+
+/// Reads the finish reason from a raw OpenAI, Anthropic or Gemini response body.
+pub fn finish_reason_from_raw(body: &Value) -> Option<FinishReason> {
+    let openai = || body.pointer("/choices/0/finish_reason")?.as_str();
+    let anthropic = || body.get("stop_reason")?.as_str();
+    let gemini = || body.pointer("/candidates/0/finishReason")?.as_str();
+
+    let reason = openai().or_else(anthropic).or_else(gemini)?;
+    Some(match reason.to_ascii_lowercase().as_str() {
+        "stop" | "end_turn" | "stop_sequence" | "pause_turn" => FinishReason::Stop,
+        "length" | "max_tokens" | "model_context_window_exceeded" => FinishReason::Length,
+        "tool_calls" | "tool_use" | "function_call" => FinishReason::ToolCalls,
+        "content_filter" | "refusal" | "safety" | "recitation" | "blocklist"
+        | "prohibited_content" | "spii" => FinishReason::ContentFilter,
+        _ => return None,
+    })
+}
+
+/// Reads an OpenAI refusal message from a raw response body.
+pub fn refusal_from_raw(body: &Value) -> Option<String> {
+    body.pointer("/choices/0/message/refusal")?
+        .as_str()
+        .filter(|refusal| !refusal.trim().is_empty())
+        .map(str::to_string)
+}
+
+/// Best guess at the finish reason when the provider's is not available.
+///
+/// Tool calls imply `tool_calls`; reaching `max_tokens` implies `length`.
+pub fn inferred_finish_reason(
+    tool_calls: &[&ToolCall],
+    usage: &Usage,
+    max_tokens: Option<u32>,
+) -> FinishReason {
+    let completion = usage.completion_tokens.unwrap_or_default().max(0) as u32;
+    if !tool_calls.is_empty() {
+        FinishReason::ToolCalls
+    } else if max_tokens.is_some_and(|max| completion >= max) {
+        FinishReason::Length
+    } else {
+        FinishReason::Stop
+    }
+}
+
+/// Converts genai usage; `None` when the provider reported no token counts.
+pub fn openai_usage(usage: &Usage) -> Option<CompletionUsage> {
+    if usage.prompt_tokens.is_none() && usage.completion_tokens.is_none() {
+        return None;
+    }
+    let count = |tokens: Option<i32>| tokens.unwrap_or_default().max(0) as u32;
+    let prompt_tokens = count(usage.prompt_tokens);
+    let completion_tokens = count(usage.completion_tokens);
+    let cached = usage
+        .prompt_tokens_details
+        .as_ref()
+        .and_then(|d| d.cached_tokens);
+    let reasoning = usage
+        .completion_tokens_details
+        .as_ref()
+        .and_then(|d| d.reasoning_tokens);
+
+    let usage_value = serde_json::json!({
+        "prompt_tokens": prompt_tokens,
+        "completion_tokens": completion_tokens,
+        "total_tokens": usage.total_tokens.map_or(prompt_tokens + completion_tokens, |t| count(Some(t))),
+        "prompt_tokens_details": cached.map(|c| serde_json::json!({ "cached_tokens": c })),
+        "completion_tokens_details": reasoning.map(|r| serde_json::json!({ "reasoning_tokens": r })),
+    });
+    serde_json::from_value(usage_value).ok()
+}
+
+/// One assistant choice, as collected from a buffered or streamed response.
+#[derive(Debug, Clone)]
+pub struct ChoiceParts {
+    pub content: String,
+    pub refusal: Option<String>,
+    pub tool_calls: Vec<ToolCall>,
+    pub finish_reason: FinishReason,
+}
+
+/// Builds a single-choice OpenAI response from one assistant choice.
+///
+/// Shared by the buffered path and the streaming accumulator so both store
+/// identically shaped payloads. Calls to tools named in `custom_tools` are
+/// returned as `custom` tool calls carrying their raw `input`.
+pub fn openai_response_from_parts(
+    model: String,
+    choice: ChoiceParts,
+    usage: Option<CompletionUsage>,
+    custom_tools: &HashSet<String>,
+) -> CreateChatCompletionResponse {
+    let openai_tool_calls: Vec<ChatCompletionMessageToolCalls> = choice
+        .tool_calls
+        .iter()
+        .map(|tc| openai_tool_call(tc, custom_tools))
         .collect();
 
+    let content = if choice.content.trim().is_empty() {
+        Value::Null
+    } else {
+        Value::String(choice.content)
+    };
     let message_value = serde_json::json!({
         "role": "assistant",
-        "content": if content.trim().is_empty() { serde_json::Value::Null } else { serde_json::Value::String(content) },
-        "tool_calls": if openai_tool_calls.is_empty() { serde_json::Value::Null } else { serde_json::to_value(openai_tool_calls).unwrap_or(serde_json::Value::Null) },
+        "content": content,
+        "refusal": choice.refusal,
+        "tool_calls": if openai_tool_calls.is_empty() { Value::Null } else { serde_json::to_value(openai_tool_calls).unwrap_or(Value::Null) },
     });
 
     let message: ChatCompletionResponseMessage =
@@ -102,21 +373,74 @@ pub fn adapt_genai_to_openai(resp: ChatResponse, model: String) -> CreateChatCom
         "choices": vec![ChatChoice {
             index: 0,
             message,
-            finish_reason: Some(FinishReason::Stop),
+            finish_reason: Some(choice.finish_reason),
             logprobs: None,
         }],
-        "usage": Some(CompletionUsage {
-            prompt_tokens: 0,
-            completion_tokens: 0,
-            total_tokens: 0,
-            prompt_tokens_details: None,
-            completion_tokens_details: None,
-        }),
+        "usage": usage,
     });
 
     serde_json::from_value(response_value).expect("constructed OpenAI response is valid")
 }
 
+fn openai_tool_call(
+    tool_call: &ToolCall,
+    custom_tools: &HashSet<String>,
+) -> ChatCompletionMessageToolCalls {
+    if custom_tools.contains(&tool_call.fn_name) {
+        let input = match tool_call.fn_arguments.get("input") {
+            Some(Value::String(input)) => input.clone(),
+            Some(other) => other.to_string(),
+            None => match &tool_call.fn_arguments {
+                Value::String(input) => input.clone(),
+                other => other.to_string(),
+            },
+        };
+        return ChatCompletionMessageToolCalls::Custom(ChatCompletionMessageCustomToolCall {
+            id: tool_call.call_id.clone(),
+            custom_tool: CustomTool {
+                name: tool_call.fn_name.clone(),
+                input,
+            },
+        });
+    }
+
+    ChatCompletionMessageToolCalls::Function(ChatCompletionMessageToolCall {
+        id: tool_call.call_id.clone(),
+        function: FunctionCall {
+            name: tool_call.fn_name.clone(),
+            arguments: serde_json::to_string(&tool_call.fn_arguments)
+                .unwrap_or_else(|_| "{}".to_string()),
+        },
+    })
+}
+
+/// Combines single-choice responses to one request into an `n`-choice response.
+///
+/// genai asks providers for one choice per call, so `n > 1` is served by `n`
+/// calls. The first response keeps its id; usage is the sum of all calls.
+pub fn merge_choices(
+    responses: Vec<CreateChatCompletionResponse>,
+) -> Option<CreateChatCompletionResponse> {
+    let mut responses = responses.into_iter();
+    let mut merged = responses.next()?;
+    for response in responses {
+        merged.usage = match (merged.usage.take(), response.usage) {
+            (Some(total), Some(usage)) => Some(CompletionUsage {
+                prompt_tokens: total.prompt_tokens + usage.prompt_tokens,
+                completion_tokens: total.completion_tokens + usage.completion_tokens,
+                total_tokens: total.total_tokens + usage.total_tokens,
+                ..total
+            }),
+            (total, usage) => total.or(usage),
+        };
+        merged.choices.extend(response.choices);
+    }
+    for (index, choice) in merged.choices.iter_mut().enumerate() {
+        choice.index = index as u32;
+    }
+    Some(merged)
+}
+
 fn openai_message_to_genai_message(m: ChatCompletionRequestMessage) -> Option<ChatMessage> {
     match m {
         ChatCompletionRequestMessage::Developer(dev) => Some(ChatMessage::system(
@@ -125,9 +449,9 @@ fn openai_message_to_genai_message(m: ChatCompletionRequestMessage) -> Option<Ch
         ChatCompletionRequestMessage::System(sys) => Some(ChatMessage::system(
             openai_system_content_to_text(sys.content),
         )),
-        ChatCompletionRequestMessage::User(user) => {
-            Some(ChatMessage::user(openai_user_content_to_text(user.content)))
-        }
+        ChatCompletionRequestMessage::User(user) => Some(ChatMessage::user(
+            openai_user_content_to_genai(user.content),
+        )),
         ChatCompletionRequestMessage::Assistant(asst) => {
             let mut content = MessageContent::default();
 
@@ -250,22 +574,77 @@ fn openai_tool_content_to_text(content: ChatCompletionRequestToolMessageContent)
     }
 }
 
-fn openai_user_content_to_text(content: ChatCompletionRequestUserMessageContent) -> String {
+fn openai_user_content_to_genai(
+    content: ChatCompletionRequestUserMessageContent,
+) -> MessageContent {
     match content {
-        ChatCompletionRequestUserMessageContent::Text(t) => t,
-        ChatCompletionRequestUserMessageContent::Array(parts) => parts
-            .into_iter()
-            .map(|p| match p {
-                ChatCompletionRequestUserMessageContentPart::Text(t) => t.text,
-                ChatCompletionRequestUserMessageContentPart::ImageUrl(img) => {
-                    format!("[image_url:{}]", img.image_url.url)
-                }
-                ChatCompletionRequestUserMessageContentPart::InputAudio(_) => {
-                    "[input_audio]".into()
+        ChatCompletionRequestUserMessageContent::Text(t) => MessageContent::from_text(t),
+        ChatCompletionRequestUserMessageContent::Array(parts) => {
+            parts.into_iter().map(openai_user_part_to_genai).collect()
+        }
+    }
+}
+
+/// Converts a user content part; images, audio and inline files become
+/// binary parts the provider receives as such.
+fn openai_user_part_to_genai(part: ChatCompletionRequestUserMessageContentPart) -> ContentPart {
+    match part {
+        ChatCompletionRequestUserMessageContentPart::Text(t) => ContentPart::Text(t.text),
+        ChatCompletionRequestUserMessageContentPart::ImageUrl(img) => {
+            let url = img.image_url.url;
+            match parse_data_url(&url) {
+                Some((content_type, data)) => {
+                    ContentPart::from_binary_base64(content_type, data, None)
                 }
-                ChatCompletionRequestUserMessageContentPart::File(_) => "[file]".into(),
-            })
-            .collect::<Vec<_>>()
-            .join(" "),
+                None => ContentPart::from_binary_url(image_content_type(&url), url, None),
+            }
+        }
+        ChatCompletionRequestUserMessageContentPart::InputAudio(audio) => {
+            let content_type = match audio.input_audio.format {
+                InputAudioFormat::Wav => "audio/wav",
+                InputAudioFormat::Mp3 => "audio/mpeg",
+            };
+            ContentPart::from_binary_base64(content_type, audio.input_audio.data, None)
+        }
+        ChatCompletionRequestUserMessageContentPart::File(file) => {
+            // `FileObject` keeps its fields private.
+            let file = serde_json::to_value(&file.file).unwrap_or_default();
+            let field = |name: &str| file.get(name).and_then(Value::as_str).map(str::to_string);
+            let filename = field("filename");
+            match field("file_data") {
+                Some(data) => match parse_data_url(&data) {
+                    Some((content_type, data)) => {
+                        ContentPart::from_binary_base64(content_type, data, filename)
+                    }
+                    None => ContentPart::from_binary_base64("application/pdf", data, filename),
+                },
+                // Uploaded file ids only mean something to OpenAI itself.
+                None => ContentPart::Text(format!(
+                    "[file:{}]",
+                    field("file_id").or(filename).unwrap_or_default()
+                )),
+            }
+        }
+    }
+}
+
+/// Splits `data:<type>;base64,<data>` into its content type and payload.
+fn parse_data_url(url: &str) -> Option<(&str, &str)> {
+    let (header, data) = url.strip_prefix("data:")?.split_once(',')?;
+    let content_type = header.strip_suffix(";base64")?;
+    Some((content_type, data))
+}
+
+fn image_content_type(url: &str) -> &'static str {
+    let path = url
+        .split(['?', '#'])
+        .next()
+        .unwrap_or(url)
+        .to_ascii_lowercase();
+    match path.rsplit('.').next() {
+        Some("png") => "image/png",
+        Some("gif") => "image/gif",
+        Some("webp") => "image/webp",
+        _ => "image/jpeg",
     }
 }
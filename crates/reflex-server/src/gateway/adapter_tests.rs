@@ -0,0 +1,334 @@
+//! Tests for the OpenAI <-> genai adapter.
+
+use std::collections::HashSet;
+
+use async_openai::types::chat::{
+    ChatCompletionMessageToolCalls, CreateChatCompletionRequest, CreateChatCompletionResponse,
+    FinishReason,
+};
+use genai::ModelIden;
+use genai::adapter::AdapterKind;
+use genai::chat::{
+    BinarySource, ChatResponse, ChatRole, ContentPart, MessageContent, ToolCall, Usage,
+};
+
+use crate::gateway::adapter::{
+    adapt_genai_to_openai, adapt_openai_to_genai, custom_tool_names, finish_reason_from_raw,
+    inferred_finish_reason, merge_choices, openai_usage, unsupported_tool_choice,
+};
+
+fn request(body: serde_json::Value) -> CreateChatCompletionRequest {
+    let mut body = body;
+    body["model"] = "gpt-4o".into();
+    if body.get("messages").is_none() {
+        body["messages"] = serde_json::json!([{"role": "user", "content": "hi"}]);
+    }
+    serde_json::from_value(body).unwrap()
+}
+
+fn tools() -> serde_json::Value {
+    serde_json::json!([
+        {"type": "function", "function": {"name": "get_weather", "parameters": {"type": "object"}}},
+        {"type": "function", "function": {"name": "get_time"}},
+        {"type": "custom", "custom": {"name": "run_sql", "description": "Runs a query", "format": {"type": "text"}}},
+    ])
+}
+
+fn offered(tool_choice: serde_json::Value) -> Vec<String> {
+    let req = request(serde_json::json!({"tools": tools(), "tool_choice": tool_choice}));
+    adapt_openai_to_genai(req)
+        .tools
+        .unwrap_or_default()
+        .into_iter()
+        .map(|tool| tool.name)
+        .collect()
+}
+
+fn genai_response(
+    content: MessageContent,
+    raw: Option<serde_json::Value>,
+    usage: Usage,
+) -> ChatResponse {
+    let model_iden = ModelIden::new(AdapterKind::OpenAI, "gpt-4o");
+    ChatResponse {
+        content,
+        reasoning_content: None,
+        model_iden: model_iden.clone(),
+        provider_model_iden: model_iden,
+        usage,
+        captured_raw_body: raw,
+    }
+}
+
+#[test]
+fn test_tool_choice_narrows_offered_tools() {
+    let all = vec!["get_weather", "get_time", "run_sql"];
+    assert_eq!(offered(serde_json::json!("auto")), all);
+    assert_eq!(offered(serde_json::json!("required")), all);
+    assert!(offered(serde_json::json!("none")).is_empty());
+    assert_eq!(
+        offered(serde_json::json!({"type": "function", "function": {"name": "get_time"}})),
+        vec!["get_time"]
+    );
+    assert_eq!(
+        offered(serde_json::json!({"type": "custom", "custom": {"name": "run_sql"}})),
+        vec!["run_sql"]
+    );
+    assert_eq!(
+        offered(serde_json::json!({
+            "type": "allowed_tools",
+            "allowed_tools": [{
+                "mode": "auto",
+                "tools": [
+                    {"type": "function", "function": {"name": "get_weather"}},
+                    {"type": "custom", "custom": {"name": "run_sql"}},
+                ],
+            }],
+        })),
+        vec!["get_weather", "run_sql"]
+    );
+}
+
+#[test]
+fn test_forced_tool_choice_is_unsupported() {
+    let refused = |tool_choice: serde_json::Value| {
+        unsupported_tool_choice(&request(
+            serde_json::json!({"tools": tools(), "tool_choice": tool_choice}),
+        ))
+    };
+    assert!(refused(serde_json::json!("required")).is_some());
+    assert!(
+        refused(serde_json::json!({"type": "function", "function": {"name": "get_time"}}))
+            .unwrap()
+            .contains("get_time")
+    );
+    assert!(
+        refused(serde_json::json!({"type": "custom", "custom": {"name": "run_sql"}})).is_some()
+    );
+    let allowed = |mode: &str| {
+        serde_json::json!({
+            "type": "allowed_tools",
+            "allowed_tools": [{
+                "mode": mode,
+                "tools": [{"type": "function", "function": {"name": "get_weather"}}],
+            }],
+        })
+    };
+    assert!(refused(allowed("required")).is_some());
+
+    assert!(refused(allowed("auto")).is_none());
+    assert!(refused(serde_json::json!("auto")).is_none());
+    assert!(refused(serde_json::json!("none")).is_none());
+    assert!(unsupported_tool_choice(&request(serde_json::json!({}))).is_none());
+}
+
+#[test]
+fn test_custom_tools_round_trip_as_custom_calls() {
+    let req = request(serde_json::json!({"tools": tools()}));
+    let genai_req = adapt_openai_to_genai(req.clone());
+    let run_sql = genai_req
+        .tools
+        .unwrap()
+        .into_iter()
+        .find(|tool| tool.name == "run_sql")
+        .unwrap();
+    assert_eq!(run_sql.description.as_deref(), Some("Runs a query"));
+    assert_eq!(
+        run_sql.schema.unwrap()["properties"]["input"]["type"],
+        "string"
+    );
+    assert_eq!(
+        custom_tool_names(&req),
+        HashSet::from(["run_sql".to_string()])
+    );
+
+    let content = MessageContent::from_tool_calls(vec![
+        ToolCall {
+            call_id: "call_1".to_string(),
+            fn_name: "run_sql".to_string(),
+            fn_arguments: serde_json::json!({"input": "SELECT 1"}),
+        },
+        ToolCall {
+            call_id: "call_2".to_string(),
+            fn_name: "get_time".to_string(),
+            fn_arguments: serde_json::json!({}),
+        },
+    ]);
+    let response = adapt_genai_to_openai(
+        genai_response(content, None, Usage::default()),
+        "gpt-4o".into(),
+        &req,
+    );
+
+    let choice = &response.choices[0];
+    assert_eq!(choice.finish_reason, Some(FinishReason::ToolCalls));
+    match choice.message.tool_calls.as_deref().unwrap() {
+        [
+            ChatCompletionMessageToolCalls::Custom(custom),
+            ChatCompletionMessageToolCalls::Function(function),
+        ] => {
+            assert_eq!(custom.custom_tool.name, "run_sql");
+            assert_eq!(custom.custom_tool.input, "SELECT 1");
+            assert_eq!(function.function.name, "get_time");
+        }
+        other => panic!("unexpected tool calls: {:?}", other),
+    }
+}
+
+#[test]
+#[allow(deprecated)]
+fn test_raw_body_supplies_refusal_and_identity() {
+    let raw = serde_json::json!({
+        "id": "chatcmpl-provider",
+        "object": "chat.completion",
+        "created": 1_700_000_000,
+        "system_fingerprint": "fp_123",
+        "choices": [{
+            "index": 0,
+            "message": {"role": "assistant", "content": null, "refusal": "I can't help with that."},
+            "finish_reason": "stop",
+        }],
+    });
+    let req = request(serde_json::json!({}));
+    let response = adapt_genai_to_openai(
+        genai_response(MessageContent::default(), Some(raw), Usage::default()),
+        "gpt-4o".into(),
+        &req,
+    );
+
+    assert_eq!(response.id, "chatcmpl-provider");
+    assert_eq!(response.created, 1_700_000_000);
+    assert_eq!(response.system_fingerprint.as_deref(), Some("fp_123"));
+    let message = &response.choices[0].message;
+    assert_eq!(message.content, None);
+    assert_eq!(message.refusal.as_deref(), Some("I can't help with that."));
+}
+
+#[test]
+fn test_user_media_parts_become_binary_content() {
+    let req = request(serde_json::json!({
+        "messages": [{
+            "role": "user",
+            "content": [
+                {"type": "text", "text": "Describe these"},
+                {"type": "image_url", "image_url": {"url": "data:image/png;base64,iVBORw0KGgo="}},
+                {"type": "image_url", "image_url": {"url": "https://example.com/cat.webp?size=large"}},
+                {"type": "input_audio", "input_audio": {"data": "UklGRg==", "format": "wav"}},
+                {"type": "file", "file": {"file_data": "data:application/pdf;base64,JVBERi0=", "filename": "a.pdf"}},
+            ],
+        }],
+    }));
+    let message = adapt_openai_to_genai(req).messages.remove(0);
+    assert!(matches!(message.role, ChatRole::User));
+
+    let parts = message.content.into_parts();
+    assert_eq!(parts.len(), 5);
+    assert_eq!(parts[0].as_text(), Some("Describe these"));
+
+    let binaries: Vec<_> = parts[1..]
+        .iter()
+        .map(|part| match part {
+            ContentPart::Binary(binary) => binary,
+            other => panic!("expected a binary part, got {:?}", other),
+        })
+        .collect();
+    assert_eq!(binaries[0].content_type, "image/png");
+    assert!(matches!(&binaries[0].source, BinarySource::Base64(data) if &**data == "iVBORw0KGgo="));
+    assert_eq!(binaries[1].content_type, "image/webp");
+    assert!(matches!(&binaries[1].source, BinarySource::Url(url) if url.starts_with("https://")));
+    assert_eq!(binaries[2].content_type, "audio/wav");
+    assert_eq!(binaries[3].content_type, "application/pdf");
+    assert_eq!(binaries[3].name.as_deref(), Some("a.pdf"));
+}
+
+#[test]
+fn test_merge_choices_reindexes_and_sums_usage() {
+    let single = |content: &str| -> CreateChatCompletionResponse {
+        serde_json::from_value(serde_json::json!({
+            "id": format!("chatcmpl-{}", content),
+            "object": "chat.completion",
+            "created": 0,
+            "model": "gpt-4o",
+            "choices": [{
+                "index": 0,
+                "message": {"role": "assistant", "content": content},
+                "finish_reason": "stop",
+            }],
+            "usage": {"prompt_tokens": 5, "completion_tokens": 3, "total_tokens": 8},
+        }))
+        .unwrap()
+    };
+
+    let merged = merge_choices(vec![single("a"), single("b"), single("c")]).unwrap();
+    assert_eq!(merged.id, "chatcmpl-a");
+    let contents: Vec<_> = merged
+        .choices
+        .iter()
+        .map(|c| (c.index, c.message.content.clone().unwrap()))
+        .collect();
+    assert_eq!(
+        contents,
+        vec![(0, "a".into()), (1, "b".into()), (2, "c".into())]
+    );
+    let usage = merged.usage.unwrap();
+    assert_eq!(
+        (
+            usage.prompt_tokens,
+            usage.completion_tokens,
+            usage.total_tokens
+        ),
+        (15, 9, 24)
+    );
+    assert!(merge_choices(Vec::new()).is_none());
+}
+
+#[test]
+fn test_finish_reason_from_raw_reads_each_provider() {
+    let cases = [
+        (
+            serde_json::json!({"choices": [{"finish_reason": "length"}]}),
+            FinishReason::Length,
+        ),
+        (
+            serde_json::json!({"stop_reason": "tool_use"}),
+            FinishReason::ToolCalls,
+        ),
+        (
+            serde_json::json!({"stop_reason": "end_turn"}),
+            FinishReason::Stop,
+        ),
+        (
+            serde_json::json!({"candidates": [{"finishReason": "SAFETY"}]}),
+            FinishReason::ContentFilter,
+        ),
+    ];
+    for (body, expected) in cases {
+        assert_eq!(finish_reason_from_raw(&body), Some(expected), "{}", body);
+    }
+    assert_eq!(finish_reason_from_raw(&serde_json::json!({})), None);
+}
+
+#[test]
+fn test_usage_is_kept_and_implies_length() {
+    let usage = Usage {
+        prompt_tokens: Some(12),
+        completion_tokens: Some(64),
+        total_tokens: Some(76),
+        ..Usage::default()
+    };
+
+    let converted = openai_usage(&usage).unwrap();
+    assert_eq!(converted.prompt_tokens, 12);
+    assert_eq!(converted.completion_tokens, 64);
+    assert_eq!(converted.total_tokens, 76);
+    assert!(openai_usage(&Usage::default()).is_none());
+
+    assert_eq!(
+        inferred_finish_reason(&[], &usage, Some(64)),
+        FinishReason::Length
+    );
+    assert_eq!(
+        inferred_finish_reason(&[], &usage, Some(100)),
+        FinishReason::Stop
+    );
+}
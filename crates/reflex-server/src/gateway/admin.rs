@@ -0,0 +1,527 @@
+//! Authenticated admin API (`/admin/...`).
+//!
+//! Requests must carry `Authorization: Bearer <REFLEX_ADMIN_TOKEN>`; without a
+//! configured token every admin route answers 401.
+//!
+//! Invalidation removes an entry from all three places it lives: the L1 map,
+//! the rkyv file on disk and the vector index point. Tenants are given either
+//! as the API key clients send (`tenant`) or as the hashed id used in storage
+//! paths (`tenant_id`). With a tenant registry, `tenant` may also be a
+//! registered tenant id.
+//!
+//! Browsing lists stored entries page by page and decodes a single entry
+//! (payload, timestamp, embedding norm, L1 residency) for debugging hits.
+
+use std::collections::HashSet;
+
+use async_openai::types::chat::CreateChatCompletionRequest;
+use axum::{
+    Json,
+    extract::{Path, Query, State},
+    http::HeaderMap,
+};
+use serde::{Deserialize, Serialize};
+use tracing::{info, instrument, warn};
+
+use crate::gateway::error::GatewayError;
+use crate::gateway::handler::StoreContext;
+use crate::gateway::payload::CachePayload;
+use crate::gateway::state::HandlerState;
+use crate::gateway::tenants::TenantRegistry;
+use reflex::cache::{BqSearchBackend, StorageLoader};
+use reflex::storage::StorageWriter;
+use reflex::vectordb::{embedding_bytes_to_f32, generate_point_id};
+
+/// Page size used when `/admin/entries` is called without `limit`.
+pub const DEFAULT_PAGE_SIZE: usize = 50;
+
+/// Largest page `/admin/entries` returns.
+pub const MAX_PAGE_SIZE: usize = 500;
+
+/// What an invalidation removed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InvalidationCounts {
+    /// Entries evicted from the in-memory L1 cache.
+    pub l1_removed: usize,
+    /// rkyv files deleted from storage.
+    pub files_removed: usize,
+    /// Point ids submitted for deletion to the vector index.
+    pub points_submitted: usize,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct InvalidateRequestBody {
+    #[serde(default)]
+    pub tenant: Option<String>,
+    #[serde(default)]
+    pub tenant_id: Option<u64>,
+    /// The chat completion request whose cached response should be dropped.
+    pub request: serde_json::Value,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct InvalidateKeyBody {
+    pub storage_key: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct InvalidateTenantBody {
+    #[serde(default)]
+    pub tenant: Option<String>,
+    #[serde(default)]
+    pub tenant_id: Option<u64>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct InvalidateAgeBody {
+    /// Entries stored more than this many seconds ago are removed.
+    pub older_than_secs: u64,
+    /// Restricts the sweep to one tenant (all tenants when both are absent).
+    #[serde(default)]
+    pub tenant: Option<String>,
+    #[serde(default)]
+    pub tenant_id: Option<u64>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ListEntriesQuery {
+    #[serde(default)]
+    pub tenant: Option<String>,
+    #[serde(default)]
+    pub tenant_id: Option<u64>,
+    #[serde(default)]
+    pub offset: usize,
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+/// One page of stored entries, ordered by storage key.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EntryPage {
+    pub entries: Vec<EntrySummary>,
+    /// Entries matching the query across all pages.
+    pub total: usize,
+    pub offset: usize,
+    pub limit: usize,
+    /// Offset of the following page; `None` on the last page.
+    pub next_offset: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EntrySummary {
+    pub storage_key: String,
+    pub tenant_id: u64,
+    /// Hex, as in the storage key.
+    pub context_hash: String,
+    /// Unix seconds; `None` when the file could not be read.
+    pub timestamp: Option<i64>,
+    pub semantic_request: Option<String>,
+    pub l1_resident: bool,
+}
+
+/// A single stored entry, decoded.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EntryDetail {
+    pub storage_key: String,
+    pub tenant_id: u64,
+    pub context_hash: String,
+    pub timestamp: i64,
+    pub l1_resident: bool,
+    pub embedding_dim: usize,
+    /// L2 norm of the stored embedding; `None` if its length is unexpected.
+    pub embedding_norm: Option<f32>,
+    pub payload_bytes: usize,
+    pub payload: Option<CachePayload>,
+    /// Why `payload` could not be decoded, if it could not.
+    pub payload_error: Option<String>,
+}
+
+/// An entry to remove, identified the way storage and the index key it.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct Target {
+    storage_key: String,
+    tenant_id: u64,
+    context_hash: u64,
+}
+
+/// `POST /admin/invalidate/request`: drops the entry an exact request would hit.
+#[instrument(skip_all)]
+pub async fn invalidate_request_handler<B, S>(
+    State(state): State<HandlerState<B, S>>,
+    headers: HeaderMap,
+    Json(body): Json<InvalidateRequestBody>,
+) -> Result<Json<InvalidationCounts>, GatewayError>
+where
+    B: BqSearchBackend + Clone + Send + Sync + 'static,
+    S: StorageLoader + StorageWriter + Clone + Send + Sync + 'static,
+{
+    require_admin(&state, &headers)?;
+
+    let tenant_id = resolve_tenant(
+        state.tenants.as_deref(),
+        body.tenant.as_deref(),
+        body.tenant_id,
+    )
+    .unwrap_or_else(|| reflex::hashing::hash_tenant_id("default"));
+    let mut request: CreateChatCompletionRequest = serde_json::from_value(body.request)
+        .map_err(|e| GatewayError::InvalidRequest(format!("Invalid request schema: {}", e)))?;
+    // Keyed as the chat handler keys it: a model-keyed route renames the model.
+    state.router.apply(&mut request);
+    let keyed = state.media.keyed(&request).await;
+    let ctx = StoreContext::for_tenant(tenant_id, &keyed.request)?;
+
+    let target = Target {
+        storage_key: ctx.storage_key(),
+        tenant_id,
+        context_hash: ctx.context_hash,
+    };
+    invalidate(&state, vec![target]).await.map(Json)
+}
+
+/// `POST /admin/invalidate/key`: drops one entry by storage key.
+#[instrument(skip_all)]
+pub async fn invalidate_key_handler<B, S>(
+    State(state): State<HandlerState<B, S>>,
+    headers: HeaderMap,
+    Json(body): Json<InvalidateKeyBody>,
+) -> Result<Json<InvalidationCounts>, GatewayError>
+where
+    B: BqSearchBackend + Clone + Send + Sync + 'static,
+    S: StorageLoader + StorageWriter + Clone + Send + Sync + 'static,
+{
+    require_admin(&state, &headers)?;
+
+    let target = target_for_key(&body.storage_key).ok_or_else(|| {
+        GatewayError::InvalidRequest(format!(
+            "Invalid storage key '{}': expected {{tenant_id}}/{{context_hash:016x}}.rkyv",
+            body.storage_key
+        ))
+    })?;
+    invalidate(&state, vec![target]).await.map(Json)
+}
+
+/// `POST /admin/invalidate/tenant`: drops every entry of one tenant.
+#[instrument(skip_all)]
+pub async fn invalidate_tenant_handler<B, S>(
+    State(state): State<HandlerState<B, S>>,
+    headers: HeaderMap,
+    Json(body): Json<InvalidateTenantBody>,
+) -> Result<Json<InvalidationCounts>, GatewayError>
+where
+    B: BqSearchBackend + Clone + Send + Sync + 'static,
+    S: StorageLoader + StorageWriter + Clone + Send + Sync + 'static,
+{
+    require_admin(&state, &headers)?;
+
+    let tenant_id = resolve_tenant(
+        state.tenants.as_deref(),
+        body.tenant.as_deref(),
+        body.tenant_id,
+    )
+    .ok_or_else(|| {
+        GatewayError::InvalidRequest("One of `tenant` or `tenant_id` is required".to_string())
+    })?;
+
+    let targets = list_targets(&state, Some(tenant_id)).await?;
+    invalidate(&state, targets).await.map(Json)
+}
+
+/// `POST /admin/invalidate/age`: drops entries stored more than `older_than_secs` ago.
+#[instrument(skip_all)]
+pub async fn invalidate_age_handler<B, S>(
+    State(state): State<HandlerState<B, S>>,
+    headers: HeaderMap,
+    Json(body): Json<InvalidateAgeBody>,
+) -> Result<Json<InvalidationCounts>, GatewayError>
+where
+    B: BqSearchBackend + Clone + Send + Sync + 'static,
+    S: StorageLoader + StorageWriter + Clone + Send + Sync + 'static,
+{
+    require_admin(&state, &headers)?;
+
+    let tenant_id = resolve_tenant(
+        state.tenants.as_deref(),
+        body.tenant.as_deref(),
+        body.tenant_id,
+    );
+    let cutoff = chrono::Utc::now()
+        .timestamp()
+        .saturating_sub(body.older_than_secs.min(i64::MAX as u64) as i64);
+
+    let storage = state.tiered_cache.l2().storage();
+    let mut targets = Vec::new();
+    for target in list_targets(&state, tenant_id).await? {
+        match storage.load(&target.storage_key, target.tenant_id).await {
+            Some(entry) if entry.timestamp < cutoff => targets.push(target),
+            Some(_) => {}
+            None => warn!(storage_key = %target.storage_key, "Skipping unreadable entry"),
+        }
+    }
+
+    invalidate(&state, targets).await.map(Json)
+}
+
+/// `GET /admin/entries`: lists stored entries, optionally for one tenant.
+#[instrument(skip_all)]
+pub async fn list_entries_handler<B, S>(
+    State(state): State<HandlerState<B, S>>,
+    headers: HeaderMap,
+    Query(query): Query<ListEntriesQuery>,
+) -> Result<Json<EntryPage>, GatewayError>
+where
+    B: BqSearchBackend + Clone + Send + Sync + 'static,
+    S: StorageLoader + StorageWriter + Clone + Send + Sync + 'static,
+{
+    require_admin(&state, &headers)?;
+
+    let tenant_id = resolve_tenant(
+        state.tenants.as_deref(),
+        query.tenant.as_deref(),
+        query.tenant_id,
+    );
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_PAGE_SIZE)
+        .clamp(1, MAX_PAGE_SIZE);
+
+    let mut targets = list_targets(&state, tenant_id).await?;
+    targets.sort_unstable_by(|a, b| a.storage_key.cmp(&b.storage_key));
+    let total = targets.len();
+
+    let resident = l1_resident_set(&state);
+    let storage = state.tiered_cache.l2().storage();
+    let mut entries = Vec::new();
+    for target in targets.into_iter().skip(query.offset).take(limit) {
+        let entry = storage.load(&target.storage_key, target.tenant_id).await;
+        let semantic_request = entry.as_ref().and_then(|e| {
+            serde_json::from_slice::<CachePayload>(&e.payload_blob)
+                .ok()
+                .map(|p| p.semantic_request)
+        });
+        entries.push(EntrySummary {
+            l1_resident: resident.contains(&(target.tenant_id, target.context_hash)),
+            context_hash: format!("{:016x}", target.context_hash),
+            timestamp: entry.as_ref().map(|e| e.timestamp),
+            semantic_request,
+            storage_key: target.storage_key,
+            tenant_id: target.tenant_id,
+        });
+    }
+
+    let end = query.offset.saturating_add(entries.len());
+    Ok(Json(EntryPage {
+        entries,
+        total,
+        offset: query.offset,
+        limit,
+        next_offset: (end < total).then_some(end),
+    }))
+}
+
+/// `GET /admin/entries/{tenant_id}/{context_hash}`: decodes one stored entry.
+#[instrument(skip_all)]
+pub async fn get_entry_handler<B, S>(
+    State(state): State<HandlerState<B, S>>,
+    headers: HeaderMap,
+    Path((tenant_id, context_hash)): Path<(u64, String)>,
+) -> Result<Json<EntryDetail>, GatewayError>
+where
+    B: BqSearchBackend + Clone + Send + Sync + 'static,
+    S: StorageLoader + StorageWriter + Clone + Send + Sync + 'static,
+{
+    require_admin(&state, &headers)?;
+
+    let hash = context_hash.strip_suffix(".rkyv").unwrap_or(&context_hash);
+    let context_hash = u64::from_str_radix(hash, 16).map_err(|_| {
+        GatewayError::InvalidRequest(format!("Invalid context hash '{}': expected hex", hash))
+    })?;
+    let storage_key = StoreContext::storage_key_for(tenant_id, context_hash);
+
+    let entry = state
+        .tiered_cache
+        .l2()
+        .storage()
+        .load(&storage_key, tenant_id)
+        .await
+        .ok_or_else(|| GatewayError::NotFound(format!("no entry at '{}'", storage_key)))?;
+
+    let embedding_norm = embedding_bytes_to_f32(&entry.embedding)
+        .ok()
+        .map(|v| v.iter().map(|x| x * x).sum::<f32>().sqrt());
+    let (payload, payload_error) = match serde_json::from_slice::<CachePayload>(&entry.payload_blob)
+    {
+        Ok(payload) => (Some(payload), None),
+        Err(e) => (None, Some(e.to_string())),
+    };
+
+    Ok(Json(EntryDetail {
+        storage_key,
+        tenant_id,
+        context_hash: format!("{:016x}", context_hash),
+        timestamp: entry.timestamp,
+        l1_resident: l1_resident_set(&state).contains(&(tenant_id, context_hash)),
+        embedding_dim: entry.embedding.len() / 2,
+        embedding_norm,
+        payload_bytes: entry.payload_blob.len(),
+        payload,
+        payload_error,
+    }))
+}
+
+/// Rejects the request unless it carries the configured admin bearer token.
+pub(crate) fn require_admin<B, S>(
+    state: &HandlerState<B, S>,
+    headers: &HeaderMap,
+) -> Result<(), GatewayError>
+where
+    B: BqSearchBackend + Clone + Send + Sync + 'static,
+    S: StorageLoader + Clone + Send + Sync + 'static,
+{
+    let expected = state.admin_token.as_deref().ok_or_else(|| {
+        GatewayError::Unauthorized("admin API is disabled; set REFLEX_ADMIN_TOKEN".to_string())
+    })?;
+
+    let presented = headers
+        .get("Authorization")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .map(str::trim)
+        .unwrap_or_default();
+
+    // blake3::Hash equality is constant-time.
+    if blake3::hash(presented.as_bytes()) == blake3::hash(expected.as_bytes()) {
+        Ok(())
+    } else {
+        Err(GatewayError::Unauthorized(
+            "invalid admin token".to_string(),
+        ))
+    }
+}
+
+/// Resolves a tenant given as an API key or as an already hashed id.
+///
+/// Keys found in `registry` map to their tenant; anything else is hashed, which
+/// also resolves registered tenant ids.
+pub(crate) fn resolve_tenant(
+    registry: Option<&TenantRegistry>,
+    tenant: Option<&str>,
+    tenant_id: Option<u64>,
+) -> Option<u64> {
+    tenant_id.or_else(|| {
+        let tenant = tenant?.trim();
+        registry
+            .and_then(|r| r.lookup(tenant))
+            .map(|t| t.tenant_id)
+            .or_else(|| Some(reflex::hashing::hash_tenant_id(tenant)))
+    })
+}
+
+fn target_for_key(storage_key: &str) -> Option<Target> {
+    let (tenant_id, context_hash) = StoreContext::parse_storage_key(storage_key)?;
+    Some(Target {
+        storage_key: storage_key.to_string(),
+        tenant_id,
+        context_hash,
+    })
+}
+
+/// `(tenant_id, context_hash)` of every entry currently resident in L1.
+fn l1_resident_set<B, S>(state: &HandlerState<B, S>) -> HashSet<(u64, u64)>
+where
+    B: BqSearchBackend + Clone + Send + Sync + 'static,
+    S: StorageLoader + Clone + Send + Sync + 'static,
+{
+    let mut resident = HashSet::new();
+    state.tiered_cache.for_each_l1_entry(|entry| {
+        resident.insert((entry.tenant_id.to_native(), entry.context_hash.to_native()));
+    });
+    resident
+}
+
+async fn list_targets<B, S>(
+    state: &HandlerState<B, S>,
+    tenant_id: Option<u64>,
+) -> Result<Vec<Target>, GatewayError>
+where
+    B: BqSearchBackend + Clone + Send + Sync + 'static,
+    S: StorageLoader + StorageWriter + Clone + Send + Sync + 'static,
+{
+    let storage = state.tiered_cache.l2().storage().clone();
+    let keys = tokio::task::spawn_blocking(move || storage.list_keys(tenant_id))
+        .await
+        .map_err(|e| GatewayError::InternalError(format!("Storage list task failed: {}", e)))?
+        .map_err(|e| GatewayError::StorageError(e.to_string()))?;
+
+    Ok(keys.iter().filter_map(|key| target_for_key(key)).collect())
+}
+
+/// Removes the entry stored under `storage_key`; a malformed key removes nothing.
+pub(crate) async fn invalidate_storage_key<B, S>(
+    state: &HandlerState<B, S>,
+    storage_key: &str,
+) -> Result<InvalidationCounts, GatewayError>
+where
+    B: BqSearchBackend + Clone + Send + Sync + 'static,
+    S: StorageLoader + StorageWriter + Clone + Send + Sync + 'static,
+{
+    match target_for_key(storage_key) {
+        Some(target) => invalidate(state, vec![target]).await,
+        None => Ok(InvalidationCounts::default()),
+    }
+}
+
+/// Removes `targets` from L1, storage and the vector index.
+async fn invalidate<B, S>(
+    state: &HandlerState<B, S>,
+    targets: Vec<Target>,
+) -> Result<InvalidationCounts, GatewayError>
+where
+    B: BqSearchBackend + Clone + Send + Sync + 'static,
+    S: StorageLoader + StorageWriter + Clone + Send + Sync + 'static,
+{
+    if targets.is_empty() {
+        return Ok(InvalidationCounts::default());
+    }
+
+    let wanted: HashSet<(u64, u64)> = targets
+        .iter()
+        .map(|t| (t.tenant_id, t.context_hash))
+        .collect();
+    let l1_removed = state.tiered_cache.remove_l1_where(|entry| {
+        wanted.contains(&(entry.tenant_id.to_native(), entry.context_hash.to_native()))
+    });
+
+    let storage = state.tiered_cache.l2().storage().clone();
+    let keys: Vec<String> = targets.iter().map(|t| t.storage_key.clone()).collect();
+    let files_removed = tokio::task::spawn_blocking(move || {
+        let mut removed = 0;
+        for key in &keys {
+            if storage.delete(key)? {
+                removed += 1;
+            }
+        }
+        Ok::<_, reflex::storage::StorageError>(removed)
+    })
+    .await
+    .map_err(|e| GatewayError::InternalError(format!("Storage delete task failed: {}", e)))?
+    .map_err(|e| GatewayError::StorageError(e.to_string()))?;
+
+    let point_ids: Vec<u64> = wanted
+        .iter()
+        .map(|(tenant_id, context_hash)| generate_point_id(*tenant_id, *context_hash))
+        .collect();
+    let points_submitted = point_ids.len();
+    state
+        .bq_client
+        .delete_points(&state.collection_name, point_ids)
+        .await
+        .map_err(|e| GatewayError::StorageError(format!("Vector index delete failed: {}", e)))?;
+
+    let counts = InvalidationCounts {
+        l1_removed,
+        files_removed,
+        points_submitted,
+    };
+    info!(?counts, "Cache entries invalidated");
+    Ok(counts)
+}
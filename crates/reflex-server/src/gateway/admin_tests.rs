@@ -0,0 +1,69 @@
+//! Tests for admin API helpers.
+
+use async_openai::types::chat::CreateChatCompletionRequest;
+use serde_json::json;
+
+use crate::gateway::admin::resolve_tenant;
+use crate::gateway::handler::StoreContext;
+use crate::gateway::tenants::TenantRegistry;
+
+#[test]
+fn test_storage_key_round_trips() {
+    let request: CreateChatCompletionRequest = serde_json::from_value(json!({
+        "model": "gpt-4o",
+        "messages": [{"role": "user", "content": "Hello"}]
+    }))
+    .unwrap();
+    let ctx = StoreContext::for_tenant(7, &request).unwrap();
+
+    assert_eq!(
+        StoreContext::parse_storage_key(&ctx.storage_key()),
+        Some((7, ctx.context_hash))
+    );
+}
+
+#[test]
+fn test_parse_storage_key_rejects_malformed_keys() {
+    for key in [
+        "",
+        "7",
+        "7/abc",
+        "tenant/00000000000000ff.rkyv",
+        "7/not-hex.rkyv",
+        "../7/00000000000000ff.rkyv",
+    ] {
+        assert_eq!(StoreContext::parse_storage_key(key), None, "{key}");
+    }
+}
+
+#[test]
+fn test_resolve_tenant_prefers_hashed_id() {
+    assert_eq!(resolve_tenant(None, Some("sk-a"), Some(9)), Some(9));
+    assert_eq!(
+        resolve_tenant(None, Some(" sk-a "), None),
+        Some(reflex::hashing::hash_tenant_id("sk-a"))
+    );
+    assert_eq!(resolve_tenant(None, None, None), None);
+}
+
+#[test]
+fn test_resolve_tenant_maps_registered_keys() {
+    let registry = TenantRegistry::from_toml_str(
+        r#"
+        [[tenants]]
+        id = "acme"
+        api_keys = ["sk-acme"]
+        "#,
+    )
+    .unwrap();
+    let acme = reflex::hashing::hash_tenant_id("acme");
+
+    assert_eq!(
+        resolve_tenant(Some(&registry), Some("sk-acme"), None),
+        Some(acme)
+    );
+    assert_eq!(
+        resolve_tenant(Some(&registry), Some("acme"), None),
+        Some(acme)
+    );
+}
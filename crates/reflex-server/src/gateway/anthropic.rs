@@ -0,0 +1,615 @@
+//! Anthropic Messages API (`POST /v1/messages`) ingress.
+//!
+//! Requests are converted to the canonical chat completion form before keying,
+//! so a Messages request and an OpenAI request with the same content derive
+//! the same semantic text and share cache entries. The provider call goes
+//! through the existing OpenAI → genai adapter.
+
+use async_openai::types::chat::{
+    ChatCompletionMessageToolCalls, CreateChatCompletionRequest, CreateChatCompletionResponse,
+    FinishReason,
+};
+use axum::{
+    Json,
+    extract::State,
+    http::{HeaderMap, HeaderValue},
+    response::sse::Event,
+    response::{IntoResponse, Response},
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use tracing::{debug, instrument};
+
+use crate::gateway::cache_control::apply_cache_headers;
+use crate::gateway::error::GatewayError;
+use crate::gateway::explain::debug_headers;
+use crate::gateway::feedback::apply_entry_header;
+use crate::gateway::handler::{admit_request, complete_request, report_savings, store_context};
+use crate::gateway::state::HandlerState;
+use crate::gateway::streaming::{
+    StreamEncoder, StreamMeta, ToolCallDelta, json_event, serve_stream,
+};
+use reflex::cache::{BqSearchBackend, REFLEX_STATUS_HEADER, StorageLoader};
+use reflex::storage::StorageWriter;
+
+/// Messages API request body.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MessagesRequest {
+    pub model: String,
+    pub max_tokens: u32,
+    pub messages: Vec<Message>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub system: Option<MessageContent>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tools: Option<Vec<Tool>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_choice: Option<ToolChoice>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stop_sequences: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub top_k: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stream: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<Value>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    User,
+    Assistant,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Message {
+    pub role: Role,
+    pub content: MessageContent,
+}
+
+/// A plain string or a list of content blocks.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum MessageContent {
+    Text(String),
+    Blocks(Vec<ContentBlock>),
+}
+
+impl MessageContent {
+    fn blocks(&self) -> Vec<ContentBlock> {
+        match self {
+            MessageContent::Text(text) => vec![ContentBlock::Text { text: text.clone() }],
+            MessageContent::Blocks(blocks) => blocks.clone(),
+        }
+    }
+
+    fn joined_text(&self) -> String {
+        match self {
+            MessageContent::Text(text) => text.clone(),
+            MessageContent::Blocks(blocks) => blocks
+                .iter()
+                .filter_map(|b| match b {
+                    ContentBlock::Text { text } => Some(text.as_str()),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+                .join("\n"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ContentBlock {
+    Text {
+        text: String,
+    },
+    Image {
+        source: ImageSource,
+    },
+    ToolUse {
+        id: String,
+        name: String,
+        input: Value,
+    },
+    ToolResult {
+        tool_use_id: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        content: Option<MessageContent>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        is_error: Option<bool>,
+    },
+    /// Block types the gateway does not translate (documents, thinking, ...).
+    #[serde(other)]
+    Unsupported,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ImageSource {
+    Base64 { media_type: String, data: String },
+    Url { url: String },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Tool {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Absent for Anthropic server tools, which are not forwarded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub input_schema: Option<Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ToolChoice {
+    Auto,
+    Any,
+    Tool { name: String },
+    None,
+}
+
+/// Messages API response body.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MessagesResponse {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub role: Role,
+    pub model: String,
+    pub content: Vec<ContentBlock>,
+    pub stop_reason: Option<String>,
+    pub stop_sequence: Option<String>,
+    pub usage: Usage,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Usage {
+    pub input_tokens: u32,
+    pub output_tokens: u32,
+}
+
+#[instrument(skip(state, request, headers), fields(model = tracing::field::Empty))]
+pub async fn messages_handler<B, S>(
+    State(state): State<HandlerState<B, S>>,
+    headers: HeaderMap,
+    Json(request): Json<Value>,
+) -> Response
+where
+    B: BqSearchBackend + Clone + Send + Sync + 'static,
+    S: StorageLoader + StorageWriter + Clone + Send + Sync + 'static,
+{
+    match handle_messages(state, headers, request).await {
+        Ok(response) => response,
+        Err(e) => error_response(e),
+    }
+}
+
+async fn handle_messages<B, S>(
+    state: HandlerState<B, S>,
+    headers: HeaderMap,
+    request: Value,
+) -> Result<Response, GatewayError>
+where
+    B: BqSearchBackend + Clone + Send + Sync + 'static,
+    S: StorageLoader + StorageWriter + Clone + Send + Sync + 'static,
+{
+    let tenant = admit_request(&state, &headers)?;
+    let request: MessagesRequest = serde_json::from_value(request)
+        .map_err(|e| GatewayError::InvalidRequest(format!("Invalid request schema: {}", e)))?;
+    tracing::Span::current().record("model", tracing::field::display(&request.model));
+
+    let mut chat_request = messages_to_chat_request(&request)?;
+    let route = state.router.apply(&mut chat_request);
+    let store_ctx = store_context(&state, tenant, &headers, &chat_request, route).await?;
+
+    debug!(hash = %store_ctx.l1_key, "Processing messages request");
+    let trace = store_ctx.debug_trace.clone();
+
+    if request.stream.unwrap_or(false) {
+        let mut response = serve_stream(
+            state,
+            chat_request,
+            store_ctx,
+            MessagesStreamEncoder::default(),
+        )
+        .await?;
+        response.headers_mut().extend(debug_headers(trace.as_ref()));
+        return Ok(response);
+    }
+
+    let outcome = complete_request(&state, chat_request, &store_ctx).await?;
+
+    let mut response = Json(chat_response_to_messages(&outcome.payload.response)).into_response();
+    response.headers_mut().insert(
+        REFLEX_STATUS_HEADER,
+        HeaderValue::from_static(outcome.status.as_header_value()),
+    );
+    report_savings(
+        &state,
+        &store_ctx,
+        &outcome.payload.response,
+        outcome.status,
+        response.headers_mut(),
+    );
+    apply_entry_header(&outcome, response.headers_mut());
+    response.headers_mut().extend(debug_headers(trace.as_ref()));
+    apply_cache_headers(
+        response.headers_mut(),
+        &store_ctx.directives,
+        outcome.stored_at,
+    );
+    Ok(response)
+}
+
+/// Renders a [`GatewayError`] as a Messages API error body, keeping its status
+/// code and `X-Reflex-Status` header.
+fn error_response(err: GatewayError) -> Response {
+    let message = err.to_string();
+    let gateway_response = err.into_response();
+    let status = gateway_response.status();
+
+    let error_type = match status.as_u16() {
+        400 => "invalid_request_error",
+        401 => "authentication_error",
+        403 => "permission_error",
+        404 => "not_found_error",
+        429 => "rate_limit_error",
+        _ => "api_error",
+    };
+
+    let body = Json(json!({
+        "type": "error",
+        "error": { "type": error_type, "message": message },
+    }));
+
+    (status, gateway_response.headers().clone(), body).into_response()
+}
+
+/// Converts a Messages request to the canonical chat completion request.
+pub fn messages_to_chat_request(
+    request: &MessagesRequest,
+) -> Result<CreateChatCompletionRequest, GatewayError> {
+    let mut messages = Vec::new();
+
+    if let Some(system) = &request.system {
+        messages.push(json!({ "role": "system", "content": system.joined_text() }));
+    }
+
+    for message in &request.messages {
+        match message.role {
+            Role::User => push_user_message(&mut messages, &message.content),
+            Role::Assistant => messages.push(assistant_message(&message.content)),
+        }
+    }
+
+    let mut chat = json!({
+        "model": request.model,
+        "messages": messages,
+        "max_completion_tokens": request.max_tokens,
+    });
+
+    if let Some(tools) = &request.tools {
+        let tools: Vec<Value> = tools
+            .iter()
+            .filter_map(|tool| {
+                let Some(schema) = &tool.input_schema else {
+                    debug!(tool = %tool.name, "Skipping server tool without input_schema");
+                    return None;
+                };
+                let mut function = json!({ "name": tool.name, "parameters": schema });
+                if let Some(description) = &tool.description {
+                    function["description"] = Value::from(description.as_str());
+                }
+                Some(json!({ "type": "function", "function": function }))
+            })
+            .collect();
+        if !tools.is_empty() {
+            chat["tools"] = Value::Array(tools);
+        }
+    }
+
+    if let Some(tool_choice) = &request.tool_choice {
+        chat["tool_choice"] = match tool_choice {
+            ToolChoice::Auto => json!("auto"),
+            ToolChoice::Any => json!("required"),
+            ToolChoice::None => json!("none"),
+            ToolChoice::Tool { name } => {
+                json!({ "type": "function", "function": { "name": name } })
+            }
+        };
+    }
+
+    if let Some(stop) = &request.stop_sequences {
+        chat["stop"] = json!(stop);
+    }
+    if let Some(temperature) = request.temperature {
+        chat["temperature"] = json!(temperature);
+    }
+    if let Some(top_p) = request.top_p {
+        chat["top_p"] = json!(top_p);
+    }
+    if let Some(stream) = request.stream {
+        chat["stream"] = json!(stream);
+    }
+
+    serde_json::from_value(chat)
+        .map_err(|e| GatewayError::InvalidRequest(format!("Unsupported message content: {}", e)))
+}
+
+/// Tool results become `tool` messages ahead of the remaining user content.
+fn push_user_message(messages: &mut Vec<Value>, content: &MessageContent) {
+    if let MessageContent::Text(text) = content {
+        messages.push(json!({ "role": "user", "content": text }));
+        return;
+    }
+
+    let mut parts = Vec::new();
+    for block in content.blocks() {
+        match block {
+            ContentBlock::ToolResult {
+                tool_use_id,
+                content,
+                ..
+            } => messages.push(json!({
+                "role": "tool",
+                "tool_call_id": tool_use_id,
+                "content": content.map(|c| c.joined_text()).unwrap_or_default(),
+            })),
+            ContentBlock::Text { text } => parts.push(json!({ "type": "text", "text": text })),
+            ContentBlock::Image { source } => {
+                let url = match source {
+                    ImageSource::Base64 { media_type, data } => {
+                        format!("data:{};base64,{}", media_type, data)
+                    }
+                    ImageSource::Url { url } => url,
+                };
+                parts.push(json!({ "type": "image_url", "image_url": { "url": url } }));
+            }
+            ContentBlock::ToolUse { .. } | ContentBlock::Unsupported => {}
+        }
+    }
+
+    // A lone text block is keyed like a plain string so it matches OpenAI clients.
+    match parts.as_slice() {
+        [] => {}
+        [single] if single["type"] == "text" => {
+            messages.push(json!({ "role": "user", "content": single["text"] }));
+        }
+        _ => messages.push(json!({ "role": "user", "content": parts })),
+    }
+}
+
+fn assistant_message(content: &MessageContent) -> Value {
+    let text = content.joined_text();
+    let tool_calls: Vec<Value> = content
+        .blocks()
+        .into_iter()
+        .filter_map(|block| match block {
+            ContentBlock::ToolUse { id, name, input } => Some(json!({
+                "id": id,
+                "type": "function",
+                "function": { "name": name, "arguments": input.to_string() },
+            })),
+            _ => None,
+        })
+        .collect();
+
+    let mut message = json!({ "role": "assistant" });
+    if !text.is_empty() {
+        message["content"] = Value::from(text);
+    }
+    if !tool_calls.is_empty() {
+        message["tool_calls"] = Value::Array(tool_calls);
+    }
+    message
+}
+
+/// Converts a (possibly cached) chat completion response to a Messages response.
+pub fn chat_response_to_messages(response: &CreateChatCompletionResponse) -> MessagesResponse {
+    let mut content = Vec::new();
+    let mut stop_reason = None;
+
+    if let Some(choice) = response.choices.first() {
+        if let Some(text) = choice.message.content.as_deref()
+            && !text.is_empty()
+        {
+            content.push(ContentBlock::Text {
+                text: text.to_string(),
+            });
+        }
+
+        for call in choice.message.tool_calls.iter().flatten() {
+            if let ChatCompletionMessageToolCalls::Function(call) = call {
+                content.push(ContentBlock::ToolUse {
+                    id: call.id.clone(),
+                    name: call.function.name.clone(),
+                    input: parse_arguments(&call.function.arguments),
+                });
+            }
+        }
+
+        stop_reason = Some(stop_reason_for(choice.finish_reason).to_string());
+    }
+
+    let usage = response
+        .usage
+        .as_ref()
+        .map(|u| Usage {
+            input_tokens: u.prompt_tokens,
+            output_tokens: u.completion_tokens,
+        })
+        .unwrap_or_default();
+
+    MessagesResponse {
+        id: message_id(&response.id),
+        kind: "message".to_string(),
+        role: Role::Assistant,
+        model: response.model.clone(),
+        content,
+        stop_reason,
+        stop_sequence: None,
+        usage,
+    }
+}
+
+fn stop_reason_for(finish_reason: Option<FinishReason>) -> &'static str {
+    match finish_reason {
+        Some(FinishReason::Length) => "max_tokens",
+        Some(FinishReason::ToolCalls) | Some(FinishReason::FunctionCall) => "tool_use",
+        Some(FinishReason::ContentFilter) => "refusal",
+        Some(FinishReason::Stop) | None => "end_turn",
+    }
+}
+
+fn message_id(chat_id: &str) -> String {
+    format!(
+        "msg_{}",
+        chat_id.strip_prefix("chatcmpl-").unwrap_or(chat_id)
+    )
+}
+
+fn parse_arguments(arguments: &str) -> Value {
+    serde_json::from_str(arguments).unwrap_or_else(|_| Value::String(arguments.to_string()))
+}
+
+/// Messages API SSE: `message_start`, content block events, `message_delta`, `message_stop`.
+#[derive(Default)]
+pub(crate) struct MessagesStreamEncoder {
+    /// Index and kind of the content block currently open, if any.
+    open_block: Option<(usize, OpenBlock)>,
+    next_block: usize,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum OpenBlock {
+    Text,
+    ToolUse(usize),
+}
+
+impl MessagesStreamEncoder {
+    fn close_block(&mut self) -> Vec<Event> {
+        match self.open_block.take() {
+            Some((index, _)) => vec![json_event(
+                Some("content_block_stop"),
+                &json!({ "type": "content_block_stop", "index": index }),
+            )],
+            None => Vec::new(),
+        }
+    }
+
+    /// Closes the open block if it differs from `kind` and opens a new one.
+    fn ensure_block(&mut self, kind: OpenBlock, content_block: Value) -> (usize, Vec<Event>) {
+        if let Some((index, open)) = self.open_block
+            && open == kind
+        {
+            return (index, Vec::new());
+        }
+
+        let mut events = self.close_block();
+        let index = self.next_block;
+        self.next_block += 1;
+        self.open_block = Some((index, kind));
+        events.push(json_event(
+            Some("content_block_start"),
+            &json!({
+                "type": "content_block_start",
+                "index": index,
+                "content_block": content_block,
+            }),
+        ));
+        (index, events)
+    }
+}
+
+impl StreamEncoder for MessagesStreamEncoder {
+    fn begin(&mut self, meta: &StreamMeta) -> Vec<Event> {
+        vec![json_event(
+            Some("message_start"),
+            &json!({
+                "type": "message_start",
+                "message": {
+                    "id": message_id(&meta.id),
+                    "type": "message",
+                    "role": "assistant",
+                    "model": meta.model,
+                    "content": [],
+                    "stop_reason": null,
+                    "stop_sequence": null,
+                    "usage": { "input_tokens": 0, "output_tokens": 0 },
+                },
+            }),
+        )]
+    }
+
+    fn text(&mut self, text: &str) -> Vec<Event> {
+        let (index, mut events) =
+            self.ensure_block(OpenBlock::Text, json!({ "type": "text", "text": "" }));
+        events.push(json_event(
+            Some("content_block_delta"),
+            &json!({
+                "type": "content_block_delta",
+                "index": index,
+                "delta": { "type": "text_delta", "text": text },
+            }),
+        ));
+        events
+    }
+
+    fn tool_call(&mut self, delta: &ToolCallDelta) -> Vec<Event> {
+        let (index, mut events) = self.ensure_block(
+            OpenBlock::ToolUse(delta.index),
+            json!({
+                "type": "tool_use",
+                "id": delta.id.as_deref().unwrap_or_default(),
+                "name": delta.name.as_deref().unwrap_or_default(),
+                "input": {},
+            }),
+        );
+        if !delta.arguments.is_empty() {
+            events.push(json_event(
+                Some("content_block_delta"),
+                &json!({
+                    "type": "content_block_delta",
+                    "index": index,
+                    "delta": { "type": "input_json_delta", "partial_json": delta.arguments },
+                }),
+            ));
+        }
+        events
+    }
+
+    fn finish(&mut self, response: &CreateChatCompletionResponse) -> Vec<Event> {
+        let mut events = self.close_block();
+        let message = chat_response_to_messages(response);
+
+        events.push(json_event(
+            Some("message_delta"),
+            &json!({
+                "type": "message_delta",
+                "delta": { "stop_reason": message.stop_reason, "stop_sequence": null },
+                "usage": { "output_tokens": message.usage.output_tokens },
+            }),
+        ));
+        events.push(json_event(
+            Some("message_stop"),
+            &json!({ "type": "message_stop" }),
+        ));
+        events
+    }
+
+    fn error(&mut self, message: &str) -> Vec<Event> {
+        vec![json_event(
+            Some("error"),
+            &json!({
+                "type": "error",
+                "error": { "type": "api_error", "message": message },
+            }),
+        )]
+    }
+}
@@ -0,0 +1,138 @@
+//! Tests for Messages API request/response conversion.
+
+use async_openai::types::chat::{
+    ChatCompletionRequestMessage, ChatCompletionRequestUserMessageContent,
+    CreateChatCompletionResponse,
+};
+use serde_json::{Value, json};
+
+use crate::gateway::anthropic::{
+    MessagesRequest, chat_response_to_messages, messages_to_chat_request,
+};
+
+fn parse(value: Value) -> MessagesRequest {
+    serde_json::from_value(value).expect("valid messages request")
+}
+
+#[test]
+fn test_system_and_plain_text_map_to_chat_messages() {
+    let chat = messages_to_chat_request(&parse(json!({
+        "model": "claude-sonnet-4-5",
+        "max_tokens": 256,
+        "system": "Be brief.",
+        "messages": [{"role": "user", "content": "Hello"}]
+    })))
+    .unwrap();
+
+    assert_eq!(chat.messages.len(), 2);
+    assert!(matches!(
+        chat.messages[0],
+        ChatCompletionRequestMessage::System(_)
+    ));
+    assert_eq!(chat.max_completion_tokens, Some(256));
+}
+
+#[test]
+fn test_single_text_block_keys_like_plain_string() {
+    let from_string = messages_to_chat_request(&parse(json!({
+        "model": "m", "max_tokens": 1,
+        "messages": [{"role": "user", "content": "Hello"}]
+    })))
+    .unwrap();
+    let from_block = messages_to_chat_request(&parse(json!({
+        "model": "m", "max_tokens": 1,
+        "messages": [{"role": "user", "content": [{"type": "text", "text": "Hello"}]}]
+    })))
+    .unwrap();
+
+    let ChatCompletionRequestMessage::User(user) = &from_block.messages[0] else {
+        panic!("expected user message");
+    };
+    assert!(matches!(
+        user.content,
+        ChatCompletionRequestUserMessageContent::Text(_)
+    ));
+    assert_eq!(
+        serde_json::to_value(&from_string).unwrap(),
+        serde_json::to_value(&from_block).unwrap()
+    );
+}
+
+#[test]
+fn test_tool_use_and_tool_result_round_trip() {
+    let chat = messages_to_chat_request(&parse(json!({
+        "model": "m", "max_tokens": 64,
+        "tools": [{"name": "get_weather", "input_schema": {"type": "object"}}],
+        "tool_choice": {"type": "tool", "name": "get_weather"},
+        "messages": [
+            {"role": "user", "content": "Weather in Paris?"},
+            {"role": "assistant", "content": [
+                {"type": "tool_use", "id": "toolu_1", "name": "get_weather", "input": {"city": "Paris"}}
+            ]},
+            {"role": "user", "content": [
+                {"type": "tool_result", "tool_use_id": "toolu_1", "content": "18C"}
+            ]}
+        ]
+    })))
+    .unwrap();
+
+    let value = serde_json::to_value(&chat).unwrap();
+    assert_eq!(value["messages"][1]["tool_calls"][0]["id"], "toolu_1");
+    assert_eq!(
+        value["messages"][1]["tool_calls"][0]["function"]["arguments"],
+        r#"{"city":"Paris"}"#
+    );
+    assert_eq!(value["messages"][2]["role"], "tool");
+    assert_eq!(value["messages"][2]["tool_call_id"], "toolu_1");
+    assert_eq!(value["tool_choice"]["function"]["name"], "get_weather");
+    assert_eq!(value["tools"][0]["function"]["name"], "get_weather");
+}
+
+#[test]
+fn test_server_tools_without_schema_are_skipped() {
+    let chat = messages_to_chat_request(&parse(json!({
+        "model": "m", "max_tokens": 1,
+        "tools": [{"type": "web_search_20250305", "name": "web_search"}],
+        "messages": [{"role": "user", "content": "hi"}]
+    })))
+    .unwrap();
+
+    assert!(chat.tools.is_none());
+}
+
+#[test]
+fn test_chat_response_maps_tool_calls_and_stop_reason() {
+    let response: CreateChatCompletionResponse = serde_json::from_value(json!({
+        "id": "chatcmpl-abc",
+        "object": "chat.completion",
+        "created": 1,
+        "model": "m",
+        "choices": [{
+            "index": 0,
+            "message": {
+                "role": "assistant",
+                "content": "Checking.",
+                "tool_calls": [{
+                    "id": "call_1",
+                    "type": "function",
+                    "function": {"name": "lookup", "arguments": "{\"q\":1}"}
+                }]
+            },
+            "finish_reason": "tool_calls"
+        }],
+        "usage": {"prompt_tokens": 5, "completion_tokens": 7, "total_tokens": 12}
+    }))
+    .unwrap();
+
+    let message = chat_response_to_messages(&response);
+    let value = serde_json::to_value(&message).unwrap();
+
+    assert_eq!(value["id"], "msg_abc");
+    assert_eq!(value["type"], "message");
+    assert_eq!(value["stop_reason"], "tool_use");
+    assert_eq!(value["content"][0]["type"], "text");
+    assert_eq!(value["content"][1]["type"], "tool_use");
+    assert_eq!(value["content"][1]["input"]["q"], 1);
+    assert_eq!(value["usage"]["input_tokens"], 5);
+    assert_eq!(value["usage"]["output_tokens"], 7);
+}
@@ -0,0 +1,90 @@
+//! HTTP `Cache-Control` handling for gateway requests and responses.
+//!
+//! Request directives:
+//! - `no-cache`: skip the lookup, still store the provider response
+//! - `no-store`: look up as usual, but never store a miss
+//! - `only-if-cached`: never call the provider; a miss is a 504
+//! - `max-age=N`: ignore entries stored more than `N` seconds ago
+//!
+//! Unknown directives (and malformed `max-age` values) are ignored.
+
+use axum::http::{HeaderMap, HeaderValue, header};
+use reflex::cache::LookupOptions;
+
+/// Cache directives parsed from a request's `Cache-Control` headers.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheDirectives {
+    pub no_cache: bool,
+    pub no_store: bool,
+    pub only_if_cached: bool,
+    pub max_age: Option<u64>,
+}
+
+impl CacheDirectives {
+    /// Parses every `Cache-Control` header on the request.
+    pub fn from_headers(headers: &HeaderMap) -> Self {
+        let mut directives = Self::default();
+
+        let values = headers
+            .get_all(header::CACHE_CONTROL)
+            .iter()
+            .filter_map(|v| v.to_str().ok());
+
+        for directive in values.flat_map(|v| v.split(',')) {
+            let directive = directive.trim();
+            let (name, value) = match directive.split_once('=') {
+                Some((name, value)) => (name.trim(), Some(value.trim().trim_matches('"'))),
+                None => (directive, None),
+            };
+
+            match name.to_ascii_lowercase().as_str() {
+                "no-cache" => directives.no_cache = true,
+                "no-store" => directives.no_store = true,
+                "only-if-cached" => directives.only_if_cached = true,
+                "max-age" => {
+                    if let Some(secs) = value.and_then(|v| v.parse().ok()) {
+                        // Repeated max-age directives: the strictest wins.
+                        directives.max_age =
+                            Some(directives.max_age.map_or(secs, |cur: u64| cur.min(secs)));
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        directives
+    }
+
+    /// Lookup constraints implied by these directives.
+    pub fn lookup_options(&self) -> LookupOptions {
+        match self.max_age {
+            Some(secs) => LookupOptions::default().max_age(secs),
+            None => LookupOptions::default(),
+        }
+    }
+}
+
+/// Sets `Cache-Control` (and `Age` for cache hits) on a gateway response.
+///
+/// Responses are tenant-scoped, so shared caches are told not to reuse them;
+/// `no-store` on the request is echoed back.
+pub(crate) fn apply_cache_headers(
+    headers: &mut HeaderMap,
+    directives: &CacheDirectives,
+    stored_at: Option<i64>,
+) {
+    let cache_control = if directives.no_store {
+        "private, no-store"
+    } else {
+        "private"
+    };
+    headers.insert(
+        header::CACHE_CONTROL,
+        HeaderValue::from_static(cache_control),
+    );
+
+    if let Some(stored_at) = stored_at {
+        let age = (chrono::Utc::now().timestamp() - stored_at).max(0);
+        headers.insert(header::AGE, HeaderValue::from(age));
+    }
+}
@@ -0,0 +1,49 @@
+//! Gateway configuration files, read as TOML or JSON by extension.
+
+use std::path::{Path, PathBuf};
+
+use serde::de::DeserializeOwned;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum ConfigFileError {
+    #[error("failed to read {path}: {source}")]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+
+    #[error("failed to parse {path}: {message}")]
+    Parse { path: PathBuf, message: String },
+
+    #[error("unsupported format {path}: expected .toml or .json")]
+    UnsupportedFormat { path: PathBuf },
+}
+
+/// Reads and deserializes a `.toml` or `.json` file.
+pub(crate) fn load<T: DeserializeOwned>(path: &Path) -> Result<T, ConfigFileError> {
+    let contents = std::fs::read_to_string(path).map_err(|source| ConfigFileError::Io {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    let parse_error = |message: String| ConfigFileError::Parse {
+        path: path.to_path_buf(),
+        message,
+    };
+
+    match path.extension().and_then(|e| e.to_str()) {
+        Some("toml") => toml::from_str(&contents).map_err(|e| parse_error(e.to_string())),
+        Some("json") => serde_json::from_str(&contents).map_err(|e| parse_error(e.to_string())),
+        _ => Err(ConfigFileError::UnsupportedFormat {
+            path: path.to_path_buf(),
+        }),
+    }
+}
+
+/// The path in environment variable `name`, if set and non-empty.
+pub(crate) fn path_from_env(name: &str) -> Option<PathBuf> {
+    std::env::var_os(name)
+        .filter(|v| !v.is_empty())
+        .map(PathBuf::from)
+}
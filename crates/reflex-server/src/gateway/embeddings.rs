@@ -0,0 +1,134 @@
+//! OpenAI-compatible `POST /v1/embeddings` backed by the node's [`SinterEmbedder`].
+//!
+//! [`SinterEmbedder`]: reflex::embedding::SinterEmbedder
+
+use async_openai::types::embeddings::{
+    Base64Embedding, Base64EmbeddingVector, CreateBase64EmbeddingResponse, CreateEmbeddingRequest,
+    CreateEmbeddingResponse, Embedding, EmbeddingInput, EmbeddingUsage, EncodingFormat,
+};
+use axum::{
+    Json,
+    extract::State,
+    response::{IntoResponse, Response},
+};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use tracing::{debug, instrument};
+
+use crate::gateway::error::GatewayError;
+use crate::gateway::state::HandlerState;
+use reflex::cache::{BqSearchBackend, StorageLoader};
+use reflex::storage::StorageWriter;
+
+/// Embeds one string or an array of strings with the cache's embedding model.
+///
+/// Token-id inputs are rejected: they are tokenizer-specific and would not
+/// match the embedder's vocabulary. `dimensions`, when present, must equal the
+/// embedder's output dimension.
+#[instrument(skip(state, request), fields(model = tracing::field::Empty))]
+pub async fn embeddings_handler<B, S>(
+    State(state): State<HandlerState<B, S>>,
+    Json(request): Json<serde_json::Value>,
+) -> Result<Response, GatewayError>
+where
+    B: BqSearchBackend + Clone + Send + Sync + 'static,
+    S: StorageLoader + StorageWriter + Clone + Send + Sync + 'static,
+{
+    let request: CreateEmbeddingRequest = serde_json::from_value(request)
+        .map_err(|e| GatewayError::InvalidRequest(format!("Invalid request schema: {}", e)))?;
+    tracing::Span::current().record("model", tracing::field::display(&request.model));
+
+    let inputs = match request.input {
+        EmbeddingInput::String(s) => vec![s],
+        EmbeddingInput::StringArray(v) => v,
+        EmbeddingInput::IntegerArray(_) | EmbeddingInput::ArrayOfIntegerArray(_) => {
+            return Err(GatewayError::InvalidRequest(
+                "Token array inputs are not supported; send text".to_string(),
+            ));
+        }
+    };
+
+    if inputs.is_empty() || inputs.iter().any(|s| s.is_empty()) {
+        return Err(GatewayError::InvalidRequest(
+            "Input must be a non-empty string or array of non-empty strings".to_string(),
+        ));
+    }
+
+    let embedding_dim = state.tiered_cache.l2().embedder().embedding_dim();
+    if let Some(dimensions) = request.dimensions
+        && dimensions as usize != embedding_dim
+    {
+        return Err(GatewayError::InvalidRequest(format!(
+            "Unsupported dimensions {}; this model produces {}",
+            dimensions, embedding_dim
+        )));
+    }
+
+    debug!(inputs = inputs.len(), "Embedding request");
+
+    let cache = state.tiered_cache.clone();
+    let (vectors, prompt_tokens) = tokio::task::spawn_blocking(move || {
+        let embedder = cache.l2().embedder();
+        let max_seq_len = embedder.config().max_seq_len;
+
+        let mut prompt_tokens = 0usize;
+        for text in &inputs {
+            prompt_tokens += embedder.count_tokens(text)?.min(max_seq_len);
+        }
+
+        let texts: Vec<&str> = inputs.iter().map(String::as_str).collect();
+        let vectors = embedder.embed_batch(&texts)?;
+        Ok::<_, reflex::embedding::EmbeddingError>((vectors, prompt_tokens))
+    })
+    .await
+    .map_err(|e| GatewayError::InternalError(format!("Embedding task failed: {}", e)))?
+    .map_err(|e| GatewayError::EmbeddingFailed(e.to_string()))?;
+
+    let usage = EmbeddingUsage {
+        prompt_tokens: prompt_tokens as u32,
+        total_tokens: prompt_tokens as u32,
+    };
+
+    let vectors = vectors
+        .into_iter()
+        .map(|v| v.iter().map(|x| x.to_f32()).collect::<Vec<f32>>());
+
+    let response = match request.encoding_format.unwrap_or_default() {
+        EncodingFormat::Float => Json(CreateEmbeddingResponse {
+            object: "list".to_string(),
+            model: request.model,
+            data: vectors
+                .enumerate()
+                .map(|(index, embedding)| Embedding {
+                    index: index as u32,
+                    object: "embedding".to_string(),
+                    embedding,
+                })
+                .collect(),
+            usage,
+        })
+        .into_response(),
+        EncodingFormat::Base64 => Json(CreateBase64EmbeddingResponse {
+            object: "list".to_string(),
+            model: request.model,
+            data: vectors
+                .enumerate()
+                .map(|(index, embedding)| Base64Embedding {
+                    index: index as u32,
+                    object: "embedding".to_string(),
+                    embedding: Base64EmbeddingVector(encode_base64(&embedding)),
+                })
+                .collect(),
+            usage,
+        })
+        .into_response(),
+    };
+
+    Ok(response)
+}
+
+/// Encodes a vector as base64 over little-endian `f32` bytes, as OpenAI does.
+fn encode_base64(vector: &[f32]) -> String {
+    let bytes: Vec<u8> = vector.iter().flat_map(|v| v.to_le_bytes()).collect();
+    BASE64.encode(bytes)
+}
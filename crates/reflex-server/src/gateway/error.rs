@@ -1,6 +1,6 @@
 use axum::{
     Json,
-    http::{HeaderMap, HeaderValue, StatusCode},
+    http::{HeaderMap, HeaderValue, StatusCode, header},
     response::{IntoResponse, Response},
 };
 use thiserror::Error;
@@ -33,6 +33,25 @@ pub enum GatewayError {
 
     #[error("internal error: {0}")]
     InternalError(String),
+
+    #[error("not cached: only-if-cached was requested and no cached response matched")]
+    NotCached,
+
+    #[error("unauthorized: {0}")]
+    Unauthorized(String),
+
+    #[error("not found: {0}")]
+    NotFound(String),
+
+    /// `retry_after` is the number of seconds until the quota resets.
+    #[error("quota exceeded: {message}")]
+    QuotaExceeded { message: String, retry_after: u64 },
+
+    #[error("rate limited: {message}")]
+    RateLimited { message: String, retry_after: u64 },
+
+    #[error("provider unavailable: {message}")]
+    ProviderUnavailable { message: String, retry_after: u64 },
 }
 
 #[derive(serde::Serialize)]
@@ -80,6 +99,26 @@ impl IntoResponse for GatewayError {
                 self.to_string(),
                 "internal_error",
             ),
+            GatewayError::NotCached => (StatusCode::GATEWAY_TIMEOUT, self.to_string(), "MISS"),
+            GatewayError::Unauthorized(_) => {
+                (StatusCode::UNAUTHORIZED, self.to_string(), "unauthorized")
+            }
+            GatewayError::NotFound(_) => (StatusCode::NOT_FOUND, self.to_string(), "not_found"),
+            GatewayError::QuotaExceeded { .. } => (
+                StatusCode::TOO_MANY_REQUESTS,
+                self.to_string(),
+                "quota_exceeded",
+            ),
+            GatewayError::RateLimited { .. } => (
+                StatusCode::TOO_MANY_REQUESTS,
+                self.to_string(),
+                "rate_limited",
+            ),
+            GatewayError::ProviderUnavailable { .. } => (
+                StatusCode::SERVICE_UNAVAILABLE,
+                self.to_string(),
+                "provider_unavailable",
+            ),
         };
 
         let mut headers = HeaderMap::new();
@@ -88,6 +127,13 @@ impl IntoResponse for GatewayError {
             HeaderValue::from_str(reflex_status).unwrap_or(HeaderValue::from_static("error")),
         );
 
+        if let GatewayError::RateLimited { retry_after, .. }
+        | GatewayError::QuotaExceeded { retry_after, .. }
+        | GatewayError::ProviderUnavailable { retry_after, .. } = &self
+        {
+            headers.insert(header::RETRY_AFTER, HeaderValue::from(*retry_after));
+        }
+
         let body = Json(ErrorResponse {
             error: error_message,
             code: status.as_u16(),
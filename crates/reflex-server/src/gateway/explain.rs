@@ -0,0 +1,241 @@
+//! Lookup traces: `POST /v1/reflex/explain` and `X-Reflex-Debug`.
+//!
+//! `/v1/reflex/explain` takes a chat completion request and runs its lookup
+//! as a dry run. Nothing is stored, no provider is called, no metrics are
+//! recorded and multi-answer entries do not rotate. The response is the
+//! [`LookupTrace`]: the L1 key and whether it matched, each L2 candidate with
+//! its BQ, rescored and cross-encoder scores, the L3 threshold and the
+//! decision.
+//!
+//! The lookup is otherwise the real one, so it has the same side effects as a
+//! lookup for `/v1/chat/completions`: the call spends the tenant's request
+//! budget and daily quota, image URLs are fetched when `REFLEX_MEDIA_FETCH`
+//! is on, and the NVMe loader deletes candidate files written in another
+//! entry format.
+//!
+//! A chat, Messages or Responses request sent with `X-Reflex-Debug: 1` gets
+//! the trace of the lookup it was served from, as compact JSON in an
+//! `X-Reflex-Debug` response header. The header leaves out the semantic
+//! texts, which only the endpoint returns.
+
+use std::sync::{Arc, Mutex};
+
+use async_openai::types::chat::CreateChatCompletionRequest;
+use axum::{
+    Json,
+    extract::State,
+    http::{HeaderMap, HeaderName, HeaderValue},
+};
+use reflex::cache::{BqSearchBackend, ReflexStatus, StorageLoader};
+use reflex::scoring::VerificationResult;
+use reflex::storage::{CacheEntry, StorageWriter};
+use reflex::vectordb::rescoring::ScoredCandidate;
+use serde::{Serialize, Serializer};
+use tracing::{instrument, warn};
+
+use crate::gateway::error::GatewayError;
+use crate::gateway::handler::{
+    StoreContext, admit_request, explain_lookup, store_context, validate_no_legacy_fields,
+};
+use crate::gateway::state::HandlerState;
+
+/// Request header asking for a trace, and the response header carrying it.
+pub const DEBUG_HEADER: &str = "x-reflex-debug";
+
+/// What the exact tier found.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum L1Outcome {
+    #[default]
+    Miss,
+    Hit,
+    /// An entry matched but its payload could not be decoded.
+    Unreadable,
+}
+
+/// Why an L2 candidate was not sent to L3.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Exclusion {
+    UnreadablePayload,
+    MediaMismatch,
+    ScopeMismatch,
+    /// Reported through feedback as a wrong answer to this query.
+    KnownFalsePositive,
+}
+
+/// One L2 candidate and how each stage scored it.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CandidateTrace {
+    pub storage_key: String,
+    /// Unix seconds when the entry was stored.
+    pub stored_at: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub semantic_request: Option<String>,
+    /// Similarity reported by the binary-quantized search.
+    pub bq_score: Option<f32>,
+    /// Full-precision cosine similarity from the rescorer.
+    pub rescored_score: f32,
+    /// Cross-encoder score; `None` when the candidate was excluded.
+    pub cross_encoder_score: Option<f32>,
+    pub excluded: Option<Exclusion>,
+}
+
+impl CandidateTrace {
+    pub(crate) fn new(candidate: &ScoredCandidate) -> Self {
+        Self {
+            storage_key: storage_key(&candidate.entry),
+            stored_at: candidate.entry.timestamp,
+            semantic_request: None,
+            bq_score: candidate.bq_score,
+            rescored_score: candidate.score,
+            cross_encoder_score: None,
+            excluded: None,
+        }
+    }
+
+    /// Whether this candidate is `entry`.
+    pub(crate) fn is_for(&self, entry: &CacheEntry) -> bool {
+        self.storage_key == storage_key(entry)
+    }
+}
+
+fn storage_key(entry: &CacheEntry) -> String {
+    StoreContext::storage_key_for(entry.tenant_id, entry.context_hash)
+}
+
+/// How a lookup went through the tiers.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LookupTrace {
+    pub tenant_id: u64,
+    pub l1_key: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub semantic_text: Option<String>,
+    pub scope_key: Option<String>,
+    pub media_key: Option<String>,
+    pub max_age_secs: Option<u64>,
+    pub top_k: Option<usize>,
+    pub exact_only: bool,
+    /// `Cache-Control: no-cache` skipped the lookup.
+    pub skipped: bool,
+    pub l1: L1Outcome,
+    /// Hits returned by the BQ search, before loading and rescoring.
+    pub bq_candidates: usize,
+    /// Rescored candidates, best first.
+    pub candidates: Vec<CandidateTrace>,
+    pub threshold: f32,
+    /// L3 result; `None` when no candidate reached L3.
+    #[serde(rename = "l3", serialize_with = "verification_status")]
+    pub verification: Option<VerificationResult>,
+    /// `X-Reflex-Status` the lookup leads to.
+    pub decision: &'static str,
+}
+
+impl LookupTrace {
+    pub(crate) fn new(ctx: &StoreContext, threshold: f32) -> Self {
+        let options = ctx.lookup_options();
+        Self {
+            tenant_id: ctx.tenant_id,
+            l1_key: ctx.l1_key.clone(),
+            semantic_text: Some(ctx.semantic_text.clone()),
+            scope_key: ctx.scope_key.clone(),
+            media_key: ctx.media_key.clone(),
+            max_age_secs: options.max_age_secs,
+            top_k: options.top_k,
+            exact_only: options.exact_only,
+            skipped: false,
+            l1: L1Outcome::Miss,
+            bq_candidates: 0,
+            candidates: Vec::new(),
+            threshold,
+            verification: None,
+            decision: ReflexStatus::Miss.as_header_value(),
+        }
+    }
+
+    /// The trace without the semantic texts, as a header value.
+    pub fn header_value(&self) -> Option<HeaderValue> {
+        let mut trace = self.clone();
+        trace.semantic_text = None;
+        for candidate in &mut trace.candidates {
+            candidate.semantic_request = None;
+        }
+        let json = serde_json::to_string(&trace).ok()?;
+        HeaderValue::from_str(&json)
+            .inspect_err(|e| warn!(error = %e, "Lookup trace is not a valid header value"))
+            .ok()
+    }
+}
+
+fn verification_status<S: Serializer>(
+    result: &Option<VerificationResult>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    result
+        .as_ref()
+        .map(VerificationResult::debug_status)
+        .serialize(serializer)
+}
+
+/// `POST /v1/reflex/explain`: traces the lookup for a chat completion request.
+///
+/// The caller's tenant is resolved as for `/v1/chat/completions`, and the
+/// call counts against its request budget and daily quota.
+#[instrument(skip(state, headers, request))]
+pub async fn explain_handler<B, S>(
+    State(state): State<HandlerState<B, S>>,
+    headers: HeaderMap,
+    Json(request): Json<serde_json::Value>,
+) -> Result<Json<LookupTrace>, GatewayError>
+where
+    B: BqSearchBackend + Clone + Send + Sync + 'static,
+    S: StorageLoader + StorageWriter + Clone + Send + Sync + 'static,
+{
+    let tenant = admit_request(&state, &headers)?;
+    validate_no_legacy_fields(&request)?;
+    let mut request: CreateChatCompletionRequest = serde_json::from_value(request)
+        .map_err(|e| GatewayError::InvalidRequest(format!("Invalid request schema: {}", e)))?;
+    let route = state.router.apply(&mut request);
+    let ctx = store_context(&state, tenant, &headers, &request, route).await?;
+    Ok(Json(explain_lookup(&state, &ctx).await?))
+}
+
+/// Where a request that asked for `X-Reflex-Debug` keeps the trace of the
+/// lookup it is served from.
+///
+/// The slot travels in the request's [`StoreContext`]; each lookup replaces
+/// the trace, so a degraded retry reports the lookup that served it.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct TraceSlot(Arc<Mutex<Option<LookupTrace>>>);
+
+impl TraceSlot {
+    /// A slot if `headers` ask for a trace.
+    pub(crate) fn requested(headers: &HeaderMap) -> Option<Self> {
+        headers
+            .get(DEBUG_HEADER)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+            .then(Self::default)
+    }
+
+    pub(crate) fn record(&self, trace: LookupTrace) {
+        *self.0.lock().expect("lock poisoned") = Some(trace);
+    }
+
+    /// Overrides the recorded decision, for a hit that is not served.
+    pub(crate) fn set_decision(&self, decision: &'static str) {
+        if let Some(trace) = self.0.lock().expect("lock poisoned").as_mut() {
+            trace.decision = decision;
+        }
+    }
+}
+
+/// The `X-Reflex-Debug` header for the trace in `slot`, else an empty map.
+pub(crate) fn debug_headers(slot: Option<&TraceSlot>) -> HeaderMap {
+    let mut debug = HeaderMap::new();
+    let trace = slot.and_then(|slot| slot.0.lock().expect("lock poisoned").clone());
+    if let Some(value) = trace.as_ref().and_then(LookupTrace::header_value) {
+        debug.insert(HeaderName::from_static(DEBUG_HEADER), value);
+    }
+    debug
+}
@@ -0,0 +1,74 @@
+//! Tests for lookup traces.
+
+use async_openai::types::chat::CreateChatCompletionRequest;
+use reflex::scoring::VerificationResult;
+use serde_json::{Value, json};
+
+use crate::gateway::explain::{CandidateTrace, Exclusion, L1Outcome, LookupTrace};
+use crate::gateway::handler::StoreContext;
+
+fn trace() -> LookupTrace {
+    let request: CreateChatCompletionRequest = serde_json::from_value(json!({
+        "model": "gpt-4o",
+        "messages": [{"role": "user", "content": "What is Rust?"}]
+    }))
+    .unwrap();
+    let ctx = StoreContext::for_tenant(7, &request).unwrap();
+    LookupTrace::new(&ctx, 0.7)
+}
+
+fn candidate(key: &str, excluded: Option<Exclusion>) -> CandidateTrace {
+    CandidateTrace {
+        storage_key: key.to_string(),
+        stored_at: 1_700_000_000,
+        semantic_request: Some("What's Rust?".to_string()),
+        bq_score: Some(0.8),
+        rescored_score: 0.9,
+        cross_encoder_score: excluded.is_none().then_some(0.5),
+        excluded,
+    }
+}
+
+#[test]
+fn test_new_trace_starts_as_a_miss() {
+    let trace = trace();
+    assert_eq!(trace.tenant_id, 7);
+    assert_eq!(trace.l1, L1Outcome::Miss);
+    assert_eq!(trace.decision, "MISS");
+    assert_eq!(trace.threshold, 0.7);
+    assert!(trace.semantic_text.unwrap().contains("What is Rust?"));
+    assert!(!trace.exact_only);
+    assert!(!trace.skipped);
+}
+
+#[test]
+fn test_trace_serializes_verification_and_exclusions() {
+    let mut trace = trace();
+    trace.candidates = vec![
+        candidate("7/0000000000000001.rkyv", None),
+        candidate("7/0000000000000002.rkyv", Some(Exclusion::ScopeMismatch)),
+    ];
+    trace.verification = Some(VerificationResult::Rejected { top_score: 0.5 });
+
+    let json = serde_json::to_value(&trace).unwrap();
+    assert_eq!(json["l1"], "miss");
+    assert_eq!(json["l3"], "REJECTED");
+    assert_eq!(json["candidates"][0]["excluded"], Value::Null);
+    assert_eq!(json["candidates"][1]["excluded"], "scope_mismatch");
+    assert_eq!(json["candidates"][1]["cross_encoder_score"], Value::Null);
+    assert_eq!(json["candidates"][0]["semantic_request"], "What's Rust?");
+}
+
+#[test]
+fn test_header_value_leaves_out_semantic_texts() {
+    let mut trace = trace();
+    trace.candidates = vec![candidate("7/0000000000000001.rkyv", None)];
+
+    let header = trace.header_value().unwrap();
+    let json: Value = serde_json::from_slice(header.as_bytes()).unwrap();
+    assert!(json.get("semantic_text").is_none());
+    assert!(json["candidates"][0].get("semantic_request").is_none());
+    assert_eq!(json["candidates"][0]["rescored_score"].as_f64(), Some(0.9));
+    assert_eq!(json["l1_key"], trace.l1_key);
+    assert_eq!(json["l3"], Value::Null);
+}
@@ -0,0 +1,305 @@
+//! Client feedback on served hits: `POST /v1/reflex/feedback`.
+//!
+//! Every hit carries an `X-Reflex-Entry-Id` header naming the entry it was
+//! served from, and a response `id` of its own: the stored response's id with
+//! a random suffix. A client that finds the answer was wrong posts that `id`
+//! (in any of the chat, Messages or Responses forms) or the entry id with a
+//! verdict:
+//!
+//! ```json
+//! {"id": "chatcmpl-...", "verdict": "negative"}
+//! ```
+//!
+//! A negative verdict invalidates the entry in all tiers. When the entry was
+//! served for a different semantic text than its own, the pair of that text
+//! and the entry is also recorded as a known false positive: the entry stays
+//! out of that query's L3 candidates even after it is stored again. Known
+//! false positives are kept in memory until restart.
+//!
+//! Feedback only reaches hits served to the caller's own tenant, among the
+//! most recent [`SERVED_HITS_CAPACITY`]. Verdicts are counted per tenant (see
+//! [`telemetry`](crate::gateway::telemetry)).
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::Mutex;
+
+use axum::{
+    Json,
+    extract::State,
+    http::{HeaderMap, HeaderName, HeaderValue},
+};
+use serde::{Deserialize, Serialize};
+use tracing::{info, instrument};
+
+use crate::gateway::admin::{InvalidationCounts, invalidate_storage_key};
+use crate::gateway::error::GatewayError;
+use crate::gateway::handler::{CacheOutcome, StoreContext, admit_request};
+use crate::gateway::state::HandlerState;
+use crate::gateway::telemetry;
+use reflex::cache::{BqSearchBackend, StorageLoader};
+use reflex::storage::StorageWriter;
+
+/// Response header naming the entry a hit was served from.
+pub const ENTRY_ID_HEADER: &str = "x-reflex-entry-id";
+
+/// Served hits remembered per gateway for feedback to refer to.
+pub const SERVED_HITS_CAPACITY: usize = 10_000;
+
+/// A client's judgement of a served answer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Verdict {
+    Positive,
+    Negative,
+}
+
+impl Verdict {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Verdict::Positive => "positive",
+            Verdict::Negative => "negative",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct FeedbackBody {
+    /// The `id` of the response the hit was served as.
+    #[serde(default)]
+    pub id: Option<String>,
+    /// The `X-Reflex-Entry-Id` of the hit.
+    #[serde(default)]
+    pub entry_id: Option<String>,
+    pub verdict: Verdict,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeedbackResponse {
+    pub verdict: Verdict,
+    pub storage_key: String,
+    /// What a negative verdict removed.
+    pub invalidated: Option<InvalidationCounts>,
+    /// A negative verdict recorded the query and entry as a false positive.
+    pub false_positive: bool,
+}
+
+/// A hit as it was served.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServedHit {
+    pub storage_key: String,
+    /// [`query_key`] of the request, when its semantic text differs from
+    /// the entry's.
+    pub query_key: Option<String>,
+}
+
+/// Recently served hits and the false positives reported against them.
+#[derive(Debug)]
+pub struct FeedbackStore {
+    capacity: usize,
+    served: Mutex<ServedLog>,
+    /// `(tenant_id, query_key, storage_key)`.
+    false_positives: Mutex<HashSet<(u64, String, String)>>,
+}
+
+#[derive(Debug, Default)]
+struct ServedLog {
+    hits: HashMap<(u64, String), ServedHit>,
+    /// Insertion order, oldest first.
+    order: VecDeque<(u64, String)>,
+}
+
+impl Default for FeedbackStore {
+    fn default() -> Self {
+        Self::with_capacity(SERVED_HITS_CAPACITY)
+    }
+}
+
+impl FeedbackStore {
+    /// A store remembering up to `capacity` served ids.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            capacity,
+            served: Mutex::default(),
+            false_positives: Mutex::default(),
+        }
+    }
+
+    /// Remembers `hit` under each of `ids`, dropping the oldest ids when full.
+    pub fn record(&self, tenant_id: u64, ids: &[&str], hit: ServedHit) {
+        let mut log = self.served.lock().expect("lock poisoned");
+        for id in ids {
+            let key = (tenant_id, id_base(id).to_string());
+            if log.hits.insert(key.clone(), hit.clone()).is_none() {
+                log.order.push_back(key);
+            }
+        }
+        while log.order.len() > self.capacity {
+            if let Some(oldest) = log.order.pop_front() {
+                log.hits.remove(&oldest);
+            }
+        }
+    }
+
+    /// The hit last served to `tenant_id` under a response or entry `id`.
+    pub fn served(&self, tenant_id: u64, id: &str) -> Option<ServedHit> {
+        let log = self.served.lock().expect("lock poisoned");
+        log.hits.get(&(tenant_id, id_base(id).to_string())).cloned()
+    }
+
+    /// Records that `storage_key` wrongly answered `query_key`. Returns
+    /// `false` if the pair was already known.
+    pub fn add_false_positive(&self, tenant_id: u64, query_key: &str, storage_key: &str) -> bool {
+        self.false_positives.lock().expect("lock poisoned").insert((
+            tenant_id,
+            query_key.to_string(),
+            storage_key.to_string(),
+        ))
+    }
+
+    /// Whether `storage_key` was reported as a wrong answer to `query_key`.
+    pub fn is_false_positive(&self, tenant_id: u64, query_key: &str, storage_key: &str) -> bool {
+        self.false_positives
+            .lock()
+            .expect("lock poisoned")
+            .contains(&(tenant_id, query_key.to_string(), storage_key.to_string()))
+    }
+
+    /// Gives a hit served for `ctx` a response id of its own and remembers
+    /// it, so feedback on one hit is not mistaken for another of the same
+    /// entry. Coalesced hits name no entry and are not remembered.
+    pub(crate) fn track(&self, ctx: &StoreContext, hit: &mut CacheOutcome) {
+        let Some(storage_key) = &hit.storage_key else {
+            return;
+        };
+        let Some(entry_id) = entry_id(storage_key) else {
+            return;
+        };
+        hit.payload.response.id = served_id(&hit.payload.response.id);
+        let served = ServedHit {
+            storage_key: storage_key.clone(),
+            query_key: (hit.payload.semantic_request != ctx.semantic_text)
+                .then(|| query_key(&ctx.semantic_text)),
+        };
+        self.record(
+            ctx.tenant_id,
+            &[&hit.payload.response.id, &entry_id],
+            served,
+        );
+    }
+}
+
+/// A response id for one serving of the stored response `id`: the stored id
+/// with a random suffix.
+pub fn served_id(id: &str) -> String {
+    format!("{}-{:016x}", id, fastrand::u64(..))
+}
+
+/// Identifies a query by its semantic text.
+pub fn query_key(semantic_text: &str) -> String {
+    blake3::hash(semantic_text.as_bytes()).to_string()
+}
+
+/// The entry id of a storage key: its context hash, as in the file name.
+pub fn entry_id(storage_key: &str) -> Option<String> {
+    let (_, context_hash) = StoreContext::parse_storage_key(storage_key)?;
+    Some(format!("{:016x}", context_hash))
+}
+
+/// A response id without the prefix of the API it was served through.
+fn id_base(id: &str) -> &str {
+    let id = id.trim();
+    ["chatcmpl-", "msg_", "resp_"]
+        .iter()
+        .find_map(|prefix| id.strip_prefix(prefix))
+        .unwrap_or(id)
+}
+
+/// Adds `X-Reflex-Entry-Id` to `headers` when `hit` came from a stored entry.
+pub(crate) fn apply_entry_header(hit: &CacheOutcome, headers: &mut HeaderMap) {
+    if let Some(value) = hit
+        .storage_key
+        .as_deref()
+        .and_then(entry_id)
+        .and_then(|id| HeaderValue::from_str(&id).ok())
+    {
+        headers.insert(HeaderName::from_static(ENTRY_ID_HEADER), value);
+    }
+}
+
+/// `POST /v1/reflex/feedback`: records a verdict on a hit served to the
+/// caller's tenant.
+#[instrument(skip_all)]
+pub async fn feedback_handler<B, S>(
+    State(state): State<HandlerState<B, S>>,
+    headers: HeaderMap,
+    Json(body): Json<FeedbackBody>,
+) -> Result<Json<FeedbackResponse>, GatewayError>
+where
+    B: BqSearchBackend + Clone + Send + Sync + 'static,
+    S: StorageLoader + StorageWriter + Clone + Send + Sync + 'static,
+{
+    let tenant = admit_request(&state, &headers)?;
+    let served = served_hit(&state, tenant.tenant_id, &body)?;
+    telemetry::record_feedback(tenant.tenant_id, body.verdict);
+
+    let mut response = FeedbackResponse {
+        verdict: body.verdict,
+        storage_key: served.storage_key.clone(),
+        invalidated: None,
+        false_positive: false,
+    };
+    if body.verdict == Verdict::Negative {
+        if let Some(query_key) = &served.query_key {
+            state
+                .feedback
+                .add_false_positive(tenant.tenant_id, query_key, &served.storage_key);
+            response.false_positive = true;
+        }
+        response.invalidated = Some(invalidate_storage_key(&state, &served.storage_key).await?);
+        info!(storage_key = %served.storage_key, false_positive = response.false_positive, "Negative feedback on hit");
+    }
+    Ok(Json(response))
+}
+
+/// The hit `body` refers to. An entry id that was not served recently still
+/// names its entry, with no query to pair it with.
+fn served_hit<B, S>(
+    state: &HandlerState<B, S>,
+    tenant_id: u64,
+    body: &FeedbackBody,
+) -> Result<ServedHit, GatewayError>
+where
+    B: BqSearchBackend + Clone + Send + Sync + 'static,
+    S: StorageLoader + Clone + Send + Sync + 'static,
+{
+    let id = body
+        .entry_id
+        .as_deref()
+        .or(body.id.as_deref())
+        .map(str::trim)
+        .filter(|id| !id.is_empty())
+        .ok_or_else(|| {
+            GatewayError::InvalidRequest("One of `id` or `entry_id` is required".to_string())
+        })?;
+    if let Some(served) = state.feedback.served(tenant_id, id) {
+        return Ok(served);
+    }
+    match &body.entry_id {
+        Some(entry_id) => {
+            let context_hash = u64::from_str_radix(entry_id.trim(), 16).map_err(|_| {
+                GatewayError::InvalidRequest(format!(
+                    "Invalid entry id '{}': expected 16 hex digits",
+                    entry_id
+                ))
+            })?;
+            Ok(ServedHit {
+                storage_key: StoreContext::storage_key_for(tenant_id, context_hash),
+                query_key: None,
+            })
+        }
+        None => Err(GatewayError::NotFound(format!(
+            "No recent cache hit with id '{}'",
+            id
+        ))),
+    }
+}
@@ -0,0 +1,84 @@
+//! Tests for feedback bookkeeping.
+
+use crate::gateway::feedback::{FeedbackStore, ServedHit, Verdict, entry_id, query_key, served_id};
+
+fn hit(storage_key: &str) -> ServedHit {
+    ServedHit {
+        storage_key: storage_key.to_string(),
+        query_key: Some(query_key("What's Rust?")),
+    }
+}
+
+#[test]
+fn test_served_hit_is_found_by_any_response_id_form() {
+    let store = FeedbackStore::default();
+    store.record(
+        7,
+        &["chatcmpl-abc", "00000000000000ff"],
+        hit("7/00000000000000ff.rkyv"),
+    );
+
+    for id in ["chatcmpl-abc", "msg_abc", "resp_abc", "00000000000000ff"] {
+        assert_eq!(
+            store.served(7, id).map(|h| h.storage_key),
+            Some("7/00000000000000ff.rkyv".to_string()),
+            "{id}"
+        );
+    }
+}
+
+#[test]
+fn test_served_hits_are_per_tenant() {
+    let store = FeedbackStore::default();
+    store.record(7, &["chatcmpl-abc"], hit("7/00000000000000ff.rkyv"));
+    assert!(store.served(8, "chatcmpl-abc").is_none());
+}
+
+#[test]
+fn test_oldest_served_hits_are_forgotten() {
+    let store = FeedbackStore::with_capacity(2);
+    store.record(7, &["a"], hit("7/0000000000000001.rkyv"));
+    store.record(7, &["b"], hit("7/0000000000000002.rkyv"));
+    store.record(7, &["c"], hit("7/0000000000000003.rkyv"));
+
+    assert!(store.served(7, "a").is_none());
+    assert!(store.served(7, "b").is_some());
+    assert!(store.served(7, "c").is_some());
+}
+
+#[test]
+fn test_false_positives_pair_query_and_entry() {
+    let store = FeedbackStore::default();
+    let query = query_key("What's Rust?");
+    assert!(store.add_false_positive(7, &query, "7/00000000000000ff.rkyv"));
+    assert!(!store.add_false_positive(7, &query, "7/00000000000000ff.rkyv"));
+
+    assert!(store.is_false_positive(7, &query, "7/00000000000000ff.rkyv"));
+    assert!(!store.is_false_positive(8, &query, "7/00000000000000ff.rkyv"));
+    assert!(!store.is_false_positive(7, &query, "7/0000000000000001.rkyv"));
+    assert!(!store.is_false_positive(7, &query_key("What is Go?"), "7/00000000000000ff.rkyv"));
+}
+
+#[test]
+fn test_entry_id_is_the_context_hash() {
+    assert_eq!(
+        entry_id("7/00000000000000ff.rkyv").as_deref(),
+        Some("00000000000000ff")
+    );
+    assert_eq!(entry_id("not-a-key"), None);
+}
+
+#[test]
+fn test_served_ids_extend_the_stored_id_uniquely() {
+    let first = served_id("chatcmpl-abc");
+    let second = served_id("chatcmpl-abc");
+    assert!(first.starts_with("chatcmpl-abc-"));
+    assert_ne!(first, second);
+}
+
+#[test]
+fn test_verdict_parses_lowercase() {
+    let verdict: Verdict = serde_json::from_str("\"negative\"").unwrap();
+    assert_eq!(verdict, Verdict::Negative);
+    assert_eq!(Verdict::Positive.as_str(), "positive");
+}
@@ -0,0 +1,80 @@
+//! Response body formats for non-streaming chat completions.
+
+use std::str::FromStr;
+
+use axum::http::HeaderMap;
+
+use crate::gateway::error::GatewayError;
+
+/// Request header selecting the body format for `/v1/chat/completions`.
+pub const REFLEX_FORMAT_HEADER: &str = "X-Reflex-Format";
+
+/// Environment variable holding the deployment default format.
+pub const REFLEX_FORMAT_ENV: &str = "REFLEX_HIT_FORMAT";
+
+/// How a cached (or freshly stored) payload is returned to the client.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HitFormat {
+    /// The stored `CreateChatCompletionResponse`, verbatim (tool calls included).
+    OpenAi,
+    /// `choices[0].message.content` replaced by a Tauq encoding of the payload.
+    #[default]
+    Tauq,
+    /// A JSON object with cache metadata wrapping the stored response.
+    Envelope,
+}
+
+impl HitFormat {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HitFormat::OpenAi => "openai",
+            HitFormat::Tauq => "tauq",
+            HitFormat::Envelope => "envelope",
+        }
+    }
+
+    /// Reads the deployment default from `REFLEX_HIT_FORMAT` (unset or invalid: Tauq).
+    pub fn from_env() -> Self {
+        match std::env::var(REFLEX_FORMAT_ENV) {
+            Ok(value) => value.parse().unwrap_or_else(|e| {
+                tracing::warn!("{}; using tauq", e);
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        }
+    }
+
+    /// Picks the format from `X-Reflex-Format`, falling back to `default`.
+    pub fn negotiate(headers: &HeaderMap, default: Self) -> Result<Self, GatewayError> {
+        let Some(value) = headers.get(REFLEX_FORMAT_HEADER) else {
+            return Ok(default);
+        };
+        value
+            .to_str()
+            .map_err(|_| GatewayError::InvalidRequest("Invalid X-Reflex-Format header".into()))?
+            .parse()
+            .map_err(GatewayError::InvalidRequest)
+    }
+}
+
+impl FromStr for HitFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "openai" => Ok(HitFormat::OpenAi),
+            "tauq" => Ok(HitFormat::Tauq),
+            "envelope" => Ok(HitFormat::Envelope),
+            other => Err(format!(
+                "unknown response format '{}': expected openai, tauq or envelope",
+                other
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for HitFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
@@ -1,23 +1,43 @@
+use std::time::Instant;
+
 use async_openai::types::chat::*;
 use axum::{
     Json,
     extract::State,
     http::{HeaderMap, HeaderValue, StatusCode},
-    response::{
-        IntoResponse, Response,
-        sse::{Event, Sse},
-    },
+    response::{IntoResponse, Response},
 };
-use futures_util::stream;
-use std::convert::Infallible;
-use tracing::{debug, error, info, instrument};
+use futures_util::future::try_join_all;
+use tracing::{debug, error, info, instrument, warn};
 
+use crate::gateway::adapter::{
+    adapt_genai_to_openai, adapt_openai_to_genai, merge_choices, requested_choices,
+    unsupported_tool_choice,
+};
+use crate::gateway::cache_control::{CacheDirectives, apply_cache_headers};
 use crate::gateway::error::GatewayError;
+use crate::gateway::explain::{
+    CandidateTrace, Exclusion, L1Outcome, LookupTrace, TraceSlot, debug_headers,
+};
+use crate::gateway::feedback::{apply_entry_header, query_key};
+use crate::gateway::format::{HitFormat, REFLEX_FORMAT_HEADER};
+use crate::gateway::inflight::{Flight, FlightKey, FlightOutcome};
 use crate::gateway::payload::CachePayload;
+use crate::gateway::provider_keys::ProviderKeys;
+use crate::gateway::rate_limit::Budget;
+use crate::gateway::routing::{Route, with_fallbacks};
+use crate::gateway::sampling::SamplingMode;
+use crate::gateway::semantic_key::{SemanticKeyExtractor, SemanticKeyStrategy};
+use crate::gateway::shadow::spawn_shadow_check;
 use crate::gateway::state::HandlerState;
-use crate::gateway::streaming::handle_streaming_request;
+use crate::gateway::streaming::{ChatChunkEncoder, serve_stream};
+use crate::gateway::telemetry::{self, Tier};
+use crate::gateway::tenants::{Tenant, TenantPolicy, resolve_tenant};
+use crate::gateway::upstream::{ProviderFailure, provider_of};
+use genai::chat::ChatOptions;
 use reflex::cache::{
-    BqSearchBackend, REFLEX_STATUS_HEADER, ReflexStatus, StorageLoader, TieredLookupResult,
+    BqSearchBackend, LookupOptions, REFLEX_STATUS_HEADER, ReflexStatus, StorageLoader,
+    TieredLookupResult,
 };
 use reflex::payload::TauqEncoder;
 use reflex::scoring::VerificationResult;
@@ -34,55 +54,636 @@ where
     B: BqSearchBackend + Clone + Send + Sync + 'static,
     S: StorageLoader + StorageWriter + Clone + Send + Sync + 'static,
 {
+    let tenant = admit_request(&state, &headers)?;
     validate_no_legacy_fields(&request)?;
-    let request: CreateChatCompletionRequest = serde_json::from_value(request)
+    let mut request: CreateChatCompletionRequest = serde_json::from_value(request)
         .map_err(|e| GatewayError::InvalidRequest(format!("Invalid request schema: {}", e)))?;
     tracing::Span::current().record("model", tracing::field::display(&request.model));
 
-    let _auth_token = headers
+    let format = HitFormat::negotiate(&headers, state.hit_format)?;
+    let route = state.router.apply(&mut request);
+    let store_ctx = store_context(&state, tenant, &headers, &request, route).await?;
+
+    debug!(hash = %store_ctx.l1_key, "Processing chat completion request");
+    let trace = store_ctx.debug_trace.clone();
+
+    if request.stream.unwrap_or(false) {
+        let mut response =
+            serve_stream(state, request, store_ctx, ChatChunkEncoder::default()).await?;
+        response.headers_mut().extend(debug_headers(trace.as_ref()));
+        return Ok(response);
+    }
+
+    let outcome = complete_request(&state, request, &store_ctx).await?;
+    let mut savings = HeaderMap::new();
+    report_savings(
+        &state,
+        &store_ctx,
+        &outcome.payload.response,
+        outcome.status,
+        &mut savings,
+    );
+    apply_entry_header(&outcome, &mut savings);
+    let mut response = make_response(outcome.payload, outcome.status, format)?;
+    response.headers_mut().extend(savings);
+    response.headers_mut().extend(debug_headers(trace.as_ref()));
+    apply_cache_headers(
+        response.headers_mut(),
+        &store_ctx.directives,
+        outcome.stored_at,
+    );
+    Ok(response)
+}
+
+/// Resolves the caller's tenant, spends one token of its request budget and
+/// counts the request against its daily quota.
+pub(crate) fn admit_request<B, S>(
+    state: &HandlerState<B, S>,
+    headers: &HeaderMap,
+) -> Result<Tenant, GatewayError>
+where
+    B: BqSearchBackend + Clone + Send + Sync + 'static,
+    S: StorageLoader + Clone + Send + Sync + 'static,
+{
+    let tenant = resolve_tenant(state.tenants.as_deref(), state.provider_key_mode, headers)?;
+    state
+        .rate_limiter
+        .acquire(Budget::Requests, tenant.tenant_id, &tenant.policy)?;
+    // Charged last so that requests turned away by the rate limiter do not
+    // use up the daily quota.
+    if let Some(registry) = state.tenants.as_deref() {
+        registry.charge(&tenant, chrono::Utc::now().timestamp())?;
+    }
+    Ok(tenant)
+}
+
+/// The caller's key from `Authorization: Bearer` or `x-api-key`, if any.
+pub(crate) fn api_key(headers: &HeaderMap) -> Option<&str> {
+    headers
         .get("Authorization")
         .and_then(|val| val.to_str().ok())
         .and_then(|s| s.strip_prefix("Bearer "))
-        .map(|s| s.trim().to_string());
+        .or_else(|| headers.get("x-api-key").and_then(|val| val.to_str().ok()))
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+}
+
+/// Resolves the tenant token from `Authorization: Bearer` or `x-api-key`.
+///
+/// Requests without either share the `"default"` tenant.
+pub(crate) fn tenant_token(headers: &HeaderMap) -> String {
+    api_key(headers).unwrap_or("default").to_string()
+}
 
-    let request_bytes = serde_json::to_vec(&request)
-        .map_err(|e| GatewayError::InvalidRequest(format!("Serialization failed: {}", e)))?;
-    let request_hash = blake3::hash(&request_bytes);
-    let request_hash_u64 = reflex::hashing::hash_to_u64(request_hash.as_bytes());
+/// The keys and policies under which a canonical request routed along
+/// `route` is looked up and stored.
+pub(crate) async fn store_context<B, S>(
+    state: &HandlerState<B, S>,
+    tenant: Tenant,
+    headers: &HeaderMap,
+    request: &CreateChatCompletionRequest,
+    route: Route,
+) -> Result<StoreContext, GatewayError>
+where
+    B: BqSearchBackend + Clone + Send + Sync + 'static,
+    S: StorageLoader + Clone + Send + Sync + 'static,
+{
+    let keyed = state.media.keyed(request).await;
+    let extractor = route
+        .semantic_key
+        .or(tenant.policy.semantic_key)
+        .map(SemanticKeyStrategy::extractor)
+        .unwrap_or(state.semantic_key.as_ref());
+    Ok(StoreContext::new(tenant, headers, &keyed.request)?
+        .with_semantic_key(extractor, &keyed.request)
+        .with_route(route)
+        .with_media_key(keyed.media_key)
+        .with_sampling(state.sampling.mode(request))
+        .with_debug_trace(TraceSlot::requested(headers)))
+}
+
+/// A payload served to the client and the tier it came from.
+#[derive(Debug, Clone)]
+pub(crate) struct CacheOutcome {
+    pub payload: CachePayload,
+    pub status: ReflexStatus,
+    /// Unix seconds when a hit was originally stored; `None` on a miss.
+    pub stored_at: Option<i64>,
+    /// Storage key of the entry a hit was served from.
+    pub storage_key: Option<String>,
+}
+
+/// Looks up `request` and, on a miss, calls the provider and stores the result.
+///
+/// Every non-streaming ingress funnels through here once its request has been
+/// converted to the canonical chat completion form.
+pub(crate) async fn complete_request<B, S>(
+    state: &HandlerState<B, S>,
+    request: CreateChatCompletionRequest,
+    store_ctx: &StoreContext,
+) -> Result<CacheOutcome, GatewayError>
+where
+    B: BqSearchBackend + Clone + Send + Sync + 'static,
+    S: StorageLoader + StorageWriter + Clone + Send + Sync + 'static,
+{
+    if let Some(message) = unsupported_tool_choice(&request) {
+        return Err(GatewayError::InvalidRequest(message));
+    }
 
-    debug!(hash = %request_hash, "Processing chat completion request");
+    if let Some(mut hit) = lookup_cached_payload(state, store_ctx).await? {
+        state.feedback.track(store_ctx, &mut hit);
+        spawn_shadow_check(state, &request, store_ctx, &hit);
+        return Ok(hit);
+    }
 
-    let semantic_text = semantic_text_from_request(&request);
+    if store_ctx.directives.only_if_cached {
+        return Err(GatewayError::NotCached);
+    }
 
-    let token = _auth_token.unwrap_or_else(|| "default".to_string());
-    let tenant_id_hash = reflex::hashing::hash_tenant_id(&token);
+    let flight = match state.inflight.join(store_ctx.flight_key()) {
+        Flight::Leader(guard) => Some(guard),
+        Flight::Follower(waiter) => {
+            debug!("Identical request in flight - waiting for its result");
+            match waiter.wait().await {
+                FlightOutcome::Completed(payload) => {
+                    telemetry::record_lookup(store_ctx, Tier::Inflight, true);
+                    return Ok(CacheOutcome {
+                        payload: *payload,
+                        status: ReflexStatus::HitInflight,
+                        stored_at: None,
+                        storage_key: None,
+                    });
+                }
+                FlightOutcome::Failed(error) => return Err(error),
+                FlightOutcome::Abandoned => None,
+            }
+        }
+    };
 
-    let stream_requested = request.stream.unwrap_or(false);
+    // Each requested choice is a provider call.
+    state.rate_limiter.acquire_n(
+        Budget::Misses,
+        store_ctx.tenant_id,
+        &store_ctx.policy,
+        u32::from(requested_choices(&request)),
+    )?;
+    debug!("Cache Miss - Calling Provider");
 
-    if stream_requested {
-        debug!("Streaming request received - bypassing cache");
-        if state.mock_provider {
-            debug!("Mock provider enabled - returning mock streaming response");
-            let mock_sse =
-                create_mock_streaming_response(request.model.clone(), semantic_text.clone());
-            return Ok(mock_sse.into_response());
+    let response = match call_provider(state, request, store_ctx).await {
+        Ok(response) => response,
+        Err(e) => {
+            // Followers are abandoned rather than failed, so each of them can
+            // fall back to a degraded hit as well.
+            if let Some(mut outcome) = lookup_degraded(state, store_ctx, &e).await? {
+                state.feedback.track(store_ctx, &mut outcome);
+                return Ok(outcome);
+            }
+            if let Some(flight) = &flight {
+                flight.fail(&e);
+            }
+            return Err(e);
         }
-        let sse = handle_streaming_request::<B>(
-            state.genai_client.clone(),
-            &request.model,
-            request.clone(),
-            tenant_id_hash,
-            request_hash_u64,
-            semantic_text,
-        )
+    };
+
+    let payload = CachePayload {
+        semantic_request: store_ctx.semantic_text.clone(),
+        response,
+        media_key: store_ctx.media_key.clone(),
+        scope_key: store_ctx.scope_key.clone(),
+        variants: Vec::new(),
+    };
+    if let Some(flight) = &flight {
+        flight.complete(&payload);
+    }
+    if !store_ctx.should_store() {
+        debug!("Storing disabled for this request, skipping store");
+    } else {
+        store_cache_payload(state, store_ctx, &payload).await?;
+    }
+
+    Ok(CacheOutcome {
+        payload,
+        status: ReflexStatus::Miss,
+        stored_at: None,
+        storage_key: None,
+    })
+}
+
+/// Calls the models of the request's route in order until one answers.
+///
+/// The response names the concrete model that produced it.
+pub(crate) async fn call_provider<B, S>(
+    state: &HandlerState<B, S>,
+    request: CreateChatCompletionRequest,
+    store_ctx: &StoreContext,
+) -> Result<CreateChatCompletionResponse, GatewayError>
+where
+    B: BqSearchBackend + Clone + Send + Sync + 'static,
+    S: StorageLoader + Clone + Send + Sync + 'static,
+{
+    let (_, response) = with_fallbacks(&store_ctx.route, |model| {
+        call_model(state, request.clone(), model, store_ctx)
+    })
+    .await?;
+    Ok(response)
+}
+
+/// Calls the provider (or the mock provider) for a non-streaming completion.
+async fn call_model<B, S>(
+    state: &HandlerState<B, S>,
+    request: CreateChatCompletionRequest,
+    model: String,
+    store_ctx: &StoreContext,
+) -> Result<CreateChatCompletionResponse, GatewayError>
+where
+    B: BqSearchBackend + Clone + Send + Sync + 'static,
+    S: StorageLoader + Clone + Send + Sync + 'static,
+{
+    let client = store_ctx.provider_keys.client(&state.genai_client);
+    let timeout = state.router.model_timeout(&model);
+    let own_key = store_ctx.provider_keys.overrides(&model);
+    // The raw body carries the provider's finish reason.
+    let options = ChatOptions::default().with_capture_raw_body(true);
+
+    let choices = usize::from(requested_choices(&request));
+
+    let started = Instant::now();
+    let response = state
+        .upstream
+        .call(&model, timeout, own_key, || async {
+            let responses = if state.mock_provider {
+                if !state.mock_latency.is_zero() {
+                    tokio::time::sleep(state.mock_latency).await;
+                }
+                let response = mock_response(&model, &store_ctx.semantic_text)?;
+                vec![response; choices]
+            } else {
+                // genai returns one choice per call, so `n` choices take `n` calls.
+                let calls = (0..choices).map(|_| async {
+                    let genai_req = adapt_openai_to_genai(request.clone());
+                    let genai_resp = client
+                        .exec_chat(&model, genai_req, Some(&options))
+                        .await
+                        .map_err(|e| {
+                            error!("Provider error: {}", e);
+                            ProviderFailure::from_genai(&e, "Upstream service request failed")
+                        })?;
+                    Ok::<_, ProviderFailure>(adapt_genai_to_openai(
+                        genai_resp,
+                        model.clone(),
+                        &request,
+                    ))
+                });
+                try_join_all(calls).await?
+            };
+            merge_choices(responses).ok_or_else(|| {
+                GatewayError::ProviderError("Provider returned no choices".to_string()).into()
+            })
+        })
         .await?;
-        return Ok(sse.into_response());
+    telemetry::record_provider(&model, started);
+
+    Ok(response)
+}
+
+/// The mock provider's answer: it echoes the semantic text.
+fn mock_response(
+    model: &str,
+    semantic_text: &str,
+) -> Result<CreateChatCompletionResponse, GatewayError> {
+    let content = format!("Mock response for: {}", semantic_text);
+    let response_value = serde_json::json!({
+        "id": format!("chatcmpl-{}", uuid::Uuid::new_v4()),
+        "object": "chat.completion",
+        "created": chrono::Utc::now().timestamp() as u32,
+        "model": model,
+        "choices": [{
+            "index": 0,
+            "message": { "role": "assistant", "content": content },
+            "finish_reason": "stop"
+        }],
+        "usage": {
+            "prompt_tokens": 10,
+            "completion_tokens": 10,
+            "total_tokens": 20
+        }
+    });
+
+    serde_json::from_value::<CreateChatCompletionResponse>(response_value)
+        .map_err(|e| GatewayError::SerializationFailed(e.to_string()))
+}
+
+/// Keys under which a provider response is looked up and stored.
+///
+/// Built once per request so the streaming and non-streaming paths write
+/// through the same L1 key, storage key and vector index point.
+#[derive(Debug, Clone)]
+pub(crate) struct StoreContext {
+    pub tenant_id: u64,
+    pub l1_key: String,
+    pub context_hash: u64,
+    pub semantic_text: String,
+    pub model: String,
+    pub directives: CacheDirectives,
+    pub policy: TenantPolicy,
+    pub provider_keys: ProviderKeys,
+    /// Concrete models to call on a miss.
+    pub route: Route,
+    /// Content hash of the request's media parts; see [`crate::gateway::media`].
+    pub media_key: Option<String>,
+    /// Hash of what the semantic text leaves out; see [`crate::gateway::semantic_key`].
+    pub scope_key: Option<String>,
+    /// How the request's sampling settings let it be cached.
+    pub sampling: SamplingMode,
+    /// Receives the lookup trace when the request asked for `X-Reflex-Debug`.
+    pub debug_trace: Option<TraceSlot>,
+}
+
+impl StoreContext {
+    /// Derives the cache keys and `Cache-Control` directives for a canonical
+    /// chat completion request served for `tenant`.
+    pub fn new(
+        tenant: Tenant,
+        headers: &HeaderMap,
+        request: &CreateChatCompletionRequest,
+    ) -> Result<Self, GatewayError> {
+        let mut ctx = Self::for_tenant(tenant.tenant_id, request)?;
+        ctx.directives = CacheDirectives::from_headers(headers);
+        ctx.policy = tenant.policy;
+        ctx.provider_keys = tenant.provider_keys.for_model(&request.model);
+        Ok(ctx)
+    }
+
+    /// Derives the cache keys for `request` on behalf of an already resolved tenant.
+    pub fn for_tenant(
+        tenant_id: u64,
+        request: &CreateChatCompletionRequest,
+    ) -> Result<Self, GatewayError> {
+        // `stream` only changes the transport, so streamed and buffered requests
+        // share one exact key.
+        let mut hash_basis = request.clone();
+        hash_basis.stream = None;
+        hash_basis.stream_options = None;
+        let request_bytes = serde_json::to_vec(&hash_basis)
+            .map_err(|e| GatewayError::InvalidRequest(format!("Serialization failed: {}", e)))?;
+        let request_hash = blake3::hash(&request_bytes);
+
+        Ok(Self {
+            tenant_id,
+            l1_key: request_hash.to_string(),
+            context_hash: reflex::hashing::hash_to_u64(request_hash.as_bytes()),
+            semantic_text: semantic_text_from_request(request),
+            model: request.model.clone(),
+            directives: CacheDirectives::default(),
+            policy: TenantPolicy::default(),
+            provider_keys: ProviderKeys::default(),
+            route: Route::direct(&request.model),
+            media_key: None,
+            scope_key: None,
+            sampling: SamplingMode::default(),
+            debug_trace: None,
+        })
+    }
+
+    /// Replaces the direct route to the requested model.
+    ///
+    /// A caller's key belongs to the provider of the route's first model, so
+    /// fallbacks to other providers are dropped rather than sent that key.
+    pub fn with_route(mut self, mut route: Route) -> Self {
+        let provider = provider_of(route.primary());
+        if self.provider_keys.has_caller() {
+            route.models.retain(|model| provider_of(model) == provider);
+        }
+        self.provider_keys = self.provider_keys.for_model(route.primary());
+        self.route = route;
+        self
+    }
+
+    /// Derives the semantic text and scope with `extractor` instead of
+    /// from the full request.
+    pub fn with_semantic_key(
+        mut self,
+        extractor: &dyn SemanticKeyExtractor,
+        request: &CreateChatCompletionRequest,
+    ) -> Self {
+        self.semantic_text = extractor.semantic_text(request);
+        self.scope_key = extractor.scope(request);
+        self
+    }
+
+    /// Records the media key of the request the keys were derived from.
+    pub fn with_media_key(mut self, media_key: Option<String>) -> Self {
+        self.media_key = media_key;
+        self
+    }
+
+    /// Applies the sampling policy's mode for this request.
+    pub fn with_sampling(mut self, sampling: SamplingMode) -> Self {
+        self.sampling = sampling;
+        self
+    }
+
+    /// Records lookup traces in `slot`.
+    pub fn with_debug_trace(mut self, slot: Option<TraceSlot>) -> Self {
+        self.debug_trace = slot;
+        self
+    }
+
+    /// Lookup constraints from the request directives and the tenant policy.
+    ///
+    /// When both set a maximum age, the stricter one applies.
+    pub fn lookup_options(&self) -> LookupOptions {
+        let mut options = self.directives.lookup_options();
+        if let Some(ttl) = self.policy.ttl_secs {
+            options.max_age_secs = Some(options.max_age_secs.map_or(ttl, |secs| secs.min(ttl)));
+        }
+        options.top_k = self.policy.top_k;
+        options.exact_only = self.sampling == SamplingMode::ExactOnly;
+        options.scope_hash = self.scope_hash();
+        options
+    }
+
+    /// The scope as indexed with L2 points, so a scoped search only sees
+    /// entries of the same scope.
+    pub fn scope_hash(&self) -> Option<u64> {
+        self.scope_key
+            .as_ref()
+            .map(|scope| reflex::hashing::hash_to_u64(scope.as_bytes()))
+    }
+
+    /// Whether a provider response for this request may be stored.
+    pub fn should_store(&self) -> bool {
+        self.policy.store && !self.directives.no_store
+    }
+
+    /// Storage key of the rkyv entry (`{tenant}/{context_hash:016x}.rkyv`).
+    pub fn storage_key(&self) -> String {
+        Self::storage_key_for(self.tenant_id, self.context_hash)
+    }
+
+    /// Storage key of the entry with `tenant_id` and `context_hash`.
+    pub fn storage_key_for(tenant_id: u64, context_hash: u64) -> String {
+        reflex::storage::storage_key(tenant_id, context_hash)
+    }
+
+    /// Key under which identical in-flight misses are coalesced.
+    pub fn flight_key(&self) -> FlightKey {
+        (
+            self.tenant_id,
+            self.l1_key.clone(),
+            self.provider_keys.caller_fingerprint(),
+        )
+    }
+
+    /// Inverse of [`storage_key`](Self::storage_key): `(tenant_id, context_hash)`.
+    pub fn parse_storage_key(key: &str) -> Option<(u64, u64)> {
+        reflex::storage::parse_storage_key(key)
+    }
+}
+
+/// Runs the L1 → L2 → L3 lookup and returns the cached payload on a hit.
+///
+/// `Cache-Control: no-cache` skips the lookup entirely; `max-age` and the
+/// tenant's TTL and `top_k` are enforced by the tiered cache, and the tenant's
+/// L3 threshold replaces the scorer's.
+pub(crate) async fn lookup_cached_payload<B, S>(
+    state: &HandlerState<B, S>,
+    ctx: &StoreContext,
+) -> Result<Option<CacheOutcome>, GatewayError>
+where
+    B: BqSearchBackend + Clone + Send + Sync + 'static,
+    S: StorageLoader + Clone + Send + Sync + 'static,
+{
+    let outcome = lookup_at_threshold(state, ctx, l3_threshold(state, ctx)).await?;
+    if let Some(hit) = &outcome
+        && collects_another_answer(ctx, hit)
+    {
+        debug!(
+            answers = hit.payload.answer_count(),
+            "Entry not full yet - collecting another answer"
+        );
+        if let Some(slot) = &ctx.debug_trace {
+            slot.set_decision(ReflexStatus::Miss.as_header_value());
+        }
+        return Ok(None);
+    }
+    Ok(outcome)
+}
+
+/// The tenant's L3 threshold, else the scorer's.
+pub(crate) fn l3_threshold<B, S>(state: &HandlerState<B, S>, ctx: &StoreContext) -> f32
+where
+    B: BqSearchBackend + Clone + Send + Sync + 'static,
+    S: StorageLoader + Clone + Send + Sync + 'static,
+{
+    ctx.policy
+        .l3_threshold
+        .unwrap_or_else(|| state.scorer.threshold())
+}
+
+/// An exact repeat collects another answer until its entry is full.
+fn collects_another_answer(ctx: &StoreContext, hit: &CacheOutcome) -> bool {
+    matches!(ctx.sampling, SamplingMode::Variants(max)
+        if hit.status == ReflexStatus::HitL1Exact && hit.payload.answer_count() < max)
+        && ctx.should_store()
+        && !ctx.directives.only_if_cached
+}
+
+/// Repeats the lookup at the degraded L3 threshold when `error` reports an
+/// open provider circuit and a degraded threshold is configured.
+pub(crate) async fn lookup_degraded<B, S>(
+    state: &HandlerState<B, S>,
+    ctx: &StoreContext,
+    error: &GatewayError,
+) -> Result<Option<CacheOutcome>, GatewayError>
+where
+    B: BqSearchBackend + Clone + Send + Sync + 'static,
+    S: StorageLoader + Clone + Send + Sync + 'static,
+{
+    let (GatewayError::ProviderUnavailable { .. }, Some(threshold)) =
+        (error, state.upstream.config().degraded_threshold)
+    else {
+        return Ok(None);
+    };
+
+    let outcome = lookup_at_threshold(state, ctx, threshold).await?;
+    telemetry::record_lookup(ctx, Tier::Degraded, outcome.is_some());
+    Ok(outcome.map(|outcome| {
+        warn!(threshold, "Provider unavailable - serving degraded hit");
+        CacheOutcome {
+            status: ReflexStatus::HitDegraded,
+            ..outcome
+        }
+    }))
+}
+
+async fn lookup_at_threshold<B, S>(
+    state: &HandlerState<B, S>,
+    ctx: &StoreContext,
+    threshold: f32,
+) -> Result<Option<CacheOutcome>, GatewayError>
+where
+    B: BqSearchBackend + Clone + Send + Sync + 'static,
+    S: StorageLoader + Clone + Send + Sync + 'static,
+{
+    let (outcome, trace) = trace_lookup(state, ctx, threshold).await?;
+    telemetry::record_trace(ctx, &trace);
+    if let Some(slot) = &ctx.debug_trace {
+        slot.record(trace);
+    }
+
+    Ok(outcome.map(|mut outcome| {
+        let count = outcome.payload.answer_count();
+        if count > 1 {
+            outcome.payload.select(state.sampling.pick_variant(count));
+        }
+        outcome
+    }))
+}
+
+/// The lookup [`lookup_cached_payload`] makes for `ctx`, as a dry run that
+/// records no metrics and serves no rotating answer.
+pub(crate) async fn explain_lookup<B, S>(
+    state: &HandlerState<B, S>,
+    ctx: &StoreContext,
+) -> Result<LookupTrace, GatewayError>
+where
+    B: BqSearchBackend + Clone + Send + Sync + 'static,
+    S: StorageLoader + Clone + Send + Sync + 'static,
+{
+    let (outcome, mut trace) = trace_lookup(state, ctx, l3_threshold(state, ctx)).await?;
+    if outcome.is_some_and(|hit| collects_another_answer(ctx, &hit)) {
+        trace.decision = ReflexStatus::Miss.as_header_value();
+    }
+    Ok(trace)
+}
+
+/// Runs the L1 → L2 → L3 lookup at `threshold` and reports how each tier
+/// decided, without side effects beyond the lookup itself.
+async fn trace_lookup<B, S>(
+    state: &HandlerState<B, S>,
+    ctx: &StoreContext,
+    threshold: f32,
+) -> Result<(Option<CacheOutcome>, LookupTrace), GatewayError>
+where
+    B: BqSearchBackend + Clone + Send + Sync + 'static,
+    S: StorageLoader + Clone + Send + Sync + 'static,
+{
+    let mut trace = LookupTrace::new(ctx, threshold);
+    if ctx.directives.no_cache {
+        debug!("Cache-Control: no-cache, skipping lookup");
+        trace.skipped = true;
+        return Ok((None, trace));
     }
 
-    let request_hash_str = request_hash.to_string();
     let tiered_result = state
         .tiered_cache
-        .lookup_with_semantic_query(&request_hash_str, &semantic_text, tenant_id_hash)
+        .lookup_with_options(
+            &ctx.l1_key,
+            &ctx.semantic_text,
+            ctx.tenant_id,
+            ctx.lookup_options(),
+        )
         .await
         .map_err(|e| GatewayError::CacheLookupFailed(e.to_string()))?;
 
@@ -96,9 +697,21 @@ where
 
             let raw_payload = String::from_utf8_lossy(&archived.payload_blob);
             match serde_json::from_str::<CachePayload>(&raw_payload) {
-                Ok(cache_payload) => Some((cache_payload, ReflexStatus::HitL1Exact)),
+                Ok(payload) => {
+                    trace.l1 = L1Outcome::Hit;
+                    Some(CacheOutcome {
+                        payload,
+                        status: ReflexStatus::HitL1Exact,
+                        stored_at: Some(archived.timestamp.to_native()),
+                        storage_key: Some(StoreContext::storage_key_for(
+                            archived.tenant_id.to_native(),
+                            archived.context_hash.to_native(),
+                        )),
+                    })
+                }
                 Err(e) => {
                     tracing::warn!("Failed to parse L1 payload: {}. Treating as miss.", e);
+                    trace.l1 = L1Outcome::Unreadable;
                     None
                 }
             }
@@ -108,15 +721,37 @@ where
                 candidates = l2_result.candidates().len(),
                 "L2 semantic hit, verifying..."
             );
+            trace.bq_candidates = l2_result.bq_candidates_count();
 
+            let query_key = query_key(&ctx.semantic_text);
             let mut valid_candidates = Vec::new();
             for c in l2_result.candidates() {
+                let mut candidate = CandidateTrace::new(c);
                 let raw_payload = String::from_utf8_lossy(&c.entry.payload_blob);
-                if let Ok(payload) = serde_json::from_str::<CachePayload>(&raw_payload) {
-                    let mut temp_entry = c.entry.clone();
-                    temp_entry.payload_blob = payload.semantic_request.as_bytes().to_vec();
-                    valid_candidates.push((temp_entry, c.score, payload));
+                match serde_json::from_str::<CachePayload>(&raw_payload) {
+                    _ if state.feedback.is_false_positive(
+                        ctx.tenant_id,
+                        &query_key,
+                        &candidate.storage_key,
+                    ) =>
+                    {
+                        candidate.excluded = Some(Exclusion::KnownFalsePositive);
+                    }
+                    // Near-identical text about different images, or under a
+                    // different scope, is not a match.
+                    Ok(payload) if payload.media_key != ctx.media_key => {
+                        candidate.excluded = Some(Exclusion::MediaMismatch);
+                    }
+                    Ok(payload) if payload.scope_key != ctx.scope_key => {
+                        candidate.excluded = Some(Exclusion::ScopeMismatch);
+                    }
+                    Ok(payload) => {
+                        candidate.semantic_request = Some(payload.semantic_request.clone());
+                        valid_candidates.push((c.entry.clone(), c.score, payload));
+                    }
+                    Err(_) => candidate.excluded = Some(Exclusion::UnreadablePayload),
                 }
+                trace.candidates.push(candidate);
             }
 
             let candidates_for_scoring: Vec<(CacheEntry, f32)> = valid_candidates
@@ -124,19 +759,38 @@ where
                 .map(|(e, s, _)| (e.clone(), *s))
                 .collect();
 
-            let (verified_entry, verification_result) = state
+            let (scored, verification_result) = state
                 .scorer
-                .verify_candidates(&semantic_text, candidates_for_scoring)
+                .verify_candidates_with_details_and_threshold(
+                    &ctx.semantic_text,
+                    candidates_for_scoring,
+                    threshold,
+                )
                 .map_err(GatewayError::ScoringFailed)?;
 
+            for verified in &scored {
+                if let Some(candidate) = trace
+                    .candidates
+                    .iter_mut()
+                    .find(|c| c.is_for(&verified.entry))
+                {
+                    candidate.cross_encoder_score = Some(verified.cross_encoder_score);
+                }
+            }
+            trace.verification = Some(verification_result.clone());
+
             match verification_result {
                 VerificationResult::Verified { score } => {
                     info!(score = score, "L3 verification passed");
-                    let entry = verified_entry.ok_or_else(|| {
-                        GatewayError::InternalError(
-                            "L3 verification returned Verified without an entry".to_string(),
-                        )
-                    })?;
+                    // Scored candidates are sorted best-first.
+                    let entry = &scored
+                        .first()
+                        .ok_or_else(|| {
+                            GatewayError::InternalError(
+                                "L3 verification returned Verified without an entry".to_string(),
+                            )
+                        })?
+                        .entry;
                     let payload = valid_candidates
                         .iter()
                         .find(|(e, _, _)| e.context_hash == entry.context_hash)
@@ -147,7 +801,15 @@ where
                             )
                         })?;
 
-                    Some((payload, ReflexStatus::HitL3Verified))
+                    Some(CacheOutcome {
+                        payload,
+                        status: ReflexStatus::HitL3Verified,
+                        stored_at: Some(entry.timestamp),
+                        storage_key: Some(StoreContext::storage_key_for(
+                            entry.tenant_id,
+                            entry.context_hash,
+                        )),
+                    })
                 }
                 VerificationResult::Rejected { top_score } => {
                     debug!(score = top_score, "L3 verification rejected");
@@ -162,78 +824,85 @@ where
         TieredLookupResult::Miss => None,
     };
 
-    if let Some((resp, status)) = cached_response {
-        return make_response(resp, status);
+    if let Some(outcome) = &cached_response {
+        trace.decision = outcome.status.as_header_value();
     }
+    Ok((cached_response, trace))
+}
 
-    debug!("Cache Miss - Calling Provider");
-
-    let model = request.model.clone();
-
-    let response = if state.mock_provider {
-        let content = format!("Mock response for: {}", semantic_text);
-        let response_value = serde_json::json!({
-            "id": format!("chatcmpl-{}", uuid::Uuid::new_v4()),
-            "object": "chat.completion",
-            "created": chrono::Utc::now().timestamp() as u32,
-            "model": model.clone(),
-            "choices": [{
-                "index": 0,
-                "message": { "role": "assistant", "content": content },
-                "finish_reason": "stop"
-            }],
-            "usage": {
-                "prompt_tokens": 10,
-                "completion_tokens": 10,
-                "total_tokens": 20
-            }
-        });
-
-        serde_json::from_value::<CreateChatCompletionResponse>(response_value)
-            .map_err(|e| GatewayError::SerializationFailed(e.to_string()))?
-    } else {
-        let genai_req = crate::gateway::adapter::adapt_openai_to_genai(request.clone());
-
-        let genai_resp = state
-            .genai_client
-            .exec_chat(&model, genai_req, None)
-            .await
-            .map_err(|e| {
-                error!("Provider error: {}", e);
-                GatewayError::ProviderError("Upstream service request failed".to_string())
-            })?;
-
-        crate::gateway::adapter::adapt_genai_to_openai(genai_resp, model.clone())
-    };
+/// `payload` added to the answers already stored under `ctx`'s key.
+async fn with_stored_answers<B, S>(
+    state: &HandlerState<B, S>,
+    ctx: &StoreContext,
+    payload: &CachePayload,
+    max: usize,
+) -> CachePayload
+where
+    B: BqSearchBackend + Clone + Send + Sync + 'static,
+    S: StorageLoader + Clone + Send + Sync + 'static,
+{
+    let stored = state
+        .tiered_cache
+        .l2()
+        .storage()
+        .load(&ctx.storage_key(), ctx.tenant_id)
+        .await
+        .and_then(|entry| serde_json::from_slice::<CachePayload>(&entry.payload_blob).ok());
+    match stored {
+        Some(mut stored) => {
+            stored.add_variant(payload.response.clone(), max);
+            stored
+        }
+        None => payload.clone(),
+    }
+}
 
+/// Writes a provider response through storage, L1 and the vector index.
+pub(crate) async fn store_cache_payload<B, S>(
+    state: &HandlerState<B, S>,
+    ctx: &StoreContext,
+    payload: &CachePayload,
+) -> Result<(), GatewayError>
+where
+    B: BqSearchBackend + Clone + Send + Sync + 'static,
+    S: StorageLoader + StorageWriter + Clone + Send + Sync + 'static,
+{
     let timestamp = chrono::Utc::now().timestamp();
 
-    let payload = CachePayload {
-        semantic_request: semantic_text.clone(),
-        response: response.clone(),
+    let merged;
+    let payload = match ctx.sampling {
+        SamplingMode::Variants(max) => {
+            merged = with_stored_answers(state, ctx, payload, max).await;
+            &merged
+        }
+        _ => payload,
     };
-    let payload_json = serde_json::to_string(&payload)
+
+    let payload_json = serde_json::to_string(payload)
         .map_err(|e| GatewayError::SerializationFailed(e.to_string()))?;
 
+    let started = Instant::now();
     let embedding_f16 = state
         .tiered_cache
         .l2()
         .embedder()
-        .embed(&semantic_text)
+        .embed(&ctx.semantic_text)
         .map_err(|e| GatewayError::EmbeddingFailed(e.to_string()))?;
+    reflex::telemetry::record_stage(reflex::telemetry::STAGE_EMBEDDING, started);
 
     let embedding_bytes: Vec<u8> = embedding_f16.iter().flat_map(|v| v.to_le_bytes()).collect();
 
     let cache_entry = CacheEntry {
-        tenant_id: tenant_id_hash,
-        context_hash: request_hash_u64,
+        format: CacheEntry::FORMAT,
+        tenant_id: ctx.tenant_id,
+        context_hash: ctx.context_hash,
         timestamp,
         embedding: embedding_bytes,
+        verification_text: payload.semantic_request.clone(),
         payload_blob: payload_json.into_bytes(),
     };
 
-    let entry_id = format!("{:016x}", request_hash_u64);
-    let storage_key = format!("{}/{}.rkyv", tenant_id_hash, entry_id);
+    let storage_key = ctx.storage_key();
 
     let serialized_bytes = rkyv::to_bytes::<rkyv::rancor::Error>(&cache_entry)
         .map_err(|e| GatewayError::SerializationFailed(e.to_string()))?;
@@ -247,31 +916,82 @@ where
     .map_err(|e| GatewayError::StorageError(format!("Storage write task failed: {}", e)))?
     .map_err(|e| GatewayError::StorageError(e.to_string()))?;
 
-    let l1_key = request_hash.to_string();
     state
         .tiered_cache
-        .insert_l1(&l1_key, tenant_id_hash, mmap_handle);
+        .insert_l1(&ctx.l1_key, ctx.tenant_id, mmap_handle);
 
     let embedding_f32: Vec<f32> = embedding_f16.iter().map(|v| v.to_f32()).collect();
     let vector_dim = state.tiered_cache.l2().config().vector_size;
     spawn_index_update(
         state.bq_client.clone(),
         state.collection_name.clone(),
-        tenant_id_hash,
-        request_hash_u64,
+        ctx.tenant_id,
+        ctx.context_hash,
         timestamp,
         embedding_f32,
         storage_key,
+        ctx.scope_hash(),
         vector_dim,
     );
 
-    make_response(payload, ReflexStatus::Miss)
+    Ok(())
 }
 
+/// Reports the tokens and cost a hit saved in `headers` and the savings
+/// counters. Misses save nothing and are left alone.
+pub(crate) fn report_savings<B, S>(
+    state: &HandlerState<B, S>,
+    ctx: &StoreContext,
+    response: &CreateChatCompletionResponse,
+    status: ReflexStatus,
+    headers: &mut HeaderMap,
+) where
+    B: BqSearchBackend + Clone + Send + Sync + 'static,
+    S: StorageLoader + Clone + Send + Sync + 'static,
+{
+    if !status.is_hit() {
+        return;
+    }
+    if let Some(savings) = state.prices.savings(response) {
+        savings.apply_headers(headers);
+        telemetry::record_savings(ctx, &savings);
+    }
+}
+
+/// Renders a non-streaming chat completion body in the negotiated [`HitFormat`].
 pub(crate) fn make_response(
     payload: CachePayload,
     status: ReflexStatus,
+    format: HitFormat,
 ) -> Result<Response, GatewayError> {
+    let body = match format {
+        HitFormat::OpenAi => serde_json::to_value(&payload.response)
+            .map_err(|e| GatewayError::SerializationFailed(e.to_string()))?,
+        HitFormat::Tauq => serde_json::to_value(tauq_response(payload)?)
+            .map_err(|e| GatewayError::SerializationFailed(e.to_string()))?,
+        HitFormat::Envelope => serde_json::json!({
+            "object": "reflex.envelope",
+            "status": status.as_header_value(),
+            "cached": status.is_hit(),
+            "semantic_request": payload.semantic_request,
+            "response": payload.response,
+        }),
+    };
+
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        REFLEX_STATUS_HEADER,
+        HeaderValue::from_static(status.as_header_value()),
+    );
+    headers.insert(
+        REFLEX_FORMAT_HEADER,
+        HeaderValue::from_static(format.as_str()),
+    );
+    Ok((StatusCode::OK, headers, Json(body)).into_response())
+}
+
+/// Replaces the first choice with a Tauq encoding of the whole payload.
+fn tauq_response(payload: CachePayload) -> Result<CreateChatCompletionResponse, GatewayError> {
     let payload_json = serde_json::to_value(&payload).unwrap_or_default();
     let tauq_content = TauqEncoder::encode(&payload_json);
 
@@ -291,13 +1011,7 @@ pub(crate) fn make_response(
     let mut wrapper = payload.response;
     wrapper.object = "chat.completion".to_string();
     wrapper.choices = vec![choice];
-
-    let mut headers = HeaderMap::new();
-    headers.insert(
-        REFLEX_STATUS_HEADER,
-        HeaderValue::from_static(status.as_header_value()),
-    );
-    Ok((StatusCode::OK, headers, Json(wrapper)).into_response())
+    Ok(wrapper)
 }
 
 pub(crate) fn validate_no_legacy_fields(req: &serde_json::Value) -> Result<(), GatewayError> {
@@ -435,6 +1149,7 @@ pub(crate) fn spawn_index_update<B>(
     timestamp: i64,
     vector: Vec<f32>,
     storage_key: String,
+    scope_hash: Option<u64>,
     vector_dim: u64,
 ) -> bool
 where
@@ -449,6 +1164,7 @@ where
         context_hash,
         timestamp,
         storage_key: Some(storage_key),
+        scope_hash,
     };
 
     tokio::spawn(async move {
@@ -457,6 +1173,7 @@ where
             .await
         {
             error!(error = %e, "Failed to ensure BQ collection");
+            telemetry::record_index_upsert_failure();
             return;
         }
 
@@ -469,6 +1186,7 @@ where
             .await
         {
             error!(error = %e, "Failed to upsert point to BQ index");
+            telemetry::record_index_upsert_failure();
             return;
         }
 
@@ -480,46 +1198,3 @@ where
 
     true
 }
-
-/// Creates a mock SSE streaming response for testing purposes.
-///
-/// This function generates a simple SSE stream that emits a single mock chunk
-/// followed by a `[DONE]` marker, mimicking the OpenAI streaming response format.
-fn create_mock_streaming_response(
-    model: String,
-    semantic_text: String,
-) -> Sse<impl futures_util::Stream<Item = Result<Event, Infallible>> + Send> {
-    let content = format!("Mock streaming response for: {}", semantic_text);
-
-    let chunk_response = serde_json::json!({
-        "id": format!("chatcmpl-{}", uuid::Uuid::new_v4()),
-        "object": "chat.completion.chunk",
-        "created": chrono::Utc::now().timestamp() as u32,
-        "model": model,
-        "choices": [{
-            "index": 0,
-            "delta": { "role": "assistant", "content": content },
-            "finish_reason": null
-        }]
-    });
-
-    let done_response = serde_json::json!({
-        "id": format!("chatcmpl-{}", uuid::Uuid::new_v4()),
-        "object": "chat.completion.chunk",
-        "created": chrono::Utc::now().timestamp() as u32,
-        "model": model,
-        "choices": [{
-            "index": 0,
-            "delta": {},
-            "finish_reason": "stop"
-        }]
-    });
-
-    let events = vec![
-        Ok(Event::default().data(chunk_response.to_string())),
-        Ok(Event::default().data(done_response.to_string())),
-        Ok(Event::default().data("[DONE]")),
-    ];
-
-    Sse::new(stream::iter(events))
-}
@@ -175,6 +175,9 @@ fn mock_cache_payload() -> CachePayload {
             r#"{"model":"gpt-4","messages":[{"role":"user","content":"Hello, world!"}]}"#
                 .to_string(),
         response: mock_completion_response("gpt-4"),
+        media_key: None,
+        scope_key: None,
+        variants: Vec::new(),
     }
 }
 
@@ -577,12 +580,13 @@ mod semantic_text_from_request_tests {
 
 mod make_response_tests {
     use super::*;
+    use crate::gateway::format::HitFormat;
     use crate::gateway::handler::make_response;
 
     #[tokio::test]
     async fn test_make_response_hit_l1_exact() {
         let payload = mock_cache_payload();
-        let result = make_response(payload, ReflexStatus::HitL1Exact);
+        let result = make_response(payload, ReflexStatus::HitL1Exact, HitFormat::Tauq);
 
         assert!(result.is_ok());
         let response = result.unwrap();
@@ -597,7 +601,7 @@ mod make_response_tests {
     #[tokio::test]
     async fn test_make_response_hit_l3_verified() {
         let payload = mock_cache_payload();
-        let result = make_response(payload, ReflexStatus::HitL3Verified);
+        let result = make_response(payload, ReflexStatus::HitL3Verified, HitFormat::Tauq);
 
         assert!(result.is_ok());
         let response = result.unwrap();
@@ -612,7 +616,7 @@ mod make_response_tests {
     #[tokio::test]
     async fn test_make_response_miss() {
         let payload = mock_cache_payload();
-        let result = make_response(payload, ReflexStatus::Miss);
+        let result = make_response(payload, ReflexStatus::Miss, HitFormat::Tauq);
 
         assert!(result.is_ok());
         let response = result.unwrap();
@@ -627,7 +631,7 @@ mod make_response_tests {
     #[tokio::test]
     async fn test_make_response_body_structure() {
         let payload = mock_cache_payload();
-        let result = make_response(payload, ReflexStatus::HitL1Exact);
+        let result = make_response(payload, ReflexStatus::HitL1Exact, HitFormat::Tauq);
         let response = result.unwrap();
 
         let body = response.into_body();
@@ -644,7 +648,7 @@ mod make_response_tests {
     #[tokio::test]
     async fn test_make_response_tauq_encoded_content() {
         let payload = mock_cache_payload();
-        let result = make_response(payload, ReflexStatus::HitL1Exact);
+        let result = make_response(payload, ReflexStatus::HitL1Exact, HitFormat::Tauq);
         let response = result.unwrap();
 
         let body = response.into_body();
@@ -656,6 +660,78 @@ mod make_response_tests {
             .unwrap();
         assert!(!content.is_empty());
     }
+
+    #[tokio::test]
+    async fn test_make_response_openai_format_is_verbatim() {
+        let mut payload = mock_cache_payload();
+        payload.response = serde_json::from_value(serde_json::json!({
+            "id": "chatcmpl-tools",
+            "object": "chat.completion",
+            "created": 1702512000_u32,
+            "model": "gpt-4",
+            "choices": [{
+                "index": 0,
+                "message": {
+                    "role": "assistant",
+                    "content": null,
+                    "tool_calls": [{
+                        "id": "call_1",
+                        "type": "function",
+                        "function": {"name": "lookup", "arguments": "{}"}
+                    }]
+                },
+                "finish_reason": "tool_calls"
+            }]
+        }))
+        .unwrap();
+        let expected = serde_json::to_value(&payload.response).unwrap();
+
+        let response = make_response(payload, ReflexStatus::HitL1Exact, HitFormat::OpenAi).unwrap();
+        assert_eq!(response.headers().get("x-reflex-format").unwrap(), "openai");
+
+        let bytes = response.into_body().collect().await.unwrap().to_bytes();
+        let body_json: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(body_json, expected);
+        assert_eq!(body_json["choices"][0]["finish_reason"], "tool_calls");
+    }
+
+    #[tokio::test]
+    async fn test_make_response_envelope_format_carries_metadata() {
+        let payload = mock_cache_payload();
+        let response =
+            make_response(payload, ReflexStatus::HitL2Semantic, HitFormat::Envelope).unwrap();
+
+        let bytes = response.into_body().collect().await.unwrap().to_bytes();
+        let body_json: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(body_json["object"], "reflex.envelope");
+        assert_eq!(body_json["status"], "HIT_L2_SEMANTIC");
+        assert_eq!(body_json["cached"], true);
+        assert_eq!(
+            body_json["response"]["choices"][0]["message"]["content"],
+            "Hello! How can I help you?"
+        );
+    }
+
+    #[test]
+    fn test_hit_format_negotiation() {
+        let mut headers = axum::http::HeaderMap::new();
+        assert_eq!(
+            HitFormat::negotiate(&headers, HitFormat::Envelope).unwrap(),
+            HitFormat::Envelope
+        );
+
+        headers.insert("x-reflex-format", "OpenAI".parse().unwrap());
+        assert_eq!(
+            HitFormat::negotiate(&headers, HitFormat::Tauq).unwrap(),
+            HitFormat::OpenAi
+        );
+
+        headers.insert("x-reflex-format", "yaml".parse().unwrap());
+        assert!(matches!(
+            HitFormat::negotiate(&headers, HitFormat::Tauq),
+            Err(GatewayError::InvalidRequest(_))
+        ));
+    }
 }
 
 mod spawn_index_update_tests {
@@ -683,6 +759,7 @@ mod spawn_index_update_tests {
             1702512000, // timestamp
             vector,
             "test/storage/key.rkyv".to_string(),
+            None,
             reflex::constants::DEFAULT_VECTOR_SIZE_U64,
         );
 
@@ -709,6 +786,7 @@ mod spawn_index_update_tests {
             1702512000,
             vector,
             "test/key.rkyv".to_string(),
+            None,
             reflex::constants::DEFAULT_VECTOR_SIZE_U64,
         );
 
@@ -742,6 +820,7 @@ mod spawn_index_update_tests {
             1702512000,
             vector,
             storage_key.to_string(),
+            None,
             reflex::constants::DEFAULT_VECTOR_SIZE_U64,
         );
 
@@ -1047,6 +1126,17 @@ mod chat_completions_handler_tests {
 mod error_handling_tests {
     use super::*;
 
+    #[tokio::test]
+    async fn test_gateway_error_not_cached_response() {
+        let response = GatewayError::NotCached.into_response();
+
+        assert_eq!(response.status(), StatusCode::GATEWAY_TIMEOUT);
+        assert_eq!(
+            response.headers().get(REFLEX_STATUS_HEADER).unwrap(),
+            "MISS"
+        );
+    }
+
     #[tokio::test]
     async fn test_gateway_error_invalid_request_response() {
         let err = GatewayError::InvalidRequest("Test error".to_string());
@@ -1313,14 +1403,19 @@ mod l1_cache_hit_tests {
         let payload = CachePayload {
             semantic_request: semantic_request.to_string(),
             response: mock_completion_response("gpt-4"),
+            media_key: None,
+            scope_key: None,
+            variants: Vec::new(),
         };
         let payload_json = serde_json::to_string(&payload).unwrap();
 
         let entry = CacheEntry {
+            format: CacheEntry::FORMAT,
             tenant_id: reflex::hashing::hash_tenant_id("default"),
             context_hash: 12345,
             timestamp: chrono::Utc::now().timestamp(),
             embedding: vec![0u8; reflex::constants::EMBEDDING_F16_BYTES],
+            verification_text: semantic_request.to_string(),
             payload_blob: payload_json.into_bytes(),
         };
 
@@ -1380,10 +1475,12 @@ mod l1_cache_hit_tests {
         let request_hash = blake3::hash(&request_bytes);
 
         let entry = CacheEntry {
+            format: CacheEntry::FORMAT,
             tenant_id: reflex::hashing::hash_tenant_id("default"),
             context_hash: 12345,
             timestamp: chrono::Utc::now().timestamp(),
             embedding: vec![0u8; reflex::constants::EMBEDDING_F16_BYTES],
+            verification_text: String::new(),
             payload_blob: b"not valid json at all".to_vec(),
         };
 
@@ -1434,14 +1531,19 @@ mod l2_l3_verification_tests {
         let payload = CachePayload {
             semantic_request: semantic_request.to_string(),
             response: mock_completion_response("gpt-4"),
+            media_key: None,
+            scope_key: None,
+            variants: Vec::new(),
         };
         let payload_json = serde_json::to_string(&payload).unwrap();
 
         CacheEntry {
+            format: CacheEntry::FORMAT,
             tenant_id,
             context_hash,
             timestamp: chrono::Utc::now().timestamp(),
             embedding: vec![0u8; reflex::constants::EMBEDDING_F16_BYTES],
+            verification_text: semantic_request.to_string(),
             payload_blob: payload_json.into_bytes(),
         }
     }
@@ -1595,10 +1697,12 @@ mod l2_l3_verification_tests {
         let storage_key = format!("{}/invalid_payload.rkyv", tenant_id);
 
         let entry = CacheEntry {
+            format: CacheEntry::FORMAT,
             tenant_id,
             context_hash,
             timestamp: chrono::Utc::now().timestamp(),
             embedding: vec![0u8; reflex::constants::EMBEDDING_F16_BYTES],
+            verification_text: String::new(),
             payload_blob: b"invalid json {{{".to_vec(),
         };
         state
@@ -1684,6 +1788,7 @@ mod spawn_index_update_error_tests {
             1702512000,
             vector,
             "test/key.rkyv".to_string(),
+            None,
             reflex::constants::DEFAULT_VECTOR_SIZE_U64,
         );
 
@@ -1713,6 +1818,7 @@ mod spawn_index_update_error_tests {
             1702512000,
             wrong_dim_vector,
             "test/key.rkyv".to_string(),
+            None,
             reflex::constants::DEFAULT_VECTOR_SIZE_U64,
         );
 
@@ -1948,3 +2054,189 @@ mod edge_case_tests {
         assert_eq!(response.status(), StatusCode::OK);
     }
 }
+
+// =============================================================================
+// Streaming cache tests
+// =============================================================================
+
+mod streaming_cache_tests {
+    use super::*;
+
+    async fn send(router: &Router, body: serde_json::Value) -> axum::response::Response {
+        let request = Request::builder()
+            .method("POST")
+            .uri("/v1/chat/completions")
+            .header("Content-Type", "application/json")
+            .body(Body::from(serde_json::to_string(&body).unwrap()))
+            .unwrap();
+
+        router.clone().oneshot(request).await.unwrap()
+    }
+
+    /// Parses the `data:` payloads of an SSE body, excluding `[DONE]`.
+    async fn sse_chunks(response: axum::response::Response) -> (Vec<serde_json::Value>, bool) {
+        let bytes = response.into_body().collect().await.unwrap().to_bytes();
+        let text = String::from_utf8(bytes.to_vec()).unwrap();
+
+        let mut chunks = Vec::new();
+        let mut done = false;
+        for data in text.lines().filter_map(|l| l.strip_prefix("data: ")) {
+            if data == "[DONE]" {
+                done = true;
+            } else {
+                chunks.push(serde_json::from_str(data).unwrap());
+            }
+        }
+        (chunks, done)
+    }
+
+    async fn wait_for_l1<B, S>(state: &HandlerState<B, S>)
+    where
+        B: BqSearchBackend + Clone + Send + Sync + 'static,
+        S: reflex::cache::StorageLoader + Clone + Send + Sync + 'static,
+    {
+        for _ in 0..100 {
+            state.tiered_cache.run_pending_tasks_l1();
+            if !state.tiered_cache.l1_is_empty() {
+                return;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        panic!("streamed response was never stored");
+    }
+
+    #[tokio::test]
+    async fn test_streaming_miss_uses_stable_chunk_id() {
+        let (state, _temp_dir) = setup_test_state().await;
+        let router = create_test_router(state);
+
+        let response = send(&router, streaming_request_json()).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers().get(REFLEX_STATUS_HEADER).unwrap(),
+            "MISS"
+        );
+
+        let (chunks, done) = sse_chunks(response).await;
+        assert!(done);
+        assert!(chunks.len() >= 2);
+        let id = chunks[0]["id"].as_str().unwrap();
+        assert!(chunks.iter().all(|c| c["id"] == id));
+        assert_eq!(
+            chunks.last().unwrap()["choices"][0]["finish_reason"],
+            "stop"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_streaming_miss_is_replayed_on_next_stream() {
+        let (state, _temp_dir) = setup_test_state().await;
+        let router = create_test_router(state.clone());
+
+        let (first, _) = sse_chunks(send(&router, streaming_request_json()).await).await;
+        let streamed: String = first
+            .iter()
+            .filter_map(|c| c["choices"][0]["delta"]["content"].as_str())
+            .collect();
+
+        wait_for_l1(&state).await;
+
+        let response = send(&router, streaming_request_json()).await;
+        assert_eq!(
+            response.headers().get(REFLEX_STATUS_HEADER).unwrap(),
+            "HIT_L1_EXACT"
+        );
+
+        let (chunks, done) = sse_chunks(response).await;
+        assert!(done);
+        let replayed: String = chunks
+            .iter()
+            .filter_map(|c| c["choices"][0]["delta"]["content"].as_str())
+            .collect();
+        assert_eq!(replayed, streamed);
+        assert_eq!(
+            chunks.last().unwrap()["choices"][0]["finish_reason"],
+            "stop"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_streaming_miss_serves_non_streaming_hit() {
+        let (state, _temp_dir) = setup_test_state().await;
+        let router = create_test_router(state.clone());
+
+        let _ = sse_chunks(send(&router, streaming_request_json()).await).await;
+        wait_for_l1(&state).await;
+
+        let mut buffered = streaming_request_json();
+        buffered.as_object_mut().unwrap().remove("stream");
+        let response = send(&router, buffered).await;
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers().get(REFLEX_STATUS_HEADER).unwrap(),
+            "HIT_L1_EXACT"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_non_streaming_miss_serves_streaming_hit() {
+        let (state, _temp_dir) = setup_test_state().await;
+        let router = create_test_router(state);
+
+        let mut buffered = streaming_request_json();
+        buffered.as_object_mut().unwrap().remove("stream");
+        let response = send(&router, buffered).await;
+        assert_eq!(
+            response.headers().get(REFLEX_STATUS_HEADER).unwrap(),
+            "MISS"
+        );
+
+        let response = send(&router, streaming_request_json()).await;
+        assert_eq!(
+            response.headers().get(REFLEX_STATUS_HEADER).unwrap(),
+            "HIT_L1_EXACT"
+        );
+        let (chunks, done) = sse_chunks(response).await;
+        assert!(done);
+        assert_eq!(chunks[0]["choices"][0]["delta"]["role"], "assistant");
+    }
+}
+
+mod cache_control_tests {
+    use crate::gateway::cache_control::CacheDirectives;
+    use axum::http::{HeaderMap, HeaderValue, header};
+
+    fn directives(values: &[&'static str]) -> CacheDirectives {
+        let mut headers = HeaderMap::new();
+        for value in values {
+            headers.append(header::CACHE_CONTROL, HeaderValue::from_static(value));
+        }
+        CacheDirectives::from_headers(&headers)
+    }
+
+    #[test]
+    fn test_no_header_means_no_directives() {
+        assert_eq!(directives(&[]), CacheDirectives::default());
+    }
+
+    #[test]
+    fn test_parses_combined_directives_case_insensitively() {
+        let parsed = directives(&["No-Cache, no-store", "ONLY-IF-CACHED, max-age=\"30\""]);
+
+        assert!(parsed.no_cache);
+        assert!(parsed.no_store);
+        assert!(parsed.only_if_cached);
+        assert_eq!(parsed.max_age, Some(30));
+    }
+
+    #[test]
+    fn test_strictest_max_age_wins_and_malformed_is_ignored() {
+        assert_eq!(directives(&["max-age=60, max-age=10"]).max_age, Some(10));
+        assert_eq!(directives(&["max-age=soon"]).max_age, None);
+        assert_eq!(
+            directives(&["max-age=5"]).lookup_options().max_age_secs,
+            Some(5)
+        );
+    }
+}
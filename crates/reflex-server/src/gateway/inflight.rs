@@ -0,0 +1,165 @@
+//! Single-flight coalescing of identical cache misses.
+//!
+//! The first request to miss for a given tenant, exact request hash and caller
+//! provider key becomes the leader and calls the provider. Identical requests arriving before the
+//! leader finishes wait for its result instead of calling the provider
+//! themselves, and are answered with `X-Reflex-Status: HIT_INFLIGHT`.
+//!
+//! A leader whose provider call fails or is refused as unavailable shares that
+//! error, with its status, with every waiter. Callers with their own provider
+//! key only wait on calls made with the same key, so a key the provider
+//! rejects is never blamed on another. A leader that fails otherwise, or is
+//! dropped without finishing (client disconnect, interrupted stream setup),
+//! releases its waiters with [`FlightOutcome::Abandoned`], and each makes its
+//! own provider call.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use tokio::sync::watch;
+
+use crate::gateway::error::GatewayError;
+use crate::gateway::payload::CachePayload;
+
+/// `(tenant_id, exact request hash, caller provider key fingerprint)`.
+pub type FlightKey = (u64, String, Option<String>);
+
+type FlightResult = Result<CachePayload, SharedFailure>;
+
+/// A leader's error as its waiters receive it.
+#[derive(Debug, Clone)]
+enum SharedFailure {
+    Provider(String),
+    Unavailable { message: String, retry_after: u64 },
+}
+
+impl SharedFailure {
+    fn into_error(self) -> GatewayError {
+        match self {
+            SharedFailure::Provider(message) => GatewayError::ProviderError(message),
+            SharedFailure::Unavailable {
+                message,
+                retry_after,
+            } => GatewayError::ProviderUnavailable {
+                message,
+                retry_after,
+            },
+        }
+    }
+}
+
+/// Registry of provider calls currently in flight.
+#[derive(Debug, Default)]
+pub struct InflightRequests {
+    flights: Mutex<HashMap<FlightKey, watch::Receiver<Option<FlightResult>>>>,
+}
+
+/// Role of a request that missed the cache.
+pub enum Flight {
+    /// No identical call is in flight; this request makes it.
+    Leader(FlightGuard),
+    /// An identical call is in flight; wait for its result.
+    Follower(FlightWaiter),
+}
+
+impl InflightRequests {
+    /// Joins the flight for `key`, becoming its leader if there is none.
+    pub fn join(self: &Arc<Self>, key: FlightKey) -> Flight {
+        let mut flights = self.flights.lock().expect("lock poisoned");
+        if let Some(rx) = flights.get(&key) {
+            return Flight::Follower(FlightWaiter { rx: rx.clone() });
+        }
+
+        let (tx, rx) = watch::channel(None);
+        flights.insert(key.clone(), rx);
+        Flight::Leader(FlightGuard {
+            registry: Arc::clone(self),
+            key,
+            tx,
+        })
+    }
+
+    /// Number of flights currently in progress.
+    pub fn len(&self) -> usize {
+        self.flights.lock().expect("lock poisoned").len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Held by the leader; the flight stays joinable until this is dropped.
+///
+/// Waiters are released as soon as [`complete`](Self::complete) or
+/// [`fail`](Self::fail) is called. Keeping the guard alive while the result is
+/// stored lets requests that arrive in the meantime join instead of missing L1.
+#[derive(Debug)]
+pub struct FlightGuard {
+    registry: Arc<InflightRequests>,
+    key: FlightKey,
+    tx: watch::Sender<Option<FlightResult>>,
+}
+
+impl FlightGuard {
+    /// Hands the provider response to every waiter.
+    pub fn complete(&self, payload: &CachePayload) {
+        self.tx.send_replace(Some(Ok(payload.clone())));
+    }
+
+    /// Hands a provider error to every waiter.
+    ///
+    /// Other errors are not shared: the waiters are abandoned once the guard
+    /// drops.
+    pub fn fail(&self, error: &GatewayError) {
+        let failure = match error {
+            GatewayError::ProviderError(message) => SharedFailure::Provider(message.clone()),
+            GatewayError::ProviderUnavailable {
+                message,
+                retry_after,
+            } => SharedFailure::Unavailable {
+                message: message.clone(),
+                retry_after: *retry_after,
+            },
+            _ => return,
+        };
+        self.tx.send_replace(Some(Err(failure)));
+    }
+}
+
+impl Drop for FlightGuard {
+    fn drop(&mut self) {
+        if let Ok(mut flights) = self.registry.flights.lock() {
+            flights.remove(&self.key);
+        }
+    }
+}
+
+/// Held by a request waiting on another request's provider call.
+#[derive(Debug)]
+pub struct FlightWaiter {
+    rx: watch::Receiver<Option<FlightResult>>,
+}
+
+/// What a waiter received from its leader.
+#[derive(Debug)]
+pub enum FlightOutcome {
+    Completed(Box<CachePayload>),
+    /// The leader's provider error, with its original status.
+    Failed(GatewayError),
+    /// The leader went away without a result.
+    Abandoned,
+}
+
+impl FlightWaiter {
+    pub async fn wait(mut self) -> FlightOutcome {
+        match self.rx.wait_for(Option::is_some).await {
+            Ok(result) => match result.as_ref() {
+                Some(Ok(payload)) => FlightOutcome::Completed(Box::new(payload.clone())),
+                Some(Err(failure)) => FlightOutcome::Failed(failure.clone().into_error()),
+                None => FlightOutcome::Abandoned,
+            },
+            Err(_) => FlightOutcome::Abandoned,
+        }
+    }
+}
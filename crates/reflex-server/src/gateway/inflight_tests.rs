@@ -0,0 +1,167 @@
+//! Tests for single-flight coalescing.
+
+use std::sync::Arc;
+
+use async_openai::types::chat::CreateChatCompletionResponse;
+use serde_json::json;
+
+use crate::gateway::error::GatewayError;
+use crate::gateway::inflight::{
+    Flight, FlightGuard, FlightKey, FlightOutcome, FlightWaiter, InflightRequests,
+};
+use crate::gateway::payload::CachePayload;
+
+fn payload(content: &str) -> CachePayload {
+    let response: CreateChatCompletionResponse = serde_json::from_value(json!({
+        "id": "chatcmpl-1",
+        "object": "chat.completion",
+        "created": 1,
+        "model": "m",
+        "choices": [{
+            "index": 0,
+            "message": {"role": "assistant", "content": content},
+            "finish_reason": "stop"
+        }]
+    }))
+    .unwrap();
+    CachePayload {
+        semantic_request: "q".to_string(),
+        response,
+        media_key: None,
+        scope_key: None,
+        variants: Vec::new(),
+    }
+}
+
+fn key(tenant_id: u64) -> FlightKey {
+    (tenant_id, "request-hash".to_string(), None)
+}
+
+fn leader(flight: Flight) -> FlightGuard {
+    match flight {
+        Flight::Leader(guard) => guard,
+        Flight::Follower(_) => panic!("expected to lead"),
+    }
+}
+
+fn follower(flight: Flight) -> FlightWaiter {
+    match flight {
+        Flight::Follower(waiter) => waiter,
+        Flight::Leader(_) => panic!("expected to follow"),
+    }
+}
+
+#[tokio::test]
+async fn test_followers_receive_leader_payload() {
+    let registry = Arc::new(InflightRequests::default());
+    let guard = leader(registry.join(key(1)));
+    let waiters: Vec<_> = (0..3).map(|_| follower(registry.join(key(1)))).collect();
+
+    let handles: Vec<_> = waiters
+        .into_iter()
+        .map(|w| tokio::spawn(w.wait()))
+        .collect();
+    guard.complete(&payload("shared"));
+
+    for handle in handles {
+        match handle.await.unwrap() {
+            FlightOutcome::Completed(p) => {
+                assert_eq!(
+                    p.response.choices[0].message.content.as_deref(),
+                    Some("shared")
+                );
+            }
+            other => panic!("unexpected outcome: {:?}", other),
+        }
+    }
+
+    // Requests arriving after completion but before the guard drops still join.
+    assert!(matches!(
+        follower(registry.join(key(1))).wait().await,
+        FlightOutcome::Completed(_)
+    ));
+    drop(guard);
+    assert!(registry.is_empty());
+}
+
+#[tokio::test]
+async fn test_failure_is_shared_with_followers() {
+    let registry = Arc::new(InflightRequests::default());
+    let guard = leader(registry.join(key(1)));
+    let waiter = follower(registry.join(key(1)));
+
+    guard.fail(&GatewayError::ProviderError("upstream down".to_string()));
+
+    match waiter.wait().await {
+        FlightOutcome::Failed(GatewayError::ProviderError(message)) => {
+            assert_eq!(message, "upstream down")
+        }
+        other => panic!("unexpected outcome: {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_unavailable_failure_keeps_its_retry_after() {
+    let registry = Arc::new(InflightRequests::default());
+    let guard = leader(registry.join(key(1)));
+    let waiter = follower(registry.join(key(1)));
+
+    guard.fail(&GatewayError::ProviderUnavailable {
+        message: "circuit open".to_string(),
+        retry_after: 30,
+    });
+
+    assert!(matches!(
+        waiter.wait().await,
+        FlightOutcome::Failed(GatewayError::ProviderUnavailable {
+            retry_after: 30,
+            ..
+        })
+    ));
+}
+
+#[tokio::test]
+async fn test_other_failures_abandon_followers() {
+    let registry = Arc::new(InflightRequests::default());
+    let guard = leader(registry.join(key(1)));
+    let waiter = follower(registry.join(key(1)));
+
+    guard.fail(&GatewayError::InternalError("store failed".to_string()));
+    drop(guard);
+
+    assert!(matches!(waiter.wait().await, FlightOutcome::Abandoned));
+}
+
+#[tokio::test]
+async fn test_dropped_leader_abandons_followers_and_frees_key() {
+    let registry = Arc::new(InflightRequests::default());
+    let guard = leader(registry.join(key(1)));
+    let waiter = follower(registry.join(key(1)));
+
+    drop(guard);
+
+    assert!(matches!(waiter.wait().await, FlightOutcome::Abandoned));
+    assert!(registry.is_empty());
+    let _next = leader(registry.join(key(1)));
+}
+
+#[test]
+fn test_flights_are_tenant_scoped() {
+    let registry = Arc::new(InflightRequests::default());
+    let _a = leader(registry.join(key(1)));
+    let _b = leader(registry.join(key(2)));
+
+    assert_eq!(registry.len(), 2);
+}
+
+#[test]
+fn test_flights_are_scoped_by_caller_key() {
+    let registry = Arc::new(InflightRequests::default());
+    let with_key =
+        |fingerprint: &str| (1, "request-hash".to_string(), Some(fingerprint.to_string()));
+    let _server = leader(registry.join(key(1)));
+    let _caller = leader(registry.join(with_key("a")));
+    let _other = leader(registry.join(with_key("b")));
+
+    assert_eq!(registry.len(), 3);
+}
@@ -0,0 +1,412 @@
+//! Content hashes for the image, audio and file parts of a request.
+//!
+//! Cache keys are derived from a copy of the request in which every media
+//! part is replaced by `blake3:<hex>` of its content:
+//!
+//! - data URLs, `input_audio` data and inline `file_data` are base64-decoded
+//!   and the bytes hashed;
+//! - `http(s)` image URLs are hashed as written or, with
+//!   `REFLEX_MEDIA_FETCH=true`, fetched and their bytes hashed, so the same
+//!   image behind differently signed URLs shares one key;
+//! - uploaded `file_id`s already name their content and are kept.
+//!
+//! Fetching runs for every request with image URLs, hits included, on
+//! URLs the caller chose. Only hosts in `REFLEX_MEDIA_FETCH_HOSTS` are
+//! fetched; their names are resolved before connecting and private,
+//! loopback and link-local addresses are refused, and redirects are not
+//! followed. Anything refused is keyed by its URL.
+//!
+//! The hashes feed the exact key and the semantic text. Their combination,
+//! the request's media key, is stored with each entry, and L2 candidates with
+//! a different media key are dropped before verification: a request can only
+//! be answered from an entry about the same images.
+
+use std::borrow::Cow;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_openai::types::chat::CreateChatCompletionRequest;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use reqwest::Url;
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use serde_json::Value;
+use thiserror::Error;
+use tracing::warn;
+
+pub const MEDIA_FETCH_ENV: &str = "REFLEX_MEDIA_FETCH";
+pub const MEDIA_FETCH_MAX_BYTES_ENV: &str = "REFLEX_MEDIA_FETCH_MAX_BYTES";
+pub const MEDIA_FETCH_TIMEOUT_ENV: &str = "REFLEX_MEDIA_FETCH_TIMEOUT_SECS";
+pub const MEDIA_FETCH_HOSTS_ENV: &str = "REFLEX_MEDIA_FETCH_HOSTS";
+pub const MEDIA_FETCH_ALLOW_PRIVATE_ENV: &str = "REFLEX_MEDIA_FETCH_ALLOW_PRIVATE";
+
+#[derive(Debug, Error)]
+#[error("invalid {name} '{value}': {message}")]
+pub struct MediaConfigError {
+    pub name: &'static str,
+    pub value: String,
+    pub message: String,
+}
+
+/// How media parts are hashed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MediaConfig {
+    /// Fetch `http(s)` image URLs and hash their bytes instead of the URL.
+    pub fetch_urls: bool,
+    /// Larger downloads fall back to hashing the URL.
+    pub max_fetch_bytes: usize,
+    pub fetch_timeout: Duration,
+    /// Hosts URLs are fetched from: exact names, or `*.example.com` for
+    /// its subdomains. Other URLs are hashed as written.
+    pub fetch_hosts: Vec<String>,
+    /// Also fetch from private, loopback and link-local addresses.
+    pub allow_private_addresses: bool,
+}
+
+impl Default for MediaConfig {
+    fn default() -> Self {
+        Self {
+            fetch_urls: false,
+            max_fetch_bytes: 20 * 1024 * 1024,
+            fetch_timeout: Duration::from_secs(10),
+            fetch_hosts: Vec::new(),
+            allow_private_addresses: false,
+        }
+    }
+}
+
+impl MediaConfig {
+    /// Reads the `REFLEX_MEDIA_FETCH*` variables over the defaults.
+    pub fn from_env() -> Result<Self, MediaConfigError> {
+        let mut config = Self::default();
+        if let Some(value) = env_var(MEDIA_FETCH_ENV) {
+            config.fetch_urls = parse_bool(MEDIA_FETCH_ENV, value)?;
+        }
+        if let Some(value) = env_var(MEDIA_FETCH_HOSTS_ENV) {
+            config.fetch_hosts = value
+                .split(',')
+                .map(|host| host.trim().to_ascii_lowercase())
+                .filter(|host| !host.is_empty())
+                .collect();
+        }
+        if config.fetch_urls && config.fetch_hosts.is_empty() {
+            return Err(MediaConfigError {
+                name: MEDIA_FETCH_ENV,
+                value: "true".to_string(),
+                message: format!(
+                    "{} must list the hosts to fetch from",
+                    MEDIA_FETCH_HOSTS_ENV
+                ),
+            });
+        }
+        if let Some(value) = env_var(MEDIA_FETCH_ALLOW_PRIVATE_ENV) {
+            config.allow_private_addresses = parse_bool(MEDIA_FETCH_ALLOW_PRIVATE_ENV, value)?;
+        }
+        if let Some(value) = env_var(MEDIA_FETCH_MAX_BYTES_ENV) {
+            config.max_fetch_bytes = value.parse().map_err(|e| MediaConfigError {
+                name: MEDIA_FETCH_MAX_BYTES_ENV,
+                message: format!("{}", e),
+                value,
+            })?;
+        }
+        if let Some(value) = env_var(MEDIA_FETCH_TIMEOUT_ENV) {
+            let secs: u64 = value.parse().map_err(|e| MediaConfigError {
+                name: MEDIA_FETCH_TIMEOUT_ENV,
+                message: format!("{}", e),
+                value,
+            })?;
+            config.fetch_timeout = Duration::from_secs(secs);
+        }
+        Ok(config)
+    }
+
+    /// Whether `host` is in [`fetch_hosts`](Self::fetch_hosts).
+    fn allows_host(&self, host: &str) -> bool {
+        let host = host.to_ascii_lowercase();
+        self.fetch_hosts
+            .iter()
+            .any(|allowed| match allowed.strip_prefix('*') {
+                Some(suffix) => suffix.starts_with('.') && host.ends_with(suffix),
+                None => host == *allowed,
+            })
+    }
+}
+
+fn parse_bool(name: &'static str, value: String) -> Result<bool, MediaConfigError> {
+    match value.to_ascii_lowercase().as_str() {
+        "1" | "true" | "yes" | "on" => Ok(true),
+        "0" | "false" | "no" | "off" => Ok(false),
+        _ => Err(MediaConfigError {
+            name,
+            value,
+            message: "expected true or false".to_string(),
+        }),
+    }
+}
+
+fn env_var(name: &str) -> Option<String> {
+    std::env::var(name)
+        .ok()
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
+/// A request as it is keyed, with media parts replaced by content hashes.
+#[derive(Debug)]
+pub struct KeyedRequest<'a> {
+    /// Borrowed unchanged when the request has no media.
+    pub request: Cow<'a, CreateChatCompletionRequest>,
+    /// Hash over the request's media hashes in order; `None` without media.
+    pub media_key: Option<String>,
+}
+
+/// Hashes media parts, fetching URLs when configured to.
+#[derive(Debug)]
+pub struct MediaHasher {
+    config: MediaConfig,
+    client: reqwest::Client,
+}
+
+impl Default for MediaHasher {
+    fn default() -> Self {
+        Self::new(MediaConfig::default())
+    }
+}
+
+impl MediaHasher {
+    pub fn new(config: MediaConfig) -> Self {
+        // No proxy: a proxy would resolve the host itself, past the resolver.
+        let client = reqwest::Client::builder()
+            .timeout(config.fetch_timeout)
+            .redirect(reqwest::redirect::Policy::none())
+            .no_proxy()
+            .dns_resolver(Arc::new(FetchResolver {
+                allow_private: config.allow_private_addresses,
+            }))
+            .build()
+            .unwrap_or_default();
+        Self { config, client }
+    }
+
+    pub fn config(&self) -> &MediaConfig {
+        &self.config
+    }
+
+    /// The keyed form of `request`.
+    pub async fn keyed<'a>(&self, request: &'a CreateChatCompletionRequest) -> KeyedRequest<'a> {
+        let Ok(mut messages) = serde_json::to_value(&request.messages) else {
+            return unkeyed(request);
+        };
+
+        let mut hashes = Vec::new();
+        for part in media_parts(&mut messages) {
+            let Some((pointer, source)) = media_source(part) else {
+                continue;
+            };
+            let hash = self.hash(&source).await;
+            if let Some(slot) = part.pointer_mut(pointer) {
+                *slot = Value::String(format!("blake3:{}", hash.to_hex()));
+            }
+            hashes.push(hash);
+        }
+        if hashes.is_empty() {
+            return unkeyed(request);
+        }
+
+        let mut keyed = request.clone();
+        match serde_json::from_value(messages) {
+            Ok(messages) => keyed.messages = messages,
+            Err(e) => {
+                warn!(error = %e, "Could not rebuild keyed messages; keying raw media");
+                return unkeyed(request);
+            }
+        }
+
+        let mut combined = blake3::Hasher::new();
+        for hash in &hashes {
+            combined.update(hash.as_bytes());
+        }
+        KeyedRequest {
+            request: Cow::Owned(keyed),
+            media_key: Some(combined.finalize().to_hex().to_string()),
+        }
+    }
+
+    async fn hash(&self, source: &MediaSource) -> blake3::Hash {
+        match source {
+            MediaSource::Inline(data) => match STANDARD.decode(data.trim()) {
+                Ok(bytes) => blake3::hash(&bytes),
+                Err(_) => blake3::hash(data.as_bytes()),
+            },
+            MediaSource::Url(url) => {
+                if self.config.fetch_urls
+                    && let Some(fetchable) = self.fetchable(url)
+                {
+                    match self.fetch_hash(fetchable).await {
+                        Ok(hash) => return hash,
+                        Err(e) => {
+                            warn!(url = %url, error = %e, "Media fetch failed; keying by URL")
+                        }
+                    }
+                }
+                blake3::hash(url.as_bytes())
+            }
+        }
+    }
+
+    /// `url` parsed, if it is `http(s)` on an allowed host.
+    fn fetchable(&self, url: &str) -> Option<Url> {
+        let url = Url::parse(url).ok()?;
+        let allowed = matches!(url.scheme(), "http" | "https")
+            && url
+                .host_str()
+                .is_some_and(|host| self.config.allows_host(host));
+        allowed.then_some(url)
+    }
+
+    async fn fetch_hash(&self, url: Url) -> Result<blake3::Hash, String> {
+        // Literal addresses never reach the resolver.
+        if let Some(host) = url.host_str()
+            && let Ok(ip) = host.trim_matches(['[', ']']).parse::<IpAddr>()
+            && !self.config.allow_private_addresses
+            && !is_public(ip)
+        {
+            return Err(format!("{} is not a public address", ip));
+        }
+
+        let mut response = self
+            .client
+            .get(url)
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+        // Redirects are not followed, so a 3xx is refused with the errors.
+        if !response.status().is_success() {
+            return Err(format!("status {}", response.status()));
+        }
+
+        let limit = self.config.max_fetch_bytes;
+        if response
+            .content_length()
+            .is_some_and(|len| len as usize > limit)
+        {
+            return Err(format!("larger than {} bytes", limit));
+        }
+        let mut hasher = blake3::Hasher::new();
+        let mut read = 0;
+        while let Some(chunk) = response.chunk().await.map_err(|e| e.to_string())? {
+            read += chunk.len();
+            if read > limit {
+                return Err(format!("larger than {} bytes", limit));
+            }
+            hasher.update(&chunk);
+        }
+        Ok(hasher.finalize())
+    }
+}
+
+/// Resolves fetched hosts, keeping only the addresses a fetch may reach.
+#[derive(Debug)]
+struct FetchResolver {
+    allow_private: bool,
+}
+
+impl Resolve for FetchResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let allow_private = self.allow_private;
+        Box::pin(async move {
+            let addrs: Vec<SocketAddr> = tokio::net::lookup_host((name.as_str(), 0))
+                .await?
+                .filter(|addr| allow_private || is_public(addr.ip()))
+                .collect();
+            if addrs.is_empty() {
+                return Err(format!("{} has no public address", name.as_str()).into());
+            }
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
+
+/// Whether `ip` is a publicly routable unicast address.
+fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [first, second, ..] = ip.octets();
+            // 0.0.0.0/8 and the shared 100.64.0.0/10 range.
+            let reserved = first == 0 || (first == 100 && second & 0xc0 == 64);
+            !(reserved
+                || ip.is_private()
+                || ip.is_loopback()
+                || ip.is_link_local()
+                || ip.is_broadcast()
+                || ip.is_multicast()
+                || ip.is_documentation())
+        }
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_public(IpAddr::V4(ip)),
+            None => {
+                !(ip.is_unspecified()
+                    || ip.is_loopback()
+                    || ip.is_multicast()
+                    || ip.is_unique_local()
+                    || ip.is_unicast_link_local())
+            }
+        },
+    }
+}
+
+fn unkeyed(request: &CreateChatCompletionRequest) -> KeyedRequest<'_> {
+    KeyedRequest {
+        request: Cow::Borrowed(request),
+        media_key: None,
+    }
+}
+
+enum MediaSource {
+    /// Base64 content, possibly inside a data URL.
+    Inline(String),
+    Url(String),
+}
+
+/// Every content part of every user message.
+fn media_parts(messages: &mut Value) -> impl Iterator<Item = &mut Value> {
+    messages
+        .as_array_mut()
+        .into_iter()
+        .flatten()
+        .filter(|m| m.get("role").and_then(Value::as_str) == Some("user"))
+        .filter_map(|m| m.get_mut("content")?.as_array_mut())
+        .flatten()
+}
+
+/// Where a part's content lives, and the JSON pointer of the field to replace.
+fn media_source(part: &Value) -> Option<(&'static str, MediaSource)> {
+    let field = |pointer: &str| part.pointer(pointer)?.as_str().map(str::to_string);
+    match part.get("type")?.as_str()? {
+        "image_url" => {
+            let url = field("/image_url/url")?;
+            let source = match data_url_payload(&url) {
+                Some(data) => MediaSource::Inline(data.to_string()),
+                None => MediaSource::Url(url),
+            };
+            Some(("/image_url/url", source))
+        }
+        "input_audio" => Some((
+            "/input_audio/data",
+            MediaSource::Inline(field("/input_audio/data")?),
+        )),
+        "file" => {
+            let data = field("/file/file_data")?;
+            let data = data_url_payload(&data).unwrap_or(&data).to_string();
+            Some(("/file/file_data", MediaSource::Inline(data)))
+        }
+        _ => None,
+    }
+}
+
+/// The base64 payload of a `data:<type>;base64,<data>` URL.
+fn data_url_payload(url: &str) -> Option<&str> {
+    let (header, data) = url.strip_prefix("data:")?.split_once(',')?;
+    header.ends_with(";base64").then_some(data)
+}
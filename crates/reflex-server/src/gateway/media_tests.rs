@@ -0,0 +1,238 @@
+//! Tests for media content hashing.
+
+use async_openai::types::chat::CreateChatCompletionRequest;
+use axum::{Router, routing::get};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use serde_json::json;
+use std::borrow::Cow;
+use tokio::net::TcpListener;
+
+use crate::gateway::media::{MediaConfig, MediaHasher};
+
+fn image_request(url: &str) -> CreateChatCompletionRequest {
+    serde_json::from_value(json!({
+        "model": "gpt-4o",
+        "messages": [{
+            "role": "user",
+            "content": [
+                {"type": "text", "text": "What is in this image?"},
+                {"type": "image_url", "image_url": {"url": url}}
+            ]
+        }]
+    }))
+    .unwrap()
+}
+
+/// The keyed request as JSON, and its media key.
+async fn keyed(
+    hasher: &MediaHasher,
+    request: &CreateChatCompletionRequest,
+) -> (serde_json::Value, Option<String>) {
+    let keyed = hasher.keyed(request).await;
+    (
+        serde_json::to_value(&*keyed.request).unwrap(),
+        keyed.media_key,
+    )
+}
+
+/// Fetching from test servers on this machine.
+fn local_fetch() -> MediaConfig {
+    MediaConfig {
+        fetch_urls: true,
+        fetch_hosts: vec!["127.0.0.1".to_string()],
+        allow_private_addresses: true,
+        ..MediaConfig::default()
+    }
+}
+
+/// Serves `app` on a loopback port.
+async fn serve(app: Router) -> std::net::SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    addr
+}
+
+/// Whether `url` was keyed by its own text rather than fetched.
+async fn keyed_by_url(hasher: &MediaHasher, url: &str) -> bool {
+    let (request, _) = keyed(hasher, &image_request(url)).await;
+    request["messages"][0]["content"][1]["image_url"]["url"]
+        == format!("blake3:{}", blake3::hash(url.as_bytes()).to_hex())
+}
+
+fn data_url(mime: &str, bytes: &[u8]) -> String {
+    format!("data:{};base64,{}", mime, STANDARD.encode(bytes))
+}
+
+#[tokio::test]
+async fn test_text_only_request_is_keyed_unchanged() {
+    let request: CreateChatCompletionRequest = serde_json::from_value(json!({
+        "model": "gpt-4o",
+        "messages": [{"role": "user", "content": "Hello"}]
+    }))
+    .unwrap();
+
+    let keyed = MediaHasher::default().keyed(&request).await;
+    assert!(matches!(keyed.request, Cow::Borrowed(_)));
+    assert_eq!(keyed.media_key, None);
+}
+
+#[tokio::test]
+async fn test_data_urls_are_keyed_by_decoded_bytes() {
+    let hasher = MediaHasher::default();
+    let (png, png_key) = keyed(
+        &hasher,
+        &image_request(&data_url("image/png", b"cat pixels")),
+    )
+    .await;
+    let (relabeled, relabeled_key) = keyed(
+        &hasher,
+        &image_request(&data_url("image/x-png", b"cat pixels")),
+    )
+    .await;
+    let (_, other_key) = keyed(
+        &hasher,
+        &image_request(&data_url("image/png", b"dog pixels")),
+    )
+    .await;
+
+    assert!(png_key.is_some());
+    assert_eq!(png_key, relabeled_key);
+    assert_eq!(png, relabeled);
+    assert_ne!(png_key, other_key);
+    assert_eq!(
+        png["messages"][0]["content"][1]["image_url"]["url"],
+        format!("blake3:{}", blake3::hash(b"cat pixels").to_hex())
+    );
+}
+
+#[tokio::test]
+async fn test_audio_and_file_parts_are_hashed() {
+    let request = |audio: &[u8], file: &[u8]| -> CreateChatCompletionRequest {
+        serde_json::from_value(json!({
+            "model": "gpt-4o",
+            "messages": [{
+                "role": "user",
+                "content": [
+                    {"type": "input_audio", "input_audio": {"data": STANDARD.encode(audio), "format": "wav"}},
+                    {"type": "file", "file": {"file_data": data_url("application/pdf", file), "filename": "a.pdf"}}
+                ]
+            }]
+        }))
+        .unwrap()
+    };
+    let hasher = MediaHasher::default();
+    let (_, base) = keyed(&hasher, &request(b"audio", b"file")).await;
+    let (_, audio) = keyed(&hasher, &request(b"other audio", b"file")).await;
+    let (_, file) = keyed(&hasher, &request(b"audio", b"other file")).await;
+
+    assert!(base.is_some());
+    assert_ne!(base, audio);
+    assert_ne!(base, file);
+    assert_ne!(audio, file);
+}
+
+#[tokio::test]
+async fn test_signed_urls_share_a_key_only_when_fetched() {
+    let app = Router::new()
+        .route("/cat.png", get(|| async { "cat pixels" }))
+        .route("/dog.png", get(|| async { "dog pixels" }));
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+    let first = image_request(&format!("http://{}/cat.png?sig=a", addr));
+    let second = image_request(&format!("http://{}/cat.png?sig=b", addr));
+    let dog = image_request(&format!("http://{}/dog.png?sig=a", addr));
+
+    let unfetched = MediaHasher::default();
+    assert_ne!(
+        unfetched.keyed(&first).await.media_key,
+        unfetched.keyed(&second).await.media_key
+    );
+
+    let fetching = MediaHasher::new(local_fetch());
+    let (first, first_key) = keyed(&fetching, &first).await;
+    assert_eq!(first_key, keyed(&fetching, &second).await.1);
+    assert_ne!(first_key, keyed(&fetching, &dog).await.1);
+    assert_eq!(
+        first["messages"][0]["content"][1]["image_url"]["url"],
+        format!("blake3:{}", blake3::hash(b"cat pixels").to_hex())
+    );
+}
+
+#[tokio::test]
+async fn test_oversized_fetch_falls_back_to_the_url() {
+    let app = Router::new().route("/big.png", get(|| async { "0123456789" }));
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+    let url = format!("http://{}/big.png", addr);
+    let hasher = MediaHasher::new(MediaConfig {
+        max_fetch_bytes: 4,
+        ..local_fetch()
+    });
+    let (request, _) = keyed(&hasher, &image_request(&url)).await;
+    assert_eq!(
+        request["messages"][0]["content"][1]["image_url"]["url"],
+        format!("blake3:{}", blake3::hash(url.as_bytes()).to_hex())
+    );
+}
+
+#[tokio::test]
+async fn test_only_allowed_hosts_are_fetched() {
+    let addr = serve(Router::new().route("/cat.png", get(|| async { "cat pixels" }))).await;
+    let url = format!("http://{}/cat.png", addr);
+
+    let elsewhere = MediaHasher::new(MediaConfig {
+        fetch_hosts: vec!["images.example.com".to_string()],
+        ..local_fetch()
+    });
+    assert!(keyed_by_url(&elsewhere, &url).await);
+    assert!(!keyed_by_url(&MediaHasher::new(local_fetch()), &url).await);
+}
+
+#[tokio::test]
+async fn test_private_and_link_local_addresses_are_refused() {
+    let addr = serve(Router::new().route("/cat.png", get(|| async { "cat pixels" }))).await;
+    let hasher = MediaHasher::new(MediaConfig {
+        fetch_urls: true,
+        fetch_hosts: vec![
+            "127.0.0.1".to_string(),
+            "localhost".to_string(),
+            "169.254.169.254".to_string(),
+            "10.0.0.1".to_string(),
+            "[::1]".to_string(),
+        ],
+        ..MediaConfig::default()
+    });
+
+    for url in [
+        format!("http://127.0.0.1:{}/cat.png", addr.port()),
+        // Resolved before connecting, so a name is no way around the check.
+        format!("http://localhost:{}/cat.png", addr.port()),
+        "http://169.254.169.254/latest/meta-data/".to_string(),
+        "http://10.0.0.1/cat.png".to_string(),
+        format!("http://[::1]:{}/cat.png", addr.port()),
+    ] {
+        assert!(keyed_by_url(&hasher, &url).await, "{}", url);
+    }
+}
+
+#[tokio::test]
+async fn test_redirects_are_not_followed() {
+    let addr = serve(
+        Router::new()
+            .route("/cat.png", get(|| async { "cat pixels" }))
+            .route(
+                "/moved.png",
+                get(|| async { axum::response::Redirect::temporary("/cat.png") }),
+            ),
+    )
+    .await;
+
+    let hasher = MediaHasher::new(local_fetch());
+    assert!(keyed_by_url(&hasher, &format!("http://{}/moved.png", addr)).await);
+}
@@ -7,14 +7,69 @@
 #![allow(missing_docs)]
 
 pub mod adapter;
+pub mod admin;
+pub mod anthropic;
+pub mod cache_control;
+pub mod config_file;
+pub mod embeddings;
 pub mod error;
+pub mod explain;
+pub mod feedback;
+pub mod format;
 pub mod handler;
+pub mod inflight;
+pub mod media;
 pub mod payload;
+pub mod pricing;
+pub mod provider_keys;
+pub mod rate_limit;
+pub mod responses;
+pub mod routing;
+pub mod sampling;
+pub mod semantic_key;
+pub mod shadow;
 pub mod state;
 pub mod streaming;
+pub mod telemetry;
+pub mod tenants;
+pub mod upstream;
 
+#[cfg(test)]
+mod adapter_tests;
+#[cfg(test)]
+mod admin_tests;
+#[cfg(test)]
+mod anthropic_tests;
+#[cfg(test)]
+mod explain_tests;
+#[cfg(test)]
+mod feedback_tests;
 #[cfg(test)]
 mod handler_tests;
+#[cfg(test)]
+mod inflight_tests;
+#[cfg(test)]
+mod media_tests;
+#[cfg(test)]
+mod pricing_tests;
+#[cfg(test)]
+mod provider_keys_tests;
+#[cfg(test)]
+mod rate_limit_tests;
+#[cfg(test)]
+mod responses_tests;
+#[cfg(test)]
+mod routing_tests;
+#[cfg(test)]
+mod sampling_tests;
+#[cfg(test)]
+mod semantic_key_tests;
+#[cfg(test)]
+mod shadow_tests;
+#[cfg(test)]
+mod tenants_tests;
+#[cfg(test)]
+mod upstream_tests;
 
 use axum::{
     Json, Router,
@@ -24,8 +79,24 @@ use axum::{
 };
 use tower_http::trace::TraceLayer;
 
+pub use anthropic::messages_handler;
+pub use embeddings::embeddings_handler;
+pub use explain::explain_handler;
+pub use feedback::feedback_handler;
+pub use format::HitFormat;
 pub use handler::chat_completions_handler;
+pub use media::MediaConfig;
+pub use pricing::PriceTable;
+pub use provider_keys::ProviderKeyMode;
+pub use rate_limit::RateLimitConfig;
+pub use responses::responses_handler;
+pub use routing::ModelRouter;
+pub use sampling::SamplingPolicy;
+pub use semantic_key::{SemanticKeyExtractor, SemanticKeyStrategy};
+pub use shadow::ShadowConfig;
 pub use state::HandlerState;
+pub use tenants::TenantRegistry;
+pub use upstream::UpstreamConfig;
 
 use reflex::cache::{
     BqSearchBackend, REFLEX_STATUS_ERROR, REFLEX_STATUS_HEADER, REFLEX_STATUS_HEALTHY,
@@ -38,10 +109,33 @@ where
     B: BqSearchBackend + Clone + Send + Sync + 'static,
     S: StorageLoader + StorageWriter + Clone + Send + Sync + 'static,
 {
+    telemetry::prometheus_handle();
+
     Router::new()
         .route("/healthz", get(health_handler))
         .route("/ready", get(ready_handler))
+        .route("/metrics", get(telemetry::metrics_handler))
         .route("/v1/chat/completions", post(chat_completions_handler))
+        .route("/v1/embeddings", post(embeddings_handler))
+        .route("/v1/messages", post(messages_handler))
+        .route("/v1/responses", post(responses_handler))
+        .route("/v1/reflex/explain", post(explain_handler))
+        .route("/v1/reflex/feedback", post(feedback_handler))
+        .route(
+            "/admin/invalidate/request",
+            post(admin::invalidate_request_handler),
+        )
+        .route("/admin/invalidate/key", post(admin::invalidate_key_handler))
+        .route(
+            "/admin/invalidate/tenant",
+            post(admin::invalidate_tenant_handler),
+        )
+        .route("/admin/invalidate/age", post(admin::invalidate_age_handler))
+        .route("/admin/entries", get(admin::list_entries_handler))
+        .route(
+            "/admin/entries/{tenant_id}/{context_hash}",
+            get(admin::get_entry_handler),
+        )
         .layer(TraceLayer::new_for_http())
         .with_state(state)
 }
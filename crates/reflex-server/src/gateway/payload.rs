@@ -5,4 +5,41 @@ use serde::{Deserialize, Serialize};
 pub struct CachePayload {
     pub semantic_request: String,
     pub response: CreateChatCompletionResponse,
+    /// Media key of the request the response was stored for.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub media_key: Option<String>,
+    /// Scope key of the request the response was stored for.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scope_key: Option<String>,
+    /// Further answers to the same request, kept under a variants policy.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub variants: Vec<CreateChatCompletionResponse>,
+}
+
+impl CachePayload {
+    /// Stored answers, `response` included.
+    pub fn answer_count(&self) -> usize {
+        1 + self.variants.len()
+    }
+
+    /// Makes answer `index` (in `response`, `variants...` order) the one served.
+    pub fn select(&mut self, index: usize) {
+        if let Some(variant) = index.checked_sub(1).and_then(|i| self.variants.get_mut(i)) {
+            std::mem::swap(&mut self.response, variant);
+        }
+    }
+
+    /// Adds `response` as another answer, keeping the newest `max`.
+    pub fn add_variant(&mut self, response: CreateChatCompletionResponse, max: usize) {
+        self.variants.push(response);
+        let excess = self.answer_count().saturating_sub(max.max(1));
+        if excess > 0 {
+            let mut answers: Vec<_> = std::iter::once(self.response.clone())
+                .chain(self.variants.drain(..))
+                .skip(excess)
+                .collect();
+            self.response = answers.remove(0);
+            self.variants = answers;
+        }
+    }
 }
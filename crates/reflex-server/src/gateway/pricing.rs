@@ -0,0 +1,166 @@
+//! Per-model token prices, used to report what cache hits save.
+//!
+//! Each stored response keeps the token usage its provider call reported.
+//! When it is served again from the cache, those tokens (and, with a price
+//! table, their cost) count as saved. Savings are reported per hit in
+//! `X-Reflex-Tokens-Saved` / `X-Reflex-Cost-Saved` and accumulated per tenant
+//! in Prometheus counters.
+//!
+//! Prices are read from `REFLEX_PRICES_FILE`, in USD per million tokens:
+//!
+//! ```toml
+//! [models."gpt-4o"]
+//! input_per_mtok = 2.50
+//! output_per_mtok = 10.00
+//!
+//! [models."claude-sonnet-4"]
+//! input_per_mtok = 3.00
+//! output_per_mtok = 15.00
+//! ```
+//!
+//! A model without an exact entry takes the price of the longest entry it
+//! starts with, so `claude-sonnet-4` also prices `claude-sonnet-4-20250514`.
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use async_openai::types::chat::CreateChatCompletionResponse;
+use axum::http::{HeaderMap, HeaderValue};
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::gateway::config_file::{self, ConfigFileError};
+
+/// Environment variable naming the price table file.
+pub const PRICES_FILE_ENV: &str = "REFLEX_PRICES_FILE";
+
+/// Response header with the tokens a hit did not spend.
+pub const TOKENS_SAVED_HEADER: &str = "X-Reflex-Tokens-Saved";
+
+/// Response header with the USD a hit did not spend.
+pub const COST_SAVED_HEADER: &str = "X-Reflex-Cost-Saved";
+
+#[derive(Debug, Error)]
+pub enum PriceTableError {
+    #[error("price table: {0}")]
+    File(#[from] ConfigFileError),
+
+    #[error("invalid price table: {0}")]
+    Invalid(String),
+}
+
+/// USD per million tokens.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub struct ModelPrice {
+    pub input_per_mtok: f64,
+    pub output_per_mtok: f64,
+}
+
+/// Tokens and cost that serving one response from the cache avoided.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Savings {
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    /// `None` when the model has no price.
+    pub cost_usd: Option<f64>,
+}
+
+impl Savings {
+    pub fn tokens(&self) -> u64 {
+        self.prompt_tokens + self.completion_tokens
+    }
+
+    /// Sets the savings headers on a hit.
+    pub fn apply_headers(&self, headers: &mut HeaderMap) {
+        headers.insert(TOKENS_SAVED_HEADER, HeaderValue::from(self.tokens()));
+        if let Some(cost) = self.cost_usd
+            && let Ok(value) = HeaderValue::from_str(&format!("{:.6}", cost))
+        {
+            headers.insert(COST_SAVED_HEADER, value);
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct PricesFile {
+    #[serde(default)]
+    models: HashMap<String, ModelPrice>,
+}
+
+/// Prices by model name; empty unless a price file is configured.
+#[derive(Debug, Clone, Default)]
+pub struct PriceTable {
+    models: HashMap<String, ModelPrice>,
+}
+
+impl PriceTable {
+    /// Loads the table named by `REFLEX_PRICES_FILE`, if set.
+    pub fn from_env() -> Result<Option<Self>, PriceTableError> {
+        config_file::path_from_env(PRICES_FILE_ENV)
+            .map(|path| Self::from_file(&path))
+            .transpose()
+    }
+
+    /// Loads a `.toml` or `.json` price table.
+    pub fn from_file(path: &Path) -> Result<Self, PriceTableError> {
+        let file: PricesFile = config_file::load(path)?;
+        Self::from_models(file.models)
+    }
+
+    /// Parses a TOML price table.
+    pub fn from_toml_str(contents: &str) -> Result<Self, PriceTableError> {
+        let file: PricesFile =
+            toml::from_str(contents).map_err(|e| PriceTableError::Invalid(e.to_string()))?;
+        Self::from_models(file.models)
+    }
+
+    fn from_models(models: HashMap<String, ModelPrice>) -> Result<Self, PriceTableError> {
+        for (model, price) in &models {
+            let valid = |p: f64| p.is_finite() && p >= 0.0;
+            if !valid(price.input_per_mtok) || !valid(price.output_per_mtok) {
+                return Err(PriceTableError::Invalid(format!(
+                    "model '{}': prices must be non-negative numbers",
+                    model
+                )));
+            }
+        }
+        Ok(Self { models })
+    }
+
+    pub fn len(&self) -> usize {
+        self.models.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.models.is_empty()
+    }
+
+    /// The price of `model`: its own entry, or the longest entry it extends.
+    pub fn price(&self, model: &str) -> Option<ModelPrice> {
+        if let Some(price) = self.models.get(model) {
+            return Some(*price);
+        }
+        self.models
+            .iter()
+            .filter(|(name, _)| model.starts_with(name.as_str()))
+            .max_by_key(|(name, _)| name.len())
+            .map(|(_, price)| *price)
+    }
+
+    /// What serving `response` again saves; `None` if it recorded no usage.
+    pub fn savings(&self, response: &CreateChatCompletionResponse) -> Option<Savings> {
+        let usage = response.usage.as_ref()?;
+        let prompt_tokens = usage.prompt_tokens as u64;
+        let completion_tokens = usage.completion_tokens as u64;
+        let cost_usd = self.price(&response.model).map(|price| {
+            (prompt_tokens as f64 * price.input_per_mtok
+                + completion_tokens as f64 * price.output_per_mtok)
+                / 1_000_000.0
+        });
+        Some(Savings {
+            prompt_tokens,
+            completion_tokens,
+            cost_usd,
+        })
+    }
+}
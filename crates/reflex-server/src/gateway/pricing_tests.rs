@@ -0,0 +1,107 @@
+//! Tests for model prices and savings.
+
+use async_openai::types::chat::CreateChatCompletionResponse;
+use axum::http::HeaderMap;
+
+use crate::gateway::pricing::{
+    COST_SAVED_HEADER, PriceTable, PriceTableError, TOKENS_SAVED_HEADER,
+};
+
+const PRICES: &str = r#"
+[models."gpt-4o"]
+input_per_mtok = 2.5
+output_per_mtok = 10.0
+
+[models."claude"]
+input_per_mtok = 1.0
+output_per_mtok = 1.0
+
+[models."claude-sonnet-4"]
+input_per_mtok = 3.0
+output_per_mtok = 15.0
+"#;
+
+fn response(model: &str, usage: Option<(u32, u32)>) -> CreateChatCompletionResponse {
+    let usage = usage.map(|(prompt, completion)| {
+        serde_json::json!({
+            "prompt_tokens": prompt,
+            "completion_tokens": completion,
+            "total_tokens": prompt + completion,
+        })
+    });
+    serde_json::from_value(serde_json::json!({
+        "id": "chatcmpl-test",
+        "object": "chat.completion",
+        "created": 0,
+        "model": model,
+        "choices": [],
+        "usage": usage,
+    }))
+    .unwrap()
+}
+
+#[test]
+fn test_price_prefers_exact_then_longest_prefix() {
+    let prices = PriceTable::from_toml_str(PRICES).unwrap();
+    assert_eq!(prices.len(), 3);
+
+    assert_eq!(prices.price("gpt-4o").unwrap().input_per_mtok, 2.5);
+    assert_eq!(
+        prices
+            .price("claude-sonnet-4-20250514")
+            .unwrap()
+            .output_per_mtok,
+        15.0
+    );
+    assert_eq!(
+        prices.price("claude-3-5-haiku").unwrap().output_per_mtok,
+        1.0
+    );
+    assert!(prices.price("gemini-2.0-flash").is_none());
+}
+
+#[test]
+fn test_savings_price_recorded_usage() {
+    let prices = PriceTable::from_toml_str(PRICES).unwrap();
+
+    let savings = prices
+        .savings(&response("gpt-4o", Some((1_000, 500))))
+        .unwrap();
+    assert_eq!(savings.tokens(), 1_500);
+    let cost = savings.cost_usd.unwrap();
+    assert!((cost - 0.0075).abs() < 1e-12, "cost was {}", cost);
+
+    let mut headers = HeaderMap::new();
+    savings.apply_headers(&mut headers);
+    assert_eq!(headers[TOKENS_SAVED_HEADER], "1500");
+    assert_eq!(headers[COST_SAVED_HEADER], "0.007500");
+}
+
+#[test]
+fn test_unpriced_models_save_tokens_only() {
+    let savings = PriceTable::default()
+        .savings(&response("gpt-4o", Some((10, 20))))
+        .unwrap();
+    assert_eq!(savings.tokens(), 30);
+    assert_eq!(savings.cost_usd, None);
+
+    let mut headers = HeaderMap::new();
+    savings.apply_headers(&mut headers);
+    assert!(headers.contains_key(TOKENS_SAVED_HEADER));
+    assert!(!headers.contains_key(COST_SAVED_HEADER));
+
+    assert!(
+        PriceTable::default()
+            .savings(&response("gpt-4o", None))
+            .is_none()
+    );
+}
+
+#[test]
+fn test_price_table_rejects_negative_prices() {
+    let table = "[models.a]\ninput_per_mtok = -1.0\noutput_per_mtok = 1.0\n";
+    assert!(matches!(
+        PriceTable::from_toml_str(table),
+        Err(PriceTableError::Invalid(_))
+    ));
+}
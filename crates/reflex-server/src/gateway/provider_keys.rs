@@ -0,0 +1,219 @@
+//! Upstream provider credentials for cache misses (bring your own key).
+//!
+//! By default the provider is called with the keys in the server's
+//! environment. A tenant can name its own keys in the tenant registry, and with
+//! `REFLEX_PROVIDER_KEYS=passthrough` callers can send theirs in
+//! `X-Provider-Key`. Without a tenant registry, passthrough also forwards the
+//! caller's `Authorization: Bearer` / `x-api-key` token when no
+//! `X-Provider-Key` is given.
+//!
+//! For each call the caller's key wins over the tenant's key, and the tenant's
+//! key wins over the server environment. The caller's key is only used for the
+//! provider of the model the request names: calls to any other provider fall
+//! back to the tenant's or the server's key. Keys only ever reach the genai auth
+//! resolver: they are not part of cache keys or payloads, and they print as
+//! `REDACTED`.
+
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use axum::http::HeaderMap;
+use genai::adapter::AdapterKind;
+use genai::resolver::AuthData;
+use genai::{Client, ModelIden};
+use serde::Deserialize;
+
+use crate::gateway::handler::api_key;
+use crate::gateway::upstream::provider_of;
+
+/// Request header carrying the caller's own provider key.
+pub const PROVIDER_KEY_HEADER: &str = "X-Provider-Key";
+
+/// Environment variable selecting the [`ProviderKeyMode`].
+pub const PROVIDER_KEYS_ENV: &str = "REFLEX_PROVIDER_KEYS";
+
+/// Whether callers may supply the key their misses are billed to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ProviderKeyMode {
+    /// Server (or tenant registry) keys only; caller keys are ignored.
+    #[default]
+    Server,
+    /// Forward the caller's key to the provider.
+    Passthrough,
+}
+
+impl ProviderKeyMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ProviderKeyMode::Server => "server",
+            ProviderKeyMode::Passthrough => "passthrough",
+        }
+    }
+
+    /// Reads the mode from `REFLEX_PROVIDER_KEYS` (unset or invalid: server).
+    pub fn from_env() -> Self {
+        match std::env::var(PROVIDER_KEYS_ENV) {
+            Ok(value) => value.parse().unwrap_or_else(|e| {
+                tracing::warn!("{}; using server", e);
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        }
+    }
+}
+
+impl FromStr for ProviderKeyMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "server" => Ok(ProviderKeyMode::Server),
+            "passthrough" => Ok(ProviderKeyMode::Passthrough),
+            other => Err(format!(
+                "unknown provider key mode '{}': expected server or passthrough",
+                other
+            )),
+        }
+    }
+}
+
+/// A provider API key. Its `Debug` output is redacted.
+#[derive(Clone, PartialEq, Eq, Deserialize)]
+#[serde(transparent)]
+pub struct ProviderKey(String);
+
+impl ProviderKey {
+    pub fn new(key: impl Into<String>) -> Self {
+        Self(key.into())
+    }
+
+    fn auth_data(&self) -> AuthData {
+        AuthData::from_single(self.0.clone())
+    }
+}
+
+impl std::fmt::Debug for ProviderKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("ProviderKey(REDACTED)")
+    }
+}
+
+/// Keys available for one request's provider calls.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ProviderKeys {
+    /// The caller's own key.
+    caller: Option<ProviderKey>,
+    /// Provider of the requested model; the only one the caller's key is sent to.
+    caller_provider: Option<&'static str>,
+    /// Keys from the tenant registry, by provider name (`openai`, `anthropic`, ...).
+    tenant: Arc<HashMap<String, ProviderKey>>,
+}
+
+impl ProviderKeys {
+    /// Keys configured for a tenant, by provider name.
+    ///
+    /// Fails on a name genai does not know.
+    pub fn for_tenant(keys: HashMap<String, ProviderKey>) -> Result<Self, String> {
+        let keys = keys
+            .into_iter()
+            .map(|(provider, key)| {
+                let name = provider.trim().to_ascii_lowercase();
+                match AdapterKind::from_lower_str(&name) {
+                    Some(kind) => Ok((provider_name(kind).to_string(), key)),
+                    None => Err(format!("unknown provider '{}'", provider)),
+                }
+            })
+            .collect::<Result<HashMap<_, _>, _>>()?;
+        Ok(Self {
+            caller: None,
+            caller_provider: None,
+            tenant: Arc::new(keys),
+        })
+    }
+
+    /// Adds the caller's key, if `mode` allows one and the request has it.
+    ///
+    /// `token_is_tenant` is true when the bearer token only identifies the
+    /// tenant (a registry is configured) and must not be forwarded.
+    pub fn with_caller(
+        mut self,
+        mode: ProviderKeyMode,
+        headers: &HeaderMap,
+        token_is_tenant: bool,
+    ) -> Self {
+        if mode == ProviderKeyMode::Passthrough {
+            let header = headers
+                .get(PROVIDER_KEY_HEADER)
+                .and_then(|v| v.to_str().ok())
+                .map(str::trim)
+                .filter(|v| !v.is_empty());
+            let token = (!token_is_tenant).then(|| api_key(headers)).flatten();
+            self.caller = header.or(token).map(ProviderKey::new);
+        }
+        self
+    }
+
+    /// Binds the caller's key to the provider serving `model`.
+    ///
+    /// Until bound, the caller's key is not used at all.
+    pub fn for_model(mut self, model: &str) -> Self {
+        self.caller_provider = Some(provider_of(model));
+        self
+    }
+
+    /// True when the request carries its own key.
+    pub fn has_caller(&self) -> bool {
+        self.caller.is_some()
+    }
+
+    /// True when every call falls back to the server environment.
+    pub fn is_empty(&self) -> bool {
+        self.caller.is_none() && self.tenant.is_empty()
+    }
+
+    /// Identifies the caller's key without revealing it.
+    pub fn caller_fingerprint(&self) -> Option<String> {
+        self.caller
+            .as_ref()
+            .map(|key| blake3::hash(key.0.as_bytes()).to_hex().to_string())
+    }
+
+    /// The key a call to `model` should use, if not the server's.
+    pub fn key_for(&self, model: &ModelIden) -> Option<&ProviderKey> {
+        let provider = provider_name(model.adapter_kind);
+        self.caller
+            .as_ref()
+            .filter(|_| self.caller_provider == Some(provider))
+            .or_else(|| self.tenant.get(provider))
+    }
+
+    /// True when calls to `model` use one of these keys instead of the server's.
+    pub fn overrides(&self, model: &str) -> bool {
+        AdapterKind::from_model(model)
+            .is_ok_and(|kind| self.key_for(&ModelIden::new(kind, model)).is_some())
+    }
+
+    /// A genai client that authenticates with these keys.
+    ///
+    /// Returns `default` itself when there is nothing to override.
+    pub fn client(&self, default: &Client) -> Client {
+        if self.is_empty() {
+            return default.clone();
+        }
+        let keys = self.clone();
+        Client::builder()
+            .with_auth_resolver_fn(move |model: ModelIden| {
+                Ok(keys.key_for(&model).map(ProviderKey::auth_data))
+            })
+            .build()
+    }
+}
+
+/// Registry name of a provider; both OpenAI adapters share one key.
+pub(crate) fn provider_name(kind: AdapterKind) -> &'static str {
+    match kind {
+        AdapterKind::OpenAIResp => AdapterKind::OpenAI.as_lower_str(),
+        other => other.as_lower_str(),
+    }
+}
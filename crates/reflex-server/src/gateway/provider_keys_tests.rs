@@ -0,0 +1,235 @@
+//! Tests for provider key resolution.
+
+use std::collections::HashMap;
+
+use async_openai::types::chat::CreateChatCompletionRequest;
+use axum::http::{HeaderMap, HeaderValue};
+use genai::ModelIden;
+use genai::adapter::AdapterKind;
+
+use crate::gateway::handler::StoreContext;
+use crate::gateway::provider_keys::{ProviderKey, ProviderKeyMode, ProviderKeys};
+use crate::gateway::routing::Route;
+use crate::gateway::tenants::{TenantRegistry, resolve_tenant};
+
+const REGISTRY: &str = r#"
+[[tenants]]
+id = "acme"
+api_keys = ["sk-reflex-acme"]
+
+[tenants.provider_keys]
+openai = "sk-acme-openai"
+"#;
+
+fn headers(pairs: &[(&'static str, &str)]) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    for (name, value) in pairs {
+        headers.insert(*name, HeaderValue::from_str(value).unwrap());
+    }
+    headers
+}
+
+fn openai() -> ModelIden {
+    ModelIden::new(AdapterKind::OpenAI, "gpt-4o")
+}
+
+fn anthropic() -> ModelIden {
+    ModelIden::new(AdapterKind::Anthropic, "claude-sonnet-4")
+}
+
+#[test]
+fn test_mode_parses_from_str() {
+    assert_eq!(
+        " Passthrough ".parse::<ProviderKeyMode>(),
+        Ok(ProviderKeyMode::Passthrough)
+    );
+    assert_eq!("server".parse(), Ok(ProviderKeyMode::Server));
+    assert!("caller".parse::<ProviderKeyMode>().is_err());
+}
+
+#[test]
+fn test_server_mode_ignores_caller_keys() {
+    let keys = ProviderKeys::default().with_caller(
+        ProviderKeyMode::Server,
+        &headers(&[
+            ("X-Provider-Key", "sk-caller"),
+            ("Authorization", "Bearer sk-token"),
+        ]),
+        false,
+    );
+
+    assert!(keys.is_empty());
+    assert_eq!(keys.key_for(&openai()), None);
+}
+
+#[test]
+fn test_passthrough_prefers_the_provider_key_header() {
+    let both = headers(&[
+        ("X-Provider-Key", "sk-caller"),
+        ("Authorization", "Bearer sk-token"),
+    ]);
+    let token_only = headers(&[("Authorization", "Bearer sk-token")]);
+
+    let keys = ProviderKeys::default()
+        .with_caller(ProviderKeyMode::Passthrough, &both, false)
+        .for_model("claude-sonnet-4");
+    assert_eq!(
+        keys.key_for(&anthropic()),
+        Some(&ProviderKey::new("sk-caller"))
+    );
+
+    let keys = ProviderKeys::default()
+        .with_caller(ProviderKeyMode::Passthrough, &token_only, false)
+        .for_model("gpt-4o");
+    assert_eq!(keys.key_for(&openai()), Some(&ProviderKey::new("sk-token")));
+
+    // With a registry the bearer token is a Reflex key, not a provider key.
+    let keys = ProviderKeys::default().with_caller(ProviderKeyMode::Passthrough, &token_only, true);
+    assert!(keys.is_empty());
+}
+
+#[test]
+fn test_tenant_keys_are_per_provider() {
+    let keys = ProviderKeys::for_tenant(HashMap::from([(
+        "OpenAI".to_string(),
+        ProviderKey::new("sk-tenant"),
+    )]))
+    .unwrap();
+
+    assert_eq!(
+        keys.key_for(&openai()),
+        Some(&ProviderKey::new("sk-tenant"))
+    );
+    assert_eq!(
+        keys.key_for(&ModelIden::new(AdapterKind::OpenAIResp, "gpt-5")),
+        Some(&ProviderKey::new("sk-tenant"))
+    );
+    assert_eq!(keys.key_for(&anthropic()), None);
+    assert!(keys.overrides("gpt-4o"));
+    assert!(!keys.overrides("claude-sonnet-4"));
+
+    assert!(
+        ProviderKeys::for_tenant(HashMap::from([(
+            "not-a-provider".to_string(),
+            ProviderKey::new("sk")
+        )]))
+        .is_err()
+    );
+}
+
+#[test]
+fn test_registry_keys_resolve_with_the_tenant() {
+    let registry = TenantRegistry::from_toml_str(REGISTRY).unwrap();
+    let reflex_key = headers(&[("Authorization", "Bearer sk-reflex-acme")]);
+    let with_caller = headers(&[
+        ("Authorization", "Bearer sk-reflex-acme"),
+        ("X-Provider-Key", "sk-caller"),
+    ]);
+
+    let tenant =
+        resolve_tenant(Some(&registry), ProviderKeyMode::Passthrough, &reflex_key).unwrap();
+    assert_eq!(
+        tenant.provider_keys.key_for(&openai()),
+        Some(&ProviderKey::new("sk-acme-openai"))
+    );
+
+    let tenant =
+        resolve_tenant(Some(&registry), ProviderKeyMode::Passthrough, &with_caller).unwrap();
+    let keys = tenant.provider_keys.for_model("gpt-4o");
+    assert_eq!(
+        keys.key_for(&openai()),
+        Some(&ProviderKey::new("sk-caller"))
+    );
+}
+
+#[test]
+fn test_caller_keys_only_reach_the_requested_provider() {
+    let caller = headers(&[("X-Provider-Key", "sk-caller")]);
+    let keys = ProviderKeys::for_tenant(HashMap::from([(
+        "anthropic".to_string(),
+        ProviderKey::new("sk-tenant-anthropic"),
+    )]))
+    .unwrap()
+    .with_caller(ProviderKeyMode::Passthrough, &caller, false);
+
+    // Unbound, the caller's key goes nowhere.
+    assert_eq!(keys.key_for(&openai()), None);
+
+    let keys = keys.for_model("gpt-4o");
+    assert_eq!(
+        keys.key_for(&openai()),
+        Some(&ProviderKey::new("sk-caller"))
+    );
+    assert_eq!(
+        keys.key_for(&ModelIden::new(AdapterKind::OpenAIResp, "gpt-5")),
+        Some(&ProviderKey::new("sk-caller"))
+    );
+    assert_eq!(
+        keys.key_for(&anthropic()),
+        Some(&ProviderKey::new("sk-tenant-anthropic"))
+    );
+}
+
+#[test]
+fn test_caller_keys_drop_fallbacks_to_other_providers() {
+    let request: CreateChatCompletionRequest = serde_json::from_value(serde_json::json!({
+        "model": "fast",
+        "messages": [{"role": "user", "content": "hi"}]
+    }))
+    .unwrap();
+    let route = Route {
+        models: vec![
+            "gpt-4o-mini".to_string(),
+            "claude-3-5-haiku-latest".to_string(),
+            "gpt-4.1".to_string(),
+        ],
+        ..Route::default()
+    };
+    let tenant = |caller: &[(&'static str, &str)]| {
+        resolve_tenant(None, ProviderKeyMode::Passthrough, &headers(caller)).unwrap()
+    };
+
+    let ctx = StoreContext::new(
+        tenant(&[("X-Provider-Key", "sk-caller")]),
+        &HeaderMap::new(),
+        &request,
+    )
+    .unwrap()
+    .with_route(route.clone());
+    assert_eq!(ctx.route.models, vec!["gpt-4o-mini", "gpt-4.1"]);
+    assert_eq!(
+        ctx.provider_keys.key_for(&openai()),
+        Some(&ProviderKey::new("sk-caller"))
+    );
+
+    let ctx = StoreContext::new(tenant(&[]), &HeaderMap::new(), &request)
+        .unwrap()
+        .with_route(route.clone());
+    assert_eq!(ctx.route, route);
+}
+
+#[test]
+fn test_keys_never_appear_in_debug_output() {
+    let registry = TenantRegistry::from_toml_str(REGISTRY).unwrap();
+    let tenant = resolve_tenant(
+        Some(&registry),
+        ProviderKeyMode::Passthrough,
+        &headers(&[
+            ("Authorization", "Bearer sk-reflex-acme"),
+            ("X-Provider-Key", "sk-caller"),
+        ]),
+    )
+    .unwrap();
+    let request: CreateChatCompletionRequest = serde_json::from_value(serde_json::json!({
+        "model": "gpt-4o",
+        "messages": [{"role": "user", "content": "hi"}]
+    }))
+    .unwrap();
+    let ctx = StoreContext::new(tenant, &HeaderMap::new(), &request).unwrap();
+
+    let printed = format!("{:?} {:?}", ctx, registry);
+    assert!(!printed.contains("sk-caller"));
+    assert!(!printed.contains("sk-acme-openai"));
+    assert!(!printed.contains("sk-reflex-acme"));
+    assert!(printed.contains("REDACTED"));
+}
@@ -0,0 +1,338 @@
+//! Token-bucket rate limits on requests and on provider calls.
+//!
+//! Two budgets are kept apart: `requests` counts every request, hits included,
+//! and `misses` counts only provider calls. A tenant that exhausts its miss
+//! budget is refused new provider calls but keeps being served from the cache.
+//! Requests answered by a coalesced in-flight call do not spend a miss.
+//!
+//! Each budget can be limited globally (all tenants together) and per tenant.
+//! Per-tenant limits come from the tenant registry (`request_rate`,
+//! `miss_rate`), falling back to the `REFLEX_TENANT_RATE_LIMIT_*` defaults.
+//! A rate such as `600/min` allows bursts of up to 600 and refills
+//! continuously at 10 per second.
+
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::gateway::error::GatewayError;
+use crate::gateway::tenants::TenantPolicy;
+
+pub const GLOBAL_REQUESTS_ENV: &str = "REFLEX_GLOBAL_RATE_LIMIT_REQUESTS";
+pub const GLOBAL_MISSES_ENV: &str = "REFLEX_GLOBAL_RATE_LIMIT_MISSES";
+pub const TENANT_REQUESTS_ENV: &str = "REFLEX_TENANT_RATE_LIMIT_REQUESTS";
+pub const TENANT_MISSES_ENV: &str = "REFLEX_TENANT_RATE_LIMIT_MISSES";
+
+/// Idle per-tenant buckets are dropped once this many are tracked.
+const PRUNE_THRESHOLD: usize = 10_000;
+
+#[derive(Debug, Error)]
+#[error("invalid {name} '{value}': {message}")]
+pub struct RateLimitConfigError {
+    pub name: &'static str,
+    pub value: String,
+    pub message: String,
+}
+
+/// `count` events per `per`, e.g. `100/s` or `5000/h`.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct Rate {
+    pub count: u32,
+    pub per: Duration,
+}
+
+impl Rate {
+    fn refill_per_sec(&self) -> f64 {
+        self.count as f64 / self.per.as_secs_f64()
+    }
+}
+
+impl FromStr for Rate {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (count, unit) = s
+            .trim()
+            .split_once('/')
+            .ok_or_else(|| "expected <count>/<unit>, e.g. 600/min".to_string())?;
+        let count: u32 = count
+            .trim()
+            .parse()
+            .map_err(|_| format!("invalid count '{}'", count.trim()))?;
+        if count == 0 {
+            return Err("count must be at least 1".to_string());
+        }
+        let per = match unit.trim().to_ascii_lowercase().as_str() {
+            "s" | "sec" | "second" => Duration::from_secs(1),
+            "m" | "min" | "minute" => Duration::from_secs(60),
+            "h" | "hour" => Duration::from_secs(3600),
+            "d" | "day" => Duration::from_secs(86_400),
+            other => {
+                return Err(format!(
+                    "unknown unit '{}': expected s, min, h or day",
+                    other
+                ));
+            }
+        };
+        Ok(Self { count, per })
+    }
+}
+
+impl TryFrom<String> for Rate {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+/// Which budget a token is spent from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Budget {
+    Requests,
+    Misses,
+}
+
+impl Budget {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Budget::Requests => "requests",
+            Budget::Misses => "misses",
+        }
+    }
+}
+
+/// Limits not tied to a registry entry.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct RateLimitConfig {
+    /// All requests, across tenants.
+    pub global_requests: Option<Rate>,
+    /// All provider calls, across tenants.
+    pub global_misses: Option<Rate>,
+    /// Requests per tenant, unless its policy sets `request_rate`.
+    pub tenant_requests: Option<Rate>,
+    /// Provider calls per tenant, unless its policy sets `miss_rate`.
+    pub tenant_misses: Option<Rate>,
+}
+
+impl RateLimitConfig {
+    /// Reads the `REFLEX_*_RATE_LIMIT_*` variables; unset ones are unlimited.
+    pub fn from_env() -> Result<Self, RateLimitConfigError> {
+        Ok(Self {
+            global_requests: rate_from_env(GLOBAL_REQUESTS_ENV)?,
+            global_misses: rate_from_env(GLOBAL_MISSES_ENV)?,
+            tenant_requests: rate_from_env(TENANT_REQUESTS_ENV)?,
+            tenant_misses: rate_from_env(TENANT_MISSES_ENV)?,
+        })
+    }
+
+    fn global(&self, budget: Budget) -> Option<Rate> {
+        match budget {
+            Budget::Requests => self.global_requests,
+            Budget::Misses => self.global_misses,
+        }
+    }
+
+    fn tenant(&self, budget: Budget, policy: &TenantPolicy) -> Option<Rate> {
+        match budget {
+            Budget::Requests => policy.request_rate.or(self.tenant_requests),
+            Budget::Misses => policy.miss_rate.or(self.tenant_misses),
+        }
+    }
+}
+
+fn rate_from_env(name: &'static str) -> Result<Option<Rate>, RateLimitConfigError> {
+    match std::env::var(name) {
+        Ok(value) if !value.trim().is_empty() => {
+            value
+                .parse()
+                .map(Some)
+                .map_err(|message| RateLimitConfigError {
+                    name,
+                    value,
+                    message,
+                })
+        }
+        _ => Ok(None),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum BucketKey {
+    Global(Budget),
+    Tenant(u64, Budget),
+}
+
+#[derive(Debug, Clone, Copy)]
+struct TokenBucket {
+    tokens: f64,
+    updated: Instant,
+    /// Time to refill from empty.
+    window: Duration,
+}
+
+impl TokenBucket {
+    fn full(rate: Rate, now: Instant) -> Self {
+        Self {
+            tokens: rate.count as f64,
+            updated: now,
+            window: rate.per,
+        }
+    }
+
+    fn refill(&mut self, rate: Rate, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * rate.refill_per_sec()).min(rate.count as f64);
+        self.updated = now;
+        self.window = rate.per;
+    }
+
+    /// Time until `cost` tokens are available.
+    fn wait(&self, rate: Rate, cost: f64) -> Duration {
+        if self.tokens >= cost {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64((cost - self.tokens) / rate.refill_per_sec())
+        }
+    }
+}
+
+/// Token buckets for every limited budget.
+#[derive(Debug, Default)]
+pub struct RateLimiter {
+    config: RateLimitConfig,
+    buckets: Mutex<HashMap<BucketKey, TokenBucket>>,
+}
+
+impl RateLimiter {
+    pub fn new(config: RateLimitConfig) -> Self {
+        Self {
+            config,
+            buckets: Mutex::default(),
+        }
+    }
+
+    pub fn config(&self) -> &RateLimitConfig {
+        &self.config
+    }
+
+    /// Spends one `budget` token for `tenant_id`, globally and per tenant.
+    ///
+    /// Nothing is spent unless every applicable bucket has a token.
+    pub fn acquire(
+        &self,
+        budget: Budget,
+        tenant_id: u64,
+        policy: &TenantPolicy,
+    ) -> Result<(), GatewayError> {
+        self.acquire_at(budget, tenant_id, policy, Instant::now())
+    }
+
+    /// Spends `cost` `budget` tokens at once, as [`acquire`](Self::acquire)
+    /// spends one.
+    ///
+    /// A cost larger than a bucket can ever hold is refused as an invalid
+    /// request rather than rate limited, since no wait would admit it.
+    pub fn acquire_n(
+        &self,
+        budget: Budget,
+        tenant_id: u64,
+        policy: &TenantPolicy,
+        cost: u32,
+    ) -> Result<(), GatewayError> {
+        self.acquire_n_at(budget, tenant_id, policy, cost, Instant::now())
+    }
+
+    pub(crate) fn acquire_at(
+        &self,
+        budget: Budget,
+        tenant_id: u64,
+        policy: &TenantPolicy,
+        now: Instant,
+    ) -> Result<(), GatewayError> {
+        self.acquire_n_at(budget, tenant_id, policy, 1, now)
+    }
+
+    pub(crate) fn acquire_n_at(
+        &self,
+        budget: Budget,
+        tenant_id: u64,
+        policy: &TenantPolicy,
+        cost: u32,
+        now: Instant,
+    ) -> Result<(), GatewayError> {
+        let limits = [
+            (
+                BucketKey::Global(budget),
+                self.config.global(budget),
+                "global",
+            ),
+            (
+                BucketKey::Tenant(tenant_id, budget),
+                self.config.tenant(budget, policy),
+                "tenant",
+            ),
+        ];
+        if limits.iter().all(|(_, rate, _)| rate.is_none()) {
+            return Ok(());
+        }
+
+        let mut buckets = self.buckets.lock().expect("lock poisoned");
+        if buckets.len() > PRUNE_THRESHOLD {
+            prune(&mut buckets, now);
+        }
+
+        let mut refused: Option<(Duration, &'static str)> = None;
+        for (key, rate, scope) in limits {
+            let Some(rate) = rate else { continue };
+            if cost > rate.count {
+                return Err(GatewayError::InvalidRequest(format!(
+                    "request needs {} {} tokens but the {} rate allows {}",
+                    cost,
+                    budget.as_str(),
+                    scope,
+                    rate.count
+                )));
+            }
+            let bucket = buckets
+                .entry(key)
+                .or_insert_with(|| TokenBucket::full(rate, now));
+            bucket.refill(rate, now);
+            let wait = bucket.wait(rate, f64::from(cost));
+            if !wait.is_zero() && refused.is_none_or(|(longest, _)| wait > longest) {
+                refused = Some((wait, scope));
+            }
+        }
+
+        if let Some((wait, scope)) = refused {
+            crate::gateway::telemetry::record_rate_limited(budget, scope);
+            return Err(GatewayError::RateLimited {
+                message: format!("{} {} rate limit exceeded", scope, budget.as_str()),
+                retry_after: wait.as_secs_f64().ceil().max(1.0) as u64,
+            });
+        }
+
+        for (key, rate, _) in limits {
+            if rate.is_some()
+                && let Some(bucket) = buckets.get_mut(&key)
+            {
+                bucket.tokens -= f64::from(cost);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Drops per-tenant buckets idle for a full window; they have refilled and
+/// are indistinguishable from new ones.
+fn prune(buckets: &mut HashMap<BucketKey, TokenBucket>, now: Instant) {
+    buckets.retain(|key, bucket| {
+        matches!(key, BucketKey::Global(_))
+            || now.saturating_duration_since(bucket.updated) < bucket.window
+    });
+}
@@ -0,0 +1,204 @@
+//! Tests for token-bucket rate limiting.
+
+use std::time::{Duration, Instant};
+
+use crate::gateway::error::GatewayError;
+use crate::gateway::rate_limit::{Budget, Rate, RateLimitConfig, RateLimiter};
+use crate::gateway::tenants::TenantPolicy;
+
+fn rate(s: &str) -> Option<Rate> {
+    Some(s.parse().unwrap())
+}
+
+fn retry_after(result: Result<(), GatewayError>) -> u64 {
+    match result {
+        Err(GatewayError::RateLimited { retry_after, .. }) => retry_after,
+        other => panic!("expected RateLimited, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_rate_parses_units() {
+    assert_eq!(
+        "600/min".parse::<Rate>(),
+        Ok(Rate {
+            count: 600,
+            per: Duration::from_secs(60)
+        })
+    );
+    assert_eq!(
+        " 5 / s ".parse::<Rate>().unwrap().per,
+        Duration::from_secs(1)
+    );
+    assert_eq!(
+        "1/day".parse::<Rate>().unwrap().per,
+        Duration::from_secs(86_400)
+    );
+    for bad in ["600", "0/s", "ten/s", "5/week"] {
+        assert!(bad.parse::<Rate>().is_err(), "{}", bad);
+    }
+}
+
+#[test]
+fn test_bucket_allows_burst_then_refills() {
+    let limiter = RateLimiter::new(RateLimitConfig {
+        tenant_misses: rate("2/min"),
+        ..RateLimitConfig::default()
+    });
+    let policy = TenantPolicy::default();
+    let now = Instant::now();
+
+    assert!(limiter.acquire_at(Budget::Misses, 1, &policy, now).is_ok());
+    assert!(limiter.acquire_at(Budget::Misses, 1, &policy, now).is_ok());
+    assert_eq!(
+        retry_after(limiter.acquire_at(Budget::Misses, 1, &policy, now)),
+        30
+    );
+
+    // Requests are a separate budget, and unlimited here.
+    assert!(
+        limiter
+            .acquire_at(Budget::Requests, 1, &policy, now)
+            .is_ok()
+    );
+
+    let later = now + Duration::from_secs(30);
+    assert!(
+        limiter
+            .acquire_at(Budget::Misses, 1, &policy, later)
+            .is_ok()
+    );
+    assert!(
+        limiter
+            .acquire_at(Budget::Misses, 1, &policy, later)
+            .is_err()
+    );
+}
+
+#[test]
+fn test_multi_token_acquire_spends_its_cost() {
+    let limiter = RateLimiter::new(RateLimitConfig {
+        tenant_misses: rate("4/min"),
+        ..RateLimitConfig::default()
+    });
+    let policy = TenantPolicy::default();
+    let now = Instant::now();
+
+    assert!(
+        limiter
+            .acquire_n_at(Budget::Misses, 1, &policy, 3, now)
+            .is_ok()
+    );
+    // One token left: a two-token request waits for the second.
+    assert_eq!(
+        retry_after(limiter.acquire_n_at(Budget::Misses, 1, &policy, 2, now)),
+        15
+    );
+    assert!(limiter.acquire_at(Budget::Misses, 1, &policy, now).is_ok());
+
+    // More than the bucket holds is never admitted.
+    assert!(matches!(
+        limiter.acquire_n_at(Budget::Misses, 2, &policy, 5, now),
+        Err(GatewayError::InvalidRequest(_))
+    ));
+}
+
+#[test]
+fn test_tenants_have_separate_buckets_under_a_shared_global_one() {
+    let limiter = RateLimiter::new(RateLimitConfig {
+        global_requests: rate("3/s"),
+        tenant_requests: rate("2/s"),
+        ..RateLimitConfig::default()
+    });
+    let policy = TenantPolicy::default();
+    let now = Instant::now();
+
+    assert!(
+        limiter
+            .acquire_at(Budget::Requests, 1, &policy, now)
+            .is_ok()
+    );
+    assert!(
+        limiter
+            .acquire_at(Budget::Requests, 1, &policy, now)
+            .is_ok()
+    );
+    assert!(
+        limiter
+            .acquire_at(Budget::Requests, 1, &policy, now)
+            .is_err()
+    );
+    assert!(
+        limiter
+            .acquire_at(Budget::Requests, 2, &policy, now)
+            .is_ok()
+    );
+    // Tenant 2 still has a token of its own, but the global bucket is empty.
+    assert!(
+        limiter
+            .acquire_at(Budget::Requests, 2, &policy, now)
+            .is_err()
+    );
+}
+
+#[test]
+fn test_refusal_spends_nothing() {
+    let limiter = RateLimiter::new(RateLimitConfig {
+        global_misses: rate("1/s"),
+        tenant_misses: rate("1/min"),
+        ..RateLimitConfig::default()
+    });
+    let policy = TenantPolicy::default();
+    let now = Instant::now();
+
+    assert!(limiter.acquire_at(Budget::Misses, 1, &policy, now).is_ok());
+    let later = now + Duration::from_secs(1);
+    // Tenant 1 is refused by its own bucket; the global token stays for tenant 2.
+    assert_eq!(
+        retry_after(limiter.acquire_at(Budget::Misses, 1, &policy, later)),
+        59
+    );
+    assert!(
+        limiter
+            .acquire_at(Budget::Misses, 2, &policy, later)
+            .is_ok()
+    );
+}
+
+#[test]
+fn test_policy_overrides_the_tenant_default() {
+    let limiter = RateLimiter::new(RateLimitConfig {
+        tenant_misses: rate("100/s"),
+        ..RateLimitConfig::default()
+    });
+    let strict = TenantPolicy {
+        miss_rate: rate("1/h"),
+        ..TenantPolicy::default()
+    };
+    let now = Instant::now();
+
+    assert!(limiter.acquire_at(Budget::Misses, 1, &strict, now).is_ok());
+    assert_eq!(
+        retry_after(limiter.acquire_at(Budget::Misses, 1, &strict, now)),
+        3600
+    );
+    assert!(
+        limiter
+            .acquire_at(Budget::Misses, 2, &TenantPolicy::default(), now)
+            .is_ok()
+    );
+}
+
+#[test]
+fn test_unlimited_by_default() {
+    let limiter = RateLimiter::default();
+    let now = Instant::now();
+
+    for _ in 0..1000 {
+        assert!(
+            limiter
+                .acquire_at(Budget::Misses, 1, &TenantPolicy::default(), now)
+                .is_ok()
+        );
+    }
+}
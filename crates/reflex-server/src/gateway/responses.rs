@@ -0,0 +1,818 @@
+//! OpenAI Responses API (`POST /v1/responses`) ingress.
+//!
+//! Like the Messages route, requests are folded into the canonical chat
+//! completion form, so `input` + `instructions` produce the same semantic key
+//! and [`CachePayload`](crate::gateway::payload::CachePayload) entry as the
+//! equivalent chat completion. Conversations are not stored server-side, so
+//! `previous_response_id` is rejected.
+
+use async_openai::types::chat::{
+    ChatCompletionMessageToolCalls, CreateChatCompletionRequest, CreateChatCompletionResponse,
+    FinishReason,
+};
+use axum::{
+    Json,
+    extract::State,
+    http::{HeaderMap, HeaderValue},
+    response::sse::Event,
+    response::{IntoResponse, Response},
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use tracing::{debug, instrument};
+
+use crate::gateway::cache_control::apply_cache_headers;
+use crate::gateway::error::GatewayError;
+use crate::gateway::explain::debug_headers;
+use crate::gateway::feedback::apply_entry_header;
+use crate::gateway::handler::{admit_request, complete_request, report_savings, store_context};
+use crate::gateway::state::HandlerState;
+use crate::gateway::streaming::{
+    StreamEncoder, StreamMeta, ToolCallDelta, json_event, serve_stream,
+};
+use reflex::cache::{BqSearchBackend, REFLEX_STATUS_HEADER, StorageLoader};
+use reflex::storage::StorageWriter;
+
+/// Responses API request body (the subset the gateway can translate).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResponsesRequest {
+    pub model: String,
+    pub input: ResponsesInput,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub instructions: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tools: Option<Vec<ResponsesTool>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_choice: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_output_tokens: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parallel_tool_calls: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<TextConfig>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stream: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub previous_response_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<Value>,
+}
+
+/// A bare string, or a list of input items.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ResponsesInput {
+    Text(String),
+    Items(Vec<InputItem>),
+}
+
+/// Input items; messages may omit `type` ("easy input messages").
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum InputItem {
+    Typed(TypedInputItem),
+    Message(InputMessage),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TypedInputItem {
+    Message {
+        role: String,
+        content: InputContent,
+    },
+    FunctionCall {
+        call_id: String,
+        name: String,
+        arguments: String,
+    },
+    FunctionCallOutput {
+        call_id: String,
+        output: Value,
+    },
+    /// Reasoning, item references and hosted-tool items are dropped.
+    #[serde(other)]
+    Unsupported,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InputMessage {
+    pub role: String,
+    pub content: InputContent,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum InputContent {
+    Text(String),
+    Parts(Vec<InputPart>),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum InputPart {
+    InputText {
+        text: String,
+    },
+    OutputText {
+        text: String,
+    },
+    InputImage {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        image_url: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        detail: Option<String>,
+    },
+    Refusal {
+        refusal: String,
+    },
+    #[serde(other)]
+    Unsupported,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ResponsesTool {
+    Function {
+        name: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        description: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        parameters: Option<Value>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        strict: Option<bool>,
+    },
+    /// Hosted tools (web search, file search, ...) are not forwarded.
+    #[serde(other)]
+    Unsupported,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TextConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub format: Option<Value>,
+}
+
+/// Responses API `response` object.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResponseObject {
+    pub id: String,
+    pub object: String,
+    pub created_at: u32,
+    pub status: String,
+    pub model: String,
+    pub output: Vec<OutputItem>,
+    pub usage: Option<ResponseUsage>,
+    pub incomplete_details: Option<IncompleteDetails>,
+    pub error: Option<Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum OutputItem {
+    Message {
+        id: String,
+        status: String,
+        role: String,
+        content: Vec<OutputContent>,
+    },
+    FunctionCall {
+        id: String,
+        call_id: String,
+        name: String,
+        arguments: String,
+        status: String,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum OutputContent {
+    OutputText {
+        text: String,
+        annotations: Vec<Value>,
+    },
+    Refusal {
+        refusal: String,
+    },
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ResponseUsage {
+    pub input_tokens: u32,
+    pub output_tokens: u32,
+    pub total_tokens: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IncompleteDetails {
+    pub reason: String,
+}
+
+#[instrument(skip(state, request, headers), fields(model = tracing::field::Empty))]
+pub async fn responses_handler<B, S>(
+    State(state): State<HandlerState<B, S>>,
+    headers: HeaderMap,
+    Json(request): Json<Value>,
+) -> Result<Response, GatewayError>
+where
+    B: BqSearchBackend + Clone + Send + Sync + 'static,
+    S: StorageLoader + StorageWriter + Clone + Send + Sync + 'static,
+{
+    let tenant = admit_request(&state, &headers)?;
+    let request: ResponsesRequest = serde_json::from_value(request)
+        .map_err(|e| GatewayError::InvalidRequest(format!("Invalid request schema: {}", e)))?;
+    tracing::Span::current().record("model", tracing::field::display(&request.model));
+
+    let mut chat_request = responses_to_chat_request(&request)?;
+    let route = state.router.apply(&mut chat_request);
+    let store_ctx = store_context(&state, tenant, &headers, &chat_request, route).await?;
+
+    debug!(hash = %store_ctx.l1_key, "Processing responses request");
+    let trace = store_ctx.debug_trace.clone();
+
+    if request.stream.unwrap_or(false) {
+        let mut response = serve_stream(
+            state,
+            chat_request,
+            store_ctx,
+            ResponsesStreamEncoder::default(),
+        )
+        .await?;
+        response.headers_mut().extend(debug_headers(trace.as_ref()));
+        return Ok(response);
+    }
+
+    let outcome = complete_request(&state, chat_request, &store_ctx).await?;
+
+    let mut response = Json(chat_response_to_responses(&outcome.payload.response)).into_response();
+    response.headers_mut().insert(
+        REFLEX_STATUS_HEADER,
+        HeaderValue::from_static(outcome.status.as_header_value()),
+    );
+    report_savings(
+        &state,
+        &store_ctx,
+        &outcome.payload.response,
+        outcome.status,
+        response.headers_mut(),
+    );
+    apply_entry_header(&outcome, response.headers_mut());
+    response.headers_mut().extend(debug_headers(trace.as_ref()));
+    apply_cache_headers(
+        response.headers_mut(),
+        &store_ctx.directives,
+        outcome.stored_at,
+    );
+    Ok(response)
+}
+
+/// Converts a Responses request to the canonical chat completion request.
+pub fn responses_to_chat_request(
+    request: &ResponsesRequest,
+) -> Result<CreateChatCompletionRequest, GatewayError> {
+    if request.previous_response_id.is_some() {
+        return Err(GatewayError::InvalidRequest(
+            "previous_response_id is not supported; send the full conversation in `input`"
+                .to_string(),
+        ));
+    }
+
+    let mut messages = Vec::new();
+
+    if let Some(instructions) = &request.instructions {
+        messages.push(json!({ "role": "system", "content": instructions }));
+    }
+
+    match &request.input {
+        ResponsesInput::Text(text) => messages.push(json!({ "role": "user", "content": text })),
+        ResponsesInput::Items(items) => {
+            for item in items {
+                push_input_item(&mut messages, item);
+            }
+        }
+    }
+
+    let mut chat = json!({ "model": request.model, "messages": messages });
+
+    if let Some(tools) = &request.tools {
+        let tools: Vec<Value> = tools
+            .iter()
+            .filter_map(|tool| match tool {
+                ResponsesTool::Function {
+                    name,
+                    description,
+                    parameters,
+                    strict,
+                } => {
+                    let mut function = json!({ "name": name });
+                    if let Some(description) = description {
+                        function["description"] = json!(description);
+                    }
+                    if let Some(parameters) = parameters {
+                        function["parameters"] = parameters.clone();
+                    }
+                    if let Some(strict) = strict {
+                        function["strict"] = json!(strict);
+                    }
+                    Some(json!({ "type": "function", "function": function }))
+                }
+                ResponsesTool::Unsupported => {
+                    debug!("Skipping hosted tool in responses request");
+                    None
+                }
+            })
+            .collect();
+        if !tools.is_empty() {
+            chat["tools"] = Value::Array(tools);
+        }
+    }
+
+    if let Some(tool_choice) = &request.tool_choice {
+        match tool_choice {
+            Value::String(mode) => chat["tool_choice"] = json!(mode),
+            Value::Object(obj) if obj.get("type") == Some(&json!("function")) => {
+                chat["tool_choice"] =
+                    json!({ "type": "function", "function": { "name": obj.get("name") } });
+            }
+            _ => debug!("Skipping unsupported tool_choice in responses request"),
+        }
+    }
+
+    if let Some(format) = request.text.as_ref().and_then(|t| t.format.as_ref()) {
+        match format.get("type").and_then(Value::as_str) {
+            Some("json_schema") => {
+                let mut schema = format.clone();
+                if let Some(obj) = schema.as_object_mut() {
+                    obj.remove("type");
+                }
+                chat["response_format"] = json!({ "type": "json_schema", "json_schema": schema });
+            }
+            Some("json_object") => chat["response_format"] = json!({ "type": "json_object" }),
+            _ => {}
+        }
+    }
+
+    if let Some(max_output_tokens) = request.max_output_tokens {
+        chat["max_completion_tokens"] = json!(max_output_tokens);
+    }
+    if let Some(temperature) = request.temperature {
+        chat["temperature"] = json!(temperature);
+    }
+    if let Some(top_p) = request.top_p {
+        chat["top_p"] = json!(top_p);
+    }
+    if let Some(parallel) = request.parallel_tool_calls {
+        chat["parallel_tool_calls"] = json!(parallel);
+    }
+    if let Some(stream) = request.stream {
+        chat["stream"] = json!(stream);
+    }
+
+    serde_json::from_value(chat)
+        .map_err(|e| GatewayError::InvalidRequest(format!("Unsupported input: {}", e)))
+}
+
+fn push_input_item(messages: &mut Vec<Value>, item: &InputItem) {
+    match item {
+        InputItem::Message(InputMessage { role, content })
+        | InputItem::Typed(TypedInputItem::Message { role, content }) => {
+            messages.push(input_message(role, content));
+        }
+        InputItem::Typed(TypedInputItem::FunctionCall {
+            call_id,
+            name,
+            arguments,
+        }) => {
+            // Consecutive calls are folded into one assistant message, as chat expects.
+            let call = json!({
+                "id": call_id,
+                "type": "function",
+                "function": { "name": name, "arguments": arguments },
+            });
+            match messages.last_mut() {
+                Some(last) if last["role"] == "assistant" && last.get("tool_calls").is_some() => {
+                    if let Some(calls) = last["tool_calls"].as_array_mut() {
+                        calls.push(call);
+                    }
+                }
+                _ => messages.push(json!({ "role": "assistant", "tool_calls": [call] })),
+            }
+        }
+        InputItem::Typed(TypedInputItem::FunctionCallOutput { call_id, output }) => {
+            let output = match output {
+                Value::String(s) => s.clone(),
+                other => other.to_string(),
+            };
+            messages.push(json!({ "role": "tool", "tool_call_id": call_id, "content": output }));
+        }
+        InputItem::Typed(TypedInputItem::Unsupported) => {
+            debug!("Skipping unsupported input item in responses request");
+        }
+    }
+}
+
+fn input_message(role: &str, content: &InputContent) -> Value {
+    let parts = match content {
+        InputContent::Text(text) => return json!({ "role": role, "content": text }),
+        InputContent::Parts(parts) => parts,
+    };
+
+    // Assistant history and system/developer prompts only carry text in chat.
+    if role != "user" {
+        let text: Vec<&str> = parts
+            .iter()
+            .filter_map(|p| match p {
+                InputPart::InputText { text } | InputPart::OutputText { text } => {
+                    Some(text.as_str())
+                }
+                InputPart::Refusal { refusal } => Some(refusal.as_str()),
+                _ => None,
+            })
+            .collect();
+        return json!({ "role": role, "content": text.join("\n") });
+    }
+
+    let chat_parts: Vec<Value> = parts
+        .iter()
+        .filter_map(|p| match p {
+            InputPart::InputText { text } | InputPart::OutputText { text } => {
+                Some(json!({ "type": "text", "text": text }))
+            }
+            InputPart::InputImage {
+                image_url: Some(url),
+                detail,
+            } => {
+                let mut image_url = json!({ "url": url });
+                if let Some(detail) = detail {
+                    image_url["detail"] = json!(detail);
+                }
+                Some(json!({ "type": "image_url", "image_url": image_url }))
+            }
+            _ => None,
+        })
+        .collect();
+
+    // A lone text part is keyed like a plain string so it matches chat clients.
+    match chat_parts.as_slice() {
+        [single] if single["type"] == "text" => json!({ "role": role, "content": single["text"] }),
+        _ => json!({ "role": role, "content": chat_parts }),
+    }
+}
+
+/// Converts a (possibly cached) chat completion response to a Responses object.
+pub fn chat_response_to_responses(response: &CreateChatCompletionResponse) -> ResponseObject {
+    let base = id_base(&response.id);
+    let mut output = Vec::new();
+    let mut finish_reason = None;
+
+    if let Some(choice) = response.choices.first() {
+        finish_reason = choice.finish_reason;
+
+        let mut content = Vec::new();
+        if let Some(text) = choice.message.content.as_deref()
+            && !text.is_empty()
+        {
+            content.push(OutputContent::OutputText {
+                text: text.to_string(),
+                annotations: Vec::new(),
+            });
+        }
+        if let Some(refusal) = choice.message.refusal.as_deref() {
+            content.push(OutputContent::Refusal {
+                refusal: refusal.to_string(),
+            });
+        }
+        if !content.is_empty() {
+            output.push(OutputItem::Message {
+                id: format!("msg_{}", base),
+                status: "completed".to_string(),
+                role: "assistant".to_string(),
+                content,
+            });
+        }
+
+        for call in choice.message.tool_calls.iter().flatten() {
+            if let ChatCompletionMessageToolCalls::Function(call) = call {
+                output.push(OutputItem::FunctionCall {
+                    id: format!("fc_{}", call.id),
+                    call_id: call.id.clone(),
+                    name: call.function.name.clone(),
+                    arguments: call.function.arguments.clone(),
+                    status: "completed".to_string(),
+                });
+            }
+        }
+    }
+
+    let (status, incomplete_details) = status_for(finish_reason);
+
+    ResponseObject {
+        id: format!("resp_{}", base),
+        object: "response".to_string(),
+        created_at: response.created,
+        status: status.to_string(),
+        model: response.model.clone(),
+        output,
+        usage: response.usage.as_ref().map(|u| ResponseUsage {
+            input_tokens: u.prompt_tokens,
+            output_tokens: u.completion_tokens,
+            total_tokens: u.total_tokens,
+        }),
+        incomplete_details,
+        error: None,
+    }
+}
+
+fn status_for(finish_reason: Option<FinishReason>) -> (&'static str, Option<IncompleteDetails>) {
+    let reason = match finish_reason {
+        Some(FinishReason::Length) => "max_output_tokens",
+        Some(FinishReason::ContentFilter) => "content_filter",
+        _ => return ("completed", None),
+    };
+    (
+        "incomplete",
+        Some(IncompleteDetails {
+            reason: reason.to_string(),
+        }),
+    )
+}
+
+fn id_base(chat_id: &str) -> &str {
+    chat_id.strip_prefix("chatcmpl-").unwrap_or(chat_id)
+}
+
+/// Responses API SSE: `response.created`, per-item added/delta/done events, `response.completed`.
+#[derive(Default)]
+pub(crate) struct ResponsesStreamEncoder {
+    sequence: u64,
+    base: String,
+    created_at: u32,
+    model: String,
+    done_items: Vec<OutputItem>,
+    open_item: Option<OpenItem>,
+}
+
+enum OpenItem {
+    Message {
+        text: String,
+    },
+    FunctionCall {
+        tool_index: usize,
+        call_id: String,
+        name: String,
+        arguments: String,
+    },
+}
+
+impl ResponsesStreamEncoder {
+    fn event(&mut self, kind: &str, mut body: Value) -> Event {
+        body["type"] = json!(kind);
+        body["sequence_number"] = json!(self.sequence);
+        self.sequence += 1;
+        json_event(Some(kind), &body)
+    }
+
+    fn output_index(&self) -> usize {
+        self.done_items.len()
+    }
+
+    fn snapshot(&self, status: &str) -> ResponseObject {
+        ResponseObject {
+            id: format!("resp_{}", self.base),
+            object: "response".to_string(),
+            created_at: self.created_at,
+            status: status.to_string(),
+            model: self.model.clone(),
+            output: self.done_items.clone(),
+            usage: None,
+            incomplete_details: None,
+            error: None,
+        }
+    }
+
+    fn close_item(&mut self) -> Vec<Event> {
+        let Some(open) = self.open_item.take() else {
+            return Vec::new();
+        };
+        let output_index = self.output_index();
+
+        let (item, mut events) = match open {
+            OpenItem::Message { text } => {
+                let item_id = format!("msg_{}", self.base);
+                let part = OutputContent::OutputText {
+                    text: text.clone(),
+                    annotations: Vec::new(),
+                };
+                let events = vec![
+                    self.event(
+                        "response.output_text.done",
+                        json!({
+                            "item_id": item_id,
+                            "output_index": output_index,
+                            "content_index": 0,
+                            "text": text,
+                        }),
+                    ),
+                    self.event(
+                        "response.content_part.done",
+                        json!({
+                            "item_id": item_id,
+                            "output_index": output_index,
+                            "content_index": 0,
+                            "part": part,
+                        }),
+                    ),
+                ];
+                let item = OutputItem::Message {
+                    id: item_id,
+                    status: "completed".to_string(),
+                    role: "assistant".to_string(),
+                    content: vec![part],
+                };
+                (item, events)
+            }
+            OpenItem::FunctionCall {
+                call_id,
+                name,
+                arguments,
+                ..
+            } => {
+                let item_id = format!("fc_{}", call_id);
+                let events = vec![self.event(
+                    "response.function_call_arguments.done",
+                    json!({
+                        "item_id": item_id,
+                        "output_index": output_index,
+                        "arguments": arguments,
+                    }),
+                )];
+                let item = OutputItem::FunctionCall {
+                    id: item_id,
+                    call_id,
+                    name,
+                    arguments,
+                    status: "completed".to_string(),
+                };
+                (item, events)
+            }
+        };
+
+        events.push(self.event(
+            "response.output_item.done",
+            json!({ "output_index": output_index, "item": item }),
+        ));
+        self.done_items.push(item);
+        events
+    }
+}
+
+impl StreamEncoder for ResponsesStreamEncoder {
+    fn begin(&mut self, meta: &StreamMeta) -> Vec<Event> {
+        self.base = id_base(&meta.id).to_string();
+        self.created_at = meta.created;
+        self.model = meta.model.clone();
+
+        let response = self.snapshot("in_progress");
+        vec![
+            self.event("response.created", json!({ "response": response })),
+            self.event("response.in_progress", json!({ "response": response })),
+        ]
+    }
+
+    fn text(&mut self, text: &str) -> Vec<Event> {
+        let mut events = Vec::new();
+        if !matches!(self.open_item, Some(OpenItem::Message { .. })) {
+            events.extend(self.close_item());
+            let output_index = self.output_index();
+            let item_id = format!("msg_{}", self.base);
+            events.push(self.event(
+                "response.output_item.added",
+                json!({
+                    "output_index": output_index,
+                    "item": {
+                        "type": "message",
+                        "id": item_id,
+                        "status": "in_progress",
+                        "role": "assistant",
+                        "content": [],
+                    },
+                }),
+            ));
+            events.push(self.event(
+                "response.content_part.added",
+                json!({
+                    "item_id": item_id,
+                    "output_index": output_index,
+                    "content_index": 0,
+                    "part": { "type": "output_text", "text": "", "annotations": [] },
+                }),
+            ));
+            self.open_item = Some(OpenItem::Message {
+                text: String::new(),
+            });
+        }
+
+        if let Some(OpenItem::Message { text: accumulated }) = &mut self.open_item {
+            accumulated.push_str(text);
+        }
+        let output_index = self.output_index();
+        let item_id = format!("msg_{}", self.base);
+        events.push(self.event(
+            "response.output_text.delta",
+            json!({
+                "item_id": item_id,
+                "output_index": output_index,
+                "content_index": 0,
+                "delta": text,
+            }),
+        ));
+        events
+    }
+
+    fn tool_call(&mut self, delta: &ToolCallDelta) -> Vec<Event> {
+        let mut events = Vec::new();
+        let is_open = matches!(
+            self.open_item,
+            Some(OpenItem::FunctionCall { tool_index, .. }) if tool_index == delta.index
+        );
+
+        if !is_open {
+            events.extend(self.close_item());
+            let call_id = delta.id.clone().unwrap_or_default();
+            let name = delta.name.clone().unwrap_or_default();
+            let output_index = self.output_index();
+            events.push(self.event(
+                "response.output_item.added",
+                json!({
+                    "output_index": output_index,
+                    "item": {
+                        "type": "function_call",
+                        "id": format!("fc_{}", call_id),
+                        "call_id": call_id,
+                        "name": name,
+                        "arguments": "",
+                        "status": "in_progress",
+                    },
+                }),
+            ));
+            self.open_item = Some(OpenItem::FunctionCall {
+                tool_index: delta.index,
+                call_id,
+                name,
+                arguments: String::new(),
+            });
+        }
+
+        let mut item_id = String::new();
+        if let Some(OpenItem::FunctionCall {
+            call_id, arguments, ..
+        }) = &mut self.open_item
+        {
+            arguments.push_str(&delta.arguments);
+            item_id = format!("fc_{}", call_id);
+        }
+
+        if !delta.arguments.is_empty() {
+            let output_index = self.output_index();
+            events.push(self.event(
+                "response.function_call_arguments.delta",
+                json!({
+                    "item_id": item_id,
+                    "output_index": output_index,
+                    "delta": delta.arguments,
+                }),
+            ));
+        }
+        events
+    }
+
+    fn finish(&mut self, response: &CreateChatCompletionResponse) -> Vec<Event> {
+        let mut events = self.close_item();
+
+        let finish_reason = response.choices.first().and_then(|c| c.finish_reason);
+        let (status, incomplete_details) = status_for(finish_reason);
+        let mut final_response = self.snapshot(status);
+        final_response.incomplete_details = incomplete_details;
+        final_response.usage = chat_response_to_responses(response).usage;
+
+        let kind = if status == "completed" {
+            "response.completed"
+        } else {
+            "response.incomplete"
+        };
+        events.push(self.event(kind, json!({ "response": final_response })));
+        events
+    }
+
+    fn error(&mut self, message: &str) -> Vec<Event> {
+        vec![self.event(
+            "error",
+            json!({ "code": null, "message": message, "param": null }),
+        )]
+    }
+}
@@ -0,0 +1,137 @@
+//! Tests for Responses API request/response conversion.
+
+use async_openai::types::chat::CreateChatCompletionResponse;
+use serde_json::{Value, json};
+
+use crate::gateway::error::GatewayError;
+use crate::gateway::responses::{
+    ResponsesRequest, chat_response_to_responses, responses_to_chat_request,
+};
+
+fn parse(value: Value) -> ResponsesRequest {
+    serde_json::from_value(value).expect("valid responses request")
+}
+
+#[test]
+fn test_string_input_keys_like_chat_request() {
+    let from_responses = responses_to_chat_request(&parse(json!({
+        "model": "gpt-4o",
+        "instructions": "Be brief.",
+        "input": "Hello"
+    })))
+    .unwrap();
+
+    let value = serde_json::to_value(&from_responses).unwrap();
+    assert_eq!(
+        value["messages"],
+        json!([
+            {"role": "system", "content": "Be brief."},
+            {"role": "user", "content": "Hello"}
+        ])
+    );
+}
+
+#[test]
+fn test_single_input_text_part_keys_like_plain_string() {
+    let from_string = responses_to_chat_request(&parse(json!({
+        "model": "m",
+        "input": [{"role": "user", "content": "Hello"}]
+    })))
+    .unwrap();
+    let from_parts = responses_to_chat_request(&parse(json!({
+        "model": "m",
+        "input": [{
+            "type": "message",
+            "role": "user",
+            "content": [{"type": "input_text", "text": "Hello"}]
+        }]
+    })))
+    .unwrap();
+
+    assert_eq!(
+        serde_json::to_value(&from_string).unwrap(),
+        serde_json::to_value(&from_parts).unwrap()
+    );
+}
+
+#[test]
+fn test_function_calls_tools_and_options_map_to_chat() {
+    let chat = responses_to_chat_request(&parse(json!({
+        "model": "m",
+        "max_output_tokens": 64,
+        "tools": [
+            {"type": "function", "name": "get_weather", "parameters": {"type": "object"}},
+            {"type": "web_search"}
+        ],
+        "tool_choice": {"type": "function", "name": "get_weather"},
+        "text": {"format": {"type": "json_object"}},
+        "input": [
+            {"role": "user", "content": "Weather in Paris?"},
+            {"type": "function_call", "call_id": "call_1", "name": "get_weather", "arguments": "{}"},
+            {"type": "function_call_output", "call_id": "call_1", "output": "18C"},
+            {"type": "reasoning", "summary": []}
+        ]
+    })))
+    .unwrap();
+
+    let value = serde_json::to_value(&chat).unwrap();
+    assert_eq!(value["messages"].as_array().unwrap().len(), 3);
+    assert_eq!(value["messages"][1]["tool_calls"][0]["id"], "call_1");
+    assert_eq!(value["messages"][2]["role"], "tool");
+    assert_eq!(value["messages"][2]["tool_call_id"], "call_1");
+    assert_eq!(value["tools"].as_array().unwrap().len(), 1);
+    assert_eq!(value["tools"][0]["function"]["name"], "get_weather");
+    assert_eq!(value["tool_choice"]["function"]["name"], "get_weather");
+    assert_eq!(value["response_format"]["type"], "json_object");
+    assert_eq!(chat.max_completion_tokens, Some(64));
+}
+
+#[test]
+fn test_previous_response_id_is_rejected() {
+    let err = responses_to_chat_request(&parse(json!({
+        "model": "m",
+        "input": "Continue",
+        "previous_response_id": "resp_123"
+    })))
+    .unwrap_err();
+
+    assert!(matches!(err, GatewayError::InvalidRequest(_)));
+}
+
+#[test]
+fn test_chat_response_maps_output_items_and_status() {
+    let response: CreateChatCompletionResponse = serde_json::from_value(json!({
+        "id": "chatcmpl-abc",
+        "object": "chat.completion",
+        "created": 1,
+        "model": "m",
+        "choices": [{
+            "index": 0,
+            "message": {
+                "role": "assistant",
+                "content": "Checking.",
+                "tool_calls": [{
+                    "id": "call_1",
+                    "type": "function",
+                    "function": {"name": "lookup", "arguments": "{\"q\":1}"}
+                }]
+            },
+            "finish_reason": "length"
+        }],
+        "usage": {"prompt_tokens": 5, "completion_tokens": 7, "total_tokens": 12}
+    }))
+    .unwrap();
+
+    let value = serde_json::to_value(chat_response_to_responses(&response)).unwrap();
+
+    assert_eq!(value["id"], "resp_abc");
+    assert_eq!(value["object"], "response");
+    assert_eq!(value["status"], "incomplete");
+    assert_eq!(value["incomplete_details"]["reason"], "max_output_tokens");
+    assert_eq!(value["output"][0]["type"], "message");
+    assert_eq!(value["output"][0]["content"][0]["type"], "output_text");
+    assert_eq!(value["output"][0]["content"][0]["text"], "Checking.");
+    assert_eq!(value["output"][1]["type"], "function_call");
+    assert_eq!(value["output"][1]["call_id"], "call_1");
+    assert_eq!(value["usage"]["total_tokens"], 12);
+}
@@ -0,0 +1,279 @@
+//! Model aliases and provider fallback chains.
+//!
+//! A routing table read from `REFLEX_ROUTES_FILE` maps aliases to an ordered
+//! list of concrete models:
+//!
+//! ```toml
+//! cache_key = "alias"   # or "model"
+//!
+//! [aliases.fast]
+//! models = ["gpt-4o-mini", "claude-3-5-haiku-latest"]
+//! timeout_secs = 20
+//!
+//! [aliases.smart]
+//! models = ["gpt-4.1", "claude-sonnet-4-20250514"]
+//!
+//! [models."gpt-4.1"]
+//! timeout_secs = 60
+//! semantic_key = "user_turns"
+//! ```
+//!
+//! On a miss the models are tried in order. A model that errors, or does not
+//! answer within the alias's `timeout_secs` (retries included), hands over to
+//! the next one. Model names that are not aliases are called directly, without
+//! fallbacks. A `[models.<name>]` timeout bounds each single call to that
+//! model, whether or not it is reached through an alias.
+//!
+//! `semantic_key` picks the [semantic key strategy](crate::gateway::semantic_key)
+//! for requests naming the alias or model.
+//!
+//! `cache_key` decides which name goes into the cache key. With `alias`
+//! (the default), requests for `fast` share entries no matter which model
+//! answered. With `model`, the alias is replaced by its first model before
+//! hashing, so `fast` and `gpt-4o-mini` share entries. Either way the stored
+//! response's `model` is the concrete model that produced it.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::path::Path;
+use std::time::Duration;
+
+use async_openai::types::chat::CreateChatCompletionRequest;
+use serde::Deserialize;
+use thiserror::Error;
+use tracing::warn;
+
+use crate::gateway::config_file::{self, ConfigFileError};
+use crate::gateway::error::GatewayError;
+use crate::gateway::semantic_key::SemanticKeyStrategy;
+
+/// Environment variable naming the routing table file.
+pub const ROUTES_FILE_ENV: &str = "REFLEX_ROUTES_FILE";
+
+#[derive(Debug, Error)]
+pub enum RoutingError {
+    #[error("routing table: {0}")]
+    File(#[from] ConfigFileError),
+
+    #[error("invalid routing table: {0}")]
+    Invalid(String),
+}
+
+/// Which model name a routed request is cached under.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CacheKeyModel {
+    /// The alias the client asked for.
+    #[default]
+    Alias,
+    /// The first model of the alias's chain.
+    Model,
+}
+
+/// Concrete models to try for one request, in order.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Route {
+    pub models: Vec<String>,
+    /// Per-attempt deadline; `None` waits for the provider.
+    pub timeout: Option<Duration>,
+    /// Semantic key strategy configured for the alias or model.
+    pub semantic_key: Option<SemanticKeyStrategy>,
+}
+
+impl Route {
+    /// A route that calls `model` and nothing else.
+    pub fn direct(model: &str) -> Self {
+        Self {
+            models: vec![model.to_string()],
+            timeout: None,
+            semantic_key: None,
+        }
+    }
+
+    /// The model tried first.
+    pub fn primary(&self) -> &str {
+        self.models.first().map(String::as_str).unwrap_or_default()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct RoutesFile {
+    #[serde(default)]
+    cache_key: CacheKeyModel,
+    #[serde(default)]
+    aliases: HashMap<String, AliasEntry>,
+    #[serde(default)]
+    models: HashMap<String, ModelEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AliasEntry {
+    models: Vec<String>,
+    #[serde(default)]
+    timeout_secs: Option<u64>,
+    #[serde(default)]
+    semantic_key: Option<SemanticKeyStrategy>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ModelEntry {
+    #[serde(default)]
+    timeout_secs: Option<u64>,
+    #[serde(default)]
+    semantic_key: Option<SemanticKeyStrategy>,
+}
+
+/// Alias table; empty unless a routing file is configured.
+#[derive(Debug, Clone, Default)]
+pub struct ModelRouter {
+    aliases: HashMap<String, Route>,
+    cache_key: CacheKeyModel,
+    /// Per-call deadlines by concrete model.
+    model_timeouts: HashMap<String, Duration>,
+    /// Semantic key strategies of models called directly.
+    model_semantic_keys: HashMap<String, SemanticKeyStrategy>,
+}
+
+impl ModelRouter {
+    /// Loads the table named by `REFLEX_ROUTES_FILE`, if set.
+    pub fn from_env() -> Result<Option<Self>, RoutingError> {
+        config_file::path_from_env(ROUTES_FILE_ENV)
+            .map(|path| Self::from_file(&path))
+            .transpose()
+    }
+
+    /// Loads a `.toml` or `.json` routing table.
+    pub fn from_file(path: &Path) -> Result<Self, RoutingError> {
+        Self::from_routes(config_file::load(path)?)
+    }
+
+    /// Parses a TOML routing table.
+    pub fn from_toml_str(contents: &str) -> Result<Self, RoutingError> {
+        let file: RoutesFile =
+            toml::from_str(contents).map_err(|e| RoutingError::Invalid(e.to_string()))?;
+        Self::from_routes(file)
+    }
+
+    fn from_routes(file: RoutesFile) -> Result<Self, RoutingError> {
+        let mut aliases = HashMap::new();
+        for (alias, entry) in file.aliases {
+            let models: Vec<String> = entry
+                .models
+                .iter()
+                .map(|m| m.trim().to_string())
+                .filter(|m| !m.is_empty())
+                .collect();
+            if models.is_empty() {
+                return Err(RoutingError::Invalid(format!(
+                    "alias '{}' has no models",
+                    alias
+                )));
+            }
+            if entry.timeout_secs == Some(0) {
+                return Err(RoutingError::Invalid(format!(
+                    "alias '{}': timeout_secs must be at least 1",
+                    alias
+                )));
+            }
+            aliases.insert(
+                alias,
+                Route {
+                    models,
+                    timeout: entry.timeout_secs.map(Duration::from_secs),
+                    semantic_key: entry.semantic_key,
+                },
+            );
+        }
+
+        let mut model_timeouts = HashMap::new();
+        let mut model_semantic_keys = HashMap::new();
+        for (model, entry) in file.models {
+            if entry.timeout_secs == Some(0) {
+                return Err(RoutingError::Invalid(format!(
+                    "model '{}': timeout_secs must be at least 1",
+                    model
+                )));
+            }
+            if let Some(secs) = entry.timeout_secs {
+                model_timeouts.insert(model.clone(), Duration::from_secs(secs));
+            }
+            if let Some(strategy) = entry.semantic_key {
+                model_semantic_keys.insert(model, strategy);
+            }
+        }
+
+        Ok(Self {
+            aliases,
+            cache_key: file.cache_key,
+            model_timeouts,
+            model_semantic_keys,
+        })
+    }
+
+    pub fn cache_key(&self) -> CacheKeyModel {
+        self.cache_key
+    }
+
+    /// Deadline for a single call to the concrete `model`, if configured.
+    pub fn model_timeout(&self, model: &str) -> Option<Duration> {
+        self.model_timeouts.get(model).copied()
+    }
+
+    /// The route for `model`: its alias chain, or `model` alone.
+    pub fn route(&self, model: &str) -> Route {
+        self.aliases.get(model).cloned().unwrap_or_else(|| Route {
+            semantic_key: self.model_semantic_keys.get(model).copied(),
+            ..Route::direct(model)
+        })
+    }
+
+    /// Routes `request`, naming it by its first concrete model when the
+    /// table is keyed by model.
+    pub fn apply(&self, request: &mut CreateChatCompletionRequest) -> Route {
+        let route = self.route(&request.model);
+        if self.cache_key == CacheKeyModel::Model {
+            request.model = route.primary().to_string();
+        }
+        route
+    }
+}
+
+/// Runs `call` for each model of `route` until one succeeds.
+///
+/// Returns the model that answered along with its result, or the last error.
+pub(crate) async fn with_fallbacks<T, F, Fut>(
+    route: &Route,
+    mut call: F,
+) -> Result<(String, T), GatewayError>
+where
+    F: FnMut(String) -> Fut,
+    Fut: Future<Output = Result<T, GatewayError>>,
+{
+    let mut last_error = None;
+    for (attempt, model) in route.models.iter().enumerate() {
+        let result = match route.timeout {
+            Some(timeout) => tokio::time::timeout(timeout, call(model.clone()))
+                .await
+                .unwrap_or_else(|_| {
+                    Err(GatewayError::ProviderError(format!(
+                        "{} did not answer within {:?}",
+                        model, timeout
+                    )))
+                }),
+            None => call(model.clone()).await,
+        };
+
+        match result {
+            Ok(value) => return Ok((model.clone(), value)),
+            Err(e) => {
+                if let Some(next) = route.models.get(attempt + 1) {
+                    warn!(model = %model, next = %next, error = %e, "Provider failed, falling back");
+                }
+                last_error = Some(e);
+            }
+        }
+    }
+
+    Err(last_error
+        .unwrap_or_else(|| GatewayError::InternalError("route has no models".to_string())))
+}
@@ -0,0 +1,139 @@
+//! Tests for model aliases and fallback chains.
+
+use std::sync::Mutex;
+use std::time::Duration;
+
+use async_openai::types::chat::CreateChatCompletionRequest;
+
+use crate::gateway::error::GatewayError;
+use crate::gateway::routing::{CacheKeyModel, ModelRouter, Route, RoutingError, with_fallbacks};
+
+const ROUTES: &str = r#"
+cache_key = "model"
+
+[aliases.fast]
+models = ["gpt-4o-mini", "claude-3-5-haiku-latest"]
+timeout_secs = 20
+
+[models."gpt-4o-mini"]
+timeout_secs = 5
+"#;
+
+fn request(model: &str) -> CreateChatCompletionRequest {
+    serde_json::from_value(serde_json::json!({
+        "model": model,
+        "messages": [{"role": "user", "content": "hi"}]
+    }))
+    .unwrap()
+}
+
+fn route(models: &[&str]) -> Route {
+    Route {
+        models: models.iter().map(|m| m.to_string()).collect(),
+        timeout: None,
+        semantic_key: None,
+    }
+}
+
+#[test]
+fn test_router_resolves_aliases_and_passes_other_models_through() {
+    let router = ModelRouter::from_toml_str(ROUTES).unwrap();
+    assert_eq!(router.cache_key(), CacheKeyModel::Model);
+
+    let fast = router.route("fast");
+    assert_eq!(fast.models, vec!["gpt-4o-mini", "claude-3-5-haiku-latest"]);
+    assert_eq!(fast.timeout, Some(Duration::from_secs(20)));
+
+    assert_eq!(router.route("gpt-4o"), Route::direct("gpt-4o"));
+    assert_eq!(
+        router.model_timeout("gpt-4o-mini"),
+        Some(Duration::from_secs(5))
+    );
+    assert_eq!(router.model_timeout("gpt-4o"), None);
+}
+
+#[test]
+fn test_apply_rewrites_model_only_when_keyed_by_model() {
+    let by_model = ModelRouter::from_toml_str(ROUTES).unwrap();
+    let mut req = request("fast");
+    by_model.apply(&mut req);
+    assert_eq!(req.model, "gpt-4o-mini");
+
+    let by_alias =
+        ModelRouter::from_toml_str("[aliases.fast]\nmodels = [\"gpt-4o-mini\"]\n").unwrap();
+    assert_eq!(by_alias.cache_key(), CacheKeyModel::Alias);
+    let mut req = request("fast");
+    let route = by_alias.apply(&mut req);
+    assert_eq!(req.model, "fast");
+    assert_eq!(route.primary(), "gpt-4o-mini");
+}
+
+#[test]
+fn test_router_rejects_invalid_tables() {
+    for table in [
+        "[aliases.fast]\nmodels = []\n",
+        "[aliases.fast]\nmodels = [\"a\"]\ntimeout_secs = 0\n",
+        "cache_key = \"provider\"\n",
+        "[models.a]\ntimeout_secs = 0\n",
+    ] {
+        assert!(
+            matches!(
+                ModelRouter::from_toml_str(table),
+                Err(RoutingError::Invalid(_))
+            ),
+            "accepted: {}",
+            table
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_with_fallbacks_moves_to_next_model_on_error() {
+    let attempts = Mutex::new(Vec::new());
+    let (model, value) = with_fallbacks(&route(&["a", "b", "c"]), |model| {
+        attempts.lock().unwrap().push(model.clone());
+        async move {
+            if model == "a" {
+                Err(GatewayError::ProviderError("down".to_string()))
+            } else {
+                Ok(model.len())
+            }
+        }
+    })
+    .await
+    .unwrap();
+
+    assert_eq!(model, "b");
+    assert_eq!(value, 1);
+    assert_eq!(*attempts.lock().unwrap(), vec!["a", "b"]);
+}
+
+#[tokio::test]
+async fn test_with_fallbacks_times_out_slow_models() {
+    let mut chain = route(&["slow", "quick"]);
+    chain.timeout = Some(Duration::from_millis(50));
+
+    let (model, _) = with_fallbacks(&chain, |model| async move {
+        if model == "slow" {
+            tokio::time::sleep(Duration::from_secs(5)).await;
+        }
+        Ok::<_, GatewayError>(())
+    })
+    .await
+    .unwrap();
+
+    assert_eq!(model, "quick");
+}
+
+#[tokio::test]
+async fn test_with_fallbacks_returns_last_error_when_all_fail() {
+    let result: Result<(String, ()), _> = with_fallbacks(&route(&["a", "b"]), |model| async move {
+        Err(GatewayError::ProviderError(format!("{} down", model)))
+    })
+    .await;
+
+    match result {
+        Err(GatewayError::ProviderError(message)) => assert_eq!(message, "b down"),
+        other => panic!("expected ProviderError, got {:?}", other),
+    }
+}
@@ -0,0 +1,172 @@
+//! Cache policy by sampling settings.
+//!
+//! By default every request is cached the same way: one stored answer,
+//! served to exact repeats and to semantically close requests alike. A
+//! policy read from `REFLEX_SAMPLING_FILE` can treat requests differently
+//! depending on how they sample:
+//!
+//! ```toml
+//! # temperature 0 or a seed: serve exact repeats only, never a neighbour's answer
+//! deterministic_exact_only = true
+//!
+//! # temperature at or above 0.8: keep up to 3 answers per entry
+//! max_variants = 3
+//! variants_min_temperature = 0.8
+//!
+//! [models."gpt-4o-mini"]
+//! deterministic_exact_only = false
+//! max_variants = 1
+//! ```
+//!
+//! With `max_variants` above one, an exact repeat of a high-temperature
+//! request is sent to the provider until its entry holds that many answers;
+//! each new answer is added to the entry. From then on, hits rotate through
+//! the stored answers. `[models.<name>]` entries override either setting for
+//! one model (as named in the request), e.g. to turn a policy off.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use async_openai::types::chat::CreateChatCompletionRequest;
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::gateway::config_file::{self, ConfigFileError};
+
+/// Environment variable naming the sampling policy file.
+pub const SAMPLING_FILE_ENV: &str = "REFLEX_SAMPLING_FILE";
+
+const DEFAULT_VARIANTS_MIN_TEMPERATURE: f32 = 0.7;
+
+#[derive(Debug, Error)]
+pub enum SamplingPolicyError {
+    #[error("sampling policy: {0}")]
+    File(#[from] ConfigFileError),
+
+    #[error("invalid sampling policy: {0}")]
+    Invalid(String),
+}
+
+/// How one request is cached.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SamplingMode {
+    /// One answer, served to exact and semantic hits.
+    #[default]
+    Shared,
+    /// Only exact repeats hit.
+    ExactOnly,
+    /// Up to this many answers per entry, served in rotation.
+    Variants(usize),
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct SamplingFile {
+    #[serde(flatten)]
+    defaults: PolicyEntry,
+    #[serde(default)]
+    variants_min_temperature: Option<f32>,
+    #[serde(default)]
+    models: HashMap<String, PolicyEntry>,
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+struct PolicyEntry {
+    #[serde(default)]
+    deterministic_exact_only: Option<bool>,
+    #[serde(default)]
+    max_variants: Option<usize>,
+}
+
+/// Sampling policy; caches every request the same way unless configured.
+#[derive(Debug, Clone, Default)]
+pub struct SamplingPolicy {
+    deterministic_exact_only: bool,
+    max_variants: usize,
+    variants_min_temperature: f32,
+    models: HashMap<String, PolicyEntry>,
+    /// Rotates the answer served from multi-answer entries.
+    next_variant: Arc<AtomicUsize>,
+}
+
+impl SamplingPolicy {
+    /// Loads the policy named by `REFLEX_SAMPLING_FILE`, if set.
+    pub fn from_env() -> Result<Option<Self>, SamplingPolicyError> {
+        config_file::path_from_env(SAMPLING_FILE_ENV)
+            .map(|path| Self::from_file(&path))
+            .transpose()
+    }
+
+    /// Loads a `.toml` or `.json` policy.
+    pub fn from_file(path: &Path) -> Result<Self, SamplingPolicyError> {
+        Self::from_policy(config_file::load(path)?)
+    }
+
+    /// Parses a TOML policy.
+    pub fn from_toml_str(contents: &str) -> Result<Self, SamplingPolicyError> {
+        let file: SamplingFile =
+            toml::from_str(contents).map_err(|e| SamplingPolicyError::Invalid(e.to_string()))?;
+        Self::from_policy(file)
+    }
+
+    fn from_policy(file: SamplingFile) -> Result<Self, SamplingPolicyError> {
+        let entries = std::iter::once(("default", &file.defaults))
+            .chain(file.models.iter().map(|(m, e)| (m.as_str(), e)));
+        for (name, entry) in entries {
+            if entry.max_variants == Some(0) {
+                return Err(SamplingPolicyError::Invalid(format!(
+                    "{}: max_variants must be at least 1",
+                    name
+                )));
+            }
+        }
+        let variants_min_temperature = file
+            .variants_min_temperature
+            .unwrap_or(DEFAULT_VARIANTS_MIN_TEMPERATURE);
+        if !variants_min_temperature.is_finite() || variants_min_temperature < 0.0 {
+            return Err(SamplingPolicyError::Invalid(
+                "variants_min_temperature must be a non-negative number".to_string(),
+            ));
+        }
+
+        Ok(Self {
+            deterministic_exact_only: file.defaults.deterministic_exact_only.unwrap_or(false),
+            max_variants: file.defaults.max_variants.unwrap_or(1),
+            variants_min_temperature,
+            models: file.models,
+            next_variant: Arc::default(),
+        })
+    }
+
+    /// How `request` is cached under this policy.
+    pub fn mode(&self, request: &CreateChatCompletionRequest) -> SamplingMode {
+        let model = self.models.get(&request.model).copied().unwrap_or_default();
+        let exact_only = model
+            .deterministic_exact_only
+            .unwrap_or(self.deterministic_exact_only);
+        let max_variants = model.max_variants.unwrap_or(self.max_variants);
+
+        // Deprecated upstream, but clients still send it to pin their samples.
+        #[allow(deprecated)]
+        let seeded = request.seed.is_some();
+        let deterministic = request.temperature == Some(0.0) || seeded;
+        if deterministic {
+            return match exact_only {
+                true => SamplingMode::ExactOnly,
+                false => SamplingMode::Shared,
+            };
+        }
+        match request.temperature {
+            Some(t) if t >= self.variants_min_temperature && max_variants > 1 => {
+                SamplingMode::Variants(max_variants)
+            }
+            _ => SamplingMode::Shared,
+        }
+    }
+
+    /// Index of the answer to serve from an entry holding `count` answers.
+    pub fn pick_variant(&self, count: usize) -> usize {
+        self.next_variant.fetch_add(1, Ordering::Relaxed) % count.max(1)
+    }
+}
@@ -0,0 +1,149 @@
+//! Tests for the sampling policy and multi-answer payloads.
+
+use async_openai::types::chat::{CreateChatCompletionRequest, CreateChatCompletionResponse};
+use serde_json::json;
+
+use crate::gateway::payload::CachePayload;
+use crate::gateway::sampling::{SamplingMode, SamplingPolicy, SamplingPolicyError};
+
+const POLICY: &str = r#"
+deterministic_exact_only = true
+max_variants = 3
+variants_min_temperature = 0.8
+
+[models."gpt-4o-mini"]
+deterministic_exact_only = false
+max_variants = 1
+"#;
+
+fn request(
+    model: &str,
+    temperature: Option<f32>,
+    seed: Option<i64>,
+) -> CreateChatCompletionRequest {
+    serde_json::from_value(json!({
+        "model": model,
+        "messages": [{"role": "user", "content": "hi"}],
+        "temperature": temperature,
+        "seed": seed,
+    }))
+    .unwrap()
+}
+
+fn response(id: &str) -> CreateChatCompletionResponse {
+    serde_json::from_value(json!({
+        "id": id,
+        "object": "chat.completion",
+        "created": 0,
+        "model": "gpt-4o",
+        "choices": [],
+    }))
+    .unwrap()
+}
+
+fn ids(payload: &CachePayload) -> Vec<String> {
+    std::iter::once(&payload.response)
+        .chain(&payload.variants)
+        .map(|r| r.id.clone())
+        .collect()
+}
+
+#[test]
+fn test_default_policy_shares_every_request() {
+    let policy = SamplingPolicy::default();
+    assert_eq!(
+        policy.mode(&request("gpt-4o", Some(0.0), None)),
+        SamplingMode::Shared
+    );
+    assert_eq!(
+        policy.mode(&request("gpt-4o", Some(1.5), None)),
+        SamplingMode::Shared
+    );
+}
+
+#[test]
+fn test_policy_picks_mode_from_temperature_and_seed() {
+    let policy = SamplingPolicy::from_toml_str(POLICY).unwrap();
+
+    assert_eq!(
+        policy.mode(&request("gpt-4o", Some(0.0), None)),
+        SamplingMode::ExactOnly
+    );
+    assert_eq!(
+        policy.mode(&request("gpt-4o", Some(1.0), Some(7))),
+        SamplingMode::ExactOnly
+    );
+    assert_eq!(
+        policy.mode(&request("gpt-4o", Some(0.8), None)),
+        SamplingMode::Variants(3)
+    );
+    assert_eq!(
+        policy.mode(&request("gpt-4o", Some(0.5), None)),
+        SamplingMode::Shared
+    );
+    assert_eq!(
+        policy.mode(&request("gpt-4o", None, None)),
+        SamplingMode::Shared
+    );
+}
+
+#[test]
+fn test_model_entries_turn_policies_off() {
+    let policy = SamplingPolicy::from_toml_str(POLICY).unwrap();
+    assert_eq!(
+        policy.mode(&request("gpt-4o-mini", Some(0.0), None)),
+        SamplingMode::Shared
+    );
+    assert_eq!(
+        policy.mode(&request("gpt-4o-mini", Some(1.0), None)),
+        SamplingMode::Shared
+    );
+}
+
+#[test]
+fn test_invalid_policies_are_rejected() {
+    for policy in [
+        "max_variants = 0",
+        "variants_min_temperature = -1.0",
+        "[models.m]\nmax_variants = 0",
+    ] {
+        assert!(
+            matches!(
+                SamplingPolicy::from_toml_str(policy),
+                Err(SamplingPolicyError::Invalid(_))
+            ),
+            "{policy}"
+        );
+    }
+}
+
+#[test]
+fn test_pick_variant_rotates() {
+    let policy = SamplingPolicy::default();
+    let picks: Vec<_> = (0..4).map(|_| policy.pick_variant(3)).collect();
+    assert_eq!(picks, vec![0, 1, 2, 0]);
+    assert_eq!(policy.pick_variant(1), 0);
+}
+
+#[test]
+fn test_payload_keeps_the_newest_answers() {
+    let mut payload = CachePayload {
+        semantic_request: "q".to_string(),
+        response: response("a"),
+        media_key: None,
+        scope_key: None,
+        variants: Vec::new(),
+    };
+    payload.add_variant(response("b"), 3);
+    payload.add_variant(response("c"), 3);
+    assert_eq!(ids(&payload), vec!["a", "b", "c"]);
+
+    payload.add_variant(response("d"), 3);
+    assert_eq!(ids(&payload), vec!["b", "c", "d"]);
+
+    payload.select(2);
+    assert_eq!(payload.response.id, "d");
+    assert_eq!(payload.answer_count(), 3);
+    payload.select(7);
+    assert_eq!(payload.response.id, "d");
+}
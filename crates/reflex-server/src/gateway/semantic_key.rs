@@ -0,0 +1,202 @@
+//! Semantic keys: the text the semantic tiers work on for a request.
+//!
+//! The text an extractor produces is embedded for the L2 search, compared by
+//! the L3 reranker and stored with the entry. The built-in strategies are:
+//!
+//! - `full` (default): model, messages, tools, `tool_choice` and
+//!   `response_format` as JSON;
+//! - `last_user_turn`: the text of the last user message;
+//! - `user_turns`: the text of every user message, one per line;
+//! - `conversation`: user, assistant and tool messages as `role: text`
+//!   lines, without the system prompt.
+//!
+//! The narrower strategies leave part of the request out of the text. That
+//! part is hashed into a scope key. Its hash is indexed with the entry's L2
+//! point and the L2 search is filtered on it, so entries of another scope
+//! never take up candidate slots; the key is also stored with the entry and
+//! checked again before verification. The scope always covers the model,
+//! tools, `tool_choice` and `response_format`. `conversation` adds the system
+//! and developer messages, so a long system prompt separates entries without
+//! crowding the embedding. `last_user_turn` adds every message before the
+//! last user turn, so the same follow-up question in another conversation is
+//! not a match.
+//!
+//! The scope is kept out of `context_hash`, which stays the hash of the
+//! whole request: it names the entry's storage key and point id.
+//!
+//! A strategy is chosen per alias or model in the routes file, or per tenant
+//! in the tenants file (`semantic_key = "user_turns"`). The route's choice
+//! wins. Requests with neither use the server's default extractor.
+
+use async_openai::types::chat::CreateChatCompletionRequest;
+use serde::Deserialize;
+use serde_json::{Value, json};
+
+use crate::gateway::handler::semantic_text_from_request;
+
+/// Turns a request into its semantic text and scope.
+pub trait SemanticKeyExtractor: Send + Sync + std::fmt::Debug {
+    /// Text that is embedded, verified and stored for `request`.
+    fn semantic_text(&self, request: &CreateChatCompletionRequest) -> String;
+
+    /// Hash of the request parts left out of the text, which a semantic hit
+    /// must share. `None` when the text covers the whole request.
+    fn scope(&self, _request: &CreateChatCompletionRequest) -> Option<String> {
+        None
+    }
+}
+
+/// A built-in extractor, as named in configuration files.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SemanticKeyStrategy {
+    #[default]
+    Full,
+    LastUserTurn,
+    UserTurns,
+    Conversation,
+}
+
+impl SemanticKeyStrategy {
+    pub fn extractor(self) -> &'static dyn SemanticKeyExtractor {
+        match self {
+            Self::Full => &FullRequest,
+            Self::LastUserTurn => &LastUserTurn,
+            Self::UserTurns => &UserTurns,
+            Self::Conversation => &Conversation,
+        }
+    }
+}
+
+/// The whole request as JSON.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FullRequest;
+
+impl SemanticKeyExtractor for FullRequest {
+    fn semantic_text(&self, request: &CreateChatCompletionRequest) -> String {
+        semantic_text_from_request(request)
+    }
+}
+
+/// The last user message.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LastUserTurn;
+
+impl SemanticKeyExtractor for LastUserTurn {
+    fn semantic_text(&self, request: &CreateChatCompletionRequest) -> String {
+        messages(request)
+            .iter()
+            .rev()
+            .find(|m| role(m) == "user")
+            .map(message_text)
+            .unwrap_or_default()
+    }
+
+    fn scope(&self, request: &CreateChatCompletionRequest) -> Option<String> {
+        let mut history = messages(request);
+        let last_user = history.iter().rposition(|m| role(m) == "user");
+        history.truncate(last_user.unwrap_or(history.len()));
+        Some(scope_hash(request, Some(history)))
+    }
+}
+
+/// Every user message, one per line.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct UserTurns;
+
+impl SemanticKeyExtractor for UserTurns {
+    fn semantic_text(&self, request: &CreateChatCompletionRequest) -> String {
+        messages(request)
+            .iter()
+            .filter(|m| role(m) == "user")
+            .map(message_text)
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn scope(&self, request: &CreateChatCompletionRequest) -> Option<String> {
+        Some(scope_hash(request, None))
+    }
+}
+
+/// The conversation without its system prompt, which is scoped instead.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Conversation;
+
+impl SemanticKeyExtractor for Conversation {
+    fn semantic_text(&self, request: &CreateChatCompletionRequest) -> String {
+        messages(request)
+            .iter()
+            .filter(|m| !is_system(m))
+            .map(|m| format!("{}: {}", role(m), message_text(m)))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn scope(&self, request: &CreateChatCompletionRequest) -> Option<String> {
+        let system = messages(request).into_iter().filter(is_system).collect();
+        Some(scope_hash(request, Some(system)))
+    }
+}
+
+fn messages(request: &CreateChatCompletionRequest) -> Vec<Value> {
+    match serde_json::to_value(&request.messages) {
+        Ok(Value::Array(messages)) => messages,
+        _ => Vec::new(),
+    }
+}
+
+fn role(message: &Value) -> &str {
+    message
+        .get("role")
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+}
+
+fn is_system(message: &Value) -> bool {
+    matches!(role(message), "system" | "developer")
+}
+
+/// Text parts of a message, plus an assistant's tool calls.
+fn message_text(message: &Value) -> String {
+    let mut parts: Vec<String> = match message.get("content") {
+        Some(Value::String(text)) => vec![text.clone()],
+        Some(Value::Array(content)) => content
+            .iter()
+            .filter_map(|part| part.get("text").and_then(Value::as_str))
+            .map(str::to_string)
+            .collect(),
+        _ => Vec::new(),
+    };
+    for call in message
+        .get("tool_calls")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+    {
+        if let Some(function) = call.get("function") {
+            parts.push(format!(
+                "{}({})",
+                function["name"].as_str().unwrap_or_default(),
+                function["arguments"].as_str().unwrap_or_default()
+            ));
+        }
+    }
+    parts.join("\n")
+}
+
+/// Hash of the request settings and the `scoped` messages.
+fn scope_hash(request: &CreateChatCompletionRequest, scoped: Option<Vec<Value>>) -> String {
+    let mut scope = json!({
+        "model": request.model,
+        "tools": request.tools,
+        "tool_choice": request.tool_choice,
+        "response_format": request.response_format,
+    });
+    if let Some(scoped) = scoped {
+        scope["messages"] = Value::Array(scoped);
+    }
+    blake3::hash(scope.to_string().as_bytes())
+        .to_hex()
+        .to_string()
+}
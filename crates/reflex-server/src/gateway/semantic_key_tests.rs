@@ -0,0 +1,144 @@
+//! Tests for semantic key strategies.
+
+use async_openai::types::chat::CreateChatCompletionRequest;
+use serde_json::json;
+
+use crate::gateway::handler::semantic_text_from_request;
+use crate::gateway::routing::ModelRouter;
+use crate::gateway::semantic_key::SemanticKeyStrategy;
+
+fn conversation(system: &str, last_question: &str) -> CreateChatCompletionRequest {
+    serde_json::from_value(json!({
+        "model": "gpt-4o",
+        "messages": [
+            {"role": "system", "content": system},
+            {"role": "user", "content": "What is Rust?"},
+            {"role": "assistant", "content": "A systems language.", "tool_calls": [{
+                "id": "call_1",
+                "type": "function",
+                "function": {"name": "search", "arguments": "{\"q\":\"rust\"}"}
+            }]},
+            {"role": "tool", "tool_call_id": "call_1", "content": "rust-lang.org"},
+            {"role": "user", "content": [{"type": "text", "text": last_question}]}
+        ]
+    }))
+    .unwrap()
+}
+
+fn text(strategy: SemanticKeyStrategy, request: &CreateChatCompletionRequest) -> String {
+    strategy.extractor().semantic_text(request)
+}
+
+#[test]
+fn test_full_strategy_is_the_request_json() {
+    let request = conversation("Be brief.", "Who made it?");
+    let full = SemanticKeyStrategy::Full.extractor();
+    assert_eq!(
+        full.semantic_text(&request),
+        semantic_text_from_request(&request)
+    );
+    assert_eq!(full.scope(&request), None);
+}
+
+#[test]
+fn test_turn_strategies_extract_message_text() {
+    let request = conversation("Be brief.", "Who made it?");
+
+    assert_eq!(
+        text(SemanticKeyStrategy::LastUserTurn, &request),
+        "Who made it?"
+    );
+    assert_eq!(
+        text(SemanticKeyStrategy::UserTurns, &request),
+        "What is Rust?\nWho made it?"
+    );
+    assert_eq!(
+        text(SemanticKeyStrategy::Conversation, &request),
+        "user: What is Rust?\n\
+         assistant: A systems language.\nsearch({\"q\":\"rust\"})\n\
+         tool: rust-lang.org\n\
+         user: Who made it?"
+    );
+}
+
+#[test]
+fn test_system_prompt_scopes_all_but_the_user_turns_strategy() {
+    let brief = conversation("Be brief.", "Who made it?");
+    let verbose = conversation("Answer at length.", "Who made it?");
+
+    let user_turns = SemanticKeyStrategy::UserTurns.extractor();
+    assert!(user_turns.scope(&brief).is_some());
+    assert_eq!(user_turns.scope(&brief), user_turns.scope(&verbose));
+
+    let last_turn = SemanticKeyStrategy::LastUserTurn.extractor();
+    assert_ne!(last_turn.scope(&brief), last_turn.scope(&verbose));
+
+    let conversation = SemanticKeyStrategy::Conversation.extractor();
+    assert_eq!(
+        conversation.semantic_text(&brief),
+        conversation.semantic_text(&verbose)
+    );
+    assert_ne!(conversation.scope(&brief), conversation.scope(&verbose));
+}
+
+#[test]
+fn test_last_user_turn_is_scoped_by_the_conversation_before_it() {
+    let extractor = SemanticKeyStrategy::LastUserTurn.extractor();
+    let rust = conversation("Be brief.", "Who made it?");
+    let mut python = rust.clone();
+    python.messages[1] = serde_json::from_value(json!({
+        "role": "user",
+        "content": "What is Python?"
+    }))
+    .unwrap();
+
+    assert_eq!(
+        text(SemanticKeyStrategy::LastUserTurn, &python),
+        "Who made it?"
+    );
+    assert_ne!(extractor.scope(&rust), extractor.scope(&python));
+
+    // Only the earlier turns scope it, not the question itself.
+    assert_eq!(
+        extractor.scope(&rust),
+        extractor.scope(&conversation("Be brief.", "When was it released?"))
+    );
+}
+
+#[test]
+fn test_scope_covers_the_model() {
+    let mut other_model = conversation("Be brief.", "Who made it?");
+    other_model.model = "gpt-4o-mini".to_string();
+    let extractor = SemanticKeyStrategy::LastUserTurn.extractor();
+    assert_ne!(
+        extractor.scope(&conversation("Be brief.", "Who made it?")),
+        extractor.scope(&other_model)
+    );
+}
+
+#[test]
+fn test_routes_select_strategies_per_alias_and_model() {
+    let router = ModelRouter::from_toml_str(
+        r#"
+[aliases.chat]
+models = ["gpt-4o"]
+semantic_key = "conversation"
+
+[models."gpt-4o-mini"]
+semantic_key = "last_user_turn"
+"#,
+    )
+    .unwrap();
+
+    assert_eq!(
+        router.route("chat").semantic_key,
+        Some(SemanticKeyStrategy::Conversation)
+    );
+    assert_eq!(
+        router.route("gpt-4o-mini").semantic_key,
+        Some(SemanticKeyStrategy::LastUserTurn)
+    );
+    assert_eq!(router.route("gpt-4o-mini").models, vec!["gpt-4o-mini"]);
+    assert_eq!(router.model_timeout("gpt-4o-mini"), None);
+    assert_eq!(router.route("gpt-4o").semantic_key, None);
+}
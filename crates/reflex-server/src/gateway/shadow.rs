@@ -0,0 +1,314 @@
+//! Shadow verification: measures how often semantic hits are right.
+//!
+//! With `REFLEX_SHADOW_SAMPLE_RATE` above zero, that fraction of L3-verified
+//! hits is still sent to the provider in the background after the cached
+//! answer has been served. The fresh answer is compared with the cached one
+//! by the cosine similarity of their embeddings and, with
+//! `REFLEX_SHADOW_RERANKER=true`, by the cross-encoder as well. The answers
+//! agree when the similarity reaches `REFLEX_SHADOW_MIN_SIMILARITY` and the
+//! cross-encoder score, if any, exceeds the L3 threshold.
+//!
+//! Outcomes are counted per tenant and model (see
+//! [`telemetry`](crate::gateway::telemetry)). With
+//! `REFLEX_SHADOW_INVALIDATE_BELOW` set, an entry whose answer is less similar
+//! than that to the fresh one is invalidated.
+//!
+//! Shadow calls use the request's provider keys and route, spend the tenant's
+//! miss budget like any provider call and are never stored. A sampled hit is
+//! skipped when that budget is exhausted or when `REFLEX_SHADOW_MAX_IN_FLIGHT`
+//! checks are already running. Exact hits, degraded hits and entries that
+//! collect several answers (see [`sampling`](crate::gateway::sampling)) are
+//! not sampled.
+
+use async_openai::types::chat::{
+    ChatCompletionMessageToolCalls, CreateChatCompletionRequest, CreateChatCompletionResponse,
+};
+use thiserror::Error;
+use tracing::{debug, info, warn};
+
+use crate::gateway::adapter::requested_choices;
+use crate::gateway::admin::invalidate_storage_key;
+use crate::gateway::error::GatewayError;
+use crate::gateway::handler::{CacheOutcome, StoreContext, call_provider, l3_threshold};
+use crate::gateway::rate_limit::Budget;
+use crate::gateway::sampling::SamplingMode;
+use crate::gateway::state::HandlerState;
+use crate::gateway::telemetry;
+use reflex::cache::{BqSearchBackend, ReflexStatus, StorageLoader};
+use reflex::storage::StorageWriter;
+use reflex::vectordb::rescoring::cosine_similarity_f16;
+
+pub const SHADOW_SAMPLE_RATE_ENV: &str = "REFLEX_SHADOW_SAMPLE_RATE";
+pub const SHADOW_MIN_SIMILARITY_ENV: &str = "REFLEX_SHADOW_MIN_SIMILARITY";
+pub const SHADOW_RERANKER_ENV: &str = "REFLEX_SHADOW_RERANKER";
+pub const SHADOW_INVALIDATE_BELOW_ENV: &str = "REFLEX_SHADOW_INVALIDATE_BELOW";
+pub const SHADOW_MAX_IN_FLIGHT_ENV: &str = "REFLEX_SHADOW_MAX_IN_FLIGHT";
+
+#[derive(Debug, Error)]
+#[error("invalid {name} '{value}': {message}")]
+pub struct ShadowConfigError {
+    pub name: &'static str,
+    pub value: String,
+    pub message: String,
+}
+
+/// Which semantic hits are shadowed and how their answers are judged.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShadowConfig {
+    /// Fraction of semantic hits checked, from 0.0 (off) to 1.0.
+    pub sample_rate: f64,
+    /// Cosine similarity at which the two answers agree.
+    pub min_similarity: f32,
+    /// Also require the cross-encoder to verify the pair.
+    pub reranker: bool,
+    /// Entries less similar than this to the fresh answer are invalidated.
+    pub invalidate_below: Option<f32>,
+    /// Checks running at once; further samples are dropped.
+    pub max_in_flight: usize,
+}
+
+impl Default for ShadowConfig {
+    fn default() -> Self {
+        Self {
+            sample_rate: 0.0,
+            min_similarity: 0.85,
+            reranker: false,
+            invalidate_below: None,
+            max_in_flight: 16,
+        }
+    }
+}
+
+impl ShadowConfig {
+    /// Reads the `REFLEX_SHADOW_*` variables over the defaults.
+    pub fn from_env() -> Result<Self, ShadowConfigError> {
+        let mut config = Self::default();
+        if let Some(value) = env_var(SHADOW_SAMPLE_RATE_ENV) {
+            config.sample_rate = unit_interval(SHADOW_SAMPLE_RATE_ENV, value)?;
+        }
+        if let Some(value) = env_var(SHADOW_MIN_SIMILARITY_ENV) {
+            config.min_similarity = unit_interval(SHADOW_MIN_SIMILARITY_ENV, value)? as f32;
+        }
+        if let Some(value) = env_var(SHADOW_RERANKER_ENV) {
+            config.reranker = match value.to_ascii_lowercase().as_str() {
+                "1" | "true" | "yes" | "on" => true,
+                "0" | "false" | "no" | "off" => false,
+                _ => {
+                    return Err(ShadowConfigError {
+                        name: SHADOW_RERANKER_ENV,
+                        value,
+                        message: "expected true or false".to_string(),
+                    });
+                }
+            };
+        }
+        if let Some(value) = env_var(SHADOW_INVALIDATE_BELOW_ENV) {
+            config.invalidate_below =
+                Some(unit_interval(SHADOW_INVALIDATE_BELOW_ENV, value)? as f32);
+        }
+        if let Some(value) = env_var(SHADOW_MAX_IN_FLIGHT_ENV) {
+            config.max_in_flight = match value.parse::<usize>() {
+                Ok(parsed) if parsed > 0 => parsed,
+                _ => {
+                    return Err(ShadowConfigError {
+                        name: SHADOW_MAX_IN_FLIGHT_ENV,
+                        value,
+                        message: "expected a positive integer".to_string(),
+                    });
+                }
+            };
+        }
+        Ok(config)
+    }
+
+    /// Whether a semantic hit served now should be shadowed.
+    pub fn sample(&self) -> bool {
+        self.sample_rate > 0.0 && fastrand::f64() < self.sample_rate
+    }
+
+    /// Judges a cached answer against a fresh one.
+    ///
+    /// `rerank_score` is compared with `l3_threshold` when present.
+    pub fn judge(
+        &self,
+        similarity: f32,
+        rerank_score: Option<f32>,
+        l3_threshold: f32,
+    ) -> ShadowVerdict {
+        ShadowVerdict {
+            similarity,
+            rerank_score,
+            agrees: similarity >= self.min_similarity
+                && rerank_score.is_none_or(|score| score > l3_threshold),
+            invalidate: self
+                .invalidate_below
+                .is_some_and(|below| similarity < below),
+        }
+    }
+}
+
+fn env_var(name: &str) -> Option<String> {
+    std::env::var(name)
+        .ok()
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
+fn unit_interval(name: &'static str, value: String) -> Result<f64, ShadowConfigError> {
+    match value.parse::<f64>() {
+        Ok(parsed) if (0.0..=1.0).contains(&parsed) => Ok(parsed),
+        Ok(_) => Err(ShadowConfigError {
+            name,
+            value,
+            message: "must be between 0.0 and 1.0".to_string(),
+        }),
+        Err(e) => Err(ShadowConfigError {
+            name,
+            message: e.to_string(),
+            value,
+        }),
+    }
+}
+
+/// How a cached answer compared with a fresh one.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ShadowVerdict {
+    /// Cosine similarity of the two answers' embeddings.
+    pub similarity: f32,
+    /// Cross-encoder score of the pair, when the reranker is used.
+    pub rerank_score: Option<f32>,
+    pub agrees: bool,
+    /// The disagreement is strong enough to drop the entry.
+    pub invalidate: bool,
+}
+
+/// Starts a background shadow check of `hit` when it is sampled.
+///
+/// The check spends the request's miss tokens up front and holds one of the
+/// `max_in_flight` slots until it finishes; without either it is skipped.
+pub(crate) fn spawn_shadow_check<B, S>(
+    state: &HandlerState<B, S>,
+    request: &CreateChatCompletionRequest,
+    ctx: &StoreContext,
+    hit: &CacheOutcome,
+) where
+    B: BqSearchBackend + Clone + Send + Sync + 'static,
+    S: StorageLoader + StorageWriter + Clone + Send + Sync + 'static,
+{
+    if hit.status != ReflexStatus::HitL3Verified
+        || matches!(ctx.sampling, SamplingMode::Variants(_))
+        || !state.shadow.sample()
+    {
+        return;
+    }
+    let Some(storage_key) = hit.storage_key.clone() else {
+        return;
+    };
+    let Ok(permit) = state.shadow_slots.clone().try_acquire_owned() else {
+        debug!(storage_key = %storage_key, "Shadow check dropped: too many in flight");
+        telemetry::record_shadow_skipped(ctx, "dropped");
+        return;
+    };
+    if let Err(e) = state.rate_limiter.acquire_n(
+        Budget::Misses,
+        ctx.tenant_id,
+        &ctx.policy,
+        u32::from(requested_choices(request)),
+    ) {
+        debug!(storage_key = %storage_key, error = %e, "Shadow check skipped");
+        telemetry::record_shadow_skipped(ctx, "rate_limited");
+        return;
+    }
+
+    let state = state.clone();
+    let request = request.clone();
+    let ctx = ctx.clone();
+    let cached = hit.payload.response.clone();
+    tokio::spawn(async move {
+        let _permit = permit;
+        match shadow_check(&state, request, &ctx, &cached).await {
+            Ok(verdict) => {
+                debug!(
+                    similarity = verdict.similarity,
+                    rerank_score = verdict.rerank_score,
+                    agrees = verdict.agrees,
+                    storage_key = %storage_key,
+                    "Shadow check complete"
+                );
+                telemetry::record_shadow_check(&ctx, Some(&verdict));
+                if verdict.invalidate {
+                    match invalidate_storage_key(&state, &storage_key).await {
+                        Ok(_) => {
+                            info!(storage_key = %storage_key, similarity = verdict.similarity, "Shadow check invalidated entry");
+                            telemetry::record_shadow_invalidation(&ctx);
+                        }
+                        Err(e) => {
+                            warn!(storage_key = %storage_key, error = %e, "Shadow invalidation failed")
+                        }
+                    }
+                }
+            }
+            Err(e) => {
+                warn!(storage_key = %storage_key, error = %e, "Shadow check failed");
+                telemetry::record_shadow_check(&ctx, None);
+            }
+        }
+    });
+}
+
+/// Calls the provider for `request` and judges `cached` against its answer.
+async fn shadow_check<B, S>(
+    state: &HandlerState<B, S>,
+    request: CreateChatCompletionRequest,
+    ctx: &StoreContext,
+    cached: &CreateChatCompletionResponse,
+) -> Result<ShadowVerdict, GatewayError>
+where
+    B: BqSearchBackend + Clone + Send + Sync + 'static,
+    S: StorageLoader + Clone + Send + Sync + 'static,
+{
+    let fresh = call_provider(state, request, ctx).await?;
+    let (cached, fresh) = (answer_text(cached), answer_text(&fresh));
+
+    let cache = state.tiered_cache.clone();
+    let scorer = state.scorer.clone();
+    let reranker = state.shadow.reranker;
+    let (similarity, rerank_score) = tokio::task::spawn_blocking(move || {
+        let embeddings = cache
+            .l2()
+            .embedder()
+            .embed_batch(&[&cached, &fresh])
+            .map_err(|e| GatewayError::EmbeddingFailed(e.to_string()))?;
+        let similarity = cosine_similarity_f16(&embeddings[0], &embeddings[1]);
+        let rerank_score = reranker
+            .then(|| scorer.score(&cached, &fresh))
+            .transpose()
+            .map_err(GatewayError::ScoringFailed)?;
+        Ok::<_, GatewayError>((similarity, rerank_score))
+    })
+    .await
+    .map_err(|e| GatewayError::InternalError(format!("Shadow scoring task failed: {}", e)))??;
+
+    Ok(state
+        .shadow
+        .judge(similarity, rerank_score, l3_threshold(state, ctx)))
+}
+
+/// The text of every choice, with tool calls as `name(arguments)`.
+pub(crate) fn answer_text(response: &CreateChatCompletionResponse) -> String {
+    let mut parts = Vec::new();
+    for choice in &response.choices {
+        parts.extend(choice.message.content.clone());
+        for call in choice.message.tool_calls.iter().flatten() {
+            parts.push(match call {
+                ChatCompletionMessageToolCalls::Function(call) => {
+                    format!("{}({})", call.function.name, call.function.arguments)
+                }
+                ChatCompletionMessageToolCalls::Custom(call) => {
+                    format!("{}({})", call.custom_tool.name, call.custom_tool.input)
+                }
+            });
+        }
+    }
+    parts.join("\n")
+}
@@ -0,0 +1,93 @@
+//! Tests for shadow verification.
+
+use async_openai::types::chat::CreateChatCompletionResponse;
+use serde_json::json;
+
+use crate::gateway::shadow::{ShadowConfig, answer_text};
+
+fn config() -> ShadowConfig {
+    ShadowConfig {
+        sample_rate: 1.0,
+        min_similarity: 0.9,
+        reranker: false,
+        invalidate_below: None,
+        max_in_flight: 16,
+    }
+}
+
+#[test]
+fn test_default_config_is_off() {
+    let config = ShadowConfig::default();
+    assert_eq!(config.sample_rate, 0.0);
+    assert_eq!(config.min_similarity, 0.85);
+    assert!(!config.reranker);
+    assert_eq!(config.invalidate_below, None);
+    assert_eq!(config.max_in_flight, 16);
+    assert!(!config.sample());
+}
+
+#[test]
+fn test_sample_rate_one_samples_every_hit() {
+    let config = config();
+    assert!((0..100).all(|_| config.sample()));
+}
+
+#[test]
+fn test_judge_compares_similarity_with_minimum() {
+    let config = config();
+
+    let verdict = config.judge(0.95, None, 0.7);
+    assert!(verdict.agrees);
+    assert!(!verdict.invalidate);
+
+    let verdict = config.judge(0.5, None, 0.7);
+    assert!(!verdict.agrees);
+    assert!(!verdict.invalidate);
+}
+
+#[test]
+fn test_judge_requires_rerank_score_above_threshold() {
+    let config = config();
+    assert!(config.judge(0.95, Some(0.8), 0.7).agrees);
+    assert!(!config.judge(0.95, Some(0.6), 0.7).agrees);
+    assert!(!config.judge(0.5, Some(0.8), 0.7).agrees);
+}
+
+#[test]
+fn test_judge_invalidates_below_cutoff() {
+    let config = ShadowConfig {
+        invalidate_below: Some(0.6),
+        ..config()
+    };
+    assert!(config.judge(0.5, None, 0.7).invalidate);
+    assert!(!config.judge(0.7, None, 0.7).invalidate);
+}
+
+#[test]
+fn test_answer_text_includes_tool_calls() {
+    let response: CreateChatCompletionResponse = serde_json::from_value(json!({
+        "id": "chatcmpl-1",
+        "object": "chat.completion",
+        "created": 1_700_000_000,
+        "model": "gpt-4o",
+        "choices": [{
+            "index": 0,
+            "finish_reason": "tool_calls",
+            "message": {
+                "role": "assistant",
+                "content": "Checking the weather.",
+                "tool_calls": [{
+                    "id": "call_1",
+                    "type": "function",
+                    "function": {"name": "get_weather", "arguments": "{\"city\":\"Paris\"}"}
+                }]
+            }
+        }]
+    }))
+    .unwrap();
+
+    assert_eq!(
+        answer_text(&response),
+        "Checking the weather.\nget_weather({\"city\":\"Paris\"})"
+    );
+}
@@ -1,7 +1,22 @@
 use genai::Client;
 use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Semaphore;
 
+use crate::gateway::feedback::FeedbackStore;
+use crate::gateway::format::HitFormat;
+use crate::gateway::inflight::InflightRequests;
+use crate::gateway::media::{MediaConfig, MediaHasher};
+use crate::gateway::pricing::PriceTable;
+use crate::gateway::provider_keys::ProviderKeyMode;
+use crate::gateway::rate_limit::{RateLimitConfig, RateLimiter};
+use crate::gateway::routing::ModelRouter;
+use crate::gateway::sampling::SamplingPolicy;
+use crate::gateway::semantic_key::{FullRequest, SemanticKeyExtractor};
+use crate::gateway::shadow::ShadowConfig;
+use crate::gateway::tenants::TenantRegistry;
+use crate::gateway::upstream::{Upstream, UpstreamConfig};
 use reflex::cache::{BqSearchBackend, StorageLoader, TieredCache};
 use reflex::scoring::CrossEncoderScorer;
 
@@ -23,6 +38,54 @@ pub struct HandlerState<
     pub genai_client: Client,
 
     pub mock_provider: bool,
+
+    /// Default body format for non-streaming chat completions.
+    pub hit_format: HitFormat,
+
+    /// Bearer token for `/admin` routes; `None` disables them.
+    pub admin_token: Option<String>,
+
+    /// Provider calls in flight, shared by identical concurrent misses.
+    pub inflight: Arc<InflightRequests>,
+
+    /// Artificial delay before the mock provider answers.
+    pub mock_latency: Duration,
+
+    /// API key registry; `None` derives a tenant from any bearer token.
+    pub tenants: Option<Arc<TenantRegistry>>,
+
+    /// Whether callers may supply their own provider key.
+    pub provider_key_mode: ProviderKeyMode,
+
+    /// Request and provider-call budgets.
+    pub rate_limiter: Arc<RateLimiter>,
+
+    /// Model aliases and their fallback chains.
+    pub router: Arc<ModelRouter>,
+
+    /// Provider retries and circuit breakers.
+    pub upstream: Arc<Upstream>,
+
+    /// Model prices for the cost saved by hits.
+    pub prices: Arc<PriceTable>,
+
+    /// Content hashes of image, audio and file parts for the cache key.
+    pub media: Arc<MediaHasher>,
+
+    /// Cache policy by temperature and seed.
+    pub sampling: Arc<SamplingPolicy>,
+
+    /// Semantic key extractor for requests whose route and tenant set none.
+    pub semantic_key: Arc<dyn SemanticKeyExtractor>,
+
+    /// Background re-checks of sampled semantic hits.
+    pub shadow: Arc<ShadowConfig>,
+
+    /// Slots for shadow checks running at once.
+    pub shadow_slots: Arc<Semaphore>,
+
+    /// Recently served hits and the false positives reported against them.
+    pub feedback: Arc<FeedbackStore>,
 }
 
 impl<B, S> HandlerState<B, S>
@@ -46,6 +109,22 @@ where
             collection_name,
             genai_client: Client::default(),
             mock_provider,
+            hit_format: HitFormat::from_env(),
+            admin_token: admin_token_from_env(),
+            inflight: Arc::default(),
+            mock_latency: Duration::ZERO,
+            tenants: None,
+            provider_key_mode: ProviderKeyMode::from_env(),
+            rate_limiter: Arc::default(),
+            router: Arc::default(),
+            upstream: Arc::default(),
+            prices: Arc::default(),
+            media: Arc::default(),
+            sampling: Arc::default(),
+            semantic_key: Arc::new(FullRequest),
+            shadow: Arc::default(),
+            shadow_slots: Arc::new(Semaphore::new(ShadowConfig::default().max_in_flight)),
+            feedback: Arc::default(),
         }
     }
 
@@ -65,6 +144,108 @@ where
             collection_name,
             genai_client: Client::default(),
             mock_provider,
+            hit_format: HitFormat::from_env(),
+            admin_token: admin_token_from_env(),
+            inflight: Arc::default(),
+            mock_latency: Duration::ZERO,
+            tenants: None,
+            provider_key_mode: ProviderKeyMode::from_env(),
+            rate_limiter: Arc::default(),
+            router: Arc::default(),
+            upstream: Arc::default(),
+            prices: Arc::default(),
+            media: Arc::default(),
+            sampling: Arc::default(),
+            semantic_key: Arc::new(FullRequest),
+            shadow: Arc::default(),
+            shadow_slots: Arc::new(Semaphore::new(ShadowConfig::default().max_in_flight)),
+            feedback: Arc::default(),
         }
     }
+
+    /// Overrides the default body format (otherwise read from `REFLEX_HIT_FORMAT`).
+    pub fn with_hit_format(mut self, hit_format: HitFormat) -> Self {
+        self.hit_format = hit_format;
+        self
+    }
+
+    /// Delays every mock provider response by `latency`.
+    pub fn with_mock_latency(mut self, latency: Duration) -> Self {
+        self.mock_latency = latency;
+        self
+    }
+
+    /// Resolves tenants through `tenants` instead of hashing raw bearer tokens.
+    pub fn with_tenants(mut self, tenants: Option<Arc<TenantRegistry>>) -> Self {
+        self.tenants = tenants;
+        self
+    }
+
+    /// Overrides the provider key mode (otherwise read from `REFLEX_PROVIDER_KEYS`).
+    pub fn with_provider_key_mode(mut self, mode: ProviderKeyMode) -> Self {
+        self.provider_key_mode = mode;
+        self
+    }
+
+    /// Applies global and default per-tenant rate limits (unlimited otherwise).
+    pub fn with_rate_limits(mut self, config: RateLimitConfig) -> Self {
+        self.rate_limiter = Arc::new(RateLimiter::new(config));
+        self
+    }
+
+    /// Routes requested models through `router` (otherwise called as named).
+    pub fn with_router(mut self, router: ModelRouter) -> Self {
+        self.router = Arc::new(router);
+        self
+    }
+
+    /// Replaces the default provider retry and circuit breaker settings.
+    pub fn with_upstream(mut self, config: UpstreamConfig) -> Self {
+        self.upstream = Arc::new(Upstream::new(config));
+        self
+    }
+
+    /// Prices hits with `prices` (otherwise only tokens saved are reported).
+    pub fn with_prices(mut self, prices: PriceTable) -> Self {
+        self.prices = Arc::new(prices);
+        self
+    }
+
+    /// Replaces the default media hashing (URLs keyed as written).
+    pub fn with_media(mut self, config: MediaConfig) -> Self {
+        self.media = Arc::new(MediaHasher::new(config));
+        self
+    }
+
+    /// Caches requests by `policy` (otherwise regardless of sampling settings).
+    pub fn with_sampling(mut self, policy: SamplingPolicy) -> Self {
+        self.sampling = Arc::new(policy);
+        self
+    }
+
+    /// Replaces the default extractor, which embeds the full request.
+    pub fn with_semantic_key(mut self, extractor: Arc<dyn SemanticKeyExtractor>) -> Self {
+        self.semantic_key = extractor;
+        self
+    }
+
+    /// Shadows a sample of semantic hits (otherwise none).
+    pub fn with_shadow(mut self, config: ShadowConfig) -> Self {
+        self.shadow_slots = Arc::new(Semaphore::new(config.max_in_flight));
+        self.shadow = Arc::new(config);
+        self
+    }
+
+    /// Overrides the admin token (otherwise read from `REFLEX_ADMIN_TOKEN`).
+    pub fn with_admin_token(mut self, admin_token: Option<String>) -> Self {
+        self.admin_token = admin_token;
+        self
+    }
+}
+
+fn admin_token_from_env() -> Option<String> {
+    std::env::var("REFLEX_ADMIN_TOKEN")
+        .ok()
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
 }
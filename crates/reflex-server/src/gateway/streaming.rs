@@ -1,130 +1,629 @@
 use async_openai::types::chat::{
-    ChatChoiceStream, ChatCompletionStreamResponseDelta, CreateChatCompletionRequest,
-    CreateChatCompletionStreamResponse,
+    ChatCompletionMessageToolCalls, CreateChatCompletionRequest, CreateChatCompletionResponse,
+    FinishReason,
 };
+use axum::http::HeaderValue;
 use axum::response::sse::{Event, Sse};
-use futures_util::stream::{Stream, StreamExt};
-use genai::Client;
-use genai::chat::ChatStreamEvent;
+use axum::response::{IntoResponse, Response};
+use futures_util::stream::{self, Stream, StreamExt};
+use genai::chat::{ChatOptions, ChatStreamEvent, StreamChunk, StreamEnd, ToolCall, Usage};
+use serde_json::Value;
+use std::collections::HashSet;
 use std::convert::Infallible;
-use std::sync::Arc;
-use tokio::sync::Mutex;
-use tracing::error;
+use std::time::Instant;
+use tracing::{debug, error, warn};
 
-use crate::gateway::adapter::adapt_openai_to_genai;
+use crate::gateway::adapter::{
+    ChoiceParts, adapt_openai_to_genai, custom_tool_names, inferred_finish_reason,
+    max_completion_tokens, openai_response_from_parts, openai_usage, requested_choices,
+    unsupported_tool_choice,
+};
+use crate::gateway::cache_control::apply_cache_headers;
 use crate::gateway::error::GatewayError;
-use reflex::cache::BqSearchBackend;
+use crate::gateway::feedback::apply_entry_header;
+use crate::gateway::handler::{
+    StoreContext, lookup_cached_payload, lookup_degraded, report_savings, store_cache_payload,
+};
+use crate::gateway::inflight::{Flight, FlightGuard, FlightOutcome};
+use crate::gateway::payload::CachePayload;
+use crate::gateway::rate_limit::Budget;
+use crate::gateway::routing::with_fallbacks;
+use crate::gateway::shadow::spawn_shadow_check;
+use crate::gateway::state::HandlerState;
+use crate::gateway::telemetry::{self, Tier};
+use crate::gateway::upstream::ProviderFailure;
+use reflex::cache::{BqSearchBackend, REFLEX_STATUS_HEADER, ReflexStatus, StorageLoader};
+use reflex::storage::StorageWriter;
 
-/// Handles streaming chat completion requests, bypassing the semantic cache.
-///
-/// # Cache Bypass Rationale
-///
-/// Streaming requests deliberately bypass the cache for several reasons:
-/// - **Incremental delivery**: Streaming responses are delivered chunk-by-chunk via SSE,
-///   making traditional cache lookup/store semantics inefficient
-/// - **Latency sensitivity**: Streaming is typically chosen for real-time feedback;
-///   cache overhead would negate this benefit
-/// - **Response variability**: Partial responses and timing are inherently variable,
-///   making cache hit rates low and storage wasteful
-///
-/// # Type Parameter `B`
-///
-/// The generic parameter `B: BqSearchBackend` is retained for API consistency with
-/// non-streaming handlers (e.g., `handle_chat_completion`) even though it is not used
-/// in the function body. This allows callers to use a uniform handler signature and
-/// simplifies router configuration where both streaming and non-streaming paths share
-/// the same backend type.
+/// Identity of one streamed completion, shared by all of its events.
+#[derive(Debug, Clone)]
+pub(crate) struct StreamMeta {
+    pub id: String,
+    pub created: u32,
+    pub model: String,
+}
+
+/// One tool call fragment. `id` and `name` are only set on a call's first fragment.
+#[derive(Debug, Clone)]
+pub(crate) struct ToolCallDelta {
+    pub index: usize,
+    pub id: Option<String>,
+    pub name: Option<String>,
+    pub arguments: String,
+}
+
+/// Wire format of a streamed completion.
 ///
-/// # Future Work
+/// The same encoder drives live provider streams and cache replays, so a
+/// client sees identically shaped events either way.
+pub(crate) trait StreamEncoder: Send + 'static {
+    /// Events sent before any content.
+    fn begin(&mut self, meta: &StreamMeta) -> Vec<Event>;
+
+    fn text(&mut self, text: &str) -> Vec<Event>;
+
+    fn tool_call(&mut self, delta: &ToolCallDelta) -> Vec<Event>;
+
+    /// Events that close the stream once the full response is known.
+    fn finish(&mut self, response: &CreateChatCompletionResponse) -> Vec<Event>;
+
+    fn error(&mut self, message: &str) -> Vec<Event>;
+}
+
+/// Serves a streaming request: replays a cache hit, or streams from the provider.
 ///
-/// Potential enhancements for streaming cache support:
-/// - **Accumulated response caching**: Store the fully accumulated response after stream
-///   completion (see `_accumulated_content` placeholder) for subsequent non-streaming lookups
-/// - **Prefix caching**: Cache partial responses to enable "continuation" semantics
-/// - **Semantic deduplication**: Detect duplicate streaming requests in-flight and fan-out
-///   a single upstream stream to multiple clients
-pub async fn handle_streaming_request<B>(
-    client: Client,
-    model: &str,
+/// On a miss, chunks are forwarded to the client as they arrive while the
+/// assistant text and tool calls are accumulated. Once the provider signals a
+/// clean end of stream, the accumulated response is stored exactly like a
+/// non-streaming miss, so later requests (streaming or not) can hit it.
+/// Streams that end in an upstream error or are dropped by the client are
+/// never stored.
+pub(crate) async fn serve_stream<B, S, E>(
+    state: HandlerState<B, S>,
     request: CreateChatCompletionRequest,
-    _tenant_id_hash: u64,
-    _context_hash: u64,
-    _semantic_text: String,
-) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>> + Send + 'static>, GatewayError>
+    store_ctx: StoreContext,
+    encoder: E,
+) -> Result<Response, GatewayError>
 where
     B: BqSearchBackend + Clone + Send + Sync + 'static,
+    S: StorageLoader + StorageWriter + Clone + Send + Sync + 'static,
+    E: StreamEncoder,
 {
-    let genai_req = adapt_openai_to_genai(request.clone());
-    let model_owned = model.to_string();
-
-    let chat_stream_resp = client
-        .exec_chat_stream(&model_owned, genai_req, None)
-        .await
-        .map_err(|e| {
-            error!("Provider stream init error: {}", e);
-            GatewayError::ProviderError("Upstream service stream init failed".to_string())
-        })?;
-
-    let stream = chat_stream_resp.stream;
-
-    let _accumulated_content = Arc::new(Mutex::new(String::new()));
-
-    let event_stream = stream.map(move |result| match result {
-        Ok(ChatStreamEvent::Start) => Ok(Event::default().comment("start")),
-        Ok(ChatStreamEvent::Chunk(chunk)) => {
-            let text = chunk.content;
-            if !text.is_empty() {
-                let delta: ChatCompletionStreamResponseDelta = match serde_json::from_value(
-                    serde_json::json!({ "role": "assistant", "content": text }),
-                ) {
-                    Ok(d) => d,
-                    Err(e) => {
-                        error!("Failed to construct delta: {}", e);
-                        return Ok(Event::default().comment("delta-error"));
-                    }
-                };
-
-                let response: CreateChatCompletionStreamResponse =
-                    match serde_json::from_value(serde_json::json!({
-                        "id": format!("chatcmpl-{}", uuid::Uuid::new_v4()),
-                        "object": "chat.completion.chunk",
-                        "created": chrono::Utc::now().timestamp() as u32,
-                        "model": model_owned.clone(),
-                        "choices": vec![ChatChoiceStream {
-                            index: 0,
-                            delta,
-                            finish_reason: None,
-                            logprobs: None,
-                        }],
-                        "usage": serde_json::Value::Null,
-                    })) {
-                        Ok(r) => r,
-                        Err(e) => {
-                            error!("Failed to construct streaming response: {}", e);
-                            return Ok(Event::default().comment("delta-error"));
-                        }
-                    };
+    // A provider stream carries a single choice.
+    if requested_choices(&request) > 1 {
+        return Err(GatewayError::InvalidRequest(
+            "n > 1 is not supported for streamed requests".to_string(),
+        ));
+    }
+    if let Some(message) = unsupported_tool_choice(&request) {
+        return Err(GatewayError::InvalidRequest(message));
+    }
 
-                match serde_json::to_string(&response) {
-                    Ok(json) => Ok(Event::default().data(json)),
-                    Err(e) => {
-                        error!("Failed to serialize response: {}", e);
-                        Ok(Event::default().comment("serialization-error"))
-                    }
+    let directives = store_ctx.directives;
+
+    if let Some(mut hit) = lookup_cached_payload(&state, &store_ctx).await? {
+        state.feedback.track(&store_ctx, &mut hit);
+        spawn_shadow_check(&state, &request, &store_ctx, &hit);
+        let mut response = replay_cached_stream(&hit.payload.response, hit.status, encoder);
+        apply_entry_header(&hit, response.headers_mut());
+        report_savings(
+            &state,
+            &store_ctx,
+            &hit.payload.response,
+            hit.status,
+            response.headers_mut(),
+        );
+        apply_cache_headers(response.headers_mut(), &directives, hit.stored_at);
+        return Ok(response);
+    }
+
+    if directives.only_if_cached {
+        return Err(GatewayError::NotCached);
+    }
+
+    let flight = match state.inflight.join(store_ctx.flight_key()) {
+        Flight::Leader(guard) => Some(guard),
+        Flight::Follower(waiter) => {
+            debug!("Identical request in flight - waiting to replay its result");
+            match waiter.wait().await {
+                FlightOutcome::Completed(payload) => {
+                    telemetry::record_lookup(&store_ctx, Tier::Inflight, true);
+                    let mut response =
+                        replay_cached_stream(&payload.response, ReflexStatus::HitInflight, encoder);
+                    report_savings(
+                        &state,
+                        &store_ctx,
+                        &payload.response,
+                        ReflexStatus::HitInflight,
+                        response.headers_mut(),
+                    );
+                    apply_cache_headers(response.headers_mut(), &directives, None);
+                    return Ok(response);
                 }
-            } else {
-                Ok(Event::default().comment("keep-alive"))
+                FlightOutcome::Failed(error) => return Err(error),
+                FlightOutcome::Abandoned => None,
             }
         }
-        Ok(ChatStreamEvent::End(_)) => Ok(Event::default().data("[DONE]")),
-        Ok(_) => Ok(Event::default().comment("ignored-event")),
+    };
+
+    state
+        .rate_limiter
+        .acquire(Budget::Misses, store_ctx.tenant_id, &store_ctx.policy)?;
+    debug!("Cache Miss - Streaming from Provider");
+
+    let options = ChatOptions::default()
+        .with_capture_usage(true)
+        .with_capture_content(true)
+        .with_capture_tool_calls(true);
+    let client = store_ctx.provider_keys.client(&state.genai_client);
+
+    // Streamed requests are timed until the provider opens the stream. Once
+    // it is open the model is committed: a mid-stream failure is not retried.
+    let started = Instant::now();
+    let opened = with_fallbacks(&store_ctx.route, |model| {
+        let (state, store_ctx) = (&state, &store_ctx);
+        let (client, options, request) = (&client, &options, &request);
+        async move {
+            let timeout = state.router.model_timeout(&model);
+            let own_key = store_ctx.provider_keys.overrides(&model);
+            let events = state
+                .upstream
+                .call(&model, timeout, own_key, || async {
+                    if state.mock_provider {
+                        if !state.mock_latency.is_zero() {
+                            tokio::time::sleep(state.mock_latency).await;
+                        }
+                        return Ok(mock_provider_events(&store_ctx.semantic_text).boxed());
+                    }
+
+                    let genai_req = adapt_openai_to_genai(request.clone());
+                    client
+                        .exec_chat_stream(&model, genai_req, Some(options))
+                        .await
+                        .map(|resp| resp.stream.boxed())
+                        .map_err(|e| {
+                            error!(model = %model, "Provider stream init error: {}", e);
+                            ProviderFailure::from_genai(&e, "Upstream service stream init failed")
+                        })
+                })
+                .await?;
+            Ok(events)
+        }
+    })
+    .await;
+    let (model, events) = match opened {
+        Ok(opened) => opened,
         Err(e) => {
-            error!("Stream error: {}", e);
-            Ok(Event::default()
-                .event("error")
-                .data("Stream interrupted by upstream error"))
+            if let Some(mut hit) = lookup_degraded(&state, &store_ctx, &e).await? {
+                state.feedback.track(&store_ctx, &mut hit);
+                let mut response = replay_cached_stream(&hit.payload.response, hit.status, encoder);
+                apply_entry_header(&hit, response.headers_mut());
+                report_savings(
+                    &state,
+                    &store_ctx,
+                    &hit.payload.response,
+                    hit.status,
+                    response.headers_mut(),
+                );
+                apply_cache_headers(response.headers_mut(), &directives, hit.stored_at);
+                return Ok(response);
+            }
+            if let Some(flight) = &flight {
+                flight.fail(&e);
+            }
+            return Err(e);
+        }
+    };
+    telemetry::record_provider(&model, started);
+
+    let accumulator = StreamAccumulator::for_request(&request);
+    let mut response = stream_and_store(
+        state,
+        model,
+        accumulator,
+        store_ctx,
+        flight,
+        events,
+        encoder,
+    );
+    apply_cache_headers(response.headers_mut(), &directives, None);
+    Ok(response)
+}
+
+/// Replays a cached response through `encoder`.
+///
+/// Content is sent verbatim rather than Tauq-encoded, since streaming clients
+/// parse the deltas themselves.
+pub(crate) fn replay_cached_stream<E: StreamEncoder>(
+    response: &CreateChatCompletionResponse,
+    status: ReflexStatus,
+    mut encoder: E,
+) -> Response {
+    let meta = StreamMeta {
+        id: response.id.clone(),
+        created: response.created,
+        model: response.model.clone(),
+    };
+
+    let mut events = encoder.begin(&meta);
+
+    if let Some(choice) = response.choices.first() {
+        if let Some(content) = choice.message.content.as_deref()
+            && !content.is_empty()
+        {
+            events.extend(encoder.text(content));
+        }
+
+        // Custom tool calls are streamed the way a live stream delivers
+        // them: as a function call with an `input` argument.
+        let tool_calls = choice
+            .message
+            .tool_calls
+            .iter()
+            .flatten()
+            .map(|tc| match tc {
+                ChatCompletionMessageToolCalls::Function(call) => (
+                    call.id.clone(),
+                    call.function.name.clone(),
+                    call.function.arguments.clone(),
+                ),
+                ChatCompletionMessageToolCalls::Custom(call) => (
+                    call.id.clone(),
+                    call.custom_tool.name.clone(),
+                    serde_json::json!({ "input": call.custom_tool.input }).to_string(),
+                ),
+            });
+
+        for (index, (id, name, arguments)) in tool_calls.enumerate() {
+            events.extend(encoder.tool_call(&ToolCallDelta {
+                index,
+                id: Some(id),
+                name: Some(name),
+                arguments,
+            }));
+        }
+    }
+
+    events.extend(encoder.finish(response));
+
+    let events = stream::iter(events.into_iter().map(Ok::<_, Infallible>));
+    with_status(Sse::new(events).into_response(), status)
+}
+
+/// Forwards a provider stream and, once it ends cleanly, releases any
+/// coalesced waiters and stores the accumulated response.
+fn stream_and_store<B, S, St, E>(
+    state: HandlerState<B, S>,
+    model: String,
+    mut accumulator: StreamAccumulator,
+    store_ctx: StoreContext,
+    mut flight: Option<FlightGuard>,
+    events: St,
+    mut encoder: E,
+) -> Response
+where
+    B: BqSearchBackend + Clone + Send + Sync + 'static,
+    S: StorageLoader + StorageWriter + Clone + Send + Sync + 'static,
+    St: Stream<Item = genai::Result<ChatStreamEvent>> + Send + 'static,
+    E: StreamEncoder,
+{
+    let meta = StreamMeta {
+        id: format!("chatcmpl-{}", uuid::Uuid::new_v4()),
+        created: chrono::Utc::now().timestamp() as u32,
+        model: model.clone(),
+    };
+    let start = encoder.begin(&meta);
+
+    let semantic_request = store_ctx.semantic_text.clone();
+    let media_key = store_ctx.media_key.clone();
+    let scope_key = store_ctx.scope_key.clone();
+    let mut store = store_ctx.should_store().then_some((state, store_ctx));
+
+    let body = events
+        .map(move |result| {
+            let out = match result {
+                Ok(ChatStreamEvent::Chunk(c)) if !c.content.is_empty() => {
+                    accumulator.content.push_str(&c.content);
+                    encoder.text(&c.content)
+                }
+                Ok(ChatStreamEvent::Chunk(_)) => vec![Event::default().comment("keep-alive")],
+                Ok(ChatStreamEvent::ToolCallChunk(tc)) => {
+                    let delta = accumulator.push_tool_call(&tc.tool_call);
+                    encoder.tool_call(&delta)
+                }
+                Ok(ChatStreamEvent::End(end)) => {
+                    let mut response = accumulator.finish(model.clone(), &end);
+                    response.id = meta.id.clone();
+                    response.created = meta.created;
+                    let out = encoder.finish(&response);
+
+                    let payload = CachePayload {
+                        semantic_request: semantic_request.clone(),
+                        response,
+                        media_key: media_key.clone(),
+                        scope_key: scope_key.clone(),
+                        variants: Vec::new(),
+                    };
+                    if let Some(flight) = &flight {
+                        flight.complete(&payload);
+                    }
+                    match store.take() {
+                        Some((state, ctx)) => spawn_store(state, ctx, payload, flight.take()),
+                        None => flight = None,
+                    }
+                    out
+                }
+                Ok(_) => vec![Event::default().comment("ignored-event")],
+                Err(e) => {
+                    error!("Stream error: {}", e);
+                    store = None;
+                    if let Some(flight) = flight.take() {
+                        flight.fail(&GatewayError::ProviderError(
+                            "Stream interrupted by upstream error".to_string(),
+                        ));
+                    }
+                    encoder.error("Stream interrupted by upstream error")
+                }
+            };
+            stream::iter(out)
+        })
+        .flatten();
+
+    let event_stream = stream::iter(start).chain(body).map(Ok::<_, Infallible>);
+
+    with_status(Sse::new(event_stream).into_response(), ReflexStatus::Miss)
+}
+
+/// Stores a completed stream; `flight` stays joinable until the write lands.
+fn spawn_store<B, S>(
+    state: HandlerState<B, S>,
+    ctx: StoreContext,
+    payload: CachePayload,
+    flight: Option<FlightGuard>,
+) where
+    B: BqSearchBackend + Clone + Send + Sync + 'static,
+    S: StorageLoader + StorageWriter + Clone + Send + Sync + 'static,
+{
+    tokio::spawn(async move {
+        match store_cache_payload(&state, &ctx, &payload).await {
+            Ok(()) => debug!(context_hash = ctx.context_hash, "Stored streamed response"),
+            Err(e) => warn!(error = %e, "Failed to store streamed response"),
         }
+        drop(flight);
     });
+}
+
+/// Synthetic provider stream used when `REFLEX_MOCK_PROVIDER` is set.
+fn mock_provider_events(
+    semantic_text: &str,
+) -> impl Stream<Item = genai::Result<ChatStreamEvent>> + Send + 'static {
+    stream::iter(vec![
+        Ok(ChatStreamEvent::Start),
+        Ok(ChatStreamEvent::Chunk(StreamChunk {
+            content: format!("Mock response for: {}", semantic_text),
+        })),
+        Ok(ChatStreamEvent::End(StreamEnd {
+            captured_usage: Some(Usage {
+                prompt_tokens: Some(10),
+                completion_tokens: Some(10),
+                total_tokens: Some(20),
+                ..Usage::default()
+            }),
+            ..StreamEnd::default()
+        })),
+    ])
+}
+
+fn with_status(mut response: Response, status: ReflexStatus) -> Response {
+    response.headers_mut().insert(
+        REFLEX_STATUS_HEADER,
+        HeaderValue::from_static(status.as_header_value()),
+    );
+    response
+}
+
+/// Serializes `value` as the data of an SSE event, optionally named.
+pub(crate) fn json_event(name: Option<&str>, value: &Value) -> Event {
+    let event = match name {
+        Some(name) => Event::default().event(name),
+        None => Event::default(),
+    };
+    match serde_json::to_string(value) {
+        Ok(json) => event.data(json),
+        Err(e) => {
+            error!("Failed to serialize response: {}", e);
+            Event::default().comment("serialization-error")
+        }
+    }
+}
+
+/// OpenAI `chat.completion.chunk` events terminated by `data: [DONE]`.
+#[derive(Default)]
+pub(crate) struct ChatChunkEncoder {
+    meta: Option<StreamMeta>,
+    sent_role: bool,
+}
+
+impl ChatChunkEncoder {
+    fn chunk(&mut self, mut delta: Value, finish_reason: Option<FinishReason>) -> Event {
+        if !self.sent_role
+            && let Some(obj) = delta.as_object_mut()
+        {
+            obj.insert("role".to_string(), Value::from("assistant"));
+            self.sent_role = true;
+        }
+
+        let meta = self.meta.as_ref();
+        json_event(
+            None,
+            &serde_json::json!({
+                "id": meta.map(|m| m.id.as_str()),
+                "object": "chat.completion.chunk",
+                "created": meta.map(|m| m.created),
+                "model": meta.map(|m| m.model.as_str()),
+                "choices": [{
+                    "index": 0,
+                    "delta": delta,
+                    "finish_reason": finish_reason,
+                    "logprobs": null,
+                }],
+            }),
+        )
+    }
+}
+
+impl StreamEncoder for ChatChunkEncoder {
+    fn begin(&mut self, meta: &StreamMeta) -> Vec<Event> {
+        self.meta = Some(meta.clone());
+        vec![Event::default().comment("start")]
+    }
+
+    fn text(&mut self, text: &str) -> Vec<Event> {
+        vec![self.chunk(serde_json::json!({ "content": text }), None)]
+    }
+
+    fn tool_call(&mut self, delta: &ToolCallDelta) -> Vec<Event> {
+        let mut call = serde_json::json!({
+            "index": delta.index,
+            "function": { "arguments": delta.arguments },
+        });
+        if let Some(id) = &delta.id {
+            call["id"] = Value::from(id.as_str());
+            call["type"] = Value::from("function");
+        }
+        if let Some(name) = &delta.name {
+            call["function"]["name"] = Value::from(name.as_str());
+        }
+        vec![self.chunk(serde_json::json!({ "tool_calls": [call] }), None)]
+    }
+
+    fn finish(&mut self, response: &CreateChatCompletionResponse) -> Vec<Event> {
+        let finish_reason = response
+            .choices
+            .first()
+            .and_then(|c| c.finish_reason)
+            .unwrap_or(FinishReason::Stop);
+
+        vec![
+            self.chunk(serde_json::json!({}), Some(finish_reason)),
+            Event::default().data("[DONE]"),
+        ]
+    }
+
+    fn error(&mut self, message: &str) -> Vec<Event> {
+        vec![Event::default().event("error").data(message)]
+    }
+}
+
+/// Collects streamed text and tool calls into a complete response.
+#[derive(Default)]
+struct StreamAccumulator {
+    content: String,
+    tool_calls: Vec<ToolCall>,
+    /// The request's completion token cap, to recognise truncated streams.
+    max_tokens: Option<u32>,
+    custom_tools: HashSet<String>,
+}
+
+impl StreamAccumulator {
+    fn for_request(request: &CreateChatCompletionRequest) -> Self {
+        Self {
+            max_tokens: max_completion_tokens(request),
+            custom_tools: custom_tool_names(request),
+            ..Self::default()
+        }
+    }
+
+    /// Records a tool call chunk and returns the fragment to forward.
+    ///
+    /// With capture enabled, genai re-sends the accumulated argument string on
+    /// every chunk; only the new suffix is forwarded.
+    fn push_tool_call(&mut self, tool_call: &ToolCall) -> ToolCallDelta {
+        let args = match &tool_call.fn_arguments {
+            Value::String(s) => s.clone(),
+            other => other.to_string(),
+        };
+
+        if let Some(index) = self
+            .tool_calls
+            .iter()
+            .position(|tc| tc.call_id == tool_call.call_id)
+        {
+            let existing = &mut self.tool_calls[index];
+            let previous = existing
+                .fn_arguments
+                .as_str()
+                .unwrap_or_default()
+                .to_string();
+            let (accumulated, delta) = match args.strip_prefix(previous.as_str()) {
+                Some(suffix) => (args.clone(), suffix.to_string()),
+                None => (format!("{}{}", previous, args), args),
+            };
+            existing.fn_arguments = Value::String(accumulated);
+
+            return ToolCallDelta {
+                index,
+                id: None,
+                name: None,
+                arguments: delta,
+            };
+        }
+
+        let index = self.tool_calls.len();
+        self.tool_calls.push(ToolCall {
+            call_id: tool_call.call_id.clone(),
+            fn_name: tool_call.fn_name.clone(),
+            fn_arguments: Value::String(args.clone()),
+        });
+
+        ToolCallDelta {
+            index,
+            id: Some(tool_call.call_id.clone()),
+            name: Some(tool_call.fn_name.clone()),
+            arguments: args,
+        }
+    }
+
+    /// Builds the final response, preferring content captured by genai.
+    fn finish(&self, model: String, end: &StreamEnd) -> CreateChatCompletionResponse {
+        let content = end
+            .captured_first_text()
+            .map(str::to_string)
+            .unwrap_or_else(|| self.content.clone());
+
+        let parsed: Vec<ToolCall> = self
+            .tool_calls
+            .iter()
+            .map(|tc| ToolCall {
+                call_id: tc.call_id.clone(),
+                fn_name: tc.fn_name.clone(),
+                fn_arguments: tc
+                    .fn_arguments
+                    .as_str()
+                    .and_then(|s| serde_json::from_str(s).ok())
+                    .unwrap_or_else(|| tc.fn_arguments.clone()),
+            })
+            .collect();
+
+        let tool_calls: Vec<&ToolCall> = match end.captured_tool_calls() {
+            Some(captured) if !captured.is_empty() => captured,
+            _ => parsed.iter().collect(),
+        };
+
+        // genai does not surface the provider's stop reason on streams.
+        let usage = end.captured_usage.clone().unwrap_or_default();
+        let finish_reason = inferred_finish_reason(&tool_calls, &usage, self.max_tokens);
 
-    Ok(Sse::new(event_stream))
+        openai_response_from_parts(
+            model,
+            ChoiceParts {
+                content,
+                refusal: None,
+                tool_calls: tool_calls.into_iter().cloned().collect(),
+                finish_reason,
+            },
+            openai_usage(&usage),
+            &self.custom_tools,
+        )
+    }
 }
@@ -0,0 +1,278 @@
+//! Prometheus metrics served at `GET /metrics`.
+//!
+//! Gateway code records through the `metrics` facade; the cache library
+//! records its own stage latencies (see [`reflex::telemetry`]). A Prometheus
+//! recorder is installed once per process the first time a router is built.
+//!
+//! Exported series:
+//! - `reflex_cache_lookups_total{tier, outcome, tenant, model}`
+//! - `reflex_l3_verifications_total{result}`
+//! - `reflex_l3_verification_score{result}` (histogram)
+//! - `reflex_stage_duration_seconds{stage}` (histogram)
+//! - `reflex_provider_duration_seconds{model}` (histogram)
+//! - `reflex_l1_entries` (gauge, sampled on scrape)
+//! - `reflex_index_upsert_failures_total`
+//! - `reflex_rate_limited_total{budget, scope}`
+//! - `reflex_provider_retries_total{provider}`
+//! - `reflex_circuit_transitions_total{provider, state}`
+//! - `reflex_tokens_saved_total{tenant, model}`
+//! - `reflex_cost_saved_microdollars_total{tenant, model}`
+//! - `reflex_shadow_checks_total{tenant, model, outcome}` (`agree`,
+//!   `disagree`, `failed`, or `rate_limited` and `dropped` for skipped checks)
+//! - `reflex_shadow_similarity{model}` (histogram)
+//! - `reflex_shadow_invalidations_total{tenant, model}`
+//! - `reflex_feedback_total{tenant, verdict}`
+
+use std::sync::OnceLock;
+use std::time::Instant;
+
+use axum::{
+    extract::State,
+    http::{HeaderValue, StatusCode, header},
+    response::{IntoResponse, Response},
+};
+use metrics_exporter_prometheus::{Matcher, PrometheusBuilder, PrometheusHandle};
+use tracing::warn;
+
+use crate::gateway::explain::{L1Outcome, LookupTrace};
+use crate::gateway::feedback::Verdict;
+use crate::gateway::handler::StoreContext;
+use crate::gateway::pricing::Savings;
+use crate::gateway::rate_limit::Budget;
+use crate::gateway::shadow::ShadowVerdict;
+use crate::gateway::state::HandlerState;
+use reflex::cache::{BqSearchBackend, StorageLoader};
+use reflex::scoring::VerificationResult;
+
+pub const LOOKUPS_TOTAL: &str = "reflex_cache_lookups_total";
+pub const VERIFICATIONS_TOTAL: &str = "reflex_l3_verifications_total";
+pub const VERIFICATION_SCORE: &str = "reflex_l3_verification_score";
+pub const PROVIDER_DURATION_SECONDS: &str = "reflex_provider_duration_seconds";
+pub const L1_ENTRIES: &str = "reflex_l1_entries";
+pub const INDEX_UPSERT_FAILURES_TOTAL: &str = "reflex_index_upsert_failures_total";
+pub const RATE_LIMITED_TOTAL: &str = "reflex_rate_limited_total";
+pub const PROVIDER_RETRIES_TOTAL: &str = "reflex_provider_retries_total";
+pub const CIRCUIT_TRANSITIONS_TOTAL: &str = "reflex_circuit_transitions_total";
+pub const TOKENS_SAVED_TOTAL: &str = "reflex_tokens_saved_total";
+pub const COST_SAVED_MICRODOLLARS_TOTAL: &str = "reflex_cost_saved_microdollars_total";
+pub const SHADOW_CHECKS_TOTAL: &str = "reflex_shadow_checks_total";
+pub const SHADOW_SIMILARITY: &str = "reflex_shadow_similarity";
+pub const SHADOW_INVALIDATIONS_TOTAL: &str = "reflex_shadow_invalidations_total";
+pub const FEEDBACK_TOTAL: &str = "reflex_feedback_total";
+
+const LATENCY_BUCKETS: &[f64] = &[
+    0.0005, 0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0,
+    60.0,
+];
+const SCORE_BUCKETS: &[f64] = &[0.1, 0.2, 0.3, 0.4, 0.5, 0.6, 0.7, 0.8, 0.9, 0.95, 1.0];
+
+static PROMETHEUS: OnceLock<PrometheusHandle> = OnceLock::new();
+
+/// Cache tier a lookup outcome is counted against.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tier {
+    L1,
+    L2,
+    L3,
+    /// Served by a coalesced in-flight provider call.
+    Inflight,
+    /// Looser-threshold lookup while the provider's circuit is open.
+    Degraded,
+}
+
+impl Tier {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Tier::L1 => "l1",
+            Tier::L2 => "l2",
+            Tier::L3 => "l3",
+            Tier::Inflight => "inflight",
+            Tier::Degraded => "degraded",
+        }
+    }
+}
+
+/// Returns the process-wide Prometheus handle, installing the recorder on first use.
+///
+/// If the host application already installed a different global recorder,
+/// the handle still renders but stays empty.
+pub fn prometheus_handle() -> &'static PrometheusHandle {
+    PROMETHEUS.get_or_init(|| {
+        let recorder = PrometheusBuilder::new()
+            .set_buckets_for_metric(Matcher::Suffix("_seconds".to_string()), LATENCY_BUCKETS)
+            .and_then(|b| {
+                b.set_buckets_for_metric(
+                    Matcher::Full(VERIFICATION_SCORE.to_string()),
+                    SCORE_BUCKETS,
+                )
+            })
+            .and_then(|b| {
+                b.set_buckets_for_metric(
+                    Matcher::Full(SHADOW_SIMILARITY.to_string()),
+                    SCORE_BUCKETS,
+                )
+            })
+            .expect("histogram buckets are non-empty")
+            .build_recorder();
+        let handle = recorder.handle();
+        if let Err(e) = metrics::set_global_recorder(recorder) {
+            warn!(error = %e, "Metrics recorder already installed; /metrics will be empty");
+        }
+        handle
+    })
+}
+
+/// `GET /metrics`: Prometheus text exposition format.
+pub async fn metrics_handler<B, S>(State(state): State<HandlerState<B, S>>) -> Response
+where
+    B: BqSearchBackend + Clone + Send + Sync + 'static,
+    S: StorageLoader + Clone + Send + Sync + 'static,
+{
+    // The L1 entry count is eventually consistent; settle it before sampling.
+    state.tiered_cache.run_pending_tasks_l1();
+    metrics::gauge!(L1_ENTRIES).set(state.tiered_cache.l1_len() as f64);
+
+    (
+        StatusCode::OK,
+        [(
+            header::CONTENT_TYPE,
+            HeaderValue::from_static("text/plain; version=0.0.4"),
+        )],
+        prometheus_handle().render(),
+    )
+        .into_response()
+}
+
+/// Counts one hit or miss at `tier` for the request described by `ctx`.
+pub(crate) fn record_lookup(ctx: &StoreContext, tier: Tier, hit: bool) {
+    metrics::counter!(
+        LOOKUPS_TOTAL,
+        "tier" => tier.as_str(),
+        "outcome" => if hit { "hit" } else { "miss" },
+        "tenant" => ctx.tenant_id.to_string(),
+        "model" => ctx.model.clone(),
+    )
+    .increment(1);
+}
+
+/// Counts the tiers a lookup went through, as [`record_lookup`] does for
+/// each, and its L3 outcome.
+pub(crate) fn record_trace(ctx: &StoreContext, trace: &LookupTrace) {
+    if trace.skipped {
+        return;
+    }
+    record_lookup(ctx, Tier::L1, trace.l1 == L1Outcome::Hit);
+    if trace.l1 != L1Outcome::Miss {
+        return;
+    }
+    match &trace.verification {
+        Some(result) => {
+            record_lookup(ctx, Tier::L2, true);
+            record_verification(result);
+            record_lookup(ctx, Tier::L3, result.is_verified());
+        }
+        None => record_lookup(ctx, Tier::L2, false),
+    }
+}
+
+/// Counts an L3 outcome and records its best score.
+pub(crate) fn record_verification(result: &VerificationResult) {
+    let (label, score) = match result {
+        VerificationResult::Verified { score } => ("verified", Some(*score)),
+        VerificationResult::Rejected { top_score } => ("rejected", Some(*top_score)),
+        VerificationResult::NoCandidates => ("no_candidates", None),
+    };
+    metrics::counter!(VERIFICATIONS_TOTAL, "result" => label).increment(1);
+    if let Some(score) = score {
+        metrics::histogram!(VERIFICATION_SCORE, "result" => label).record(score as f64);
+    }
+}
+
+/// Records provider latency for `model` since `started`.
+pub(crate) fn record_provider(model: &str, started: Instant) {
+    metrics::histogram!(PROVIDER_DURATION_SECONDS, "model" => model.to_string())
+        .record(started.elapsed().as_secs_f64());
+}
+
+pub(crate) fn record_index_upsert_failure() {
+    metrics::counter!(INDEX_UPSERT_FAILURES_TOTAL).increment(1);
+}
+
+/// Counts a request refused by the `scope` (`global` or `tenant`) bucket of `budget`.
+pub(crate) fn record_rate_limited(budget: Budget, scope: &'static str) {
+    metrics::counter!(RATE_LIMITED_TOTAL, "budget" => budget.as_str(), "scope" => scope)
+        .increment(1);
+}
+
+/// Counts a provider call retried after a transient failure.
+pub(crate) fn record_provider_retry(provider: &'static str) {
+    metrics::counter!(PROVIDER_RETRIES_TOTAL, "provider" => provider).increment(1);
+}
+
+/// Counts `provider`'s circuit moving to `state` (`open` or `closed`).
+pub(crate) fn record_circuit(provider: &'static str, state: &'static str) {
+    metrics::counter!(CIRCUIT_TRANSITIONS_TOTAL, "provider" => provider, "state" => state)
+        .increment(1);
+}
+
+/// Adds what a hit for the request described by `ctx` saved.
+pub(crate) fn record_savings(ctx: &StoreContext, savings: &Savings) {
+    let tenant = ctx.tenant_id.to_string();
+    metrics::counter!(TOKENS_SAVED_TOTAL, "tenant" => tenant.clone(), "model" => ctx.model.clone())
+        .increment(savings.tokens());
+    if let Some(cost) = savings.cost_usd {
+        metrics::counter!(COST_SAVED_MICRODOLLARS_TOTAL, "tenant" => tenant, "model" => ctx.model.clone())
+            .increment((cost * 1_000_000.0).round() as u64);
+    }
+}
+
+/// Counts a shadow check of a hit for `ctx`; `None` when it failed.
+pub(crate) fn record_shadow_check(ctx: &StoreContext, verdict: Option<&ShadowVerdict>) {
+    let outcome = match verdict {
+        Some(verdict) if verdict.agrees => "agree",
+        Some(_) => "disagree",
+        None => "failed",
+    };
+    metrics::counter!(
+        SHADOW_CHECKS_TOTAL,
+        "tenant" => ctx.tenant_id.to_string(),
+        "model" => ctx.model.clone(),
+        "outcome" => outcome,
+    )
+    .increment(1);
+    if let Some(verdict) = verdict {
+        metrics::histogram!(SHADOW_SIMILARITY, "model" => ctx.model.clone())
+            .record(verdict.similarity as f64);
+    }
+}
+
+/// Counts a sampled hit whose shadow check was not run, by `outcome`.
+pub(crate) fn record_shadow_skipped(ctx: &StoreContext, outcome: &'static str) {
+    metrics::counter!(
+        SHADOW_CHECKS_TOTAL,
+        "tenant" => ctx.tenant_id.to_string(),
+        "model" => ctx.model.clone(),
+        "outcome" => outcome,
+    )
+    .increment(1);
+}
+
+/// Counts an entry dropped after its shadow check disagreed.
+pub(crate) fn record_shadow_invalidation(ctx: &StoreContext) {
+    metrics::counter!(
+        SHADOW_INVALIDATIONS_TOTAL,
+        "tenant" => ctx.tenant_id.to_string(),
+        "model" => ctx.model.clone(),
+    )
+    .increment(1);
+}
+
+/// Counts a client verdict on a hit served to `tenant_id`.
+pub(crate) fn record_feedback(tenant_id: u64, verdict: Verdict) {
+    metrics::counter!(
+        FEEDBACK_TOTAL,
+        "tenant" => tenant_id.to_string(),
+        "verdict" => verdict.as_str(),
+    )
+    .increment(1);
+}
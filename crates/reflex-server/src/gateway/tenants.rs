@@ -0,0 +1,298 @@
+//! Tenant registry: API keys mapped to stable tenants and their cache policy.
+//!
+//! Without a registry every distinct bearer token is its own tenant (see
+//! [`tenant_token`]). With one, only listed keys are accepted and all keys of a
+//! tenant share one namespace, so keys can be rotated without orphaning the
+//! tenant's cache.
+//!
+//! The registry is read from `REFLEX_TENANTS_FILE`, TOML or JSON by extension:
+//!
+//! ```toml
+//! [[tenants]]
+//! id = "acme"
+//! api_keys = ["sk-acme-1", "sk-acme-2"]
+//! l3_threshold = 0.85
+//! ttl_secs = 86400
+//! top_k = 5
+//! store = true
+//! max_requests_per_day = 100000
+//! request_rate = "600/min"
+//! miss_rate = "60/min"
+//! semantic_key = "conversation"
+//!
+//! [tenants.provider_keys]
+//! openai = "sk-proj-..."
+//! ```
+
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::sync::Mutex;
+
+use axum::http::HeaderMap;
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::gateway::config_file::{self, ConfigFileError};
+use crate::gateway::error::GatewayError;
+use crate::gateway::handler::{api_key, tenant_token};
+use crate::gateway::provider_keys::{ProviderKey, ProviderKeyMode, ProviderKeys};
+use crate::gateway::rate_limit::Rate;
+use crate::gateway::semantic_key::SemanticKeyStrategy;
+
+/// Environment variable naming the registry file.
+pub const TENANTS_FILE_ENV: &str = "REFLEX_TENANTS_FILE";
+
+const SECS_PER_DAY: i64 = 86_400;
+
+#[derive(Debug, Error)]
+pub enum TenantRegistryError {
+    #[error("tenant registry: {0}")]
+    File(#[from] ConfigFileError),
+
+    #[error("invalid tenant registry: {0}")]
+    Invalid(String),
+}
+
+/// Cache behaviour for one tenant. Unset fields fall back to server defaults.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct TenantPolicy {
+    /// L3 verification threshold, overriding the reranker's.
+    #[serde(default)]
+    pub l3_threshold: Option<f32>,
+    /// Entries older than this are never served.
+    #[serde(default)]
+    pub ttl_secs: Option<u64>,
+    /// Maximum number of L2 candidates handed to L3.
+    #[serde(default)]
+    pub top_k: Option<usize>,
+    /// Whether provider responses may be stored for this tenant.
+    #[serde(default = "default_store")]
+    pub store: bool,
+    /// Requests accepted per UTC day; further requests get a 429.
+    #[serde(default)]
+    pub max_requests_per_day: Option<u64>,
+    /// Token-bucket limit on all requests, e.g. `"600/min"`.
+    #[serde(default)]
+    pub request_rate: Option<Rate>,
+    /// Token-bucket limit on provider calls, e.g. `"60/min"`.
+    #[serde(default)]
+    pub miss_rate: Option<Rate>,
+    /// Semantic key strategy, unless the request's route sets one.
+    #[serde(default)]
+    pub semantic_key: Option<SemanticKeyStrategy>,
+}
+
+fn default_store() -> bool {
+    true
+}
+
+impl Default for TenantPolicy {
+    fn default() -> Self {
+        Self {
+            l3_threshold: None,
+            ttl_secs: None,
+            top_k: None,
+            store: true,
+            max_requests_per_day: None,
+            request_rate: None,
+            miss_rate: None,
+            semantic_key: None,
+        }
+    }
+}
+
+/// The tenant a request is served for.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Tenant {
+    pub tenant_id: u64,
+    pub policy: TenantPolicy,
+    /// Keys this tenant's misses are billed to.
+    pub provider_keys: ProviderKeys,
+}
+
+#[derive(Debug, Deserialize)]
+struct RegistryFile {
+    #[serde(default)]
+    tenants: Vec<TenantEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TenantEntry {
+    id: String,
+    api_keys: Vec<String>,
+    #[serde(default)]
+    provider_keys: HashMap<String, ProviderKey>,
+    #[serde(flatten)]
+    policy: TenantPolicy,
+}
+
+/// API keys and the tenants they belong to.
+///
+/// Keys are held as blake3 hashes, never in plain text.
+#[derive(Debug, Default)]
+pub struct TenantRegistry {
+    keys: HashMap<blake3::Hash, Tenant>,
+    /// `tenant_id -> (UTC day, requests that day)` for tenants with a quota.
+    usage: Mutex<HashMap<u64, (i64, u64)>>,
+}
+
+impl TenantRegistry {
+    /// Loads the registry named by `REFLEX_TENANTS_FILE`, if set.
+    pub fn from_env() -> Result<Option<Self>, TenantRegistryError> {
+        config_file::path_from_env(TENANTS_FILE_ENV)
+            .map(|path| Self::from_file(&path))
+            .transpose()
+    }
+
+    /// Loads a `.toml` or `.json` registry file.
+    pub fn from_file(path: &Path) -> Result<Self, TenantRegistryError> {
+        let file: RegistryFile = config_file::load(path)?;
+        Self::from_entries(file.tenants)
+    }
+
+    /// Parses a TOML registry.
+    pub fn from_toml_str(contents: &str) -> Result<Self, TenantRegistryError> {
+        let file: RegistryFile =
+            toml::from_str(contents).map_err(|e| TenantRegistryError::Invalid(e.to_string()))?;
+        Self::from_entries(file.tenants)
+    }
+
+    fn from_entries(entries: Vec<TenantEntry>) -> Result<Self, TenantRegistryError> {
+        let mut ids = HashSet::new();
+        let mut keys = HashMap::new();
+
+        for entry in entries {
+            let id = entry.id.trim();
+            if id.is_empty() {
+                return Err(TenantRegistryError::Invalid(
+                    "tenant id must not be empty".to_string(),
+                ));
+            }
+            if entry.api_keys.is_empty() {
+                return Err(TenantRegistryError::Invalid(format!(
+                    "tenant '{}' has no api_keys",
+                    id
+                )));
+            }
+            if let Some(threshold) = entry.policy.l3_threshold
+                && !(0.0..=1.0).contains(&threshold)
+            {
+                return Err(TenantRegistryError::Invalid(format!(
+                    "tenant '{}': l3_threshold must be between 0.0 and 1.0",
+                    id
+                )));
+            }
+            if entry.policy.top_k == Some(0) {
+                return Err(TenantRegistryError::Invalid(format!(
+                    "tenant '{}': top_k must be at least 1",
+                    id
+                )));
+            }
+
+            let tenant_id = reflex::hashing::hash_tenant_id(id);
+            if !ids.insert(tenant_id) {
+                return Err(TenantRegistryError::Invalid(format!(
+                    "duplicate tenant id '{}'",
+                    id
+                )));
+            }
+
+            let provider_keys = ProviderKeys::for_tenant(entry.provider_keys)
+                .map_err(|e| TenantRegistryError::Invalid(format!("tenant '{}': {}", id, e)))?;
+            let tenant = Tenant {
+                tenant_id,
+                policy: entry.policy,
+                provider_keys,
+            };
+            for key in &entry.api_keys {
+                let key = key.trim();
+                if key.is_empty() {
+                    return Err(TenantRegistryError::Invalid(format!(
+                        "tenant '{}' has an empty api key",
+                        id
+                    )));
+                }
+                if keys
+                    .insert(blake3::hash(key.as_bytes()), tenant.clone())
+                    .is_some()
+                {
+                    return Err(TenantRegistryError::Invalid(format!(
+                        "an api key of tenant '{}' is already registered",
+                        id
+                    )));
+                }
+            }
+        }
+
+        Ok(Self {
+            keys,
+            usage: Mutex::default(),
+        })
+    }
+
+    /// Number of registered API keys.
+    pub fn key_count(&self) -> usize {
+        self.keys.len()
+    }
+
+    /// Returns the tenant owning `api_key`.
+    pub fn lookup(&self, api_key: &str) -> Option<&Tenant> {
+        self.keys.get(&blake3::hash(api_key.trim().as_bytes()))
+    }
+
+    /// Resolves the caller's key.
+    ///
+    /// The request is not counted against the tenant's quota until it is
+    /// admitted; see [`TenantRegistry::charge`].
+    pub fn authenticate(&self, headers: &HeaderMap) -> Result<Tenant, GatewayError> {
+        api_key(headers)
+            .and_then(|key| self.lookup(key))
+            .cloned()
+            .ok_or_else(|| GatewayError::Unauthorized("unknown API key".to_string()))
+    }
+
+    /// Counts one request for `tenant` at Unix time `now`.
+    pub fn charge(&self, tenant: &Tenant, now: i64) -> Result<(), GatewayError> {
+        let Some(limit) = tenant.policy.max_requests_per_day else {
+            return Ok(());
+        };
+
+        let day = now.div_euclid(SECS_PER_DAY);
+        let mut usage = self.usage.lock().expect("lock poisoned");
+        let (window, count) = usage.entry(tenant.tenant_id).or_insert((day, 0));
+        if *window != day {
+            *window = day;
+            *count = 0;
+        }
+        if *count >= limit {
+            return Err(GatewayError::QuotaExceeded {
+                message: format!("daily request quota of {} exhausted", limit),
+                retry_after: ((day + 1) * SECS_PER_DAY - now) as u64,
+            });
+        }
+        *count += 1;
+        Ok(())
+    }
+}
+
+/// Resolves the tenant for a request, and the provider keys it may use.
+///
+/// With a registry, unknown or missing keys are rejected. Without one, the
+/// tenant is derived from the raw token and gets the default policy.
+pub(crate) fn resolve_tenant(
+    registry: Option<&TenantRegistry>,
+    key_mode: ProviderKeyMode,
+    headers: &HeaderMap,
+) -> Result<Tenant, GatewayError> {
+    let mut tenant = match registry {
+        Some(registry) => registry.authenticate(headers)?,
+        None => Tenant {
+            tenant_id: reflex::hashing::hash_tenant_id(&tenant_token(headers)),
+            ..Tenant::default()
+        },
+    };
+    tenant.provider_keys = tenant
+        .provider_keys
+        .with_caller(key_mode, headers, registry.is_some());
+    Ok(tenant)
+}
@@ -0,0 +1,217 @@
+//! Tests for the tenant registry.
+
+use async_openai::types::chat::CreateChatCompletionRequest;
+use axum::http::{HeaderMap, HeaderValue};
+
+use crate::gateway::config_file::ConfigFileError;
+use crate::gateway::error::GatewayError;
+use crate::gateway::handler::StoreContext;
+use crate::gateway::provider_keys::ProviderKeyMode;
+use crate::gateway::tenants::{TenantPolicy, TenantRegistry, TenantRegistryError, resolve_tenant};
+
+const REGISTRY: &str = r#"
+[[tenants]]
+id = "acme"
+api_keys = ["sk-acme-old", "sk-acme-new"]
+l3_threshold = 0.9
+ttl_secs = 3600
+top_k = 3
+store = false
+
+[[tenants]]
+id = "globex"
+api_keys = ["sk-globex"]
+max_requests_per_day = 2
+"#;
+
+fn bearer(key: &str) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        "Authorization",
+        HeaderValue::from_str(&format!("Bearer {}", key)).unwrap(),
+    );
+    headers
+}
+
+#[test]
+fn test_keys_of_one_tenant_share_a_stable_id() {
+    let registry = TenantRegistry::from_toml_str(REGISTRY).unwrap();
+    let old = registry.lookup("sk-acme-old").unwrap();
+    let new = registry.lookup("sk-acme-new").unwrap();
+
+    assert_eq!(old, new);
+    assert_eq!(old.tenant_id, reflex::hashing::hash_tenant_id("acme"));
+    assert_eq!(
+        old.policy,
+        TenantPolicy {
+            l3_threshold: Some(0.9),
+            ttl_secs: Some(3600),
+            top_k: Some(3),
+            store: false,
+            max_requests_per_day: None,
+            request_rate: None,
+            miss_rate: None,
+            semantic_key: None,
+        }
+    );
+    assert_eq!(registry.key_count(), 3);
+}
+
+#[test]
+fn test_unset_policy_fields_use_defaults() {
+    let registry = TenantRegistry::from_toml_str(REGISTRY).unwrap();
+    let globex = registry.lookup("sk-globex").unwrap();
+
+    assert!(globex.policy.store);
+    assert_eq!(globex.policy.l3_threshold, None);
+}
+
+#[test]
+fn test_unknown_and_missing_keys_are_rejected() {
+    let registry = TenantRegistry::from_toml_str(REGISTRY).unwrap();
+
+    assert!(matches!(
+        registry.authenticate(&bearer("sk-guess")),
+        Err(GatewayError::Unauthorized(_))
+    ));
+    assert!(matches!(
+        registry.authenticate(&HeaderMap::new()),
+        Err(GatewayError::Unauthorized(_))
+    ));
+
+    let mut headers = HeaderMap::new();
+    headers.insert("x-api-key", HeaderValue::from_static("sk-acme-new"));
+    assert!(registry.authenticate(&headers).is_ok());
+}
+
+#[test]
+fn test_without_registry_any_token_is_its_own_tenant() {
+    let tenant = resolve_tenant(None, ProviderKeyMode::Server, &bearer("sk-anything")).unwrap();
+
+    assert_eq!(
+        tenant.tenant_id,
+        reflex::hashing::hash_tenant_id("sk-anything")
+    );
+    assert_eq!(tenant.policy, TenantPolicy::default());
+    assert_eq!(
+        resolve_tenant(None, ProviderKeyMode::Server, &HeaderMap::new())
+            .unwrap()
+            .tenant_id,
+        reflex::hashing::hash_tenant_id("default")
+    );
+}
+
+#[test]
+fn test_daily_quota_resets_at_utc_midnight() {
+    let registry = TenantRegistry::from_toml_str(REGISTRY).unwrap();
+    let globex = registry.lookup("sk-globex").unwrap().clone();
+    let day = 20_000 * 86_400;
+
+    assert!(registry.charge(&globex, day).is_ok());
+    assert!(registry.charge(&globex, day + 10).is_ok());
+    assert!(matches!(
+        registry.charge(&globex, day + 20),
+        Err(GatewayError::QuotaExceeded {
+            retry_after: 86_380,
+            ..
+        })
+    ));
+    assert!(registry.charge(&globex, day + 86_400).is_ok());
+}
+
+#[test]
+fn test_exhausted_quota_sends_retry_after() {
+    use axum::response::IntoResponse;
+
+    let response = GatewayError::QuotaExceeded {
+        message: "daily request quota of 1 exhausted".to_string(),
+        retry_after: 3600,
+    }
+    .into_response();
+
+    assert_eq!(response.status(), 429);
+    assert_eq!(response.headers()["retry-after"], "3600");
+}
+
+#[test]
+fn test_invalid_registries_are_refused() {
+    let duplicate_key = r#"
+        [[tenants]]
+        id = "a"
+        api_keys = ["sk-shared"]
+
+        [[tenants]]
+        id = "b"
+        api_keys = ["sk-shared"]
+    "#;
+    let bad_threshold = r#"
+        [[tenants]]
+        id = "a"
+        api_keys = ["sk-a"]
+        l3_threshold = 1.5
+    "#;
+    let no_keys = r#"
+        [[tenants]]
+        id = "a"
+        api_keys = []
+    "#;
+
+    for contents in [duplicate_key, bad_threshold, no_keys] {
+        assert!(matches!(
+            TenantRegistry::from_toml_str(contents),
+            Err(TenantRegistryError::Invalid(_))
+        ));
+    }
+}
+
+#[test]
+fn test_from_file_reads_json_and_toml() {
+    let dir = tempfile::tempdir().unwrap();
+    let json_path = dir.path().join("tenants.json");
+    std::fs::write(
+        &json_path,
+        r#"{"tenants": [{"id": "acme", "api_keys": ["sk-acme"], "top_k": 2}]}"#,
+    )
+    .unwrap();
+    let toml_path = dir.path().join("tenants.toml");
+    std::fs::write(&toml_path, REGISTRY).unwrap();
+
+    let from_json = TenantRegistry::from_file(&json_path).unwrap();
+    let from_toml = TenantRegistry::from_file(&toml_path).unwrap();
+
+    assert_eq!(from_json.lookup("sk-acme").unwrap().policy.top_k, Some(2));
+    assert_eq!(from_toml.key_count(), 3);
+    assert!(matches!(
+        TenantRegistry::from_file(&dir.path().join("tenants.yaml")),
+        Err(TenantRegistryError::File(ConfigFileError::Io { .. }))
+    ));
+    std::fs::write(dir.path().join("tenants.yaml"), "").unwrap();
+    assert!(matches!(
+        TenantRegistry::from_file(&dir.path().join("tenants.yaml")),
+        Err(TenantRegistryError::File(
+            ConfigFileError::UnsupportedFormat { .. }
+        ))
+    ));
+}
+
+#[test]
+fn test_policy_combines_with_cache_control() {
+    let registry = TenantRegistry::from_toml_str(REGISTRY).unwrap();
+    let acme = registry.lookup("sk-acme-old").unwrap().clone();
+    let request: CreateChatCompletionRequest = serde_json::from_value(serde_json::json!({
+        "model": "gpt-4o",
+        "messages": [{"role": "user", "content": "hi"}]
+    }))
+    .unwrap();
+
+    let mut headers = bearer("sk-acme-old");
+    headers.insert("Cache-Control", HeaderValue::from_static("max-age=60"));
+    let strict = StoreContext::new(acme.clone(), &headers, &request).unwrap();
+    headers.insert("Cache-Control", HeaderValue::from_static("max-age=86400"));
+    let loose = StoreContext::new(acme, &headers, &request).unwrap();
+
+    assert_eq!(strict.lookup_options().max_age_secs, Some(60));
+    assert_eq!(loose.lookup_options().max_age_secs, Some(3600));
+    assert_eq!(loose.lookup_options().top_k, Some(3));
+    assert!(!loose.should_store());
+}
@@ -0,0 +1,374 @@
+//! Retries, deadlines and circuit breaking for provider calls.
+//!
+//! Every provider call goes through [`Upstream::call`]. A call that fails with
+//! a transient error (HTTP 429 or 5xx, a failed connection, or no answer within
+//! its timeout) is retried after a full-jitter exponential backoff; any other
+//! error is returned at once.
+//!
+//! Transient failures other than 429s also count against a circuit breaker per
+//! provider (`openai`, `anthropic`, ...). A 429 is about the key's quota rather
+//! than the provider's health, and so is any failure of a call made with a
+//! caller's or tenant's own key: neither counts, so one tenant's exhausted key
+//! cannot open the circuit for everyone. After `failure_threshold` consecutive failures
+//! the circuit opens and calls to that provider fail fast with a 503 for
+//! `cooldown`. The first call after the cooldown is let through as a probe:
+//! success closes the circuit, failure opens it again.
+//!
+//! With `REFLEX_DEGRADED_L3_THRESHOLD` set, a miss whose provider circuit is
+//! open is retried against the cache at that looser L3 threshold and, if
+//! something verifies, served with `X-Reflex-Status: HIT_DEGRADED`.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::str::FromStr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use genai::adapter::AdapterKind;
+use thiserror::Error;
+use tracing::{info, warn};
+
+use crate::gateway::error::GatewayError;
+use crate::gateway::provider_keys::provider_name;
+use crate::gateway::telemetry;
+
+pub const PROVIDER_TIMEOUT_ENV: &str = "REFLEX_PROVIDER_TIMEOUT_SECS";
+pub const PROVIDER_MAX_RETRIES_ENV: &str = "REFLEX_PROVIDER_MAX_RETRIES";
+pub const CIRCUIT_FAILURE_THRESHOLD_ENV: &str = "REFLEX_CIRCUIT_FAILURE_THRESHOLD";
+pub const CIRCUIT_COOLDOWN_ENV: &str = "REFLEX_CIRCUIT_COOLDOWN_SECS";
+pub const DEGRADED_THRESHOLD_ENV: &str = "REFLEX_DEGRADED_L3_THRESHOLD";
+
+#[derive(Debug, Error)]
+#[error("invalid {name} '{value}': {message}")]
+pub struct UpstreamConfigError {
+    pub name: &'static str,
+    pub value: String,
+    pub message: String,
+}
+
+/// Retry, timeout and circuit breaker settings shared by all providers.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UpstreamConfig {
+    /// Deadline for one provider call, unless the model sets its own.
+    pub timeout: Option<Duration>,
+    /// Retries after the first attempt.
+    pub max_retries: u32,
+    /// Backoff ceiling before the first retry; doubles with each retry.
+    pub base_backoff: Duration,
+    /// Upper bound on any one backoff.
+    pub max_backoff: Duration,
+    /// Consecutive transient failures that open a circuit; 0 disables it.
+    pub failure_threshold: u32,
+    /// How long an open circuit rejects calls before letting a probe through.
+    pub cooldown: Duration,
+    /// L3 threshold for hits served while a circuit is open.
+    pub degraded_threshold: Option<f32>,
+}
+
+impl Default for UpstreamConfig {
+    fn default() -> Self {
+        Self {
+            timeout: None,
+            max_retries: 2,
+            base_backoff: Duration::from_millis(250),
+            max_backoff: Duration::from_secs(5),
+            failure_threshold: 5,
+            cooldown: Duration::from_secs(30),
+            degraded_threshold: None,
+        }
+    }
+}
+
+impl UpstreamConfig {
+    /// Reads the `REFLEX_PROVIDER_*`, `REFLEX_CIRCUIT_*` and
+    /// `REFLEX_DEGRADED_L3_THRESHOLD` variables over the defaults.
+    pub fn from_env() -> Result<Self, UpstreamConfigError> {
+        let mut config = Self::default();
+        if let Some(secs) = env_value::<u64>(PROVIDER_TIMEOUT_ENV)? {
+            config.timeout = Some(Duration::from_secs(secs));
+        }
+        if let Some(retries) = env_value(PROVIDER_MAX_RETRIES_ENV)? {
+            config.max_retries = retries;
+        }
+        if let Some(failures) = env_value(CIRCUIT_FAILURE_THRESHOLD_ENV)? {
+            config.failure_threshold = failures;
+        }
+        if let Some(secs) = env_value::<u64>(CIRCUIT_COOLDOWN_ENV)? {
+            config.cooldown = Duration::from_secs(secs);
+        }
+        if let Some(threshold) = env_value::<f32>(DEGRADED_THRESHOLD_ENV)? {
+            if !(0.0..=1.0).contains(&threshold) {
+                return Err(UpstreamConfigError {
+                    name: DEGRADED_THRESHOLD_ENV,
+                    value: threshold.to_string(),
+                    message: "must be between 0.0 and 1.0".to_string(),
+                });
+            }
+            config.degraded_threshold = Some(threshold);
+        }
+        Ok(config)
+    }
+
+    /// A random delay before retry number `retry` (0-based).
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let ceiling = self
+            .base_backoff
+            .saturating_mul(2u32.saturating_pow(retry))
+            .min(self.max_backoff);
+        ceiling.mul_f64(fastrand::f64())
+    }
+}
+
+fn env_value<T: FromStr>(name: &'static str) -> Result<Option<T>, UpstreamConfigError>
+where
+    T::Err: std::fmt::Display,
+{
+    match std::env::var(name) {
+        Ok(value) if !value.trim().is_empty() => {
+            value
+                .trim()
+                .parse()
+                .map(Some)
+                .map_err(|e: T::Err| UpstreamConfigError {
+                    name,
+                    message: e.to_string(),
+                    value,
+                })
+        }
+        _ => Ok(None),
+    }
+}
+
+/// A failed provider call.
+#[derive(Debug)]
+pub(crate) struct ProviderFailure {
+    /// What the client sees if the call is not retried.
+    pub error: GatewayError,
+    /// Whether the call may succeed if it is retried.
+    pub transient: bool,
+    /// Whether the failure says something about the provider's health.
+    pub unhealthy: bool,
+}
+
+impl ProviderFailure {
+    /// A server error, failed connection or timeout.
+    pub fn transient(error: GatewayError) -> Self {
+        Self {
+            error,
+            transient: true,
+            unhealthy: true,
+        }
+    }
+
+    /// A 429: worth retrying, but the provider itself is fine.
+    pub fn throttled(error: GatewayError) -> Self {
+        Self {
+            error,
+            transient: true,
+            unhealthy: false,
+        }
+    }
+
+    /// Classifies a genai error, reporting it to the client as `message`.
+    pub fn from_genai(e: &genai::Error, message: &str) -> Self {
+        let error = GatewayError::ProviderError(message.to_string());
+        let webc_error = match e {
+            genai::Error::WebModelCall { webc_error, .. }
+            | genai::Error::WebAdapterCall { webc_error, .. } => webc_error,
+            _ => return error.into(),
+        };
+        match webc_error {
+            genai::webc::Error::ResponseFailedStatus { status, .. } if status.as_u16() == 429 => {
+                Self::throttled(error)
+            }
+            genai::webc::Error::ResponseFailedStatus { status, .. } if status.is_server_error() => {
+                Self::transient(error)
+            }
+            genai::webc::Error::Reqwest(e) if e.is_connect() || e.is_timeout() => {
+                Self::transient(error)
+            }
+            _ => error.into(),
+        }
+    }
+}
+
+impl From<GatewayError> for ProviderFailure {
+    fn from(error: GatewayError) -> Self {
+        Self {
+            error,
+            transient: false,
+            unhealthy: false,
+        }
+    }
+}
+
+/// Provider a model is served by, as named in circuit metrics and errors.
+pub fn provider_of(model: &str) -> &'static str {
+    AdapterKind::from_model(model)
+        .map(provider_name)
+        .unwrap_or("unknown")
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Circuit {
+    Closed {
+        failures: u32,
+    },
+    Open {
+        until: Instant,
+    },
+    /// A probe was let through at `since` and has not reported back.
+    HalfOpen {
+        since: Instant,
+    },
+}
+
+/// Retry policy and circuit state for every provider.
+#[derive(Debug, Default)]
+pub struct Upstream {
+    config: UpstreamConfig,
+    circuits: Mutex<HashMap<&'static str, Circuit>>,
+}
+
+impl Upstream {
+    pub fn new(config: UpstreamConfig) -> Self {
+        Self {
+            config,
+            circuits: Mutex::default(),
+        }
+    }
+
+    pub fn config(&self) -> &UpstreamConfig {
+        &self.config
+    }
+
+    /// Runs `call` against `model`, retrying transient failures.
+    ///
+    /// `timeout` overrides the configured deadline for each attempt. Fails
+    /// with [`GatewayError::ProviderUnavailable`] while the provider's circuit
+    /// is open. `own_key` is true when the call is made with a caller's or
+    /// tenant's key, whose failures are kept off the shared circuit.
+    pub(crate) async fn call<T, F, Fut>(
+        &self,
+        model: &str,
+        timeout: Option<Duration>,
+        own_key: bool,
+        mut call: F,
+    ) -> Result<T, GatewayError>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, ProviderFailure>>,
+    {
+        let provider = provider_of(model);
+        let timeout = timeout.or(self.config.timeout);
+        let mut retries = 0;
+        loop {
+            self.admit(provider, Instant::now())?;
+            let result = match timeout {
+                Some(timeout) => tokio::time::timeout(timeout, call())
+                    .await
+                    .unwrap_or_else(|_| {
+                        Err(ProviderFailure::transient(GatewayError::ProviderError(
+                            format!("{} did not answer within {:?}", model, timeout),
+                        )))
+                    }),
+                None => call().await,
+            };
+
+            let failure = match result {
+                Ok(value) => {
+                    self.record(provider, true, Instant::now());
+                    return Ok(value);
+                }
+                Err(failure) => failure,
+            };
+            // A rejected or throttled request still proves the provider is
+            // reachable.
+            if !failure.unhealthy {
+                self.record(provider, true, Instant::now());
+            } else if !own_key {
+                self.record(provider, false, Instant::now());
+            }
+            if !failure.transient || retries >= self.config.max_retries {
+                return Err(failure.error);
+            }
+
+            let delay = self.config.backoff(retries);
+            retries += 1;
+            telemetry::record_provider_retry(provider);
+            warn!(model = %model, retry = retries, delay_ms = delay.as_millis() as u64, error = %failure.error, "Retrying provider call");
+            tokio::time::sleep(delay).await;
+        }
+    }
+
+    /// Lets a call to `provider` through unless its circuit is open.
+    pub(crate) fn admit(&self, provider: &'static str, now: Instant) -> Result<(), GatewayError> {
+        if self.config.failure_threshold == 0 {
+            return Ok(());
+        }
+
+        let mut circuits = self.circuits.lock().expect("lock poisoned");
+        let circuit = circuits
+            .entry(provider)
+            .or_insert(Circuit::Closed { failures: 0 });
+        let wait = match *circuit {
+            Circuit::Closed { .. } => return Ok(()),
+            Circuit::Open { until } if now < until => until - now,
+            // A probe that never reported back (e.g. its client went away)
+            // is replaced after another cooldown.
+            Circuit::HalfOpen { since } if now < since + self.config.cooldown => {
+                Duration::from_secs(1)
+            }
+            Circuit::Open { .. } | Circuit::HalfOpen { .. } => {
+                *circuit = Circuit::HalfOpen { since: now };
+                info!(provider, "Circuit half-open, probing provider");
+                return Ok(());
+            }
+        };
+
+        Err(GatewayError::ProviderUnavailable {
+            message: format!("{} circuit is open", provider),
+            retry_after: wait.as_secs_f64().ceil().max(1.0) as u64,
+        })
+    }
+
+    /// Records the outcome of a call to `provider` made at `now`.
+    pub(crate) fn record(&self, provider: &'static str, healthy: bool, now: Instant) {
+        if self.config.failure_threshold == 0 {
+            return;
+        }
+
+        let mut circuits = self.circuits.lock().expect("lock poisoned");
+        let circuit = circuits
+            .entry(provider)
+            .or_insert(Circuit::Closed { failures: 0 });
+        let next = match (*circuit, healthy) {
+            (Circuit::Closed { .. }, true) => Circuit::Closed { failures: 0 },
+            (_, true) => {
+                info!(provider, "Circuit closed");
+                telemetry::record_circuit(provider, "closed");
+                Circuit::Closed { failures: 0 }
+            }
+            (Circuit::Closed { failures }, false)
+                if failures + 1 < self.config.failure_threshold =>
+            {
+                Circuit::Closed {
+                    failures: failures + 1,
+                }
+            }
+            (Circuit::Open { until }, false) => Circuit::Open { until },
+            (_, false) => {
+                warn!(
+                    provider,
+                    cooldown_secs = self.config.cooldown.as_secs(),
+                    "Circuit opened"
+                );
+                telemetry::record_circuit(provider, "open");
+                Circuit::Open {
+                    until: now + self.config.cooldown,
+                }
+            }
+        };
+        *circuit = next;
+    }
+}
@@ -0,0 +1,322 @@
+//! Tests for provider retries, timeouts and circuit breaking.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::{Duration, Instant};
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use tempfile::TempDir;
+use tower::ServiceExt;
+
+use crate::gateway::create_router_with_state;
+use crate::gateway::error::GatewayError;
+use crate::gateway::state::HandlerState;
+use crate::gateway::upstream::{ProviderFailure, Upstream, UpstreamConfig, provider_of};
+use reflex::cache::{L1CacheHandle, L2Config, L2SemanticCache, NvmeStorageLoader, TieredCache};
+use reflex::embedding::RerankerConfig;
+use reflex::embedding::sinter::{SinterConfig, SinterEmbedder};
+use reflex::scoring::CrossEncoderScorer;
+use reflex::vectordb::bq::MockBqClient;
+
+const COLLECTION: &str = "upstream_test_collection";
+
+fn config() -> UpstreamConfig {
+    UpstreamConfig {
+        max_retries: 2,
+        base_backoff: Duration::from_millis(1),
+        max_backoff: Duration::from_millis(2),
+        failure_threshold: 3,
+        cooldown: Duration::from_secs(30),
+        ..UpstreamConfig::default()
+    }
+}
+
+fn unavailable(result: Result<(), GatewayError>) -> u64 {
+    match result {
+        Err(GatewayError::ProviderUnavailable { retry_after, .. }) => retry_after,
+        other => panic!("expected ProviderUnavailable, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_backoff_is_capped_and_jittered() {
+    let config = UpstreamConfig {
+        base_backoff: Duration::from_millis(100),
+        max_backoff: Duration::from_millis(300),
+        ..UpstreamConfig::default()
+    };
+    for _ in 0..100 {
+        assert!(config.backoff(0) <= Duration::from_millis(100));
+        assert!(config.backoff(5) <= Duration::from_millis(300));
+    }
+}
+
+#[tokio::test]
+async fn test_call_retries_transient_failures() {
+    let upstream = Upstream::new(config());
+    let attempts = AtomicU32::new(0);
+
+    let result = upstream
+        .call("gpt-4o", None, false, || async {
+            if attempts.fetch_add(1, Ordering::SeqCst) < 2 {
+                Err(ProviderFailure::transient(GatewayError::ProviderError(
+                    "503".to_string(),
+                )))
+            } else {
+                Ok("answer")
+            }
+        })
+        .await;
+
+    assert_eq!(result.unwrap(), "answer");
+    assert_eq!(attempts.load(Ordering::SeqCst), 3);
+}
+
+#[tokio::test]
+async fn test_call_returns_fatal_failures_without_retrying() {
+    let upstream = Upstream::new(config());
+    let attempts = AtomicU32::new(0);
+
+    let result: Result<(), _> = upstream
+        .call("gpt-4o", None, false, || async {
+            attempts.fetch_add(1, Ordering::SeqCst);
+            Err(GatewayError::ProviderError("400".to_string()).into())
+        })
+        .await;
+
+    assert!(matches!(result, Err(GatewayError::ProviderError(_))));
+    assert_eq!(attempts.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn test_call_retries_timed_out_attempts() {
+    let upstream = Upstream::new(config());
+    let attempts = AtomicU32::new(0);
+
+    let result = upstream
+        .call("gpt-4o", Some(Duration::from_millis(20)), false, || async {
+            if attempts.fetch_add(1, Ordering::SeqCst) == 0 {
+                tokio::time::sleep(Duration::from_secs(5)).await;
+            }
+            Ok(())
+        })
+        .await;
+
+    assert!(result.is_ok());
+    assert_eq!(attempts.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn test_open_circuit_stops_retries() {
+    let upstream = Upstream::new(UpstreamConfig {
+        max_retries: 10,
+        ..config()
+    });
+    let attempts = AtomicU32::new(0);
+
+    let result: Result<(), _> = upstream
+        .call("gpt-4o", None, false, || async {
+            attempts.fetch_add(1, Ordering::SeqCst);
+            Err(ProviderFailure::transient(GatewayError::ProviderError(
+                "503".to_string(),
+            )))
+        })
+        .await;
+
+    assert!(matches!(
+        result,
+        Err(GatewayError::ProviderUnavailable { .. })
+    ));
+    assert_eq!(attempts.load(Ordering::SeqCst), 3);
+}
+
+#[tokio::test]
+async fn test_throttling_and_own_key_failures_leave_the_circuit_closed() {
+    let upstream = Upstream::new(config());
+
+    for _ in 0..5 {
+        let throttled: Result<(), _> = upstream
+            .call("gpt-4o", None, false, || async {
+                Err(ProviderFailure::throttled(GatewayError::ProviderError(
+                    "429".to_string(),
+                )))
+            })
+            .await;
+        assert!(matches!(throttled, Err(GatewayError::ProviderError(_))));
+
+        let own_key: Result<(), _> = upstream
+            .call("gpt-4o", None, true, || async {
+                Err(ProviderFailure::transient(GatewayError::ProviderError(
+                    "503".to_string(),
+                )))
+            })
+            .await;
+        assert!(matches!(own_key, Err(GatewayError::ProviderError(_))));
+    }
+
+    assert!(upstream.admit("openai", Instant::now()).is_ok());
+}
+
+#[test]
+fn test_circuit_opens_probes_and_closes() {
+    let upstream = Upstream::new(config());
+    let start = Instant::now();
+
+    for _ in 0..3 {
+        upstream.admit("openai", start).unwrap();
+        upstream.record("openai", false, start);
+    }
+    assert_eq!(unavailable(upstream.admit("openai", start)), 30);
+    assert!(upstream.admit("anthropic", start).is_ok());
+
+    // After the cooldown one probe goes through; others wait for it.
+    let later = start + Duration::from_secs(30);
+    upstream.admit("openai", later).unwrap();
+    unavailable(upstream.admit("openai", later));
+
+    // A failed probe reopens the circuit for a full cooldown.
+    upstream.record("openai", false, later);
+    assert_eq!(unavailable(upstream.admit("openai", later)), 30);
+
+    let probe = later + Duration::from_secs(30);
+    upstream.admit("openai", probe).unwrap();
+    upstream.record("openai", true, probe);
+    assert!(upstream.admit("openai", probe).is_ok());
+}
+
+#[test]
+fn test_healthy_calls_reset_the_failure_count() {
+    let upstream = Upstream::new(config());
+    let now = Instant::now();
+
+    for _ in 0..5 {
+        upstream.record("openai", false, now);
+        upstream.record("openai", false, now);
+        upstream.record("openai", true, now);
+    }
+    assert!(upstream.admit("openai", now).is_ok());
+}
+
+#[test]
+fn test_zero_failure_threshold_disables_the_breaker() {
+    let upstream = Upstream::new(UpstreamConfig {
+        failure_threshold: 0,
+        ..config()
+    });
+    let now = Instant::now();
+
+    for _ in 0..10 {
+        upstream.record("openai", false, now);
+    }
+    assert!(upstream.admit("openai", now).is_ok());
+}
+
+#[test]
+fn test_provider_of_uses_adapter_names() {
+    assert_eq!(provider_of("gpt-4o"), "openai");
+    assert_eq!(provider_of("claude-3-5-haiku-latest"), "anthropic");
+}
+
+/// A mock-provider state whose scorer never verifies distinct prompts.
+async fn strict_state(
+    upstream: UpstreamConfig,
+) -> (HandlerState<MockBqClient, NvmeStorageLoader>, TempDir) {
+    let temp_dir = TempDir::new().unwrap();
+    let storage_path = temp_dir.path().to_path_buf();
+
+    let bq_client = MockBqClient::new();
+    bq_client
+        .ensure_bq_collection(COLLECTION, reflex::constants::DEFAULT_VECTOR_SIZE_U64)
+        .await
+        .unwrap();
+    let loader = NvmeStorageLoader::new(storage_path.clone());
+    let embedder = SinterEmbedder::load(SinterConfig::stub()).unwrap();
+    let l2_cache = L2SemanticCache::new(
+        embedder,
+        bq_client.clone(),
+        loader,
+        L2Config::default().collection_name(COLLECTION),
+    )
+    .unwrap();
+    let tiered_cache = Arc::new(TieredCache::new(L1CacheHandle::new(), l2_cache));
+    let scorer =
+        Arc::new(CrossEncoderScorer::new(RerankerConfig::stub().with_threshold(1.0)).unwrap());
+
+    let state = HandlerState::new_with_mock_provider(
+        tiered_cache,
+        scorer,
+        storage_path,
+        bq_client,
+        COLLECTION.to_string(),
+        true,
+    )
+    .with_upstream(upstream);
+    (state, temp_dir)
+}
+
+async fn chat(
+    state: &HandlerState<MockBqClient, NvmeStorageLoader>,
+    prompt: &str,
+) -> (StatusCode, String, Option<String>) {
+    let body = serde_json::json!({
+        "model": "gpt-4o",
+        "messages": [{"role": "user", "content": prompt}]
+    });
+    let response = create_router_with_state(state.clone())
+        .oneshot(
+            Request::post("/v1/chat/completions")
+                .header("content-type", "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let header = |name: &str| {
+        response
+            .headers()
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string)
+    };
+    let status = header("x-reflex-status").unwrap_or_default();
+    (response.status(), status, header("retry-after"))
+}
+
+#[tokio::test]
+async fn test_open_circuit_serves_degraded_hit() {
+    let (state, _dir) = strict_state(UpstreamConfig {
+        failure_threshold: 1,
+        degraded_threshold: Some(0.0),
+        ..config()
+    })
+    .await;
+
+    let (_, status, _) = chat(&state, "What is the capital of France?").await;
+    assert_eq!(status, "MISS");
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    let (_, status, _) = chat(&state, "Name the capital city of France").await;
+    assert_eq!(status, "MISS");
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    state.upstream.record("openai", false, Instant::now());
+    let (code, status, _) = chat(&state, "France's capital is which city?").await;
+    assert_eq!(code, StatusCode::OK);
+    assert_eq!(status, "HIT_DEGRADED");
+}
+
+#[tokio::test]
+async fn test_open_circuit_without_degraded_threshold_is_503() {
+    let (state, _dir) = strict_state(UpstreamConfig {
+        failure_threshold: 1,
+        ..config()
+    })
+    .await;
+
+    state.upstream.record("openai", false, Instant::now());
+    let (code, status, retry_after) = chat(&state, "Anything at all").await;
+    assert_eq!(code, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(status, "provider_unavailable");
+    assert_eq!(retry_after.as_deref(), Some("30"));
+}
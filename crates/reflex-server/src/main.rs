@@ -14,7 +14,10 @@ use reflex::embedding::{RerankerConfig, SinterConfig, SinterEmbedder};
 use reflex::lifecycle::{LifecycleConfig, LifecycleManager, build_cloud_ops};
 use reflex::scoring::CrossEncoderScorer;
 use reflex::vectordb::bq::{BQ_COLLECTION_NAME, BqBackend, BqConfig};
-use reflex_server::gateway::{HandlerState, create_router_with_state};
+use reflex_server::gateway::{
+    HandlerState, MediaConfig, ModelRouter, PriceTable, RateLimitConfig, SamplingPolicy,
+    ShadowConfig, TenantRegistry, UpstreamConfig, create_router_with_state,
+};
 
 #[global_allocator]
 static GLOBAL: MiMalloc = MiMalloc;
@@ -99,13 +102,31 @@ async fn main() -> anyhow::Result<()> {
     let reranker_config = RerankerConfig::from_env();
     let scorer = Arc::new(CrossEncoderScorer::new(reranker_config)?);
 
+    let tenants = TenantRegistry::from_env()?.map(Arc::new);
+    if let Some(registry) = &tenants {
+        tracing::info!(keys = registry.key_count(), "Tenant registry loaded");
+    }
+    let router = ModelRouter::from_env()?.unwrap_or_default();
+    let prices = PriceTable::from_env()?.unwrap_or_default();
+    if !prices.is_empty() {
+        tracing::info!(models = prices.len(), "Price table loaded");
+    }
+
     let state = HandlerState::new(
         tiered_cache,
         scorer,
         config.storage_path.clone(),
         bq_client,
         BQ_COLLECTION_NAME.to_string(),
-    );
+    )
+    .with_tenants(tenants)
+    .with_rate_limits(RateLimitConfig::from_env()?)
+    .with_router(router)
+    .with_upstream(UpstreamConfig::from_env()?)
+    .with_prices(prices)
+    .with_media(MediaConfig::from_env()?)
+    .with_sampling(SamplingPolicy::from_env()?.unwrap_or_default())
+    .with_shadow(ShadowConfig::from_env()?);
 
     let app = create_router_with_state(state);
 
@@ -0,0 +1,342 @@
+//! `/admin` invalidation and browsing tests against the mock provider.
+
+mod common;
+
+use serde_json::{Value, json};
+use std::time::Duration;
+
+use common::harness::{TestServerConfig, spawn_test_server};
+
+const ADMIN_TOKEN: &str = "admin-secret";
+
+struct RawClient {
+    client: reqwest::Client,
+    base_url: String,
+}
+
+impl RawClient {
+    fn new(base_url: String) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            base_url,
+        }
+    }
+
+    /// Posts a chat completion and returns its `X-Reflex-Status`.
+    async fn chat(&self, prompt: &str) -> String {
+        let resp = self
+            .client
+            .post(format!("{}/v1/chat/completions", self.base_url))
+            .bearer_auth("sk-tenant")
+            .json(&chat_request(prompt))
+            .send()
+            .await
+            .expect("Request should be sent");
+        resp.headers()
+            .get("x-reflex-status")
+            .and_then(|h| h.to_str().ok())
+            .unwrap_or("unknown")
+            .to_string()
+    }
+
+    /// Posts a chat completion with `Cache-Control: no-cache`, so it is
+    /// always forwarded and stored even if a similar prompt is cached.
+    async fn store(&self, prompt: &str) {
+        let resp = self
+            .client
+            .post(format!("{}/v1/chat/completions", self.base_url))
+            .bearer_auth("sk-tenant")
+            .header("Cache-Control", "no-cache")
+            .json(&chat_request(prompt))
+            .send()
+            .await
+            .expect("Request should be sent");
+        assert_eq!(resp.status().as_u16(), 200);
+    }
+
+    /// Posts an admin request and returns (HTTP status, JSON body).
+    async fn admin(&self, path: &str, token: Option<&str>, body: &Value) -> (u16, Value) {
+        let mut request = self
+            .client
+            .post(format!("{}{}", self.base_url, path))
+            .json(body);
+        if let Some(token) = token {
+            request = request.bearer_auth(token);
+        }
+        let resp = request.send().await.expect("Request should be sent");
+        let status = resp.status().as_u16();
+        (status, resp.json().await.unwrap_or(Value::Null))
+    }
+}
+
+impl RawClient {
+    /// Gets an admin resource and returns (HTTP status, JSON body).
+    async fn admin_get(&self, path: &str) -> (u16, Value) {
+        let resp = self
+            .client
+            .get(format!("{}{}", self.base_url, path))
+            .bearer_auth(ADMIN_TOKEN)
+            .send()
+            .await
+            .expect("Request should be sent");
+        let status = resp.status().as_u16();
+        (status, resp.json().await.unwrap_or(Value::Null))
+    }
+}
+
+fn chat_request(prompt: &str) -> Value {
+    json!({
+        "model": "gpt-4o",
+        "messages": [{"role": "user", "content": prompt}]
+    })
+}
+
+async fn admin_server() -> (common::harness::TestServer, RawClient) {
+    let server = spawn_test_server(TestServerConfig {
+        admin_token: Some(ADMIN_TOKEN.to_string()),
+        ..TestServerConfig::default()
+    })
+    .await
+    .expect("Server should start");
+    let client = RawClient::new(server.url());
+    (server, client)
+}
+
+#[tokio::test]
+async fn test_admin_requires_configured_token() {
+    let server = spawn_test_server(TestServerConfig::default())
+        .await
+        .expect("Server should start");
+    let client = RawClient::new(server.url());
+    let body = json!({ "tenant": "sk-tenant" });
+
+    let (status, _) = client
+        .admin("/admin/invalidate/tenant", Some(ADMIN_TOKEN), &body)
+        .await;
+    assert_eq!(status, 401);
+
+    let (_server, client) = admin_server().await;
+    let (status, _) = client.admin("/admin/invalidate/tenant", None, &body).await;
+    assert_eq!(status, 401);
+    let (status, _) = client
+        .admin("/admin/invalidate/tenant", Some("wrong"), &body)
+        .await;
+    assert_eq!(status, 401);
+    let (status, _) = client
+        .admin("/admin/invalidate/tenant", Some(ADMIN_TOKEN), &body)
+        .await;
+    assert_eq!(status, 200);
+}
+
+#[tokio::test]
+async fn test_invalidate_exact_request_removes_every_tier() {
+    let (_server, client) = admin_server().await;
+
+    assert_eq!(client.chat("Invalidate me").await, "MISS");
+    assert_eq!(client.chat("Invalidate me").await, "HIT_L1_EXACT");
+
+    let (status, counts) = client
+        .admin(
+            "/admin/invalidate/request",
+            Some(ADMIN_TOKEN),
+            &json!({ "tenant": "sk-tenant", "request": chat_request("Invalidate me") }),
+        )
+        .await;
+    assert_eq!(status, 200);
+    assert_eq!(
+        counts,
+        json!({ "l1_removed": 1, "files_removed": 1, "points_submitted": 1 })
+    );
+
+    assert_eq!(client.chat("Invalidate me").await, "MISS");
+}
+
+#[tokio::test]
+async fn test_invalidate_request_through_a_model_keyed_route() {
+    let server = spawn_test_server(TestServerConfig {
+        admin_token: Some(ADMIN_TOKEN.to_string()),
+        router: reflex_server::gateway::ModelRouter::from_toml_str(
+            "cache_key = \"model\"\n[aliases.fast]\nmodels = [\"gpt-4o-mini\", \"gpt-4o\"]\n",
+        )
+        .unwrap(),
+        ..TestServerConfig::default()
+    })
+    .await
+    .expect("Server should start");
+    let client = RawClient::new(server.url());
+    let request = json!({
+        "model": "fast",
+        "messages": [{"role": "user", "content": "Invalidate my alias"}]
+    });
+    let chat = || async {
+        let resp = client
+            .client
+            .post(format!("{}/v1/chat/completions", client.base_url))
+            .bearer_auth("sk-tenant")
+            .json(&request)
+            .send()
+            .await
+            .expect("Request should be sent");
+        resp.headers()["x-reflex-status"]
+            .to_str()
+            .unwrap()
+            .to_string()
+    };
+
+    assert_eq!(chat().await, "MISS");
+    assert_eq!(chat().await, "HIT_L1_EXACT");
+
+    let (status, counts) = client
+        .admin(
+            "/admin/invalidate/request",
+            Some(ADMIN_TOKEN),
+            &json!({ "tenant": "sk-tenant", "request": request }),
+        )
+        .await;
+    assert_eq!(status, 200);
+    assert_eq!(counts["files_removed"], 1);
+
+    assert_eq!(chat().await, "MISS");
+}
+
+#[tokio::test]
+async fn test_invalidate_tenant_removes_all_entries() {
+    let (_server, client) = admin_server().await;
+
+    client.store("Explain quantum entanglement").await;
+    client.store("Give me a garlic pasta recipe").await;
+
+    let (status, counts) = client
+        .admin(
+            "/admin/invalidate/tenant",
+            Some(ADMIN_TOKEN),
+            &json!({ "tenant": "sk-tenant" }),
+        )
+        .await;
+    assert_eq!(status, 200);
+    assert_eq!(counts["l1_removed"], 2);
+    assert_eq!(counts["files_removed"], 2);
+
+    assert_eq!(client.chat("Explain quantum entanglement").await, "MISS");
+}
+
+#[tokio::test]
+async fn test_invalidate_by_age_spares_recent_entries() {
+    let (_server, client) = admin_server().await;
+
+    assert_eq!(client.chat("Aging prompt").await, "MISS");
+
+    let (status, counts) = client
+        .admin(
+            "/admin/invalidate/age",
+            Some(ADMIN_TOKEN),
+            &json!({ "older_than_secs": 3600 }),
+        )
+        .await;
+    assert_eq!(status, 200);
+    assert_eq!(counts["files_removed"], 0);
+    assert_eq!(client.chat("Aging prompt").await, "HIT_L1_EXACT");
+
+    tokio::time::sleep(Duration::from_millis(1100)).await;
+
+    let (status, counts) = client
+        .admin(
+            "/admin/invalidate/age",
+            Some(ADMIN_TOKEN),
+            &json!({ "older_than_secs": 0 }),
+        )
+        .await;
+    assert_eq!(status, 200);
+    assert_eq!(counts["files_removed"], 1);
+    assert_eq!(client.chat("Aging prompt").await, "MISS");
+}
+
+#[tokio::test]
+async fn test_invalidate_key_rejects_malformed_keys() {
+    let (_server, client) = admin_server().await;
+
+    let (status, _) = client
+        .admin(
+            "/admin/invalidate/key",
+            Some(ADMIN_TOKEN),
+            &json!({ "storage_key": "../etc/passwd" }),
+        )
+        .await;
+    assert_eq!(status, 400);
+
+    let (status, counts) = client
+        .admin(
+            "/admin/invalidate/key",
+            Some(ADMIN_TOKEN),
+            &json!({ "storage_key": "42/00000000000000ff.rkyv" }),
+        )
+        .await;
+    assert_eq!(status, 200);
+    assert_eq!(counts["files_removed"], 0);
+}
+
+#[tokio::test]
+async fn test_list_entries_paginates_per_tenant() {
+    let (_server, client) = admin_server().await;
+
+    for prompt in [
+        "Explain quantum entanglement",
+        "Give me a garlic pasta recipe",
+        "How do tides work?",
+    ] {
+        client.store(prompt).await;
+    }
+
+    let (status, first) = client
+        .admin_get("/admin/entries?tenant=sk-tenant&limit=2")
+        .await;
+    assert_eq!(status, 200);
+    assert_eq!(first["total"], 3);
+    assert_eq!(first["entries"].as_array().unwrap().len(), 2);
+    assert_eq!(first["next_offset"], 2);
+    assert_eq!(first["entries"][0]["l1_resident"], true);
+
+    let (_, second) = client
+        .admin_get("/admin/entries?tenant=sk-tenant&limit=2&offset=2")
+        .await;
+    assert_eq!(second["entries"].as_array().unwrap().len(), 1);
+    assert!(second["next_offset"].is_null());
+
+    let (_, other) = client.admin_get("/admin/entries?tenant=sk-other").await;
+    assert_eq!(other["total"], 0);
+}
+
+#[tokio::test]
+async fn test_get_entry_decodes_payload() {
+    let (_server, client) = admin_server().await;
+
+    assert_eq!(client.chat("Inspect me").await, "MISS");
+
+    let (_, page) = client.admin_get("/admin/entries?tenant=sk-tenant").await;
+    let summary = &page["entries"][0];
+    let path = format!(
+        "/admin/entries/{}/{}",
+        summary["tenant_id"],
+        summary["context_hash"].as_str().unwrap()
+    );
+
+    let (status, entry) = client.admin_get(&path).await;
+    assert_eq!(status, 200);
+    assert_eq!(entry["storage_key"], summary["storage_key"]);
+    assert_eq!(entry["l1_resident"], true);
+    assert!(entry["timestamp"].as_i64().unwrap() > 0);
+    assert!(entry["embedding_norm"].as_f64().unwrap() > 0.0);
+    assert!(entry["payload_error"].is_null());
+    assert!(
+        entry["payload"]["semantic_request"]
+            .as_str()
+            .unwrap()
+            .contains("Inspect me")
+    );
+    assert_eq!(entry["payload"]["response"]["object"], "chat.completion");
+
+    let (status, _) = client.admin_get("/admin/entries/1/00000000000000ff").await;
+    assert_eq!(status, 404);
+    let (status, _) = client.admin_get("/admin/entries/1/not-hex").await;
+    assert_eq!(status, 400);
+}
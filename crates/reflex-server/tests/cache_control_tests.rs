@@ -0,0 +1,138 @@
+//! `Cache-Control` request directives and response headers against the mock provider.
+
+mod common;
+
+use serde_json::{Value, json};
+use std::time::Duration;
+
+use common::harness::{TestServerConfig, spawn_test_server};
+
+struct RawClient {
+    client: reqwest::Client,
+    base_url: String,
+}
+
+impl RawClient {
+    fn new(base_url: String) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            base_url,
+        }
+    }
+
+    /// Posts a chat completion with an optional `Cache-Control` header.
+    async fn chat(&self, prompt: &str, cache_control: Option<&str>) -> reqwest::Response {
+        let mut request = self
+            .client
+            .post(format!("{}/v1/chat/completions", self.base_url))
+            .json(&chat_request(prompt));
+        if let Some(value) = cache_control {
+            request = request.header("Cache-Control", value);
+        }
+        request.send().await.expect("Request should be sent")
+    }
+}
+
+fn chat_request(prompt: &str) -> Value {
+    json!({
+        "model": "gpt-4o",
+        "messages": [{"role": "user", "content": prompt}]
+    })
+}
+
+fn header<'a>(resp: &'a reqwest::Response, name: &str) -> Option<&'a str> {
+    resp.headers().get(name).and_then(|h| h.to_str().ok())
+}
+
+#[tokio::test]
+async fn test_hit_carries_age_and_cache_control() {
+    let server = spawn_test_server(TestServerConfig::default())
+        .await
+        .expect("Server should start");
+    let client = RawClient::new(server.url());
+
+    let miss = client.chat("Age header test", None).await;
+    assert_eq!(header(&miss, "x-reflex-status"), Some("MISS"));
+    assert_eq!(header(&miss, "cache-control"), Some("private"));
+    assert!(header(&miss, "age").is_none());
+
+    let hit = client.chat("Age header test", None).await;
+    assert_eq!(header(&hit, "x-reflex-status"), Some("HIT_L1_EXACT"));
+    let age: u64 = header(&hit, "age").unwrap().parse().unwrap();
+    assert!(age < 60);
+}
+
+#[tokio::test]
+async fn test_no_store_skips_storing() {
+    let server = spawn_test_server(TestServerConfig::default())
+        .await
+        .expect("Server should start");
+    let client = RawClient::new(server.url());
+
+    let first = client.chat("No store test", Some("no-store")).await;
+    assert_eq!(header(&first, "x-reflex-status"), Some("MISS"));
+    assert_eq!(header(&first, "cache-control"), Some("private, no-store"));
+
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    let second = client.chat("No store test", None).await;
+    assert_eq!(header(&second, "x-reflex-status"), Some("MISS"));
+}
+
+#[tokio::test]
+async fn test_no_cache_skips_lookup_but_stores() {
+    let server = spawn_test_server(TestServerConfig::default())
+        .await
+        .expect("Server should start");
+    let client = RawClient::new(server.url());
+
+    client.chat("No cache test", None).await;
+
+    let bypass = client.chat("No cache test", Some("no-cache")).await;
+    assert_eq!(header(&bypass, "x-reflex-status"), Some("MISS"));
+
+    let hit = client.chat("No cache test", None).await;
+    assert_eq!(header(&hit, "x-reflex-status"), Some("HIT_L1_EXACT"));
+}
+
+#[tokio::test]
+async fn test_only_if_cached_returns_504_on_miss() {
+    let server = spawn_test_server(TestServerConfig::default())
+        .await
+        .expect("Server should start");
+    let client = RawClient::new(server.url());
+
+    let miss = client
+        .chat("Only if cached test", Some("only-if-cached"))
+        .await;
+    assert_eq!(miss.status(), 504);
+
+    // The provider was never called, so nothing was stored either.
+    let stored = client.chat("Only if cached test", None).await;
+    assert_eq!(header(&stored, "x-reflex-status"), Some("MISS"));
+
+    let hit = client
+        .chat("Only if cached test", Some("only-if-cached"))
+        .await;
+    assert_eq!(hit.status(), 200);
+    assert_eq!(header(&hit, "x-reflex-status"), Some("HIT_L1_EXACT"));
+}
+
+#[tokio::test]
+async fn test_max_age_rejects_older_entries() {
+    let server = spawn_test_server(TestServerConfig::default())
+        .await
+        .expect("Server should start");
+    let client = RawClient::new(server.url());
+
+    client.chat("Max age test", None).await;
+    tokio::time::sleep(Duration::from_millis(1100)).await;
+
+    let fresh = client.chat("Max age test", Some("max-age=3600")).await;
+    assert_eq!(header(&fresh, "x-reflex-status"), Some("HIT_L1_EXACT"));
+
+    let stale = client
+        .chat("Max age test", Some("max-age=0, only-if-cached"))
+        .await;
+    assert_eq!(stale.status(), 504);
+}
@@ -118,6 +118,9 @@ async fn test_coding_patterns_persistence() {
             status_cached,
             prompt
         );
-        assert_eq!(response.id, response_cached.id, "Response ID should match");
+        assert!(
+            response_cached.id.starts_with(&format!("{}-", response.id)),
+            "Hit ID should extend the stored response ID"
+        );
     }
 }
@@ -6,7 +6,10 @@ use reflex::embedding::RerankerConfig;
 use reflex::embedding::sinter::{SinterConfig, SinterEmbedder};
 use reflex::scoring::CrossEncoderScorer;
 use reflex::vectordb::bq::{BqClient, MockBqClient};
-use reflex_server::gateway::{HandlerState, create_router_with_state};
+use reflex_server::gateway::{
+    HandlerState, MediaConfig, ModelRouter, PriceTable, RateLimitConfig, SamplingPolicy,
+    ShadowConfig, TenantRegistry, UpstreamConfig, create_router_with_state,
+};
 use std::net::SocketAddr;
 use std::sync::Arc;
 use std::time::Duration;
@@ -25,6 +28,16 @@ pub struct TestServerConfig {
     pub collection_name: Option<String>,
     pub storage_path: Option<std::path::PathBuf>,
     pub reranker_threshold: f32,
+    pub admin_token: Option<String>,
+    pub mock_latency: Duration,
+    pub tenants: Option<Arc<TenantRegistry>>,
+    pub rate_limits: RateLimitConfig,
+    pub router: ModelRouter,
+    pub upstream: UpstreamConfig,
+    pub prices: PriceTable,
+    pub media: MediaConfig,
+    pub sampling: SamplingPolicy,
+    pub shadow: ShadowConfig,
 }
 
 impl Default for TestServerConfig {
@@ -34,6 +47,16 @@ impl Default for TestServerConfig {
             collection_name: None,
             storage_path: None,
             reranker_threshold: 0.70,
+            admin_token: None,
+            mock_latency: Duration::ZERO,
+            tenants: None,
+            rate_limits: RateLimitConfig::default(),
+            router: ModelRouter::default(),
+            upstream: UpstreamConfig::default(),
+            prices: PriceTable::default(),
+            media: MediaConfig::default(),
+            sampling: SamplingPolicy::default(),
+            shadow: ShadowConfig::default(),
         }
     }
 }
@@ -191,7 +214,17 @@ pub async fn spawn_test_server(config: TestServerConfig) -> Result<TestServer, S
         bq_client,
         collection_name,
         true,
-    );
+    )
+    .with_admin_token(config.admin_token)
+    .with_mock_latency(config.mock_latency)
+    .with_tenants(config.tenants)
+    .with_rate_limits(config.rate_limits)
+    .with_router(config.router)
+    .with_upstream(config.upstream)
+    .with_prices(config.prices)
+    .with_media(config.media)
+    .with_sampling(config.sampling)
+    .with_shadow(config.shadow);
 
     let app = create_router_with_state(state);
 
@@ -346,7 +379,17 @@ pub async fn spawn_real_server(config: TestServerConfig) -> Result<TestServer, S
         bq_client,
         collection_name,
         true, // mock_provider: true = mock LLM, false = real LLM
-    );
+    )
+    .with_admin_token(config.admin_token)
+    .with_mock_latency(config.mock_latency)
+    .with_tenants(config.tenants)
+    .with_rate_limits(config.rate_limits)
+    .with_router(config.router)
+    .with_upstream(config.upstream)
+    .with_prices(config.prices)
+    .with_media(config.media)
+    .with_sampling(config.sampling)
+    .with_shadow(config.shadow);
 
     let app = create_router_with_state(state);
 
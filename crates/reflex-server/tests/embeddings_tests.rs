@@ -0,0 +1,139 @@
+//! `POST /v1/embeddings` tests against the stub embedder.
+
+mod common;
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use reflex::embedding::SINTER_EMBEDDING_DIM;
+use serde_json::json;
+
+use common::harness::{TestServerConfig, spawn_test_server};
+
+/// Minimal client for `/v1/embeddings`; `Err` carries the non-200 status.
+struct EmbeddingsClient {
+    client: reqwest::Client,
+    url: String,
+}
+
+impl EmbeddingsClient {
+    fn new(base_url: String) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            url: format!("{}/v1/embeddings", base_url),
+        }
+    }
+
+    async fn embeddings(&self, body: serde_json::Value) -> Result<serde_json::Value, u16> {
+        let resp = self
+            .client
+            .post(&self.url)
+            .json(&body)
+            .send()
+            .await
+            .expect("Request should be sent");
+
+        match resp.status().as_u16() {
+            200 => Ok(resp.json().await.expect("Response should be JSON")),
+            status => Err(status),
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_embeddings_single_string() {
+    let server = spawn_test_server(TestServerConfig::default())
+        .await
+        .expect("Server should start");
+    let client = EmbeddingsClient::new(server.url());
+
+    let body = client
+        .embeddings(json!({"model": "qwen3-embedding", "input": "hello world"}))
+        .await
+        .expect("Request should succeed");
+
+    assert_eq!(body["object"], "list");
+    assert_eq!(body["model"], "qwen3-embedding");
+    assert_eq!(body["data"].as_array().unwrap().len(), 1);
+    assert_eq!(body["data"][0]["object"], "embedding");
+    assert_eq!(
+        body["data"][0]["embedding"].as_array().unwrap().len(),
+        SINTER_EMBEDDING_DIM
+    );
+    assert_eq!(body["usage"]["prompt_tokens"], 2);
+    assert_eq!(body["usage"]["total_tokens"], 2);
+}
+
+#[tokio::test]
+async fn test_embeddings_batch_preserves_order() {
+    let server = spawn_test_server(TestServerConfig::default())
+        .await
+        .expect("Server should start");
+    let client = EmbeddingsClient::new(server.url());
+
+    let batch = client
+        .embeddings(json!({"model": "m", "input": ["first", "second text"]}))
+        .await
+        .expect("Request should succeed");
+    let single = client
+        .embeddings(json!({"model": "m", "input": "second text"}))
+        .await
+        .expect("Request should succeed");
+
+    let data = batch["data"].as_array().unwrap();
+    assert_eq!(data.len(), 2);
+    assert_eq!(data[1]["index"], 1);
+    assert_eq!(data[1]["embedding"], single["data"][0]["embedding"]);
+    assert_eq!(batch["usage"]["prompt_tokens"], 3);
+}
+
+#[tokio::test]
+async fn test_embeddings_base64_matches_float() {
+    let server = spawn_test_server(TestServerConfig::default())
+        .await
+        .expect("Server should start");
+    let client = EmbeddingsClient::new(server.url());
+
+    let float = client
+        .embeddings(json!({"model": "m", "input": "cache me"}))
+        .await
+        .expect("Request should succeed");
+    let encoded = client
+        .embeddings(json!({"model": "m", "input": "cache me", "encoding_format": "base64"}))
+        .await
+        .expect("Request should succeed");
+
+    let bytes = BASE64
+        .decode(encoded["data"][0]["embedding"].as_str().unwrap())
+        .unwrap();
+    let decoded: Vec<f32> = bytes
+        .chunks_exact(4)
+        .map(|c| f32::from_le_bytes(c.try_into().unwrap()))
+        .collect();
+    let expected: Vec<f32> = float["data"][0]["embedding"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|v| v.as_f64().unwrap() as f32)
+        .collect();
+
+    assert_eq!(decoded, expected);
+}
+
+#[tokio::test]
+async fn test_embeddings_rejects_invalid_input() {
+    let server = spawn_test_server(TestServerConfig::default())
+        .await
+        .expect("Server should start");
+    let client = EmbeddingsClient::new(server.url());
+
+    for body in [
+        json!({"model": "m", "input": ""}),
+        json!({"model": "m", "input": []}),
+        json!({"model": "m", "input": [1, 2, 3]}),
+        json!({"model": "m", "input": "x", "dimensions": 3}),
+        json!({"input": "x"}),
+    ] {
+        let result = client.embeddings(body.clone()).await;
+        assert!(matches!(result, Err(400)), "expected 400 for {body}");
+    }
+}
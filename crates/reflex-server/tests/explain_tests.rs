@@ -0,0 +1,165 @@
+mod common;
+
+use common::harness::{TestServerConfig, spawn_test_server};
+use serde_json::{Value, json};
+
+const PROMPT: &str = "How to implement a binary tree in Rust. This includes struct definitions, insert methods, and traversal logic for a complete implementation.";
+const NEIGHBOUR: &str = "How do I implement a binary tree in Rust? I need struct definitions, insert methods, and traversal logic.";
+
+fn body(prompt: &str) -> Value {
+    json!({
+        "model": "gpt-4o",
+        "messages": [{"role": "user", "content": prompt}]
+    })
+}
+
+async fn chat(server_url: &str, prompt: &str, debug: bool) -> reqwest::Response {
+    let mut request = reqwest::Client::new()
+        .post(format!("{}/v1/chat/completions", server_url))
+        .header("X-Reflex-Format", "openai")
+        .json(&body(prompt));
+    if debug {
+        request = request.header("X-Reflex-Debug", "1");
+    }
+    request.send().await.unwrap()
+}
+
+async fn explain(server_url: &str, prompt: &str) -> Value {
+    let response = reqwest::Client::new()
+        .post(format!("{}/v1/reflex/explain", server_url))
+        .json(&body(prompt))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    response.json().await.unwrap()
+}
+
+fn status(response: &reqwest::Response) -> &str {
+    response.headers()["x-reflex-status"].to_str().unwrap()
+}
+
+async fn settle() {
+    tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+}
+
+#[tokio::test]
+async fn test_explain_is_a_dry_run() {
+    let server = spawn_test_server(TestServerConfig::default())
+        .await
+        .unwrap();
+    let url = server.url();
+
+    let trace = explain(&url, PROMPT).await;
+    assert_eq!(trace["decision"], "MISS");
+    assert_eq!(trace["l1"], "miss");
+    assert!(
+        trace["semantic_text"]
+            .as_str()
+            .unwrap()
+            .contains("binary tree")
+    );
+    settle().await;
+
+    assert_eq!(status(&chat(&url, PROMPT, false).await), "MISS");
+    settle().await;
+
+    let trace = explain(&url, PROMPT).await;
+    assert_eq!(trace["l1"], "hit");
+    assert_eq!(trace["decision"], "HIT_L1_EXACT");
+}
+
+#[tokio::test]
+async fn test_explain_reports_every_stage_of_a_semantic_hit() {
+    let server = spawn_test_server(TestServerConfig::default())
+        .await
+        .unwrap();
+    let url = server.url();
+
+    assert_eq!(status(&chat(&url, PROMPT, false).await), "MISS");
+    settle().await;
+
+    let trace = explain(&url, NEIGHBOUR).await;
+    assert_eq!(trace["l1"], "miss");
+    assert_eq!(trace["l3"], "VERIFIED");
+    assert_eq!(trace["decision"], "HIT_L3_VERIFIED");
+    assert!(trace["bq_candidates"].as_u64().unwrap() >= 1);
+    assert!(trace["threshold"].is_number());
+
+    let candidate = &trace["candidates"][0];
+    assert!(candidate["bq_score"].is_number());
+    assert!(candidate["rescored_score"].is_number());
+    let score = candidate["cross_encoder_score"].as_f64().unwrap();
+    assert!(score > trace["threshold"].as_f64().unwrap());
+    assert!(
+        candidate["semantic_request"]
+            .as_str()
+            .unwrap()
+            .contains("binary tree")
+    );
+    assert_eq!(candidate["excluded"], Value::Null);
+}
+
+#[tokio::test]
+async fn test_debug_header_carries_the_trace() {
+    let server = spawn_test_server(TestServerConfig::default())
+        .await
+        .unwrap();
+    let url = server.url();
+
+    let response = chat(&url, PROMPT, false).await;
+    assert!(response.headers().get("x-reflex-debug").is_none());
+    settle().await;
+
+    let response = chat(&url, NEIGHBOUR, true).await;
+    assert_eq!(status(&response), "HIT_L3_VERIFIED");
+    let trace: Value =
+        serde_json::from_slice(response.headers()["x-reflex-debug"].as_bytes()).unwrap();
+    assert_eq!(trace["decision"], "HIT_L3_VERIFIED");
+    assert!(trace.get("semantic_text").is_none());
+    assert!(trace["candidates"][0].get("semantic_request").is_none());
+    assert!(trace["candidates"][0]["cross_encoder_score"].is_number());
+}
+
+#[tokio::test]
+async fn test_debug_header_traces_misses_streams_and_skipped_lookups() {
+    let server = spawn_test_server(TestServerConfig::default())
+        .await
+        .unwrap();
+    let url = server.url();
+    let client = reqwest::Client::new();
+
+    let response = chat(&url, PROMPT, true).await;
+    assert_eq!(status(&response), "MISS");
+    let trace: Value =
+        serde_json::from_slice(response.headers()["x-reflex-debug"].as_bytes()).unwrap();
+    assert_eq!(trace["decision"], "MISS");
+    assert_eq!(trace["skipped"], false);
+    settle().await;
+
+    let mut streamed = body(PROMPT);
+    streamed["stream"] = json!(true);
+    let response = client
+        .post(format!("{}/v1/chat/completions", url))
+        .header("X-Reflex-Debug", "1")
+        .json(&streamed)
+        .send()
+        .await
+        .unwrap();
+    let trace: Value =
+        serde_json::from_slice(response.headers()["x-reflex-debug"].as_bytes()).unwrap();
+    assert_eq!(trace["decision"], "HIT_L1_EXACT");
+    assert_eq!(trace["l1"], "hit");
+
+    let response = client
+        .post(format!("{}/v1/chat/completions", url))
+        .header("X-Reflex-Debug", "1")
+        .header("Cache-Control", "no-cache")
+        .json(&body(PROMPT))
+        .send()
+        .await
+        .unwrap();
+    let trace: Value =
+        serde_json::from_slice(response.headers()["x-reflex-debug"].as_bytes()).unwrap();
+    assert_eq!(trace["skipped"], true);
+}
@@ -0,0 +1,167 @@
+mod common;
+
+use common::harness::{TestServerConfig, spawn_test_server};
+use serde_json::{Value, json};
+
+const PROMPT: &str = "How to implement a binary tree in Rust. This includes struct definitions, insert methods, and traversal logic for a complete implementation.";
+const NEIGHBOUR: &str = "How do I implement a binary tree in Rust? I need struct definitions, insert methods, and traversal logic.";
+
+fn body(prompt: &str) -> Value {
+    json!({
+        "model": "gpt-4o",
+        "messages": [{"role": "user", "content": prompt}]
+    })
+}
+
+/// Status, entry id header and response id.
+async fn chat(server_url: &str, prompt: &str) -> (String, Option<String>, String) {
+    let response = reqwest::Client::new()
+        .post(format!("{}/v1/chat/completions", server_url))
+        .header("X-Reflex-Format", "openai")
+        .json(&body(prompt))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    let status = response.headers()["x-reflex-status"]
+        .to_str()
+        .unwrap()
+        .to_string();
+    let entry_id = response
+        .headers()
+        .get("x-reflex-entry-id")
+        .map(|v| v.to_str().unwrap().to_string());
+    let json: Value = response.json().await.unwrap();
+    (status, entry_id, json["id"].as_str().unwrap().to_string())
+}
+
+async fn feedback(server_url: &str, token: &str, body: Value) -> reqwest::Response {
+    reqwest::Client::new()
+        .post(format!("{}/v1/reflex/feedback", server_url))
+        .bearer_auth(token)
+        .json(&body)
+        .send()
+        .await
+        .unwrap()
+}
+
+async fn settle() {
+    tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+}
+
+#[tokio::test]
+async fn test_negative_feedback_blocks_the_pairing() {
+    let server = spawn_test_server(TestServerConfig::default())
+        .await
+        .unwrap();
+    let url = server.url();
+
+    let (status, entry_id, _) = chat(&url, PROMPT).await;
+    assert_eq!(status, "MISS");
+    assert!(entry_id.is_none());
+    settle().await;
+
+    let (status, entry_id, id) = chat(&url, NEIGHBOUR).await;
+    assert_eq!(status, "HIT_L3_VERIFIED");
+    assert!(entry_id.is_some());
+
+    let response = feedback(&url, "default", json!({"id": id, "verdict": "negative"})).await;
+    assert_eq!(response.status(), 200);
+    let result: Value = response.json().await.unwrap();
+    assert_eq!(result["false_positive"], true);
+    assert_eq!(result["invalidated"]["files_removed"], 1);
+    assert!(
+        result["storage_key"]
+            .as_str()
+            .unwrap()
+            .ends_with(&format!("{}.rkyv", entry_id.unwrap()))
+    );
+
+    // The entry is gone, and once stored again it no longer answers the
+    // neighbour.
+    assert_eq!(chat(&url, PROMPT).await.0, "MISS");
+    settle().await;
+    assert_eq!(chat(&url, PROMPT).await.0, "HIT_L1_EXACT");
+
+    let trace: Value = reqwest::Client::new()
+        .post(format!("{}/v1/reflex/explain", url))
+        .json(&body(NEIGHBOUR))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(trace["candidates"][0]["excluded"], "known_false_positive");
+    assert_eq!(chat(&url, NEIGHBOUR).await.0, "MISS");
+}
+
+#[tokio::test]
+async fn test_feedback_reaches_the_hit_it_names() {
+    let server = spawn_test_server(TestServerConfig::default())
+        .await
+        .unwrap();
+    let url = server.url();
+
+    assert_eq!(chat(&url, PROMPT).await.0, "MISS");
+    settle().await;
+
+    // Both hits are served from the one entry; only the first was for
+    // another query.
+    let (status, first_entry, first) = chat(&url, NEIGHBOUR).await;
+    assert_eq!(status, "HIT_L3_VERIFIED");
+    let (status, second_entry, second) = chat(&url, PROMPT).await;
+    assert_eq!(status, "HIT_L1_EXACT");
+    assert_eq!(first_entry, second_entry);
+    assert_ne!(first, second);
+
+    let response = feedback(&url, "default", json!({"id": first, "verdict": "negative"})).await;
+    assert_eq!(response.status(), 200);
+    let result: Value = response.json().await.unwrap();
+    assert_eq!(result["false_positive"], true);
+}
+
+#[tokio::test]
+async fn test_positive_feedback_keeps_the_entry() {
+    let server = spawn_test_server(TestServerConfig::default())
+        .await
+        .unwrap();
+    let url = server.url();
+
+    chat(&url, PROMPT).await;
+    settle().await;
+    let (status, entry_id, _) = chat(&url, PROMPT).await;
+    assert_eq!(status, "HIT_L1_EXACT");
+
+    let response = feedback(
+        &url,
+        "default",
+        json!({"entry_id": entry_id, "verdict": "positive"}),
+    )
+    .await;
+    assert_eq!(response.status(), 200);
+    let result: Value = response.json().await.unwrap();
+    assert_eq!(result["invalidated"], Value::Null);
+    assert_eq!(result["false_positive"], false);
+
+    assert_eq!(chat(&url, PROMPT).await.0, "HIT_L1_EXACT");
+}
+
+#[tokio::test]
+async fn test_feedback_only_reaches_the_callers_hits() {
+    let server = spawn_test_server(TestServerConfig::default())
+        .await
+        .unwrap();
+    let url = server.url();
+
+    chat(&url, PROMPT).await;
+    settle().await;
+    let (_, _, id) = chat(&url, PROMPT).await;
+
+    let response = feedback(&url, "sk-other", json!({"id": id, "verdict": "negative"})).await;
+    assert_eq!(response.status(), 404);
+    let response = feedback(&url, "default", json!({"verdict": "negative"})).await;
+    assert_eq!(response.status(), 400);
+
+    assert_eq!(chat(&url, PROMPT).await.0, "HIT_L1_EXACT");
+}
@@ -0,0 +1,132 @@
+//! Single-flight coalescing of concurrent identical misses (mock provider).
+
+mod common;
+
+use serde_json::{Value, json};
+use std::time::Duration;
+
+use common::harness::{TestServerConfig, spawn_test_server};
+
+fn chat_request(prompt: &str, stream: bool) -> Value {
+    json!({
+        "model": "gpt-4o",
+        "stream": stream,
+        "messages": [{"role": "user", "content": prompt}]
+    })
+}
+
+/// Posts `body` and returns (`X-Reflex-Status`, raw body).
+async fn post(
+    client: &reqwest::Client,
+    url: String,
+    api_key: &str,
+    body: Value,
+) -> (String, String) {
+    let resp = client
+        .post(url)
+        .bearer_auth(api_key)
+        .json(&body)
+        .send()
+        .await
+        .expect("Request should be sent");
+    assert_eq!(resp.status().as_u16(), 200);
+    let status = resp
+        .headers()
+        .get("x-reflex-status")
+        .and_then(|h| h.to_str().ok())
+        .unwrap_or("unknown")
+        .to_string();
+    (status, resp.text().await.unwrap())
+}
+
+async fn slow_server() -> common::harness::TestServer {
+    spawn_test_server(TestServerConfig {
+        mock_latency: Duration::from_millis(300),
+        ..TestServerConfig::default()
+    })
+    .await
+    .expect("Server should start")
+}
+
+#[tokio::test]
+async fn test_concurrent_identical_misses_share_one_provider_call() {
+    let server = slow_server().await;
+    let client = reqwest::Client::new();
+    let url = format!("{}/v1/chat/completions", server.url());
+
+    let requests = (0..5).map(|_| {
+        post(
+            &client,
+            url.clone(),
+            "sk-burst",
+            chat_request("Burst prompt", false),
+        )
+    });
+    let results = futures::future::join_all(requests).await;
+
+    let misses = results.iter().filter(|(s, _)| s == "MISS").count();
+    let inflight = results.iter().filter(|(s, _)| s == "HIT_INFLIGHT").count();
+    assert_eq!(misses, 1, "statuses: {:?}", results);
+    assert_eq!(inflight, 4, "statuses: {:?}", results);
+
+    let contents: Vec<Value> = results
+        .iter()
+        .map(|(_, body)| serde_json::from_str::<Value>(body).unwrap()["id"].clone())
+        .collect();
+    assert!(contents.windows(2).all(|w| w[0] == w[1]));
+}
+
+#[tokio::test]
+async fn test_streaming_waiters_replay_inflight_result() {
+    let server = slow_server().await;
+    let client = reqwest::Client::new();
+    let url = format!("{}/v1/chat/completions", server.url());
+
+    let leader = post(
+        &client,
+        url.clone(),
+        "sk-stream",
+        chat_request("Streamed burst", false),
+    );
+    let waiter = async {
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        post(
+            &client,
+            url.clone(),
+            "sk-stream",
+            chat_request("Streamed burst", true),
+        )
+        .await
+    };
+    let ((leader_status, _), (waiter_status, waiter_body)) = tokio::join!(leader, waiter);
+
+    assert_eq!(leader_status, "MISS");
+    assert_eq!(waiter_status, "HIT_INFLIGHT");
+    assert!(waiter_body.contains("Mock response for:"));
+    assert!(waiter_body.trim_end().ends_with("data: [DONE]"));
+}
+
+#[tokio::test]
+async fn test_inflight_requests_are_not_shared_across_tenants() {
+    let server = slow_server().await;
+    let client = reqwest::Client::new();
+    let url = format!("{}/v1/chat/completions", server.url());
+
+    let (a, b) = tokio::join!(
+        post(
+            &client,
+            url.clone(),
+            "sk-tenant-a",
+            chat_request("Tenant prompt", false)
+        ),
+        post(
+            &client,
+            url.clone(),
+            "sk-tenant-b",
+            chat_request("Tenant prompt", false)
+        ),
+    );
+
+    assert_eq!(a.0, "MISS");
+    assert_eq!(b.0, "MISS");
+}
@@ -0,0 +1,196 @@
+//! `POST /v1/messages` (Anthropic Messages API) tests against the mock provider.
+
+mod common;
+
+use serde_json::{Value, json};
+use std::time::Duration;
+
+use common::harness::{TestServerConfig, spawn_test_server};
+
+struct RawClient {
+    client: reqwest::Client,
+    base_url: String,
+}
+
+impl RawClient {
+    fn new(base_url: String) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            base_url,
+        }
+    }
+
+    /// Posts `body` and returns (HTTP status, `X-Reflex-Status`, raw body).
+    async fn post(&self, path: &str, body: &Value) -> (u16, String, String) {
+        let resp = self
+            .client
+            .post(format!("{}{}", self.base_url, path))
+            .header("x-api-key", "sk-ant-test")
+            .json(body)
+            .send()
+            .await
+            .expect("Request should be sent");
+
+        let status = resp.status().as_u16();
+        let reflex_status = resp
+            .headers()
+            .get("x-reflex-status")
+            .and_then(|h| h.to_str().ok())
+            .unwrap_or("unknown")
+            .to_string();
+        (status, reflex_status, resp.text().await.unwrap())
+    }
+}
+
+fn messages_request(prompt: &str) -> Value {
+    json!({
+        "model": "claude-sonnet-4-5",
+        "max_tokens": 512,
+        "messages": [{"role": "user", "content": prompt}]
+    })
+}
+
+/// Parses named SSE events into (event name, data) pairs.
+fn sse_events(body: &str) -> Vec<(String, Value)> {
+    let mut events = Vec::new();
+    let mut name = String::new();
+    for line in body.lines() {
+        if let Some(n) = line.strip_prefix("event: ") {
+            name = n.to_string();
+        } else if let Some(data) = line.strip_prefix("data: ") {
+            events.push((name.clone(), serde_json::from_str(data).unwrap()));
+        }
+    }
+    events
+}
+
+fn streamed_text(events: &[(String, Value)]) -> String {
+    events
+        .iter()
+        .filter(|(name, _)| name == "content_block_delta")
+        .filter_map(|(_, data)| data["delta"]["text"].as_str())
+        .collect()
+}
+
+#[tokio::test]
+async fn test_messages_miss_then_exact_hit() {
+    let server = spawn_test_server(TestServerConfig::default())
+        .await
+        .expect("Server should start");
+    let client = RawClient::new(server.url());
+    let request = messages_request("What is a monad?");
+
+    let (status, reflex_status, body) = client.post("/v1/messages", &request).await;
+    assert_eq!(status, 200);
+    assert_eq!(reflex_status, "MISS");
+
+    let message: Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(message["type"], "message");
+    assert_eq!(message["role"], "assistant");
+    assert_eq!(message["stop_reason"], "end_turn");
+    assert!(message["id"].as_str().unwrap().starts_with("msg_"));
+    assert_eq!(message["content"][0]["type"], "text");
+
+    let (_, reflex_status, hit_body) = client.post("/v1/messages", &request).await;
+    assert_eq!(reflex_status, "HIT_L1_EXACT");
+    let hit: Value = serde_json::from_str(&hit_body).unwrap();
+    assert_eq!(hit["content"], message["content"]);
+}
+
+#[tokio::test]
+async fn test_messages_streaming_miss_then_replay() {
+    let server = spawn_test_server(TestServerConfig::default())
+        .await
+        .expect("Server should start");
+    let client = RawClient::new(server.url());
+    let mut request = messages_request("Stream me a haiku");
+    request["stream"] = json!(true);
+
+    let (status, reflex_status, body) = client.post("/v1/messages", &request).await;
+    assert_eq!(status, 200);
+    assert_eq!(reflex_status, "MISS");
+
+    let events = sse_events(&body);
+    let names: Vec<&str> = events.iter().map(|(n, _)| n.as_str()).collect();
+    assert_eq!(names.first(), Some(&"message_start"));
+    assert_eq!(names.last(), Some(&"message_stop"));
+    assert!(names.contains(&"content_block_start"));
+    assert!(names.contains(&"content_block_stop"));
+    let live_text = streamed_text(&events);
+    assert!(!live_text.is_empty());
+
+    let mut replay = None;
+    for _ in 0..100 {
+        let (_, reflex_status, body) = client.post("/v1/messages", &request).await;
+        if reflex_status == "HIT_L1_EXACT" {
+            replay = Some(body);
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    let replay = replay.expect("streamed response should be cached");
+
+    let events = sse_events(&replay);
+    assert_eq!(streamed_text(&events), live_text);
+    let delta = events
+        .iter()
+        .find(|(n, _)| n == "message_delta")
+        .map(|(_, d)| d)
+        .unwrap();
+    assert_eq!(delta["delta"]["stop_reason"], "end_turn");
+}
+
+#[tokio::test]
+async fn test_messages_shares_cache_with_chat_completions() {
+    let server = spawn_test_server(TestServerConfig::default())
+        .await
+        .expect("Server should start");
+    let client = RawClient::new(server.url());
+    let prompt = "Explain the borrow checker";
+
+    let (status, reflex_status, _) = client
+        .post(
+            "/v1/chat/completions",
+            &json!({
+                "model": "claude-sonnet-4-5",
+                "messages": [{"role": "user", "content": prompt}]
+            }),
+        )
+        .await;
+    assert_eq!(status, 200);
+    assert_eq!(reflex_status, "MISS");
+
+    let mut shared = None;
+    for _ in 0..100 {
+        let (_, reflex_status, body) = client.post("/v1/messages", &messages_request(prompt)).await;
+        if reflex_status.starts_with("HIT") {
+            shared = Some(body);
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+
+    let body = shared.expect("Messages request should hit the chat completion entry");
+    let message: Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(message["type"], "message");
+}
+
+#[tokio::test]
+async fn test_messages_invalid_request_uses_anthropic_error_shape() {
+    let server = spawn_test_server(TestServerConfig::default())
+        .await
+        .expect("Server should start");
+    let client = RawClient::new(server.url());
+
+    let (status, _, body) = client
+        .post(
+            "/v1/messages",
+            &json!({"model": "m", "messages": [{"role": "user", "content": "hi"}]}),
+        )
+        .await;
+
+    assert_eq!(status, 400);
+    let error: Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(error["type"], "error");
+    assert_eq!(error["error"]["type"], "invalid_request_error");
+}
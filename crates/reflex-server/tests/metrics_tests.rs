@@ -0,0 +1,164 @@
+//! `GET /metrics` tests against the mock provider.
+
+mod common;
+
+use serde_json::json;
+
+use common::harness::{TestServerConfig, spawn_test_server};
+use reflex_server::gateway::PriceTable;
+
+async fn chat(client: &reqwest::Client, base_url: &str, api_key: &str, prompt: &str) -> String {
+    let resp = client
+        .post(format!("{}/v1/chat/completions", base_url))
+        .bearer_auth(api_key)
+        .json(&json!({
+            "model": "gpt-4o",
+            "messages": [{"role": "user", "content": prompt}]
+        }))
+        .send()
+        .await
+        .expect("Request should be sent");
+    resp.headers()
+        .get("x-reflex-status")
+        .and_then(|h| h.to_str().ok())
+        .unwrap_or("unknown")
+        .to_string()
+}
+
+/// Returns the value of the sample whose line contains every fragment.
+fn sample(body: &str, name: &str, fragments: &[&str]) -> Option<f64> {
+    body.lines()
+        .filter(|line| line.starts_with(name))
+        .find(|line| fragments.iter().all(|f| line.contains(f)))
+        .and_then(|line| line.rsplit(' ').next())
+        .and_then(|value| value.parse().ok())
+}
+
+#[tokio::test]
+async fn test_metrics_expose_tier_outcomes_and_latencies() {
+    let server = spawn_test_server(TestServerConfig::default())
+        .await
+        .expect("Server should start");
+    let client = reqwest::Client::new();
+    let base_url = server.url();
+
+    // A tenant of its own keeps counts independent of other tests in this process.
+    let api_key = "sk-metrics";
+    let tenant = format!("tenant=\"{}\"", reflex::hash_tenant_id(api_key));
+
+    assert_eq!(chat(&client, &base_url, api_key, "Count me").await, "MISS");
+    assert_eq!(
+        chat(&client, &base_url, api_key, "Count me").await,
+        "HIT_L1_EXACT"
+    );
+
+    let resp = client
+        .get(format!("{}/metrics", base_url))
+        .send()
+        .await
+        .expect("Request should be sent");
+    assert_eq!(resp.status().as_u16(), 200);
+    assert!(
+        resp.headers()["content-type"]
+            .to_str()
+            .unwrap()
+            .starts_with("text/plain")
+    );
+    let body = resp.text().await.unwrap();
+
+    let lookups = "reflex_cache_lookups_total";
+    let l1_hits = sample(
+        &body,
+        lookups,
+        &[
+            "tier=\"l1\"",
+            "outcome=\"hit\"",
+            &tenant,
+            "model=\"gpt-4o\"",
+        ],
+    );
+    let l1_misses = sample(
+        &body,
+        lookups,
+        &["tier=\"l1\"", "outcome=\"miss\"", &tenant],
+    );
+    assert_eq!(l1_hits, Some(1.0));
+    assert_eq!(l1_misses, Some(1.0));
+
+    assert!(
+        sample(
+            &body,
+            "reflex_provider_duration_seconds_count",
+            &["model=\"gpt-4o\""]
+        )
+        .is_some_and(|n| n >= 1.0)
+    );
+    assert!(
+        sample(
+            &body,
+            "reflex_stage_duration_seconds_count",
+            &["stage=\"embedding\""]
+        )
+        .is_some_and(|n| n >= 1.0)
+    );
+    assert!(sample(&body, "reflex_l1_entries", &[]).is_some_and(|n| n >= 1.0));
+}
+
+#[tokio::test]
+async fn test_hits_report_tokens_and_cost_saved() {
+    let prices = PriceTable::from_toml_str(
+        "[models.\"gpt-4o\"]\ninput_per_mtok = 2.5\noutput_per_mtok = 10.0\n",
+    )
+    .unwrap();
+    let server = spawn_test_server(TestServerConfig {
+        prices,
+        ..TestServerConfig::default()
+    })
+    .await
+    .expect("Server should start");
+    let client = reqwest::Client::new();
+    let base_url = server.url();
+
+    let api_key = "sk-savings";
+    let tenant = format!("tenant=\"{}\"", reflex::hash_tenant_id(api_key));
+    let send = || {
+        client
+            .post(format!("{}/v1/chat/completions", base_url))
+            .bearer_auth(api_key)
+            .json(&json!({
+                "model": "gpt-4o",
+                "messages": [{"role": "user", "content": "Save me some tokens"}]
+            }))
+            .send()
+    };
+
+    let miss = send().await.expect("Request should be sent");
+    assert_eq!(miss.headers()["x-reflex-status"], "MISS");
+    assert!(!miss.headers().contains_key("x-reflex-tokens-saved"));
+    let body: serde_json::Value = miss.json().await.unwrap();
+    assert_eq!(body["usage"]["total_tokens"], 20);
+
+    let hit = send().await.expect("Request should be sent");
+    assert_eq!(hit.headers()["x-reflex-status"], "HIT_L1_EXACT");
+    // The mock provider reports 10 prompt and 10 completion tokens.
+    assert_eq!(hit.headers()["x-reflex-tokens-saved"], "20");
+    assert_eq!(hit.headers()["x-reflex-cost-saved"], "0.000125");
+
+    let body = client
+        .get(format!("{}/metrics", base_url))
+        .send()
+        .await
+        .expect("Request should be sent")
+        .text()
+        .await
+        .unwrap();
+    let fragments = [tenant.as_str(), "model=\"gpt-4o\""];
+    assert_eq!(
+        sample(&body, "reflex_tokens_saved_total", &fragments),
+        Some(20.0)
+    );
+    assert_eq!(
+        sample(&body, "reflex_cost_saved_microdollars_total", &fragments),
+        Some(125.0)
+    );
+}
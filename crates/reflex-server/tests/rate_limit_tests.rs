@@ -0,0 +1,142 @@
+//! Rate limiting tests against the mock provider.
+
+mod common;
+
+use serde_json::json;
+
+use common::harness::{TestServerConfig, spawn_test_server};
+use reflex_server::gateway::RateLimitConfig;
+
+/// Returns (HTTP status, `X-Reflex-Status`, `Retry-After`).
+async fn chat(
+    client: &reqwest::Client,
+    base_url: &str,
+    api_key: &str,
+    prompt: &str,
+) -> (u16, String, Option<u64>) {
+    send(client, base_url, api_key, prompt, "").await
+}
+
+/// Like [`chat`], but skips the lookup so the request always reaches the
+/// provider (the stub embedder makes unrelated prompts look alike).
+async fn miss(
+    client: &reqwest::Client,
+    base_url: &str,
+    api_key: &str,
+    prompt: &str,
+) -> (u16, String, Option<u64>) {
+    send(client, base_url, api_key, prompt, "no-cache").await
+}
+
+async fn send(
+    client: &reqwest::Client,
+    base_url: &str,
+    api_key: &str,
+    prompt: &str,
+    cache_control: &str,
+) -> (u16, String, Option<u64>) {
+    let resp = client
+        .post(format!("{}/v1/chat/completions", base_url))
+        .bearer_auth(api_key)
+        .header("Cache-Control", cache_control)
+        .json(&json!({
+            "model": "gpt-4o",
+            "messages": [{"role": "user", "content": prompt}]
+        }))
+        .send()
+        .await
+        .expect("Request should be sent");
+    let header = |name: &str| {
+        resp.headers()
+            .get(name)
+            .and_then(|h| h.to_str().ok())
+            .map(str::to_string)
+    };
+    let status = header("x-reflex-status").unwrap_or_default();
+    let retry_after = header("retry-after").and_then(|v| v.parse().ok());
+    (resp.status().as_u16(), status, retry_after)
+}
+
+#[tokio::test]
+async fn test_exhausted_miss_budget_still_serves_hits() {
+    let server = spawn_test_server(TestServerConfig {
+        rate_limits: RateLimitConfig {
+            tenant_misses: Some("1/min".parse().unwrap()),
+            ..RateLimitConfig::default()
+        },
+        ..TestServerConfig::default()
+    })
+    .await
+    .expect("Server should start");
+    let client = reqwest::Client::new();
+    let base_url = server.url();
+
+    let first = chat(&client, &base_url, "sk-agent", "Cached question").await;
+    assert_eq!((first.0, first.1.as_str()), (200, "MISS"));
+
+    let (status, reflex_status, retry_after) =
+        miss(&client, &base_url, "sk-agent", "Brand new question").await;
+    assert_eq!(status, 429);
+    assert_eq!(reflex_status, "rate_limited");
+    assert!(retry_after.is_some_and(|secs| (1..=60).contains(&secs)));
+
+    let hit = chat(&client, &base_url, "sk-agent", "Cached question").await;
+    assert_eq!((hit.0, hit.1.as_str()), (200, "HIT_L1_EXACT"));
+
+    // Other tenants have their own budget.
+    let other = miss(&client, &base_url, "sk-other", "Brand new question").await;
+    assert_eq!((other.0, other.1.as_str()), (200, "MISS"));
+}
+
+#[tokio::test]
+async fn test_request_budget_covers_hits() {
+    let server = spawn_test_server(TestServerConfig {
+        rate_limits: RateLimitConfig {
+            tenant_requests: Some("2/h".parse().unwrap()),
+            ..RateLimitConfig::default()
+        },
+        ..TestServerConfig::default()
+    })
+    .await
+    .expect("Server should start");
+    let client = reqwest::Client::new();
+    let base_url = server.url();
+
+    assert_eq!(chat(&client, &base_url, "sk-agent", "Again").await.0, 200);
+    assert_eq!(chat(&client, &base_url, "sk-agent", "Again").await.0, 200);
+    let (status, _, retry_after) = chat(&client, &base_url, "sk-agent", "Again").await;
+    assert_eq!(status, 429);
+    assert_eq!(retry_after, Some(1800));
+}
+
+#[tokio::test]
+async fn test_each_requested_choice_costs_a_miss_token() {
+    let server = spawn_test_server(TestServerConfig {
+        rate_limits: RateLimitConfig {
+            tenant_misses: Some("3/min".parse().unwrap()),
+            ..RateLimitConfig::default()
+        },
+        ..TestServerConfig::default()
+    })
+    .await
+    .expect("Server should start");
+    let client = reqwest::Client::new();
+    let post = |n: u8| {
+        client
+            .post(format!("{}/v1/chat/completions", server.url()))
+            .bearer_auth("sk-agent")
+            .header("Cache-Control", "no-cache")
+            .json(&json!({
+                "model": "gpt-4o",
+                "n": n,
+                "messages": [{"role": "user", "content": "Give me taglines"}]
+            }))
+            .send()
+    };
+
+    assert_eq!(post(2).await.unwrap().status(), 200);
+    assert_eq!(post(2).await.unwrap().status(), 429);
+    assert_eq!(post(1).await.unwrap().status(), 200);
+    // More choices than the budget ever holds.
+    assert_eq!(post(4).await.unwrap().status(), 400);
+}
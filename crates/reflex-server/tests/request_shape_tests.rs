@@ -79,3 +79,152 @@ async fn test_claude_via_openai_format_lifecycle() {
     assert_eq!(status2, "HIT_L1_EXACT");
     assert_eq!(resp2.model, "claude-3-opus");
 }
+
+#[tokio::test]
+async fn test_format_header_selects_hit_body() {
+    let server = spawn_test_server(TestServerConfig::default())
+        .await
+        .unwrap();
+    let client = reqwest::Client::new();
+    let request = create_request("gpt-4o", "Format negotiation test");
+
+    let post = |format: &'static str| {
+        client
+            .post(format!("{}/v1/chat/completions", server.url()))
+            .header("X-Reflex-Format", format)
+            .json(&request)
+            .send()
+    };
+
+    let openai = post("openai").await.unwrap();
+    assert_eq!(openai.headers()["x-reflex-format"], "openai");
+    let body: serde_json::Value = openai.json().await.unwrap();
+    assert!(
+        body["choices"][0]["message"]["content"]
+            .as_str()
+            .unwrap()
+            .starts_with("Mock response for: ")
+    );
+
+    tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+
+    let envelope = post("envelope").await.unwrap();
+    assert_eq!(envelope.headers()["x-reflex-status"], "HIT_L1_EXACT");
+    let body: serde_json::Value = envelope.json().await.unwrap();
+    assert_eq!(body["object"], "reflex.envelope");
+    assert_eq!(body["cached"], true);
+    assert_eq!(body["response"]["model"], "gpt-4o");
+
+    let invalid = post("yaml").await.unwrap();
+    assert_eq!(invalid.status(), 400);
+}
+
+#[tokio::test]
+async fn test_n_choices_are_returned_and_cached() {
+    let server = spawn_test_server(TestServerConfig::default())
+        .await
+        .unwrap();
+    let client = reqwest::Client::new();
+    let mut request = create_request("gpt-4o", "Give me three taglines");
+    request.n = Some(3);
+
+    let post = |stream: bool| {
+        let mut request = request.clone();
+        request.stream = Some(stream);
+        client
+            .post(format!("{}/v1/chat/completions", server.url()))
+            .header("X-Reflex-Format", "openai")
+            .json(&request)
+            .send()
+    };
+
+    let miss = post(false).await.unwrap();
+    assert_eq!(miss.headers()["x-reflex-status"], "MISS");
+    let body: serde_json::Value = miss.json().await.unwrap();
+    let indexes: Vec<_> = body["choices"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|c| c["index"].as_u64().unwrap())
+        .collect();
+    assert_eq!(indexes, vec![0, 1, 2]);
+    // One provider call per choice.
+    assert_eq!(body["usage"]["total_tokens"], 60);
+
+    tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+
+    let hit = post(false).await.unwrap();
+    assert_eq!(hit.headers()["x-reflex-status"], "HIT_L1_EXACT");
+    let body: serde_json::Value = hit.json().await.unwrap();
+    assert_eq!(body["choices"].as_array().unwrap().len(), 3);
+
+    let streamed = post(true).await.unwrap();
+    assert_eq!(streamed.status(), 400);
+}
+
+#[tokio::test]
+async fn test_different_images_never_share_an_entry() {
+    let server = spawn_test_server(TestServerConfig::default())
+        .await
+        .unwrap();
+    let client = reqwest::Client::new();
+    let post = |image: &str| {
+        let request = serde_json::json!({
+            "model": "gpt-4o",
+            "messages": [{
+                "role": "user",
+                "content": [
+                    {"type": "text", "text": "Describe this image"},
+                    {"type": "image_url", "image_url": {"url": format!("data:image/png;base64,{}", image)}}
+                ]
+            }]
+        });
+        client
+            .post(format!("{}/v1/chat/completions", server.url()))
+            .json(&request)
+            .send()
+    };
+
+    let cat = post("Y2F0IHBpeGVscw==").await.unwrap();
+    assert_eq!(cat.headers()["x-reflex-status"], "MISS");
+
+    tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+
+    let dog = post("ZG9nIHBpeGVscw==").await.unwrap();
+    assert_eq!(dog.headers()["x-reflex-status"], "MISS");
+
+    tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+
+    let cat_again = post("Y2F0IHBpeGVscw==").await.unwrap();
+    assert_eq!(cat_again.headers()["x-reflex-status"], "HIT_L1_EXACT");
+}
+
+#[tokio::test]
+async fn test_forced_tool_choice_is_rejected() {
+    let server = spawn_test_server(TestServerConfig::default())
+        .await
+        .unwrap();
+    let client = reqwest::Client::new();
+    let post = |tool_choice: serde_json::Value| {
+        client
+            .post(format!("{}/v1/chat/completions", server.url()))
+            .json(&serde_json::json!({
+                "model": "gpt-4o",
+                "messages": [{"role": "user", "content": "What's the weather in Paris?"}],
+                "tools": [{"type": "function", "function": {"name": "get_weather"}}],
+                "tool_choice": tool_choice,
+            }))
+            .send()
+    };
+
+    // genai cannot force a tool call, so the gateway refuses rather than
+    // silently letting the model answer in text.
+    let forced = post(serde_json::json!("required")).await.unwrap();
+    assert_eq!(forced.status(), 400);
+    let named = post(serde_json::json!({"type": "function", "function": {"name": "get_weather"}}))
+        .await
+        .unwrap();
+    assert_eq!(named.status(), 400);
+
+    assert_eq!(post(serde_json::json!("auto")).await.unwrap().status(), 200);
+}
@@ -0,0 +1,189 @@
+//! `POST /v1/responses` (OpenAI Responses API) tests against the mock provider.
+
+mod common;
+
+use serde_json::{Value, json};
+use std::time::Duration;
+
+use common::harness::{TestServerConfig, spawn_test_server};
+
+struct RawClient {
+    client: reqwest::Client,
+    base_url: String,
+}
+
+impl RawClient {
+    fn new(base_url: String) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            base_url,
+        }
+    }
+
+    /// Posts `body` and returns (HTTP status, `X-Reflex-Status`, raw body).
+    async fn post(&self, path: &str, body: &Value) -> (u16, String, String) {
+        let resp = self
+            .client
+            .post(format!("{}{}", self.base_url, path))
+            .bearer_auth("sk-test")
+            .json(body)
+            .send()
+            .await
+            .expect("Request should be sent");
+
+        let status = resp.status().as_u16();
+        let reflex_status = resp
+            .headers()
+            .get("x-reflex-status")
+            .and_then(|h| h.to_str().ok())
+            .unwrap_or("unknown")
+            .to_string();
+        (status, reflex_status, resp.text().await.unwrap())
+    }
+}
+
+fn responses_request(prompt: &str) -> Value {
+    json!({ "model": "gpt-4o", "input": prompt })
+}
+
+/// Parses SSE `data:` lines; Responses events carry their name in `type`.
+fn sse_events(body: &str) -> Vec<Value> {
+    body.lines()
+        .filter_map(|line| line.strip_prefix("data: "))
+        .map(|data| serde_json::from_str(data).unwrap())
+        .collect()
+}
+
+fn streamed_text(events: &[Value]) -> String {
+    events
+        .iter()
+        .filter(|e| e["type"] == "response.output_text.delta")
+        .filter_map(|e| e["delta"].as_str())
+        .collect()
+}
+
+#[tokio::test]
+async fn test_responses_miss_then_exact_hit() {
+    let server = spawn_test_server(TestServerConfig::default())
+        .await
+        .expect("Server should start");
+    let client = RawClient::new(server.url());
+    let request = responses_request("What is a monad?");
+
+    let (status, reflex_status, body) = client.post("/v1/responses", &request).await;
+    assert_eq!(status, 200);
+    assert_eq!(reflex_status, "MISS");
+
+    let response: Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(response["object"], "response");
+    assert_eq!(response["status"], "completed");
+    assert!(response["id"].as_str().unwrap().starts_with("resp_"));
+    assert_eq!(response["output"][0]["type"], "message");
+    assert_eq!(response["output"][0]["content"][0]["type"], "output_text");
+
+    let (_, reflex_status, hit_body) = client.post("/v1/responses", &request).await;
+    assert_eq!(reflex_status, "HIT_L1_EXACT");
+    let hit: Value = serde_json::from_str(&hit_body).unwrap();
+    // Each hit is served under an id of its own, which its output items share.
+    assert_ne!(hit["id"], response["id"]);
+    assert_eq!(
+        hit["output"][0]["content"],
+        response["output"][0]["content"]
+    );
+}
+
+#[tokio::test]
+async fn test_responses_streaming_miss_then_replay() {
+    let server = spawn_test_server(TestServerConfig::default())
+        .await
+        .expect("Server should start");
+    let client = RawClient::new(server.url());
+    let mut request = responses_request("Stream me a haiku");
+    request["stream"] = json!(true);
+
+    let (status, reflex_status, body) = client.post("/v1/responses", &request).await;
+    assert_eq!(status, 200);
+    assert_eq!(reflex_status, "MISS");
+
+    let events = sse_events(&body);
+    assert_eq!(events.first().unwrap()["type"], "response.created");
+    assert_eq!(events.last().unwrap()["type"], "response.completed");
+    let sequence: Vec<u64> = events
+        .iter()
+        .map(|e| e["sequence_number"].as_u64().unwrap())
+        .collect();
+    assert!(sequence.windows(2).all(|w| w[1] == w[0] + 1));
+    let live_text = streamed_text(&events);
+    assert!(!live_text.is_empty());
+
+    let mut replay = None;
+    for _ in 0..100 {
+        let (_, reflex_status, body) = client.post("/v1/responses", &request).await;
+        if reflex_status == "HIT_L1_EXACT" {
+            replay = Some(body);
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    let replay = replay.expect("streamed response should be cached");
+
+    let events = sse_events(&replay);
+    assert_eq!(streamed_text(&events), live_text);
+    let completed = events.last().unwrap();
+    assert_eq!(completed["type"], "response.completed");
+    assert_eq!(
+        completed["response"]["output"][0]["content"][0]["text"],
+        live_text
+    );
+}
+
+#[tokio::test]
+async fn test_responses_shares_cache_with_chat_completions() {
+    let server = spawn_test_server(TestServerConfig::default())
+        .await
+        .expect("Server should start");
+    let client = RawClient::new(server.url());
+    let prompt = "Explain the borrow checker";
+
+    let (status, reflex_status, _) = client
+        .post(
+            "/v1/chat/completions",
+            &json!({
+                "model": "gpt-4o",
+                "messages": [{"role": "user", "content": prompt}]
+            }),
+        )
+        .await;
+    assert_eq!(status, 200);
+    assert_eq!(reflex_status, "MISS");
+
+    let mut shared = None;
+    for _ in 0..100 {
+        let (_, reflex_status, body) = client
+            .post("/v1/responses", &responses_request(prompt))
+            .await;
+        if reflex_status.starts_with("HIT") {
+            shared = Some(body);
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+
+    let body = shared.expect("Responses request should hit the chat completion entry");
+    let response: Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(response["object"], "response");
+}
+
+#[tokio::test]
+async fn test_responses_previous_response_id_is_rejected() {
+    let server = spawn_test_server(TestServerConfig::default())
+        .await
+        .expect("Server should start");
+    let client = RawClient::new(server.url());
+
+    let mut request = responses_request("Continue");
+    request["previous_response_id"] = json!("resp_123");
+    let (status, _, _) = client.post("/v1/responses", &request).await;
+
+    assert_eq!(status, 400);
+}
@@ -0,0 +1,83 @@
+//! Model alias routing against the mock provider.
+
+mod common;
+
+use serde_json::{Value, json};
+
+use common::harness::{TestServerConfig, spawn_test_server};
+use reflex_server::gateway::ModelRouter;
+
+/// Returns (`X-Reflex-Status`, response body).
+async fn chat(
+    client: &reqwest::Client,
+    base_url: &str,
+    model: &str,
+    stream: bool,
+) -> (String, String) {
+    let resp = client
+        .post(format!("{}/v1/chat/completions", base_url))
+        .bearer_auth("sk-routing")
+        .json(&json!({
+            "model": model,
+            "stream": stream,
+            "messages": [{"role": "user", "content": "Which model answers?"}]
+        }))
+        .send()
+        .await
+        .expect("Request should be sent");
+    assert_eq!(resp.status().as_u16(), 200);
+    let status = resp
+        .headers()
+        .get("x-reflex-status")
+        .and_then(|h| h.to_str().ok())
+        .unwrap_or_default()
+        .to_string();
+    (status, resp.text().await.unwrap())
+}
+
+fn model_of(body: &str) -> String {
+    serde_json::from_str::<Value>(body).unwrap()["model"]
+        .as_str()
+        .unwrap()
+        .to_string()
+}
+
+async fn server(routes: &str) -> common::harness::TestServer {
+    spawn_test_server(TestServerConfig {
+        router: ModelRouter::from_toml_str(routes).unwrap(),
+        ..TestServerConfig::default()
+    })
+    .await
+    .expect("Server should start")
+}
+
+#[tokio::test]
+async fn test_alias_keyed_entries_record_the_concrete_model() {
+    let server = server("[aliases.fast]\nmodels = [\"gpt-4o-mini\", \"gpt-4o\"]\n").await;
+    let client = reqwest::Client::new();
+
+    let (status, body) = chat(&client, &server.url(), "fast", false).await;
+    assert_eq!(status, "MISS");
+    assert_eq!(model_of(&body), "gpt-4o-mini");
+
+    let (status, body) = chat(&client, &server.url(), "fast", false).await;
+    assert_eq!(status, "HIT_L1_EXACT");
+    assert_eq!(model_of(&body), "gpt-4o-mini");
+}
+
+#[tokio::test]
+async fn test_model_keyed_alias_shares_entries_with_its_model() {
+    let server =
+        server("cache_key = \"model\"\n[aliases.fast]\nmodels = [\"gpt-4o-mini\", \"gpt-4o\"]\n")
+            .await;
+    let client = reqwest::Client::new();
+
+    let (status, body) = chat(&client, &server.url(), "fast", true).await;
+    assert_eq!(status, "MISS");
+    assert!(body.contains("\"model\":\"gpt-4o-mini\""), "body: {}", body);
+
+    // The stream may still be storing, in which case the flight answers.
+    let (status, body) = chat(&client, &server.url(), "gpt-4o-mini", false).await;
+    assert!(status.starts_with("HIT_"), "status: {}", status);
+    assert_eq!(model_of(&body), "gpt-4o-mini");
+}
@@ -0,0 +1,88 @@
+mod common;
+
+use common::harness::{TestServerConfig, spawn_test_server};
+use reflex_server::gateway::SamplingPolicy;
+use serde_json::{Value, json};
+
+const POLICY: &str = r#"
+deterministic_exact_only = true
+max_variants = 2
+"#;
+
+async fn post(server_url: &str, prompt: &str, temperature: f32) -> (String, Value) {
+    let response = reqwest::Client::new()
+        .post(format!("{}/v1/chat/completions", server_url))
+        .header("X-Reflex-Format", "openai")
+        .json(&json!({
+            "model": "gpt-4o",
+            "messages": [{"role": "user", "content": prompt}],
+            "temperature": temperature,
+        }))
+        .send()
+        .await
+        .unwrap();
+    let status = response.headers()["x-reflex-status"]
+        .to_str()
+        .unwrap()
+        .to_string();
+    (status, response.json().await.unwrap())
+}
+
+async fn settle() {
+    tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+}
+
+#[tokio::test]
+async fn test_deterministic_requests_only_hit_exact_repeats() {
+    let server = spawn_test_server(TestServerConfig {
+        sampling: SamplingPolicy::from_toml_str(POLICY).unwrap(),
+        ..TestServerConfig::default()
+    })
+    .await
+    .unwrap();
+    let url = server.url();
+
+    let prompt = "How to implement a binary tree in Rust. This includes struct definitions, insert methods, and traversal logic for a complete implementation.";
+    let neighbour = "How do I implement a binary tree in Rust? I need struct definitions, insert methods, and traversal logic.";
+
+    assert_eq!(post(&url, prompt, 0.0).await.0, "MISS");
+    settle().await;
+    assert_eq!(post(&url, neighbour, 0.0).await.0, "MISS");
+    settle().await;
+    assert_eq!(post(&url, prompt, 0.0).await.0, "HIT_L1_EXACT");
+}
+
+#[tokio::test]
+async fn test_high_temperature_entries_collect_and_rotate_answers() {
+    let server = spawn_test_server(TestServerConfig {
+        sampling: SamplingPolicy::from_toml_str(POLICY).unwrap(),
+        ..TestServerConfig::default()
+    })
+    .await
+    .unwrap();
+    let url = server.url();
+    let prompt = "Write a haiku about the sea";
+
+    let (status, first) = post(&url, prompt, 1.0).await;
+    assert_eq!(status, "MISS");
+    settle().await;
+    let (status, second) = post(&url, prompt, 1.0).await;
+    assert_eq!(status, "MISS", "entry holds one of two answers");
+    settle().await;
+
+    // Each hit's id is the stored answer's id with a suffix of its own.
+    let mut served = Vec::new();
+    for _ in 0..2 {
+        let (status, body) = post(&url, prompt, 1.0).await;
+        assert_eq!(status, "HIT_L1_EXACT");
+        let id = body["id"].as_str().unwrap();
+        served.push(id.rsplit_once('-').unwrap().0.to_string());
+    }
+    served.sort();
+    let mut stored = vec![
+        first["id"].as_str().unwrap().to_string(),
+        second["id"].as_str().unwrap().to_string(),
+    ];
+    stored.sort();
+    assert_eq!(served, stored);
+}
@@ -0,0 +1,136 @@
+mod common;
+
+use common::harness::{TestServerConfig, spawn_test_server};
+use reflex_server::gateway::ModelRouter;
+use serde_json::json;
+
+const ROUTES: &str = r#"
+[aliases.support]
+models = ["gpt-4o"]
+semantic_key = "conversation"
+
+[models."gpt-4o"]
+semantic_key = "last_user_turn"
+"#;
+
+const QUESTION: &str = "How do I reset my password?";
+
+async fn post(
+    server_url: &str,
+    model: &str,
+    system: &str,
+    history: &str,
+    question: &str,
+) -> String {
+    let response = reqwest::Client::new()
+        .post(format!("{}/v1/chat/completions", server_url))
+        .json(&json!({
+            "model": model,
+            "messages": [
+                {"role": "system", "content": system},
+                {"role": "user", "content": history},
+                {"role": "assistant", "content": "Sure."},
+                {"role": "user", "content": question}
+            ]
+        }))
+        .send()
+        .await
+        .unwrap();
+    response.headers()["x-reflex-status"]
+        .to_str()
+        .unwrap()
+        .to_string()
+}
+
+async fn settle() {
+    tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+}
+
+#[tokio::test]
+async fn test_last_user_turn_strategy_is_scoped_by_earlier_context() {
+    let server = spawn_test_server(TestServerConfig {
+        router: ModelRouter::from_toml_str(ROUTES).unwrap(),
+        ..TestServerConfig::default()
+    })
+    .await
+    .unwrap();
+    let url = server.url();
+
+    assert_eq!(
+        post(
+            &url,
+            "gpt-4o",
+            "You are a support bot.",
+            "Hi there",
+            QUESTION
+        )
+        .await,
+        "MISS"
+    );
+    settle().await;
+    // The same question in another conversation is not a match.
+    assert_eq!(
+        post(
+            &url,
+            "gpt-4o",
+            "You are a helpful agent.",
+            "Good morning",
+            QUESTION
+        )
+        .await,
+        "MISS"
+    );
+    settle().await;
+    // A rewording in the same conversation is.
+    assert_eq!(
+        post(
+            &url,
+            "gpt-4o",
+            "You are a support bot.",
+            "Hi there",
+            "How do I reset my password"
+        )
+        .await,
+        "HIT_L3_VERIFIED"
+    );
+}
+
+#[tokio::test]
+async fn test_conversation_strategy_keeps_system_prompts_apart() {
+    let server = spawn_test_server(TestServerConfig {
+        router: ModelRouter::from_toml_str(ROUTES).unwrap(),
+        ..TestServerConfig::default()
+    })
+    .await
+    .unwrap();
+    let url = server.url();
+
+    assert_eq!(
+        post(
+            &url,
+            "support",
+            "You are a support bot.",
+            "Hi there",
+            QUESTION
+        )
+        .await,
+        "MISS"
+    );
+    settle().await;
+    assert_eq!(
+        post(&url, "support", "You are a pirate.", "Hi there", QUESTION).await,
+        "MISS"
+    );
+    settle().await;
+    assert_eq!(
+        post(
+            &url,
+            "support",
+            "You are a support bot.",
+            "Hi there!",
+            QUESTION
+        )
+        .await,
+        "HIT_L3_VERIFIED"
+    );
+}
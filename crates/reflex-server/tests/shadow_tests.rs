@@ -0,0 +1,136 @@
+mod common;
+
+use common::harness::{TestServerConfig, spawn_test_server};
+use reflex_server::gateway::{RateLimitConfig, ShadowConfig};
+use serde_json::json;
+
+const PROMPT: &str = "How to implement a binary tree in Rust. This includes struct definitions, insert methods, and traversal logic for a complete implementation.";
+const NEIGHBOUR: &str = "How do I implement a binary tree in Rust? I need struct definitions, insert methods, and traversal logic.";
+const OTHER_NEIGHBOUR: &str = "How can I implement a binary tree in Rust? I need struct definitions, insert methods, and traversal logic.";
+
+async fn chat(server_url: &str, prompt: &str) -> String {
+    let response = reqwest::Client::new()
+        .post(format!("{}/v1/chat/completions", server_url))
+        .header("X-Reflex-Format", "openai")
+        .json(&json!({
+            "model": "gpt-4o",
+            "messages": [{"role": "user", "content": prompt}]
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    response.headers()["x-reflex-status"]
+        .to_str()
+        .unwrap()
+        .to_string()
+}
+
+async fn metrics(server_url: &str) -> String {
+    reqwest::get(format!("{}/metrics", server_url))
+        .await
+        .unwrap()
+        .text()
+        .await
+        .unwrap()
+}
+
+async fn settle() {
+    tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+}
+
+#[tokio::test]
+async fn test_disagreeing_shadow_check_invalidates_the_entry() {
+    // The mock answers echo the prompt, so the neighbour's fresh answer never
+    // matches the cached one exactly.
+    let server = spawn_test_server(TestServerConfig {
+        shadow: ShadowConfig {
+            sample_rate: 1.0,
+            min_similarity: 1.0,
+            invalidate_below: Some(1.0),
+            ..ShadowConfig::default()
+        },
+        ..TestServerConfig::default()
+    })
+    .await
+    .unwrap();
+    let url = server.url();
+
+    assert_eq!(chat(&url, PROMPT).await, "MISS");
+    settle().await;
+    assert_eq!(chat(&url, NEIGHBOUR).await, "HIT_L3_VERIFIED");
+    settle().await;
+    settle().await;
+
+    assert_eq!(chat(&url, PROMPT).await, "MISS");
+    let metrics = metrics(&url).await;
+    assert!(metrics.contains("reflex_shadow_checks_total"));
+    assert!(metrics.contains("outcome=\"disagree\""));
+    assert!(metrics.contains("reflex_shadow_invalidations_total"));
+}
+
+#[tokio::test]
+async fn test_unsampled_hits_are_left_alone() {
+    let server = spawn_test_server(TestServerConfig::default())
+        .await
+        .unwrap();
+    let url = server.url();
+
+    assert_eq!(chat(&url, PROMPT).await, "MISS");
+    settle().await;
+    assert_eq!(chat(&url, NEIGHBOUR).await, "HIT_L3_VERIFIED");
+    settle().await;
+
+    assert_eq!(chat(&url, PROMPT).await, "HIT_L1_EXACT");
+}
+
+#[tokio::test]
+async fn test_shadow_check_spends_the_miss_budget() {
+    let server = spawn_test_server(TestServerConfig {
+        shadow: ShadowConfig {
+            sample_rate: 1.0,
+            min_similarity: 1.0,
+            invalidate_below: Some(1.0),
+            ..ShadowConfig::default()
+        },
+        rate_limits: RateLimitConfig {
+            tenant_misses: Some("1/h".parse().unwrap()),
+            ..RateLimitConfig::default()
+        },
+        ..TestServerConfig::default()
+    })
+    .await
+    .unwrap();
+    let url = server.url();
+
+    assert_eq!(chat(&url, PROMPT).await, "MISS");
+    settle().await;
+    assert_eq!(chat(&url, NEIGHBOUR).await, "HIT_L3_VERIFIED");
+    settle().await;
+
+    assert_eq!(chat(&url, PROMPT).await, "HIT_L1_EXACT");
+    assert!(metrics(&url).await.contains("outcome=\"rate_limited\""));
+}
+
+#[tokio::test]
+async fn test_samples_beyond_max_in_flight_are_dropped() {
+    let server = spawn_test_server(TestServerConfig {
+        shadow: ShadowConfig {
+            sample_rate: 1.0,
+            max_in_flight: 1,
+            ..ShadowConfig::default()
+        },
+        mock_latency: std::time::Duration::from_secs(2),
+        ..TestServerConfig::default()
+    })
+    .await
+    .unwrap();
+    let url = server.url();
+
+    assert_eq!(chat(&url, PROMPT).await, "MISS");
+    settle().await;
+    assert_eq!(chat(&url, NEIGHBOUR).await, "HIT_L3_VERIFIED");
+    assert_eq!(chat(&url, OTHER_NEIGHBOUR).await, "HIT_L3_VERIFIED");
+
+    assert!(metrics(&url).await.contains("outcome=\"dropped\""));
+}
@@ -0,0 +1,172 @@
+//! Tenant registry tests against the mock provider.
+
+mod common;
+
+use std::sync::Arc;
+
+use serde_json::json;
+
+use common::harness::{TestServerConfig, spawn_test_server};
+use reflex_server::gateway::{RateLimitConfig, TenantRegistry};
+
+const REGISTRY: &str = r#"
+[[tenants]]
+id = "acme"
+api_keys = ["sk-acme-old", "sk-acme-new"]
+
+[[tenants]]
+id = "readonly"
+api_keys = ["sk-readonly"]
+store = false
+
+[[tenants]]
+id = "metered"
+api_keys = ["sk-metered"]
+max_requests_per_day = 1
+"#;
+
+async fn server() -> common::harness::TestServer {
+    spawn_test_server(TestServerConfig {
+        tenants: Some(Arc::new(TenantRegistry::from_toml_str(REGISTRY).unwrap())),
+        ..TestServerConfig::default()
+    })
+    .await
+    .expect("Server should start")
+}
+
+/// Returns (HTTP status, `X-Reflex-Status`).
+async fn chat(
+    client: &reqwest::Client,
+    base_url: &str,
+    api_key: Option<&str>,
+    prompt: &str,
+) -> (u16, String) {
+    let mut req = client
+        .post(format!("{}/v1/chat/completions", base_url))
+        .json(&json!({
+            "model": "gpt-4o",
+            "messages": [{"role": "user", "content": prompt}]
+        }));
+    if let Some(key) = api_key {
+        req = req.bearer_auth(key);
+    }
+    let resp = req.send().await.expect("Request should be sent");
+    let status = resp
+        .headers()
+        .get("x-reflex-status")
+        .and_then(|h| h.to_str().ok())
+        .unwrap_or("unknown")
+        .to_string();
+    (resp.status().as_u16(), status)
+}
+
+#[tokio::test]
+async fn test_rotated_keys_share_the_tenant_cache() {
+    let server = server().await;
+    let client = reqwest::Client::new();
+    let base_url = server.url();
+
+    assert_eq!(
+        chat(&client, &base_url, Some("sk-acme-old"), "Rotate me").await,
+        (200, "MISS".to_string())
+    );
+    assert_eq!(
+        chat(&client, &base_url, Some("sk-acme-new"), "Rotate me").await,
+        (200, "HIT_L1_EXACT".to_string())
+    );
+}
+
+#[tokio::test]
+async fn test_unknown_or_missing_keys_are_rejected() {
+    let server = server().await;
+    let client = reqwest::Client::new();
+    let base_url = server.url();
+
+    assert_eq!(
+        chat(&client, &base_url, Some("sk-guess"), "Let me in")
+            .await
+            .0,
+        401
+    );
+    assert_eq!(chat(&client, &base_url, None, "Let me in").await.0, 401);
+
+    let resp = client
+        .post(format!("{}/v1/messages", base_url))
+        .header("x-api-key", "sk-guess")
+        .json(&json!({
+            "model": "claude-sonnet-4",
+            "max_tokens": 16,
+            "messages": [{"role": "user", "content": "Let me in"}]
+        }))
+        .send()
+        .await
+        .expect("Request should be sent");
+    assert_eq!(resp.status().as_u16(), 401);
+}
+
+#[tokio::test]
+async fn test_tenant_without_store_never_writes() {
+    let server = server().await;
+    let client = reqwest::Client::new();
+    let base_url = server.url();
+
+    for _ in 0..2 {
+        assert_eq!(
+            chat(&client, &base_url, Some("sk-readonly"), "Forget me").await,
+            (200, "MISS".to_string())
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_daily_quota_answers_429() {
+    let server = server().await;
+    let client = reqwest::Client::new();
+    let base_url = server.url();
+
+    assert_eq!(
+        chat(&client, &base_url, Some("sk-metered"), "Once").await.0,
+        200
+    );
+    assert_eq!(
+        chat(&client, &base_url, Some("sk-metered"), "Twice").await,
+        (429, "quota_exceeded".to_string())
+    );
+}
+
+#[tokio::test]
+async fn test_rate_limited_requests_leave_the_quota_alone() {
+    let registry = Arc::new(
+        TenantRegistry::from_toml_str(
+            "[[tenants]]\nid = \"metered\"\napi_keys = [\"sk-metered\"]\nmax_requests_per_day = 2\n",
+        )
+        .unwrap(),
+    );
+    let server = spawn_test_server(TestServerConfig {
+        tenants: Some(registry.clone()),
+        rate_limits: RateLimitConfig {
+            tenant_requests: Some("1/h".parse().unwrap()),
+            ..RateLimitConfig::default()
+        },
+        ..TestServerConfig::default()
+    })
+    .await
+    .expect("Server should start");
+    let client = reqwest::Client::new();
+    let base_url = server.url();
+
+    assert_eq!(
+        chat(&client, &base_url, Some("sk-metered"), "Once").await.0,
+        200
+    );
+    assert_eq!(
+        chat(&client, &base_url, Some("sk-metered"), "Twice").await,
+        (429, "rate_limited".to_string())
+    );
+
+    // The rejected request did not count: one request is left today.
+    let tenant = registry.lookup("sk-metered").unwrap();
+    let now = chrono::Utc::now().timestamp();
+    assert!(registry.charge(tenant, now).is_ok());
+    assert!(registry.charge(tenant, now).is_err());
+}